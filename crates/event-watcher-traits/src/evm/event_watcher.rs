@@ -68,7 +68,7 @@ pub trait EventWatcher {
     /// The Events that this event watcher is interested in.
     type Events: contract::EthLogDecode + Clone;
     /// The Storage backend that will be used to store the required state for this event watcher
-    type Store: HistoryStore + EventHashStore;
+    type Store: HistoryStore + EventHashStore + ReorgStore;
     /// Returns a task that should be running in the background
     /// that will watch events
     #[tracing::instrument(
@@ -84,7 +84,7 @@ pub trait EventWatcher {
         store: Arc<Self::Store>,
         contract: Self::Contract,
         handlers: Vec<EventHandlerFor<Self>>,
-        ctx: &RelayerContext,
+        ctx: &RelayerContext<Self::Store>,
     ) -> webb_relayer_utils::Result<()> {
         let backoff = backoff::backoff::Constant::new(Duration::from_secs(1));
         let task = || async {
@@ -130,6 +130,37 @@ pub trait EventWatcher {
                     history_store_key,
                     contract.deployed_at().as_u64(),
                 )?;
+                // make sure we are still building on top of the canonical chain,
+                // otherwise roll back to the last block we know is still there.
+                let maybe_ancestor = find_reorg_ancestor(
+                    &client,
+                    store.as_ref(),
+                    history_store_key,
+                    block,
+                    contract.deployed_at().as_u64(),
+                )
+                .map_err(backoff::Error::transient)
+                .await?;
+                if let Some(ancestor) = maybe_ancestor {
+                    tracing::warn!(
+                        %chain_id,
+                        from = block,
+                        to = ancestor.number,
+                        "Chain reorganization detected, rolling back",
+                    );
+                    store.rollback_to_block(history_store_key, ancestor)?;
+                    for handler in &handlers {
+                        handler
+                            .handle_rollback(
+                                store.clone(),
+                                &contract,
+                                ancestor.number,
+                            )
+                            .map_err(backoff::Error::transient)
+                            .await?;
+                    }
+                    continue;
+                }
                 let dest_block =
                     core::cmp::min(block + step, target_block_number);
 
@@ -177,9 +208,12 @@ pub trait EventWatcher {
                             }
                         });
                     if mark_as_handled {
-                        store.set_last_block_number(
+                        store.set_last_block(
                             history_store_key,
-                            log.block_number.as_u64(),
+                            BlockInfo::new(
+                                log.block_number.as_u64(),
+                                log.block_hash,
+                            ),
                         )?;
                         tracing::trace!(
                             %chain_id,
@@ -196,8 +230,26 @@ pub trait EventWatcher {
                     }
                 }
 
-                // move the block pointer to the destination block
-                store.set_last_block_number(history_store_key, dest_block)?;
+                // move the block pointer to the destination block,
+                // and remember its hash to detect reorgs later.
+                match client
+                    .get_block(dest_block)
+                    .map_err(Into::into)
+                    .map_err(backoff::Error::transient)
+                    .await?
+                    .and_then(|b| b.hash)
+                {
+                    Some(hash) => store.set_last_block(
+                        history_store_key,
+                        BlockInfo::new(dest_block, hash),
+                    )?,
+                    None => {
+                        store.set_last_block_number(
+                            history_store_key,
+                            dest_block,
+                        )?;
+                    }
+                }
                 // if we fully synced, we can update the target block number
                 let should_cooldown = dest_block == target_block_number;
                 if should_cooldown {
@@ -257,6 +309,48 @@ pub trait EventWatcher {
         Ok(())
     }
 }
+/// Checks that the last processed block is still part of the canonical chain, by comparing
+/// its hash with the parent hash of the next block.
+///
+/// On a mismatch, returns the newest cached block that is still canonical (or the deploy
+/// block, if none of them is), which is the block to roll back to.
+async fn find_reorg_ancestor<S: ReorgStore>(
+    client: &EthersTimeLagClient,
+    store: &S,
+    history_store_key: ResourceId,
+    last_block: u64,
+    deployed_at: u64,
+) -> webb_relayer_utils::Result<Option<BlockInfo>> {
+    let last_block_hash = match store.get_last_block_hash(history_store_key)? {
+        Some(hash) => hash,
+        // nothing to compare with.
+        None => return Ok(None),
+    };
+    let canonical_hash_of = |number: u64| async move {
+        let block = client.get_block(number).await?;
+        Ok::<_, webb_relayer_utils::Error>(block.and_then(|b| b.hash))
+    };
+    let is_canonical = match client.get_block(last_block + 1).await? {
+        Some(next) => next.parent_hash == last_block_hash,
+        // the next block is not there yet, compare with the last block itself.
+        None => canonical_hash_of(last_block).await? == Some(last_block_hash),
+    };
+    if is_canonical {
+        return Ok(None);
+    }
+    for cached in store.get_cached_blocks(history_store_key)? {
+        if cached.number >= last_block {
+            continue;
+        }
+        if canonical_hash_of(cached.number).await? == Some(cached.hash) {
+            return Ok(Some(cached));
+        }
+    }
+    // none of the cached blocks is canonical anymore, start over.
+    let hash = canonical_hash_of(deployed_at).await?.unwrap_or_default();
+    Ok(Some(BlockInfo::new(deployed_at, hash)))
+}
+
 /// A trait that defines a handler for a specific set of event types.
 ///
 /// The handlers are implemented separately from the watchers, so that we can have
@@ -270,7 +364,7 @@ pub trait EventHandler {
     /// The type of event this handler is for.
     type Events: contract::EthLogDecode + Clone;
     /// The storage backend that this handler will use.
-    type Store: HistoryStore + EventHashStore;
+    type Store: HistoryStore + EventHashStore + ReorgStore;
 
     /// a method to be called with the event information,
     /// it is up to the handler to decide what to do with the event.
@@ -288,6 +382,20 @@ pub trait EventHandler {
         metrics: Arc<Mutex<metric::Metrics>>,
     ) -> webb_relayer_utils::Result<()>;
 
+    /// Called after the event watcher rolled back the store to the given block number,
+    /// because of a chain reorganization.
+    ///
+    /// Handlers that keep any state derived from the events (other than the store)
+    /// should rebuild it here. By default, it does nothing.
+    async fn handle_rollback(
+        &self,
+        _store: Arc<Self::Store>,
+        _contract: &Self::Contract,
+        _block_number: u64,
+    ) -> webb_relayer_utils::Result<()> {
+        Ok(())
+    }
+
     /// Whether any of the events could be handled by the handler
     async fn can_handle_events(
         &self,
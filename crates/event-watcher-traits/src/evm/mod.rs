@@ -30,7 +30,8 @@ use webb_proposals::{ResourceId, TargetSystem, TypedChainId};
 use webb_relayer_context::RelayerContext;
 use webb_relayer_store::sled::SledQueueKey;
 use webb_relayer_store::{
-    BridgeCommand, BridgeKey, EventHashStore, HistoryStore, QueueStore,
+    BlockInfo, BridgeCommand, BridgeKey, EventHashStore, HistoryStore,
+    QueueStore, ReorgStore,
 };
 use webb_relayer_utils::metric;
 
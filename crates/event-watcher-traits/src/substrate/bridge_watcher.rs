@@ -49,7 +49,7 @@ where
     async fn run(
         &self,
         chain_id: u32,
-        ctx: RelayerContext,
+        ctx: RelayerContext<Self::Store>,
         pair: Sr25519Pair,
         store: Arc<Self::Store>,
     ) -> webb_relayer_utils::Result<()> {
@@ -60,8 +60,12 @@ where
                 webb_proposals::TypedChainId::Substrate(chain_id);
             let bridge_key = BridgeKey::new(typed_chain_id);
             let key = SledQueueKey::from_bridge_key(bridge_key);
-            let client =
-                ctx.substrate_provider::<RuntimeConfig, _>(chain_id).await?;
+            // connecting again after losing the node fails over to another
+            // endpoint, if any.
+            let client = ctx
+                .substrate_provider::<RuntimeConfig, _>(chain_id)
+                .await
+                .map_err(backoff::Error::transient)?;
             let client = Arc::new(client);
 
             loop {
@@ -142,7 +142,7 @@ where
     async fn run(
         &self,
         chain_id: u32,
-        ctx: RelayerContext,
+        ctx: RelayerContext<Self::Store>,
         store: Arc<Self::Store>,
         event_watcher_config: EventsWatcherConfig,
         handlers: Vec<EventHandlerFor<Self, RuntimeConfig>>,
@@ -66,7 +66,7 @@ mod merger;
 
 #[cfg(feature = "coingecko")]
 pub use crate::coingecko::CoinGeckoBackend;
-pub use cached::CachedPriceBackend;
+pub use cached::{CachedPrice, CachedPriceBackend};
 pub use dummy::DummyPriceBackend;
 pub use merger::PriceOracleMerger;
 
@@ -0,0 +1,468 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use typed_builder::TypedBuilder;
+use webb::evm::ethers::prelude::*;
+use webb::evm::ethers::utils::keccak256;
+use webb_proposals::ResourceId;
+use webb_relayer_store::sled::SledQueueKey;
+use webb_relayer_store::{BridgeCommand, BridgeKey, QueueStore};
+use webb_relayer_utils::metric;
+
+use crate::proposal_kind::ProposalKindExt;
+use crate::GovernedContracts;
+
+/// The length of the header of a proposal: its resource id, function signature and nonce.
+pub const PROPOSAL_HEADER_LENGTH: usize = 40;
+
+/// The directory of the inbox the imported signed proposals are moved to.
+pub const PROCESSED_DIR: &str = "processed";
+
+/// The directory of the inbox the invalid signed proposals are moved to.
+pub const REJECTED_DIR: &str = "rejected";
+
+/// How long a file of the inbox that cannot be read as a signed proposal is left
+/// in place, since it may still be being written, before it is rejected.
+pub const UNREADABLE_RETRY_PERIOD: Duration = Duration::from_secs(60);
+
+/// Where the unsigned proposals are exported to.
+#[derive(Debug, Clone)]
+pub enum Outbox {
+    /// A directory, where each proposal is written to a file named after its hash.
+    Dir(PathBuf),
+    /// An HTTP endpoint, where each proposal is posted to as JSON.
+    Http(reqwest::Url),
+}
+
+/// A proposal exported to be signed offline.
+///
+/// The Governor signs the `hash`, which is the keccak256 hash of the `data`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnsignedProposal {
+    /// The resource id of the target of the proposal.
+    pub resource_id: H256,
+    /// The nonce of the proposal.
+    pub nonce: u32,
+    /// The header of the proposal.
+    pub header: Bytes,
+    /// The proposal, encoded as bytes.
+    pub data: Bytes,
+    /// The keccak256 hash of the proposal bytes, to be signed.
+    pub hash: H256,
+}
+
+/// A proposal signed offline, to be put in the inbox as a `.json` file.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedProposal {
+    /// The proposal, encoded as bytes.
+    pub data: Bytes,
+    /// The signature of the keccak256 hash of the proposal bytes.
+    pub signature: Bytes,
+}
+
+/// A ProposalSigningBackend for a Governor whose key is kept offline (a cold key).
+///
+/// Every proposal is exported to the outbox unsigned, to be signed offline, and the
+/// signed proposals are imported from the inbox by an [`InboxWatcher`].
+#[derive(TypedBuilder)]
+pub struct AirGappedProposalSigningBackend<S>
+where
+    S: QueueStore<BridgeCommand, Key = SledQueueKey>,
+{
+    /// A map between chain id and its signature bridge system.
+    #[builder(setter(into))]
+    signature_bridges: HashSet<ResourceId>,
+    /// The other contracts of the bridges, that the governance proposals target.
+    #[builder(default)]
+    governed_contracts: GovernedContracts,
+    /// Something that implements the QueueStore trait.
+    store: Arc<S>,
+    /// Where the unsigned proposals are exported to.
+    outbox: Outbox,
+    /// The directory the signed proposals are imported from.
+    inbox: PathBuf,
+    /// The address of the Governor, that must have signed the proposals.
+    governor: Address,
+    /// How often the inbox is checked for signed proposals.
+    inbox_polling_interval: Duration,
+}
+
+impl<S> AirGappedProposalSigningBackend<S>
+where
+    S: QueueStore<BridgeCommand, Key = SledQueueKey>,
+{
+    /// Returns the watcher that imports the signed proposals from the inbox.
+    pub fn inbox_watcher(&self) -> InboxWatcher<S> {
+        InboxWatcher {
+            signature_bridges: self.signature_bridges.clone(),
+            governed_contracts: self.governed_contracts.clone(),
+            store: self.store.clone(),
+            inbox: self.inbox.clone(),
+            governor: self.governor,
+            polling_interval: self.inbox_polling_interval,
+        }
+    }
+
+    /// Exports the unsigned proposal to the outbox.
+    async fn export(
+        &self,
+        proposal: &UnsignedProposal,
+    ) -> webb_relayer_utils::Result<()> {
+        match &self.outbox {
+            Outbox::Dir(dir) => {
+                tokio::fs::create_dir_all(dir).await?;
+                let name = format!("{}.json", hex::encode(proposal.hash));
+                // written aside first, so a half written file is never picked up.
+                let tmp = dir.join(format!(".{name}.tmp"));
+                tokio::fs::write(&tmp, serde_json::to_vec_pretty(proposal)?)
+                    .await?;
+                tokio::fs::rename(&tmp, dir.join(name)).await?;
+            }
+            Outbox::Http(url) => {
+                reqwest::Client::new()
+                    .post(url.clone())
+                    .json(proposal)
+                    .send()
+                    .await?
+                    .error_for_status()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl<S> super::ProposalSigningBackend for AirGappedProposalSigningBackend<S>
+where
+    S: QueueStore<BridgeCommand, Key = SledQueueKey> + Send + Sync + 'static,
+{
+    async fn can_handle_proposal(
+        &self,
+        proposal: &(impl ProposalKindExt + Sync + Send + 'static),
+    ) -> webb_relayer_utils::Result<bool> {
+        let known_bridge = crate::is_known_bridge(
+            &self.signature_bridges,
+            &self.governed_contracts,
+            proposal,
+        );
+        Ok(known_bridge)
+    }
+
+    async fn handle_proposal(
+        &self,
+        proposal: &(impl ProposalKindExt + Sync + Send + 'static),
+        _metrics: Arc<Mutex<metric::Metrics>>,
+    ) -> webb_relayer_utils::Result<()> {
+        let header = proposal.header();
+        let proposal_bytes = proposal.to_vec();
+        let unsigned = UnsignedProposal {
+            resource_id: H256(header.resource_id().into_bytes()),
+            nonce: header.nonce().to_u32(),
+            header: proposal_bytes[..PROPOSAL_HEADER_LENGTH].to_vec().into(),
+            hash: H256(keccak256(&proposal_bytes)),
+            data: proposal_bytes.into(),
+        };
+        self.export(&unsigned).await?;
+        tracing::debug!(
+            hash = ?unsigned.hash,
+            proposal = ?hex::encode(&unsigned.data),
+            "Exported the proposal to be signed offline",
+        );
+        tracing::event!(
+            target: webb_relayer_utils::probe::TARGET,
+            tracing::Level::DEBUG,
+            kind = %webb_relayer_utils::probe::Kind::SigningBackend,
+            backend = "AirGapped",
+            exported = true,
+            data = ?hex::encode(&unsigned.data),
+            hash = ?unsigned.hash,
+        );
+        Ok(())
+    }
+}
+
+/// InboxWatcher imports the proposals signed offline from the inbox of an
+/// [`AirGappedProposalSigningBackend`], and sends them to their signature bridge.
+///
+/// A signed proposal is moved to the `processed` directory of the inbox once it is
+/// sent, or to the `rejected` one if its signature is not made by the Governor. The
+/// proposals of other signature bridges are left in the inbox, so several watchers
+/// could share it.
+///
+/// A file that cannot be read as a signed proposal is retried until it was left
+/// unchanged for [`UNREADABLE_RETRY_PERIOD`], as it may be picked up while it is
+/// still being written, and only then rejected. Writing the file aside and renaming
+/// it to `.json` once complete avoids the delay.
+#[derive(Clone)]
+pub struct InboxWatcher<S> {
+    signature_bridges: HashSet<ResourceId>,
+    governed_contracts: GovernedContracts,
+    store: Arc<S>,
+    inbox: PathBuf,
+    governor: Address,
+    polling_interval: Duration,
+}
+
+impl<S> InboxWatcher<S>
+where
+    S: QueueStore<BridgeCommand, Key = SledQueueKey>,
+{
+    /// Checks the inbox for signed proposals, forever.
+    pub async fn run(self) -> webb_relayer_utils::Result<()> {
+        for dir in [PROCESSED_DIR, REJECTED_DIR] {
+            tokio::fs::create_dir_all(self.inbox.join(dir)).await?;
+        }
+        let mut interval = tokio::time::interval(self.polling_interval);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.check_inbox().await {
+                tracing::warn!(
+                    inbox = %self.inbox.display(),
+                    "Failed to check the inbox: {}",
+                    e
+                );
+            }
+        }
+    }
+
+    /// Imports all the signed proposals that are in the inbox.
+    async fn check_inbox(&self) -> webb_relayer_utils::Result<()> {
+        let mut entries = tokio::fs::read_dir(&self.inbox).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().map_or(true, |ext| ext != "json") {
+                continue;
+            }
+            if let Err(e) = self.import(&path).await {
+                tracing::warn!(
+                    path = %path.display(),
+                    "Failed to import the signed proposal: {}",
+                    e
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Imports the signed proposal of the given file.
+    async fn import(&self, path: &Path) -> webb_relayer_utils::Result<()> {
+        let content = tokio::fs::read(path).await?;
+        let signed = serde_json::from_slice::<SignedProposal>(&content)
+            .map_err(|e| format!("invalid signed proposal: {e}"))
+            .and_then(|signed| {
+                let resource_id = resource_id_of(&signed.data)
+                    .ok_or_else(|| "the proposal has no header".to_string())?;
+                Ok((signed, resource_id))
+            });
+        // the proposals of other signature bridges are left in the inbox.
+        if let Ok((_, resource_id)) = &signed {
+            if !self.signature_bridges.contains(resource_id)
+                && !self.governed_contracts.contains(resource_id)
+            {
+                return Ok(());
+            }
+        }
+        // it may still be being written, so it is retried for a while.
+        if let Err(reason) = &signed {
+            if !is_unchanged_for(path, UNREADABLE_RETRY_PERIOD).await? {
+                tracing::debug!(
+                    path = %path.display(),
+                    "Cannot read the signed proposal yet, retrying later: {}",
+                    reason
+                );
+                return Ok(());
+            }
+        }
+        let verdict = signed.and_then(|(signed, resource_id)| {
+            self.verify(&signed)?;
+            Ok((signed, resource_id))
+        });
+        let dir = if verdict.is_ok() {
+            PROCESSED_DIR
+        } else {
+            REJECTED_DIR
+        };
+        // claim the file first, so a proposal is only sent once, even if
+        // another watcher found it too.
+        let Some(name) = path.file_name() else {
+            return Ok(());
+        };
+        let claimed = self.inbox.join(dir).join(name);
+        if tokio::fs::rename(path, &claimed).await.is_err() {
+            return Ok(());
+        }
+        let (signed, resource_id) = match verdict {
+            Ok(verdict) => verdict,
+            Err(reason) => {
+                tracing::warn!(
+                    path = %path.display(),
+                    "Rejected the signed proposal: {}",
+                    reason
+                );
+                return Ok(());
+            }
+        };
+        let bridge_key = BridgeKey::new(resource_id.typed_chain_id());
+        tracing::debug!(
+            %bridge_key,
+            proposal = ?hex::encode(&signed.data),
+            "Signaling Signature Bridge to execute proposal",
+        );
+        tracing::event!(
+            target: webb_relayer_utils::probe::TARGET,
+            tracing::Level::DEBUG,
+            kind = %webb_relayer_utils::probe::Kind::SigningBackend,
+            backend = "AirGapped",
+            signal_bridge = %bridge_key,
+            data = ?hex::encode(&signed.data),
+            signature = ?hex::encode(&signed.signature),
+        );
+        let enqueued = self.store.enqueue_item(
+            SledQueueKey::from_bridge_key(bridge_key),
+            BridgeCommand::ExecuteProposalWithSignature {
+                data: signed.data.to_vec(),
+                signature: signed.signature.to_vec(),
+            },
+        );
+        if let Err(e) = enqueued {
+            // it is the only copy of the signed proposal, so it is put back
+            // into the inbox, to be imported again on the next check.
+            if let Err(io) = tokio::fs::rename(&claimed, path).await {
+                tracing::warn!(
+                    path = %claimed.display(),
+                    "Failed to put the signed proposal back into the inbox: {}",
+                    io
+                );
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Checks that the proposal is signed by the Governor.
+    fn verify(&self, signed: &SignedProposal) -> Result<(), String> {
+        let hash = H256(keccak256(&signed.data));
+        let signer = Signature::try_from(signed.signature.as_ref())
+            .and_then(|signature| signature.recover(hash))
+            .map_err(|e| format!("invalid signature: {e}"))?;
+        if signer != self.governor {
+            return Err(format!(
+                "signed by {signer:?} instead of the governor {:?}",
+                self.governor
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Returns `true` if the file was not modified for the given period.
+async fn is_unchanged_for(
+    path: &Path,
+    period: Duration,
+) -> webb_relayer_utils::Result<bool> {
+    let modified = tokio::fs::metadata(path).await?.modified()?;
+    // a modification time in the future counts as a recent one.
+    let unchanged = modified.elapsed().map_or(false, |age| age >= period);
+    Ok(unchanged)
+}
+
+/// Returns the resource id of the target of the proposal bytes.
+fn resource_id_of(data: &[u8]) -> Option<ResourceId> {
+    let bytes: [u8; 32] = data.get(..32)?.try_into().ok()?;
+    Some(ResourceId::from(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::test_utils::*;
+    use crate::ProposalSigningBackend;
+    use webb::evm::ethers::core::rand::thread_rng;
+    use webb_relayer_store::InMemoryStore;
+
+    #[tokio::test]
+    async fn should_import_the_proposals_signed_offline() {
+        let _guard = setup_tracing();
+        let dir = tempfile::tempdir().unwrap();
+        let (outbox, inbox) = (dir.path().join("out"), dir.path().join("in"));
+        let governor = LocalWallet::new(&mut thread_rng());
+        let target = mock_resourc_id(
+            mock_target_system(Address::random()),
+            mock_typed_chain_id(1),
+        );
+        let src = mock_resourc_id(
+            mock_target_system(Address::random()),
+            mock_typed_chain_id(2),
+        );
+        let store = Arc::new(InMemoryStore::default());
+        let backend = AirGappedProposalSigningBackend::builder()
+            .signature_bridges([target])
+            .store(store.clone())
+            .outbox(Outbox::Dir(outbox.clone()))
+            .inbox(inbox.clone())
+            .governor(governor.address())
+            .inbox_polling_interval(Duration::from_millis(10))
+            .build();
+        for dir in [PROCESSED_DIR, REJECTED_DIR] {
+            std::fs::create_dir_all(inbox.join(dir)).unwrap();
+        }
+        let watcher = backend.inbox_watcher();
+        let key = SledQueueKey::from_bridge_key(BridgeKey::new(
+            target.typed_chain_id(),
+        ));
+
+        for nonce in [1, 2] {
+            let proposal = mock_evm_anchor_update_proposal(
+                mock_proposal_header(target, nonce),
+                src,
+            );
+            backend
+                .handle_proposal(&proposal, mock_metrics())
+                .await
+                .unwrap();
+        }
+        let mut exported = std::fs::read_dir(&outbox)
+            .unwrap()
+            .map(|entry| {
+                let content = std::fs::read(entry.unwrap().path()).unwrap();
+                serde_json::from_slice::<UnsignedProposal>(&content).unwrap()
+            })
+            .collect::<Vec<_>>();
+        exported.sort_by_key(|p| p.nonce);
+        assert_eq!(exported.len(), 2);
+        assert_eq!(exported[0].hash, H256(keccak256(&exported[0].data)));
+
+        // one is signed by the governor, the other by someone else.
+        let someone = LocalWallet::new(&mut thread_rng());
+        for (p, signer) in exported.iter().zip([&governor, &someone]) {
+            let signed = SignedProposal {
+                data: p.data.clone(),
+                signature: signer.sign_hash(p.hash).unwrap().to_vec().into(),
+            };
+            let path = inbox.join(format!("{}.json", p.nonce));
+            std::fs::write(path, serde_json::to_vec(&signed).unwrap()).unwrap();
+        }
+        // and another one is still being written.
+        std::fs::write(inbox.join("3.json"), b"{\"data\": \"0x").unwrap();
+        watcher.check_inbox().await.unwrap();
+
+        let Some(BridgeCommand::ExecuteProposalWithSignature { data, .. }) =
+            store.dequeue_item(key.clone()).unwrap()
+        else {
+            panic!("the signed proposal was not sent to the signature bridge");
+        };
+        assert_eq!(data, exported[0].data.to_vec());
+        assert!(
+            QueueStore::<BridgeCommand>::dequeue_item(store.as_ref(), key)
+                .unwrap()
+                .is_none()
+        );
+        assert!(inbox.join(PROCESSED_DIR).join("1.json").exists());
+        assert!(inbox.join(REJECTED_DIR).join("2.json").exists());
+        assert!(inbox.join("3.json").exists());
+    }
+}
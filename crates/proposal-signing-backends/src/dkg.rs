@@ -5,25 +5,35 @@ use webb::substrate::tangle_runtime::api::runtime_types::webb_proposals::header:
 use webb::substrate::tangle_runtime::api::runtime_types::webb_proposals::nonce::Nonce;
 use webb::substrate::subxt::{OnlineClient, PolkadotConfig};
 use webb::evm::ethers::utils;
-use webb::substrate::tangle_runtime::api::runtime_types::webb_proposals::proposal::{Proposal, ProposalKind};
-use webb_proposals::ProposalTrait;
+use webb::substrate::tangle_runtime::api::runtime_types::webb_proposals::proposal::{Proposal, ProposalKind as DkgProposalKind};
 use webb::substrate::scale::{Encode, Decode};
 use webb_relayer_utils::metric;
 use webb::substrate::tangle_runtime::api as RuntimeApi;
-use webb_relayer_store::{QueueStore, SledStore};
+use webb_relayer_store::QueueStore;
 use webb_relayer_store::sled::SledQueueKey;
 use webb_relayer_utils::static_tx_payload::TypeErasedStaticTxPayload;
+use webb_relayer_utils::substrate_endpoints::SubstrateEndpoints;
+
+use crate::proposal_kind::{ProposalKind, ProposalKindExt};
 
 type DkgConfig = PolkadotConfig;
 type DkgClient = OnlineClient<DkgConfig>;
 /// A ProposalSigningBackend that uses the DKG System for Signing Proposals.
 #[derive(typed_builder::TypedBuilder)]
-pub struct DkgProposalSigningBackend {
+pub struct DkgProposalSigningBackend<S>
+where
+    S: QueueStore<TypeErasedStaticTxPayload, Key = SledQueueKey>,
+{
+    /// The endpoints of the DKG node, connected to again whenever the connection
+    /// is lost.
     #[builder(setter(into))]
-    pub client: DkgClient,
+    endpoints: SubstrateEndpoints,
+    /// The client of the DKG node, if it is connected.
+    #[builder(default, setter(skip))]
+    client: Mutex<Option<DkgClient>>,
     /// Something that implements the QueueStore trait.
     #[builder(setter(into))]
-    store: Arc<SledStore>,
+    store: Arc<S>,
     /// The chain id of the chain that this backend is running on.
     ///
     /// This used as the source chain id for the proposals.
@@ -31,22 +41,37 @@ pub struct DkgProposalSigningBackend {
     src_chain_id: webb_proposals::TypedChainId,
 }
 
-//AnchorUpdateProposal for evm
-#[async_trait::async_trait]
-impl super::ProposalSigningBackend for DkgProposalSigningBackend {
-    async fn can_handle_proposal(
+impl<S> DkgProposalSigningBackend<S>
+where
+    S: QueueStore<TypeErasedStaticTxPayload, Key = SledQueueKey>,
+{
+    /// Returns the client of the DKG node, connecting to it first if needed.
+    async fn client(&self) -> webb_relayer_utils::Result<DkgClient> {
+        let mut client = self.client.lock().await;
+        match &*client {
+            Some(client) => Ok(client.clone()),
+            None => {
+                let connected = self.endpoints.connect().await?;
+                *client = Some(connected.clone());
+                Ok(connected)
+            }
+        }
+    }
+
+    /// Checks that the source chain and the resource of the proposal with the
+    /// given header are whitelisted on the DKG node.
+    async fn is_whitelisted(
         &self,
-        proposal: &(impl ProposalTrait + Sync + Send + 'static),
+        client: &DkgClient,
+        header: webb_proposals::ProposalHeader,
     ) -> webb_relayer_utils::Result<bool> {
-        let header = proposal.header();
         let resource_id = header.resource_id();
         let src_chain_id =
             webb_proposals_typed_chain_converter(self.src_chain_id);
         let chain_nonce_addrs = RuntimeApi::storage()
             .dkg_proposals()
             .chain_nonces(&src_chain_id);
-        let maybe_whitelisted = self
-            .client
+        let maybe_whitelisted = client
             .storage()
             .at(None)
             .await?
@@ -60,8 +85,7 @@ impl super::ProposalSigningBackend for DkgProposalSigningBackend {
         let resource_id_addrs = RuntimeApi::storage()
             .dkg_proposals()
             .resources(ResourceId(resource_id.into_bytes()));
-        let maybe_resource_id = self
-            .client
+        let maybe_resource_id = client
             .storage()
             .at(None)
             .await?
@@ -77,15 +101,45 @@ impl super::ProposalSigningBackend for DkgProposalSigningBackend {
         // all is good!
         Ok(true)
     }
+}
+
+#[async_trait::async_trait]
+impl<S> super::ProposalSigningBackend for DkgProposalSigningBackend<S>
+where
+    S: QueueStore<TypeErasedStaticTxPayload, Key = SledQueueKey>
+        + Send
+        + Sync
+        + 'static,
+{
+    async fn can_handle_proposal(
+        &self,
+        proposal: &(impl ProposalKindExt + Sync + Send + 'static),
+    ) -> webb_relayer_utils::Result<bool> {
+        let client = self.client().await?;
+        let result = self.is_whitelisted(&client, proposal.header()).await;
+        if result.is_err() {
+            // the node may be lost, so connect again the next time.
+            self.client.lock().await.take();
+        }
+        result
+    }
 
     async fn handle_proposal(
         &self,
-        proposal: &(impl ProposalTrait + Sync + Send + 'static),
+        proposal: &(impl ProposalKindExt + Sync + Send + 'static),
         _metrics: Arc<Mutex<metric::Metrics>>,
     ) -> webb_relayer_utils::Result<()> {
         let my_chain_id_addr =
             RuntimeApi::constants().dkg_proposals().chain_identifier();
-        let my_chain_id = self.client.constants().at(&my_chain_id_addr)?;
+        let client = self.client().await?;
+        let my_chain_id = match client.constants().at(&my_chain_id_addr) {
+            Ok(my_chain_id) => my_chain_id,
+            Err(e) => {
+                // the node may be lost, so connect again the next time.
+                self.client.lock().await.take();
+                return Err(e.into());
+            }
+        };
         let my_chain_id = match my_chain_id {
             TypedChainId::Substrate(chain_id) => chain_id,
             TypedChainId::PolkadotParachain(chain_id) => chain_id,
@@ -100,6 +154,7 @@ impl super::ProposalSigningBackend for DkgProposalSigningBackend {
         let src_chain_id =
             webb_proposals_typed_chain_converter(self.src_chain_id);
         tracing::debug!(
+            kind = %proposal.kind(),
             nonce = nonce.0,
             resource_id = hex::encode(resource_id.into_bytes()),
             src_chain_id = ?self.src_chain_id,
@@ -109,7 +164,7 @@ impl super::ProposalSigningBackend for DkgProposalSigningBackend {
 
         let nonce = Nonce::decode(&mut nonce.encode().as_slice())?;
         let unsigned_proposal = Proposal::Unsigned {
-            kind: ProposalKind::AnchorUpdate,
+            kind: webb_proposals_kind_converter(proposal.kind()),
             data: BoundedVec(proposal.to_vec()),
         };
         let acknowledge_proposal_tx = tx_api.acknowledge_proposal(
@@ -156,6 +211,20 @@ fn webb_proposals_typed_chain_converter(
     }
 }
 
+fn webb_proposals_kind_converter(v: ProposalKind) -> DkgProposalKind {
+    match v {
+        ProposalKind::AnchorUpdate => DkgProposalKind::AnchorUpdate,
+        ProposalKind::TokenAdd => DkgProposalKind::TokenAdd,
+        ProposalKind::TokenRemove => DkgProposalKind::TokenRemove,
+        ProposalKind::WrappingFeeUpdate => DkgProposalKind::WrappingFeeUpdate,
+        ProposalKind::ResourceIdUpdate => DkgProposalKind::ResourceIdUpdate,
+        ProposalKind::SetTreasuryHandler => DkgProposalKind::SetTreasuryHandler,
+        ProposalKind::MaxDepositLimitUpdate => {
+            DkgProposalKind::MaxDepositLimitUpdate
+        }
+    }
+}
+
 pub fn make_acknowledge_proposal_key(data_hash: [u8; 32]) -> [u8; 64] {
     let mut result = [0u8; 64];
     let prefix = b"acknowledge_proposal_fixed_key__";
@@ -0,0 +1,300 @@
+use webb::evm::ethers::types::{Address, H256, U256};
+use webb::evm::ethers::utils::id;
+use webb_proposals::evm;
+use webb_proposals::{
+    FunctionSignature, Nonce, ProposalHeader, ProposalTrait, ResourceId,
+};
+
+use crate::proposal_kind::{ProposalKind, ProposalKindExt};
+
+/// The signature of the function of a token wrapper that adds a token.
+pub const TOKEN_ADD_FUNCTION: &str = "add(address,uint32)";
+/// The signature of the function of a token wrapper that removes a token.
+pub const TOKEN_REMOVE_FUNCTION: &str = "remove(address,uint32)";
+/// The signature of the function of a token wrapper that sets the wrapping fee.
+pub const WRAPPING_FEE_UPDATE_FUNCTION: &str = "setFee(uint16,uint32)";
+/// The signature of the function of the signature bridge that sets a resource.
+pub const RESOURCE_ID_UPDATE_FUNCTION: &str =
+    "adminSetResourceWithSignature(bytes32,bytes4,uint32,bytes32,address,bytes)";
+/// The signature of the function of a treasury that sets its handler.
+pub const SET_TREASURY_HANDLER_FUNCTION: &str = "setHandler(address,uint32)";
+/// The signature of the function of an anchor that sets its maximum deposit.
+pub const MAX_DEPOSIT_LIMIT_FUNCTION: &str =
+    "configureMaximumDepositLimit(uint256,uint32)";
+
+/// A governance action, other than the anchor updates, that the relayer could
+/// originate as a proposal to the `target` resource.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum GovernanceAction {
+    /// Adds the `token` to the token wrapper.
+    #[serde(rename_all = "camelCase")]
+    TokenAdd {
+        /// The token to add.
+        token: Address,
+    },
+    /// Removes the `token` from the token wrapper.
+    #[serde(rename_all = "camelCase")]
+    TokenRemove {
+        /// The token to remove.
+        token: Address,
+    },
+    /// Sets the wrapping fee of the token wrapper.
+    #[serde(rename_all = "camelCase")]
+    WrappingFeeUpdate {
+        /// The new wrapping fee, in percent.
+        fee_percent: u16,
+    },
+    /// Sets the handler of the `new_resource_id` on the signature bridge.
+    #[serde(rename_all = "camelCase")]
+    ResourceIdUpdate {
+        /// The resource id to set.
+        new_resource_id: H256,
+        /// The handler of the resource id.
+        handler: Address,
+    },
+    /// Sets the handler of the treasury.
+    #[serde(rename_all = "camelCase")]
+    SetTreasuryHandler {
+        /// The new handler of the treasury.
+        handler: Address,
+    },
+    /// Sets the maximum deposit limit of the anchor.
+    #[serde(rename_all = "camelCase")]
+    MaxDepositLimitUpdate {
+        /// The new maximum deposit, in the smallest unit of the token.
+        limit: U256,
+    },
+}
+
+impl GovernanceAction {
+    /// The kind of the proposal of this action.
+    pub fn kind(&self) -> ProposalKind {
+        match self {
+            Self::TokenAdd { .. } => ProposalKind::TokenAdd,
+            Self::TokenRemove { .. } => ProposalKind::TokenRemove,
+            Self::WrappingFeeUpdate { .. } => ProposalKind::WrappingFeeUpdate,
+            Self::ResourceIdUpdate { .. } => ProposalKind::ResourceIdUpdate,
+            Self::SetTreasuryHandler { .. } => ProposalKind::SetTreasuryHandler,
+            Self::MaxDepositLimitUpdate { .. } => {
+                ProposalKind::MaxDepositLimitUpdate
+            }
+        }
+    }
+
+    /// The signature of the function the proposal of this action calls.
+    pub fn function(&self) -> &'static str {
+        match self {
+            Self::TokenAdd { .. } => TOKEN_ADD_FUNCTION,
+            Self::TokenRemove { .. } => TOKEN_REMOVE_FUNCTION,
+            Self::WrappingFeeUpdate { .. } => WRAPPING_FEE_UPDATE_FUNCTION,
+            Self::ResourceIdUpdate { .. } => RESOURCE_ID_UPDATE_FUNCTION,
+            Self::SetTreasuryHandler { .. } => SET_TREASURY_HANDLER_FUNCTION,
+            Self::MaxDepositLimitUpdate { .. } => MAX_DEPOSIT_LIMIT_FUNCTION,
+        }
+    }
+
+    /// Creates the proposal of this action to the `target` resource, an EVM
+    /// contract, with the given nonce.
+    #[tracing::instrument(
+        skip_all,
+        fields(
+            proposal_type = %self.kind(),
+            to = ?target.typed_chain_id(),
+            nonce = nonce,
+        )
+    )]
+    pub fn into_proposal(
+        self,
+        target: ResourceId,
+        nonce: u32,
+    ) -> GovernanceProposal {
+        let mut selector = [0u8; 4];
+        selector.copy_from_slice(&id(self.function()));
+        let header = ProposalHeader::new(
+            target,
+            FunctionSignature::from(selector),
+            Nonce::new(nonce),
+        );
+        tracing::debug!("created governance proposal");
+        match self {
+            Self::TokenAdd { token } => GovernanceProposal::TokenAdd(
+                evm::TokenAddProposal::new(header, token.to_fixed_bytes()),
+            ),
+            Self::TokenRemove { token } => GovernanceProposal::TokenRemove(
+                evm::TokenRemoveProposal::new(header, token.to_fixed_bytes()),
+            ),
+            Self::WrappingFeeUpdate { fee_percent } => {
+                GovernanceProposal::WrappingFeeUpdate(
+                    evm::WrappingFeeUpdateProposal::new(header, fee_percent),
+                )
+            }
+            Self::ResourceIdUpdate {
+                new_resource_id,
+                handler,
+            } => GovernanceProposal::ResourceIdUpdate(
+                evm::ResourceIdUpdateProposal::new(
+                    header,
+                    ResourceId::from(new_resource_id.to_fixed_bytes()),
+                    handler.to_fixed_bytes(),
+                ),
+            ),
+            Self::SetTreasuryHandler { handler } => {
+                GovernanceProposal::SetTreasuryHandler(
+                    evm::SetTreasuryHandlerProposal::new(
+                        header,
+                        handler.to_fixed_bytes(),
+                    ),
+                )
+            }
+            Self::MaxDepositLimitUpdate { limit } => {
+                let mut limit_bytes = [0u8; 32];
+                limit.to_big_endian(&mut limit_bytes);
+                GovernanceProposal::MaxDepositLimitUpdate(
+                    evm::MaxDepositLimitProposal::new(header, limit_bytes),
+                )
+            }
+        }
+    }
+}
+
+/// A governance proposal originated by the relayer, see [`GovernanceAction`].
+#[derive(Debug, Clone)]
+pub enum GovernanceProposal {
+    /// Adds a token to a token wrapper.
+    TokenAdd(evm::TokenAddProposal),
+    /// Removes a token from a token wrapper.
+    TokenRemove(evm::TokenRemoveProposal),
+    /// Updates the wrapping fee of a token wrapper.
+    WrappingFeeUpdate(evm::WrappingFeeUpdateProposal),
+    /// Sets the handler of a resource id on the signature bridge.
+    ResourceIdUpdate(evm::ResourceIdUpdateProposal),
+    /// Sets the handler of a treasury.
+    SetTreasuryHandler(evm::SetTreasuryHandlerProposal),
+    /// Updates the maximum deposit limit of an anchor.
+    MaxDepositLimitUpdate(evm::MaxDepositLimitProposal),
+}
+
+impl GovernanceProposal {
+    fn inner(&self) -> &(dyn ProposalTrait + Send + Sync) {
+        match self {
+            Self::TokenAdd(p) => p,
+            Self::TokenRemove(p) => p,
+            Self::WrappingFeeUpdate(p) => p,
+            Self::ResourceIdUpdate(p) => p,
+            Self::SetTreasuryHandler(p) => p,
+            Self::MaxDepositLimitUpdate(p) => p,
+        }
+    }
+}
+
+impl ProposalTrait for GovernanceProposal {
+    fn header(&self) -> ProposalHeader {
+        self.inner().header()
+    }
+
+    fn to_vec(&self) -> Vec<u8> {
+        self.inner().to_vec()
+    }
+}
+
+impl ProposalKindExt for GovernanceProposal {
+    fn kind(&self) -> ProposalKind {
+        match self {
+            Self::TokenAdd(_) => ProposalKind::TokenAdd,
+            Self::TokenRemove(_) => ProposalKind::TokenRemove,
+            Self::WrappingFeeUpdate(_) => ProposalKind::WrappingFeeUpdate,
+            Self::ResourceIdUpdate(_) => ProposalKind::ResourceIdUpdate,
+            Self::SetTreasuryHandler(_) => ProposalKind::SetTreasuryHandler,
+            Self::MaxDepositLimitUpdate(_) => {
+                ProposalKind::MaxDepositLimitUpdate
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::test_utils::*;
+
+    #[test]
+    fn governance_actions_should_make_proposals_of_their_kind() {
+        let target = mock_resourc_id(
+            mock_target_system(Address::random()),
+            mock_typed_chain_id(1),
+        );
+        let token = Address::random();
+        let action: GovernanceAction = serde_json::from_value(
+            serde_json::json!({ "kind": "tokenAdd", "token": token }),
+        )
+        .unwrap();
+        let proposal = action.into_proposal(target, 7);
+        assert_eq!(proposal.kind(), ProposalKind::TokenAdd);
+        assert_eq!(proposal.header().resource_id(), target);
+        assert_eq!(proposal.header().nonce().to_u32(), 7);
+        let bytes = proposal.to_vec();
+        assert_eq!(&bytes[bytes.len() - 20..], token.as_bytes());
+        assert_eq!(&bytes[32..36], &id(TOKEN_ADD_FUNCTION));
+
+        let proposal = GovernanceAction::MaxDepositLimitUpdate {
+            limit: U256::from(1_000),
+        }
+        .into_proposal(target, 8);
+        assert_eq!(proposal.kind(), ProposalKind::MaxDepositLimitUpdate);
+        assert!(proposal.kind().targets_anchor());
+    }
+
+    #[test]
+    fn governance_proposals_should_only_target_governed_contracts() {
+        let chain = mock_typed_chain_id(1);
+        let anchor =
+            mock_resourc_id(mock_target_system(Address::random()), chain);
+        let token_wrapper =
+            mock_resourc_id(mock_target_system(Address::random()), chain);
+        let treasury =
+            mock_resourc_id(mock_target_system(Address::random()), chain);
+        let signature_bridges = [anchor].into_iter().collect();
+        let governed_contracts = crate::GovernedContracts {
+            token_wrappers: [token_wrapper].into_iter().collect(),
+            treasuries: [treasury].into_iter().collect(),
+            ..Default::default()
+        };
+        let is_known = |target, action: GovernanceAction| {
+            crate::is_known_bridge(
+                &signature_bridges,
+                &governed_contracts,
+                &action.into_proposal(target, 1),
+            )
+        };
+        let token = Address::random();
+        let handler = Address::random();
+        assert!(is_known(
+            token_wrapper,
+            GovernanceAction::TokenAdd { token }
+        ));
+        assert!(is_known(
+            treasury,
+            GovernanceAction::SetTreasuryHandler { handler }
+        ));
+        // not to another contract on the same chain, or of another kind.
+        let other =
+            mock_resourc_id(mock_target_system(Address::random()), chain);
+        assert!(!is_known(other, GovernanceAction::TokenAdd { token }));
+        assert!(!is_known(treasury, GovernanceAction::TokenAdd { token }));
+        assert!(!is_known(
+            token_wrapper,
+            GovernanceAction::SetTreasuryHandler { handler }
+        ));
+        // the proposals to the anchors only target the linked anchors.
+        let limit = U256::from(1_000);
+        assert!(is_known(
+            anchor,
+            GovernanceAction::MaxDepositLimitUpdate { limit }
+        ));
+        assert!(!is_known(
+            token_wrapper,
+            GovernanceAction::MaxDepositLimitUpdate { limit }
+        ));
+    }
+}
@@ -18,15 +18,23 @@
 //! ## Overview
 //! This crate contains the proposal signing backends for the relayer.
 //! Proposal signing backends are used to sign the proposals that are generated by the relayer.
-//! There are two types of proposal signing backends:
+//! There are four types of proposal signing backends:
 //! - `MockedProposalSigningBackend`: This is a mocked proposal signing backend that is used for testing purposes.
+//! - `ThresholdProposalSigningBackend`: This signs proposals once `n-of-m` signers of the Governor signed them.
+//! - `AirGappedProposalSigningBackend`: This exports proposals to be signed offline by a cold key.
 //! - `DKGProposalSigningBackend`: This is the actual proposal signing backend that is used in production.
 //!
+//! Besides the anchor updates, the backends sign the other governance proposals (like adding a
+//! token to a token wrapper or setting a treasury handler), classified by their `ProposalKind`,
+//! that the relayer could originate from a `GovernanceAction`.
+//!
 //! This crate also contains the proposal queue that is used to queue the proposals that are generated by the relayer.
 
+use proposal_kind::{ProposalKind, ProposalKindExt};
+use std::collections::HashSet;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use webb_proposals::ProposalTrait;
+use webb_proposals::ResourceId;
 
 /// A module to handle proposals
 #[doc(hidden)]
@@ -38,33 +46,135 @@ pub mod dkg;
 #[doc(hidden)]
 pub mod mocked;
 
+#[doc(hidden)]
+pub mod threshold;
+
+#[doc(hidden)]
+pub mod air_gapped;
+
 /// A module to handle the queue of proposals
 pub mod queue;
 
+/// A module for the kinds of the proposals.
+pub mod proposal_kind;
+
+/// A module for the governance proposals originated by the relayer.
+pub mod governance;
+
+/// A module that Implements the AirGapped Proposal Signing Backend.
+pub use air_gapped::*;
 /// A module that Implements the DKG Proposal Signing Backend.
 pub use dkg::*;
 /// A module that Implements the Mocked Proposal Signing Backend.
 pub use mocked::*;
+/// A module that Implements the Threshold Proposal Signing Backend.
+pub use threshold::*;
 use webb_relayer_utils::metric;
 
 /// A Proposal Signing Backend is responsible for signing proposal `P` where `P` is anything really depending on the
 /// requirement of the user of this backend.
 ///
-/// As of now, we have two implementations of this trait:
+/// As of now, we have four implementations of this trait:
 ///
 /// - `DkgSigningBackend`: This is using the `DKG` protocol to sign the proposal.
 /// - `MockedSigningBackend`: This is using the Governor's `PrivateKey` to sign the proposal directly.
+/// - `ThresholdSigningBackend`: This is using the keys of `m` signers, and signs once `n` of them signed.
+/// - `AirGappedSigningBackend`: This exports the proposal, and imports it once signed offline.
 #[async_trait::async_trait]
 pub trait ProposalSigningBackend {
     /// A method to be called first to check if this backend can handle this proposal or not.
     async fn can_handle_proposal(
         &self,
-        proposal: &(impl ProposalTrait + Sync + Send + 'static),
+        proposal: &(impl ProposalKindExt + Sync + Send + 'static),
     ) -> webb_relayer_utils::Result<bool>;
     /// Send the Unsigned Proposal to the backend to start handling the signing process.
     async fn handle_proposal(
         &self,
-        proposal: &(impl ProposalTrait + Sync + Send + 'static),
+        proposal: &(impl ProposalKindExt + Sync + Send + 'static),
         metrics: Arc<Mutex<metric::Metrics>>,
     ) -> webb_relayer_utils::Result<()>;
 }
+
+#[async_trait::async_trait]
+impl<T> ProposalSigningBackend for Arc<T>
+where
+    T: ProposalSigningBackend + Send + Sync,
+{
+    async fn can_handle_proposal(
+        &self,
+        proposal: &(impl ProposalKindExt + Sync + Send + 'static),
+    ) -> webb_relayer_utils::Result<bool> {
+        self.as_ref().can_handle_proposal(proposal).await
+    }
+
+    async fn handle_proposal(
+        &self,
+        proposal: &(impl ProposalKindExt + Sync + Send + 'static),
+        metrics: Arc<Mutex<metric::Metrics>>,
+    ) -> webb_relayer_utils::Result<()> {
+        self.as_ref().handle_proposal(proposal, metrics).await
+    }
+}
+
+/// The contracts of a bridge, other than its anchors, that the governance
+/// proposals could target.
+#[derive(Debug, Clone, Default)]
+pub struct GovernedContracts {
+    /// The token wrappers, targeted by the token and wrapping fee proposals.
+    pub token_wrappers: HashSet<ResourceId>,
+    /// The treasuries, targeted by the treasury handler proposals.
+    pub treasuries: HashSet<ResourceId>,
+    /// The signature bridges, targeted by the resource id proposals.
+    pub bridges: HashSet<ResourceId>,
+}
+
+impl GovernedContracts {
+    /// Returns `true` if the resource is one of these contracts.
+    pub fn contains(&self, target: &ResourceId) -> bool {
+        self.token_wrappers.contains(target)
+            || self.treasuries.contains(target)
+            || self.bridges.contains(target)
+    }
+
+    /// Returns `true` if a proposal of the given kind could target the resource.
+    ///
+    /// The proposals to the anchors never target one of these contracts.
+    pub fn accepts(&self, kind: ProposalKind, target: &ResourceId) -> bool {
+        match kind {
+            ProposalKind::TokenAdd
+            | ProposalKind::TokenRemove
+            | ProposalKind::WrappingFeeUpdate => {
+                self.token_wrappers.contains(target)
+            }
+            ProposalKind::SetTreasuryHandler => {
+                self.treasuries.contains(target)
+            }
+            ProposalKind::ResourceIdUpdate => self.bridges.contains(target),
+            ProposalKind::AnchorUpdate
+            | ProposalKind::MaxDepositLimitUpdate => false,
+        }
+    }
+}
+
+/// Checks that a proposal could be sent to one of the signature bridges of a
+/// backend that signs for them.
+///
+/// The proposals to the anchors must target one of the linked anchors, while the
+/// other ones must target one of the `governed_contracts` of their kind (like a
+/// token wrapper or a treasury), on the chain of one of the linked anchors.
+pub(crate) fn is_known_bridge(
+    signature_bridges: &HashSet<ResourceId>,
+    governed_contracts: &GovernedContracts,
+    proposal: &impl ProposalKindExt,
+) -> bool {
+    let resource_id = proposal.header().resource_id();
+    let kind = proposal.kind();
+    if kind.targets_anchor() {
+        signature_bridges.contains(&resource_id)
+    } else {
+        governed_contracts.accepts(kind, &resource_id)
+            && signature_bridges
+                .iter()
+                .any(|r| r.typed_chain_id() == resource_id.typed_chain_id())
+    }
+}
@@ -2,16 +2,19 @@ use std::collections::HashSet;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use typed_builder::TypedBuilder;
-use webb::evm::ethers::core::k256::SecretKey;
 use webb::evm::ethers::prelude::*;
 use webb::evm::ethers::utils::keccak256;
-use webb_proposals::{ProposalTrait, ResourceId, TypedChainId};
+use webb_proposals::ResourceId;
 use webb_relayer_store::sled::SledQueueKey;
 use webb_relayer_store::{BridgeCommand, BridgeKey, QueueStore};
-use webb_relayer_types::private_key::PrivateKey;
+use webb_relayer_utils::evm_signer::EvmSigner;
 use webb_relayer_utils::metric;
 
-/// A ProposalSigningBackend that uses the Governor's private key to sign proposals.
+use crate::proposal_kind::ProposalKindExt;
+use crate::GovernedContracts;
+
+/// A ProposalSigningBackend that uses the Governor's key to sign proposals, either
+/// its private key or a remote signer that holds it.
 #[derive(TypedBuilder)]
 pub struct MockedProposalSigningBackend<S>
 where
@@ -20,26 +23,14 @@ where
     /// A map between chain id and its signature bridge system.
     #[builder(setter(into))]
     signature_bridges: HashSet<ResourceId>,
+    /// The other contracts of the bridges, that the governance proposals target.
+    #[builder(default)]
+    governed_contracts: GovernedContracts,
     /// Something that implements the QueueStore trait.
     store: Arc<S>,
-    /// The private key of the governor.
+    /// The signer of the governor, with its private key or a remote signer.
     /// **NOTE**: This must be the same for all signature bridges.
-    private_key: PrivateKey,
-}
-
-impl<S> MockedProposalSigningBackend<S>
-where
-    S: QueueStore<BridgeCommand, Key = SledQueueKey>,
-{
-    fn signer(
-        &self,
-        chain_id: TypedChainId,
-    ) -> webb_relayer_utils::Result<LocalWallet> {
-        let key = SecretKey::from_bytes(self.private_key.as_bytes().into())?;
-        let signer = LocalWallet::from(key)
-            .with_chain_id(chain_id.underlying_chain_id());
-        Ok(signer)
-    }
+    signer: EvmSigner,
 }
 
 #[async_trait::async_trait]
@@ -49,26 +40,28 @@ where
 {
     async fn can_handle_proposal(
         &self,
-        proposal: &(impl ProposalTrait + Sync + Send + 'static),
+        proposal: &(impl ProposalKindExt + Sync + Send + 'static),
     ) -> webb_relayer_utils::Result<bool> {
-        let resource_id = proposal.header().resource_id();
-        let known_bridge = self.signature_bridges.contains(&resource_id);
+        let known_bridge = crate::is_known_bridge(
+            &self.signature_bridges,
+            &self.governed_contracts,
+            proposal,
+        );
         Ok(known_bridge)
     }
 
     async fn handle_proposal(
         &self,
-        proposal: &(impl ProposalTrait + Sync + Send + 'static),
+        proposal: &(impl ProposalKindExt + Sync + Send + 'static),
         metrics: Arc<Mutex<metric::Metrics>>,
     ) -> webb_relayer_utils::Result<()> {
         // Proposal will be signed by active governor/maintainer.
         // Proposal will be then enqueued for execution with BridgeKey as TypedChainId
         let resource_id = proposal.header().resource_id();
         let dest_chain_id = resource_id.typed_chain_id();
-        let signer = self.signer(dest_chain_id)?;
         let proposal_bytes = proposal.to_vec();
         let hash = keccak256(&proposal_bytes);
-        let signature = signer.sign_hash(TxHash(hash))?;
+        let signature = self.signer.sign_hash(TxHash(hash)).await?;
         let bridge_key = BridgeKey::new(dest_chain_id);
         tracing::debug!(
             %bridge_key,
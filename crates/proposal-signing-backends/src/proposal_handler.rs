@@ -12,17 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::proposal_kind::ProposalKindExt;
 use crate::ProposalSigningBackend;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use webb::evm::contract::protocol_solidity::v_anchor_contract;
 use webb::evm::ethers::prelude::EthCall;
-use webb_proposals::ProposalTrait;
 use webb_relayer_utils::metric;
 
 #[tracing::instrument(skip_all)]
 pub async fn handle_proposal<PB>(
-    proposal: &(impl ProposalTrait + Sync + Send + 'static),
+    proposal: &(impl ProposalKindExt + Sync + Send + 'static),
     proposal_signing_backend: &PB,
     metrics: Arc<Mutex<metric::Metrics>>,
 ) -> webb_relayer_utils::Result<()>
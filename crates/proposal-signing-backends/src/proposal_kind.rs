@@ -0,0 +1,70 @@
+use webb_proposals::ProposalTrait;
+
+use crate::queue::QueuedAnchorUpdateProposal;
+
+/// The kinds of the proposals that the relayer could sign.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum ProposalKind {
+    /// Updates the root of a linked anchor.
+    AnchorUpdate,
+    /// Adds a token to a token wrapper.
+    TokenAdd,
+    /// Removes a token from a token wrapper.
+    TokenRemove,
+    /// Updates the wrapping fee of a token wrapper.
+    WrappingFeeUpdate,
+    /// Sets the handler of a resource id on the signature bridge.
+    ResourceIdUpdate,
+    /// Sets the handler of a treasury.
+    SetTreasuryHandler,
+    /// Updates the maximum deposit limit of an anchor.
+    MaxDepositLimitUpdate,
+}
+
+impl ProposalKind {
+    /// Whether the proposal targets an anchor, instead of another contract
+    /// (like a token wrapper or a treasury) of the bridge.
+    pub fn targets_anchor(&self) -> bool {
+        matches!(self, Self::AnchorUpdate | Self::MaxDepositLimitUpdate)
+    }
+}
+
+impl std::fmt::Display for ProposalKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+/// A proposal that knows its [`ProposalKind`], so the signing backends could
+/// classify it.
+pub trait ProposalKindExt: ProposalTrait {
+    /// The kind of the proposal.
+    fn kind(&self) -> ProposalKind;
+}
+
+impl ProposalKindExt for webb_proposals::evm::AnchorUpdateProposal {
+    fn kind(&self) -> ProposalKind {
+        ProposalKind::AnchorUpdate
+    }
+}
+
+impl ProposalKindExt for webb_proposals::substrate::AnchorUpdateProposal {
+    fn kind(&self) -> ProposalKind {
+        ProposalKind::AnchorUpdate
+    }
+}
+
+impl ProposalKindExt for QueuedAnchorUpdateProposal {
+    fn kind(&self) -> ProposalKind {
+        ProposalKind::AnchorUpdate
+    }
+}
@@ -0,0 +1,391 @@
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use webb::evm::ethers::types::{Bytes, H256};
+use webb_proposals::{ProposalTrait, ResourceId};
+use webb_relayer_utils::metric;
+
+use super::{policy::TimeDelayPolicy, ProposalHash, ProposalMetadata};
+use crate::governance::GovernanceProposal;
+use crate::proposal_kind::{ProposalKind, ProposalKindExt};
+use crate::ProposalSigningBackend;
+
+/// A queued proposal, as shown to the operators of the relayer.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedProposalInfo {
+    /// The hash of the full proposal, which identifies it in the queue.
+    pub hash: H256,
+    /// The kind of the proposal.
+    pub kind: ProposalKind,
+    /// The resource id targeted by the proposal.
+    pub resource_id: H256,
+    /// The nonce of the proposal.
+    pub nonce: u32,
+    /// The function signature of the proposal.
+    pub function_signature: Bytes,
+    /// The time at which the proposal was enqueued, in secs since the UNIX epoch.
+    pub queued_at: u64,
+    /// The time at which the proposal should be dequeued, in secs since the UNIX epoch.
+    pub should_be_dequeued_at: Option<u64>,
+}
+
+impl QueuedProposalInfo {
+    fn new<P>(proposal: &P) -> Self
+    where
+        P: ProposalKindExt + ProposalMetadata,
+    {
+        let header = proposal.header();
+        Self {
+            hash: H256(proposal.full_hash()),
+            kind: proposal.kind(),
+            resource_id: H256(header.resource_id().into_bytes()),
+            nonce: header.nonce().to_u32(),
+            function_signature: header
+                .function_signature()
+                .into_bytes()
+                .to_vec()
+                .into(),
+            queued_at: proposal.metadata().queued_at(),
+            should_be_dequeued_at: proposal.metadata().should_be_dequeued_at(),
+        }
+    }
+}
+
+/// The proposals waiting in a queue, along with the current delay of its
+/// [`TimeDelayPolicy`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProposalsQueueInfo {
+    /// The current delay of the time delay policy of the queue, in secs.
+    ///
+    /// `None` if the queue has no time delay policy.
+    pub delay: Option<u64>,
+    /// The queued proposals, in the order they are dequeued.
+    pub proposals: Vec<QueuedProposalInfo>,
+}
+
+/// Inspects and controls a running proposals queue, on behalf of the operators.
+///
+/// The proposals are identified by the hash of the full proposal.
+#[async_trait::async_trait]
+pub trait ProposalsQueueControl: Send + Sync {
+    /// Returns the queued proposals.
+    fn info(&self) -> webb_relayer_utils::Result<ProposalsQueueInfo>;
+
+    /// Removes the proposal from the queue, without signing it.
+    ///
+    /// Returns `false` if the proposal is not in the queue.
+    fn drop_proposal(&self, hash: H256) -> webb_relayer_utils::Result<bool>;
+
+    /// Makes the proposal due now, instead of waiting for the delay of the
+    /// [`TimeDelayPolicy`], the other policies of the queue still apply.
+    ///
+    /// Returns `false` if the proposal is not in the queue.
+    fn expedite(&self, hash: H256) -> webb_relayer_utils::Result<bool>;
+
+    /// Sends the proposal to the signing backend right away, bypassing the
+    /// policies of the queue, then removes it from the queue.
+    ///
+    /// The proposal is kept in the queue if the signing backend fails to handle it.
+    /// Returns `false` if the proposal is not in the queue.
+    async fn resign(&self, hash: H256) -> webb_relayer_utils::Result<bool>;
+
+    /// Sends a governance proposal originated by the operators to the signing
+    /// backend of the queue, without queueing it.
+    ///
+    /// Returns `false` if the signing backend cannot handle it, like when its target
+    /// is not one of the governed contracts of the bridge.
+    async fn propose(
+        &self,
+        proposal: GovernanceProposal,
+    ) -> webb_relayer_utils::Result<bool>;
+}
+
+/// Controls a proposals queue, and the signing backend its proposals are sent to.
+#[derive(typed_builder::TypedBuilder)]
+pub struct ProposalsQueueController<Q, PSB> {
+    /// The controlled queue.
+    queue: Q,
+    /// The time delay policy used with the queue, if any.
+    #[builder(default)]
+    time_delay_policy: Option<TimeDelayPolicy>,
+    /// The signing backend the proposals of the queue are sent to.
+    proposal_signing_backend: Arc<PSB>,
+    /// The metrics of the relayer.
+    metrics: Arc<Mutex<metric::Metrics>>,
+}
+
+#[async_trait::async_trait]
+impl<Q, PSB> ProposalsQueueControl for ProposalsQueueController<Q, PSB>
+where
+    Q: super::ProposalsQueue + Send + Sync,
+    PSB: ProposalSigningBackend + Send + Sync,
+{
+    fn info(&self) -> webb_relayer_utils::Result<ProposalsQueueInfo> {
+        let mut proposals = Vec::new();
+        // go through the whole queue, never matching a proposal.
+        self.queue.find(|p| {
+            proposals.push(QueuedProposalInfo::new(p));
+            false
+        })?;
+        Ok(ProposalsQueueInfo {
+            delay: self
+                .time_delay_policy
+                .as_ref()
+                .map(|policy| policy.delay().as_secs()),
+            proposals,
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn drop_proposal(&self, hash: H256) -> webb_relayer_utils::Result<bool> {
+        let found = self.queue.find(|p| p.full_hash() == hash.0)?.is_some();
+        if found {
+            self.queue.retain(|p| p.full_hash() != hash.0)?;
+            tracing::info!("dropped the proposal from the queue");
+        }
+        Ok(found)
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn expedite(&self, hash: H256) -> webb_relayer_utils::Result<bool> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs();
+        let delay = self
+            .time_delay_policy
+            .as_ref()
+            .map(|policy| policy.delay().as_secs())
+            .unwrap_or_default();
+        let mut found = false;
+        self.queue.modify_in_place(|p| {
+            if p.full_hash() == hash.0 {
+                let metadata = p.metadata();
+                // the time delay policy computes the time to dequeue a proposal
+                // from the time it was queued at, so it stays due if the delay
+                // is adjusted.
+                metadata.set_queued_at(now.saturating_sub(delay));
+                metadata.set_should_be_dequeued_at(now);
+                found = true;
+            }
+            Ok(())
+        })?;
+        if found {
+            tracing::info!("expedited the proposal");
+        }
+        Ok(found)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn resign(&self, hash: H256) -> webb_relayer_utils::Result<bool> {
+        let proposal = match self.queue.find(|p| p.full_hash() == hash.0)? {
+            Some(proposal) => proposal,
+            None => return Ok(false),
+        };
+        crate::proposal_handler::handle_proposal(
+            &proposal,
+            self.proposal_signing_backend.as_ref(),
+            self.metrics.clone(),
+        )
+        .await?;
+        self.queue.retain(|p| p.full_hash() != hash.0)?;
+        tracing::info!("sent the proposal to the signing backend");
+        Ok(true)
+    }
+
+    #[tracing::instrument(skip_all, fields(kind = %proposal.kind()))]
+    async fn propose(
+        &self,
+        proposal: GovernanceProposal,
+    ) -> webb_relayer_utils::Result<bool> {
+        let backend = self.proposal_signing_backend.as_ref();
+        if !backend.can_handle_proposal(&proposal).await? {
+            return Ok(false);
+        }
+        backend
+            .handle_proposal(&proposal, self.metrics.clone())
+            .await?;
+        tracing::info!("sent the governance proposal to the signing backend");
+        Ok(true)
+    }
+}
+
+/// The running proposals queues, by the resource id of the anchor they belong to.
+#[derive(Clone, Default)]
+pub struct ProposalsQueueRegistry {
+    queues: Arc<RwLock<HashMap<ResourceId, Arc<dyn ProposalsQueueControl>>>>,
+}
+
+impl ProposalsQueueRegistry {
+    /// Registers the queue of the given resource, replacing any older one.
+    ///
+    /// The queue stays registered until the returned registration is dropped,
+    /// that is, as long as the queue runs.
+    pub fn insert<C>(
+        &self,
+        resource_id: ResourceId,
+        queue: C,
+    ) -> ProposalsQueueRegistration
+    where
+        C: ProposalsQueueControl + 'static,
+    {
+        let queue: Arc<dyn ProposalsQueueControl> = Arc::new(queue);
+        self.queues.write().insert(resource_id, queue.clone());
+        ProposalsQueueRegistration {
+            registry: self.clone(),
+            resource_id,
+            queue,
+        }
+    }
+
+    /// Returns the queue of the given resource, if any.
+    pub fn get(
+        &self,
+        resource_id: &ResourceId,
+    ) -> Option<Arc<dyn ProposalsQueueControl>> {
+        self.queues.read().get(resource_id).cloned()
+    }
+}
+
+/// Keeps a queue registered in the [`ProposalsQueueRegistry`], until it is dropped.
+#[must_use = "the queue is unregistered as soon as the registration is dropped"]
+pub struct ProposalsQueueRegistration {
+    registry: ProposalsQueueRegistry,
+    resource_id: ResourceId,
+    queue: Arc<dyn ProposalsQueueControl>,
+}
+
+impl Drop for ProposalsQueueRegistration {
+    fn drop(&mut self) {
+        let mut queues = self.registry.queues.write();
+        // the queue could already be replaced by the one of a restarted watcher.
+        let is_ours = queues.get(&self.resource_id).map_or(false, |queue| {
+            std::ptr::eq(
+                Arc::as_ptr(queue) as *const (),
+                Arc::as_ptr(&self.queue) as *const (),
+            )
+        });
+        if is_ours {
+            queues.remove(&self.resource_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::test_utils::*;
+    use crate::queue::{mem::InMemoryProposalsQueue, ProposalsQueue};
+    use std::sync::atomic;
+    use webb::evm::ethers;
+
+    #[tokio::test]
+    async fn should_drop_expedite_and_resign_queued_proposals() {
+        let _guard = setup_tracing();
+        let policy = TimeDelayPolicy::builder()
+            .initial_delay(60)
+            .min_delay(60)
+            .max_delay(60)
+            .build();
+        let queue = InMemoryProposalsQueue::new();
+        let backend = Arc::new(DummySigningBackend::default());
+        let controller = ProposalsQueueController::builder()
+            .queue(queue.clone())
+            .time_delay_policy(Some(policy.clone()))
+            .proposal_signing_backend(backend.clone())
+            .metrics(mock_metrics())
+            .build();
+
+        let target_system = mock_target_system(ethers::types::Address::zero());
+        let r_id = mock_resourc_id(target_system, mock_typed_chain_id(1));
+        let src_system = mock_target_system(ethers::types::Address::zero());
+        let src_r_id = mock_resourc_id(src_system, mock_typed_chain_id(42));
+        for nonce in 1..=3 {
+            let header = mock_proposal_header(r_id, nonce);
+            let proposal = mock_evm_anchor_update_proposal(header, src_r_id);
+            queue.enqueue(proposal, policy.clone()).unwrap();
+        }
+
+        let info = controller.info().unwrap();
+        assert_eq!(info.delay, Some(60));
+        assert_eq!(
+            info.proposals.iter().map(|p| p.nonce).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(info.proposals[0].kind, ProposalKind::AnchorUpdate);
+        assert_eq!(info.proposals[0].resource_id, H256(r_id.into_bytes()));
+        assert_eq!(info.proposals[0].function_signature.to_vec(), [0x42; 4]);
+        let [first, second, third] = [0, 1, 2].map(|i| info.proposals[i].hash);
+
+        assert!(controller.drop_proposal(first).unwrap());
+        assert!(!controller.drop_proposal(first).unwrap());
+        assert_eq!(queue.len().unwrap(), 2);
+
+        // nothing is due yet.
+        assert!(queue.dequeue(policy.clone()).unwrap().is_none());
+        assert!(controller.expedite(second).unwrap());
+        // the proposals that are not due are pushed back to the queue.
+        let proposal = (0..2)
+            .find_map(|_| queue.dequeue(policy.clone()).unwrap())
+            .unwrap();
+        assert_eq!(proposal.full_hash(), second.0);
+
+        assert!(controller.resign(third).await.unwrap());
+        assert!(!controller.resign(third).await.unwrap());
+        assert_eq!(
+            backend
+                .handled_proposals_count
+                .load(atomic::Ordering::SeqCst),
+            1
+        );
+        assert!(queue.is_empty().unwrap());
+
+        // the governance proposals are sent right away, without being queued.
+        let action = crate::governance::GovernanceAction::TokenAdd {
+            token: ethers::types::Address::random(),
+        };
+        assert!(controller
+            .propose(action.into_proposal(r_id, 1))
+            .await
+            .unwrap());
+        assert_eq!(
+            backend
+                .handled_proposals_count
+                .load(atomic::Ordering::SeqCst),
+            2
+        );
+        assert!(queue.is_empty().unwrap());
+    }
+
+    #[test]
+    fn registry_should_unregister_stopped_queues() {
+        let registry = ProposalsQueueRegistry::default();
+        let controller = || {
+            ProposalsQueueController::builder()
+                .queue(InMemoryProposalsQueue::new())
+                .proposal_signing_backend(Arc::new(
+                    DummySigningBackend::default(),
+                ))
+                .metrics(mock_metrics())
+                .build()
+        };
+        let target_system = mock_target_system(ethers::types::Address::zero());
+        let r_id = mock_resourc_id(target_system, mock_typed_chain_id(1));
+
+        let registration = registry.insert(r_id, controller());
+        assert!(registry.get(&r_id).is_some());
+        drop(registration);
+        assert!(registry.get(&r_id).is_none());
+
+        // the queue of a restarted watcher is kept when the older one stops.
+        let old = registry.insert(r_id, controller());
+        let new = registry.insert(r_id, controller());
+        drop(old);
+        assert!(registry.get(&r_id).is_some());
+        drop(new);
+        assert!(registry.get(&r_id).is_none());
+    }
+}
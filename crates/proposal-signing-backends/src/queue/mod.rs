@@ -5,16 +5,22 @@ use webb::evm::ethers;
 use webb_proposals::ProposalTrait;
 use webb_relayer_utils::metric;
 
+use crate::proposal_kind::ProposalKindExt;
+
+/// A module to inspect and control the running Proposals Queues.
+pub mod control;
 /// A module for in-memory Proposals Queue.
 pub mod mem;
 /// A module for Proposals Polices.
 pub mod policy;
+/// A module for store backed Proposals Queue.
+pub mod store;
 
 /// A Proposal Queue is a simple Queue that holds the proposals that are going to be
 /// signed or already signed and need to be sent to the target system to be executed.
 pub trait ProposalsQueue {
     /// The proposal type associated with the Proposal Queue.
-    type Proposal: ProposalTrait + ProposalMetadata + Send + Sync + 'static;
+    type Proposal: ProposalKindExt + ProposalMetadata + Send + Sync + 'static;
 
     /// Enqueues a proposal into the queue with the specified policy.
     ///
@@ -149,6 +155,29 @@ pub trait ProposalsQueue {
     ) -> webb_relayer_utils::Result<Option<Self::Proposal>>
     where
         F: FnMut(&Self::Proposal) -> bool;
+
+    /// Acknowledges that a dequeued proposal was handled by the signing backend.
+    ///
+    /// A queue that persists its proposals keeps a dequeued proposal until it is
+    /// acknowledged, so it is not lost if the relayer stops before handling it.
+    /// By default, nothing is kept once a proposal is dequeued.
+    fn acknowledge(
+        &self,
+        _proposal: &Self::Proposal,
+    ) -> webb_relayer_utils::Result<()> {
+        Ok(())
+    }
+
+    /// Releases a dequeued proposal that the signing backend failed to handle.
+    ///
+    /// A queue that persists its proposals puts it back at the end of the queue,
+    /// so it is handled again later. By default, the proposal is dropped.
+    fn release(
+        &self,
+        _proposal: Self::Proposal,
+    ) -> webb_relayer_utils::Result<()> {
+        Ok(())
+    }
 }
 
 /// Associated metadata for a queued proposal.
@@ -183,6 +212,12 @@ impl QueuedProposalMetadata {
         self.queued_at.load(atomic::Ordering::SeqCst)
     }
 
+    /// Set the time at which the proposal was enqueued.
+    /// The value is the number of secs since the UNIX epoch.
+    pub fn set_queued_at(&self, queued_at: u64) {
+        self.queued_at.store(queued_at, atomic::Ordering::SeqCst);
+    }
+
     /// Set the time at which the proposal should be dequeued.
     /// The value is the number of secs since the UNIX epoch.
     pub fn set_should_be_dequeued_at(&self, should_be_dequeued_at: u64) {
@@ -327,6 +362,12 @@ pub async fn run<Queue, Policy, PSB>(
                     proposal = ?hex::encode(proposal.to_vec()),
                     "the proposal was successfully handled by the signing backend"
                 );
+                if let Err(e) = queue.acknowledge(&proposal) {
+                    tracing::error!(
+                        "Failed to acknowledge the proposal: {:?}",
+                        e
+                    );
+                }
             }
             Err(e) => {
                 tracing::error!(
@@ -334,6 +375,12 @@ pub async fn run<Queue, Policy, PSB>(
                     proposal = ?hex::encode(proposal.to_vec()),
                     "failed to handle the proposal",
                 );
+                if let Err(e) = queue.release(proposal) {
+                    tracing::error!("Failed to release the proposal: {:?}", e);
+                }
+                // the signing backend may be down, so do not retry right away.
+                tokio::time::sleep(core::time::Duration::from_millis(1100))
+                    .await;
             }
         }
     }
@@ -415,13 +462,13 @@ pub(crate) mod test_utils {
     impl crate::ProposalSigningBackend for DummySigningBackend {
         async fn can_handle_proposal(
             &self,
-            _proposal: &(impl ProposalTrait + Sync + Send + 'static),
+            _proposal: &(impl ProposalKindExt + Sync + Send + 'static),
         ) -> webb_relayer_utils::Result<bool> {
             Ok(true)
         }
         async fn handle_proposal(
             &self,
-            proposal: &(impl ProposalTrait + Sync + Send + 'static),
+            proposal: &(impl ProposalKindExt + Sync + Send + 'static),
             _metrics: Arc<Mutex<metric::Metrics>>,
         ) -> webb_relayer_utils::Result<()> {
             tracing::debug!(
@@ -0,0 +1,236 @@
+use parking_lot::RwLock;
+use std::{
+    collections::{BTreeSet, HashMap},
+    future::Future,
+    sync::Arc,
+    time::Duration,
+};
+use webb::evm::ethers::types::U256;
+use webb_proposals::{ProposalTrait, TypedChainId};
+
+use crate::queue::{ProposalHash, ProposalMetadata, ProposalsQueue};
+
+/// Maximum staleness in seconds
+pub const GAS_PRICE_MAX_STALENESS: u64 = 3600;
+/// Interval between the refreshes of the gas prices, in seconds
+pub const GAS_PRICE_REFRESH_INTERVAL: u64 = 30;
+
+/// A policy that defers the proposals while the gas price of their destination
+/// chain is above a ceiling.
+///
+/// The policy is checked synchronously, hence it does not fetch the gas prices
+/// itself: they are refreshed with [`GasPricePolicy::run`] for the chains of the
+/// proposals it has seen. A proposal is not deferred if the gas price of its chain
+/// is not known (yet), nor once it has been queued for longer than the maximum
+/// staleness, so a proposal is never stuck for good behind a high gas price.
+///
+/// Only the proposals to EVM chains are deferred, and the policy is meant to be used
+/// in the dequeue operation, since it would reject the proposals on enqueue.
+///
+/// # Example
+///
+/// ```rust
+/// # use webb_proposal_signing_backends::queue::policy::GasPricePolicy;
+/// // Defer the proposals while the gas price is above 50 gwei, for up to an hour.
+/// let policy = GasPricePolicy::builder()
+///     .max_gas_price(50_000_000_000u64)
+///     .max_staleness(3600)
+///     .build();
+/// assert_eq!(policy.gas_price(1), None);
+///```
+#[derive(Debug, Clone, typed_builder::TypedBuilder)]
+pub struct GasPricePolicy {
+    /// Maximum gas price in wei
+    #[builder(setter(into))]
+    max_gas_price: U256,
+    /// Maximum staleness in seconds
+    #[builder(default = GAS_PRICE_MAX_STALENESS)]
+    max_staleness: u64,
+    /// The last known gas prices, by the chain id of the EVM chains
+    #[builder(setter(skip), default)]
+    gas_prices: Arc<RwLock<HashMap<u32, U256>>>,
+    /// The EVM chains of the proposals seen by the policy
+    #[builder(setter(skip), default)]
+    chains: Arc<RwLock<BTreeSet<u32>>>,
+}
+
+impl GasPricePolicy {
+    /// Returns the last known gas price of the given EVM chain.
+    pub fn gas_price(&self, chain_id: u32) -> Option<U256> {
+        self.gas_prices.read().get(&chain_id).copied()
+    }
+
+    /// Sets the gas price of the given EVM chain, or forgets it if it is unknown.
+    pub fn set_gas_price(&self, chain_id: u32, gas_price: Option<U256>) {
+        let mut gas_prices = self.gas_prices.write();
+        match gas_price {
+            Some(gas_price) => gas_prices.insert(chain_id, gas_price),
+            None => gas_prices.remove(&chain_id),
+        };
+    }
+
+    /// Refreshes the gas prices of the chains of the proposals seen by the policy,
+    /// every [`GAS_PRICE_REFRESH_INTERVAL`] seconds, with the given function.
+    ///
+    /// This function will loop forever and should be run in a separate task.
+    #[tracing::instrument(skip_all)]
+    pub async fn run<F, Fut>(self, fetch_gas_price: F)
+    where
+        F: Fn(u32) -> Fut,
+        Fut: Future<Output = webb_relayer_utils::Result<U256>>,
+    {
+        loop {
+            let chains = self.chains.read().clone();
+            for chain_id in chains {
+                match fetch_gas_price(chain_id).await {
+                    Ok(gas_price) => {
+                        tracing::trace!(chain_id, %gas_price, "Gas price");
+                        self.set_gas_price(chain_id, Some(gas_price));
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            chain_id,
+                            "Failed to fetch the gas price: {}",
+                            e
+                        );
+                        // do not defer the proposals on a stale gas price.
+                        self.set_gas_price(chain_id, None);
+                    }
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(GAS_PRICE_REFRESH_INTERVAL))
+                .await;
+        }
+    }
+}
+
+impl super::ProposalPolicy for GasPricePolicy {
+    #[tracing::instrument(
+        skip_all
+        fields(
+            proposal_hash = hex::encode(proposal.full_hash()),
+            proposal_queued_at = proposal.metadata().queued_at(),
+        )
+    )]
+    fn check<Q: ProposalsQueue>(
+        &self,
+        proposal: &Q::Proposal,
+        _queue: &Q,
+    ) -> webb_relayer_utils::Result<()> {
+        let resource_id = proposal.header().resource_id();
+        let chain_id = match resource_id.typed_chain_id() {
+            TypedChainId::Evm(chain_id) => chain_id,
+            _ => return Ok(()),
+        };
+        self.chains.write().insert(chain_id);
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs();
+        let queued_for = now.saturating_sub(proposal.metadata().queued_at());
+        if queued_for >= self.max_staleness {
+            tracing::trace!(
+                queued_for,
+                self.max_staleness,
+                "Proposal is stale, ignoring the gas price",
+            );
+            return Ok(());
+        }
+        match self.gas_price(chain_id) {
+            Some(gas_price) if gas_price > self.max_gas_price => {
+                tracing::trace!(
+                    chain_id,
+                    %gas_price,
+                    max_gas_price = %self.max_gas_price,
+                    queued_for,
+                    "Gas price is too high, deferring proposal",
+                );
+                Err(webb_relayer_utils::Error::Generic(
+                    "Gas price is above the ceiling",
+                ))
+            }
+            gas_price => {
+                tracing::trace!(
+                    chain_id,
+                    gas_price = ?gas_price,
+                    "Gas price is below the ceiling, or unknown",
+                );
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::policy::ProposalPolicy;
+    use crate::queue::{mem::InMemoryProposalsQueue, test_utils::*};
+    use webb::evm::ethers;
+
+    type TestQueue = InMemoryProposalsQueue;
+
+    #[test]
+    fn should_defer_proposals_while_the_gas_price_is_too_high() {
+        let _guard = setup_tracing();
+        let policy = GasPricePolicy::builder()
+            .max_gas_price(100u64)
+            .max_staleness(60)
+            .build();
+        let queue = TestQueue::new();
+        let target_system = mock_target_system(ethers::types::Address::zero());
+        let r_id = mock_resourc_id(target_system, mock_typed_chain_id(1));
+        let src_system = mock_target_system(ethers::types::Address::zero());
+        let src_r_id = mock_resourc_id(src_system, mock_typed_chain_id(42));
+        let header = mock_proposal_header(r_id, 1);
+        let proposal = mock_evm_anchor_update_proposal(header, src_r_id);
+
+        // the gas price is not known yet.
+        assert!(policy.check(&proposal, &queue).is_ok());
+        assert_eq!(*policy.chains.read(), BTreeSet::from([1]));
+
+        policy.set_gas_price(1, Some(U256::from(101)));
+        assert!(policy.check(&proposal, &queue).is_err());
+        policy.set_gas_price(1, Some(U256::from(100)));
+        assert!(policy.check(&proposal, &queue).is_ok());
+
+        // a stale proposal is sent anyway.
+        policy.set_gas_price(1, Some(U256::from(101)));
+        let queued_at = proposal.metadata().queued_at();
+        proposal
+            .metadata()
+            .queued_at
+            .store(queued_at - 60, std::sync::atomic::Ordering::SeqCst);
+        assert!(policy.check(&proposal, &queue).is_ok());
+    }
+
+    #[test]
+    fn should_compose_with_the_time_delay_policy() {
+        let _guard = setup_tracing();
+        let gas_price_policy =
+            GasPricePolicy::builder().max_gas_price(100u64).build();
+        let time_delay_policy = super::super::TimeDelayPolicy::builder()
+            .initial_delay(0)
+            .min_delay(0)
+            .max_delay(0)
+            .build();
+        let queue = TestQueue::new();
+        let target_system = mock_target_system(ethers::types::Address::zero());
+        let r_id = mock_resourc_id(target_system, mock_typed_chain_id(1));
+        let src_system = mock_target_system(ethers::types::Address::zero());
+        let src_r_id = mock_resourc_id(src_system, mock_typed_chain_id(42));
+        let header = mock_proposal_header(r_id, 1);
+        let proposal = mock_evm_anchor_update_proposal(header, src_r_id);
+        queue.enqueue(proposal, time_delay_policy.clone()).unwrap();
+
+        gas_price_policy.set_gas_price(1, Some(U256::from(200)));
+        let dequeue_policy = (time_delay_policy, gas_price_policy.clone());
+        let proposal = queue.dequeue(dequeue_policy.clone()).unwrap();
+        assert!(proposal.is_none(), "should defer the proposal");
+        assert_eq!(queue.len().unwrap(), 1);
+
+        gas_price_policy.set_gas_price(1, Some(U256::from(50)));
+        let proposal = queue.dequeue(dequeue_policy).unwrap();
+        assert!(proposal.is_some(), "should dequeue the proposal");
+    }
+}
@@ -1,6 +1,8 @@
+mod gas_price;
 mod nonce;
 mod time;
 
+pub use gas_price::*;
 pub use nonce::*;
 pub use time::*;
 
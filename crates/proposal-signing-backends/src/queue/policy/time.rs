@@ -65,7 +65,42 @@ pub struct TimeDelayPolicy {
     current_delay: Arc<atomic::AtomicU64>,
 }
 
+/// The state of a [`TimeDelayPolicy`], that is saved with the proposals queue
+/// so the policy does not start over from its initial delay after a restart.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TimeDelayPolicyState {
+    /// The sliding window of delays, in seconds.
+    pub delays: Vec<u64>,
+    /// The current delay, in seconds.
+    pub current_delay: u64,
+}
+
 impl TimeDelayPolicy {
+    /// Returns the current state of the policy.
+    pub fn state(&self) -> TimeDelayPolicyState {
+        TimeDelayPolicyState {
+            delays: self.delays.lock().clone(),
+            current_delay: self.current_delay.load(atomic::Ordering::Relaxed),
+        }
+    }
+
+    /// Restores a state of the policy, returned by [`Self::state`].
+    ///
+    /// If the window size changed meanwhile, only the most recent delays are kept,
+    /// or the window is filled up with the initial delay.
+    pub fn restore(&self, state: TimeDelayPolicyState) {
+        let mut delays = state.delays;
+        if delays.len() > self.window_size {
+            delays.drain(..delays.len() - self.window_size);
+        }
+        let missing = self.window_size - delays.len();
+        delays
+            .splice(0..0, std::iter::repeat(self.initial_delay).take(missing));
+        *self.delays.lock() = delays;
+        self.current_delay
+            .store(state.current_delay, atomic::Ordering::Relaxed);
+    }
+
     /// Updates the current delay based on the average delay
     /// returns true if the delay was updated
     /// otherwise returns false, indecating that the delay was not updated
@@ -313,6 +348,38 @@ mod tests {
         assert_eq!(policy.delay(), Duration::from_secs(2 * INITIAL_DELAY));
     }
 
+    #[test]
+    fn should_restore_the_state_with_another_window_size() {
+        let policy = TimeDelayPolicy::builder()
+            .initial_delay(10)
+            .min_delay(5)
+            .max_delay(100)
+            .window_size(3)
+            .build();
+        policy.update_delay(2).unwrap();
+        policy.update_delay(3).unwrap();
+        let state = policy.state();
+        assert_eq!(state.delays, vec![10, 10, 20]);
+        assert_eq!(state.current_delay, 39);
+
+        let restored = TimeDelayPolicy::builder()
+            .initial_delay(10)
+            .min_delay(5)
+            .max_delay(100)
+            .window_size(3)
+            .build();
+        restored.restore(state.clone());
+        assert_eq!(restored.state(), state);
+        assert_eq!(restored.delay(), Duration::from_secs(39));
+
+        let smaller = TimeDelayPolicy::builder().window_size(2).build();
+        smaller.restore(state.clone());
+        assert_eq!(smaller.state().delays, vec![10, 20]);
+        let larger = TimeDelayPolicy::builder().window_size(4).build();
+        larger.restore(state);
+        assert_eq!(larger.state().delays, vec![INITIAL_DELAY, 10, 10, 20]);
+    }
+
     #[test]
     fn should_dequeue_proposal_at_the_right_time() {
         let _guard = setup_tracing();
@@ -0,0 +1,477 @@
+use parking_lot::{Mutex, RwLock};
+use std::collections::VecDeque;
+use std::sync::{atomic, Arc};
+use webb::evm::ethers::types::Bytes;
+use webb_proposals::{ProposalHeader, ProposalTrait};
+use webb_relayer_store::ProposalsQueueStore;
+
+use super::{
+    policy::*, ProposalHash, QueuedAnchorUpdateProposal, QueuedProposalMetadata,
+};
+use crate::PROPOSAL_HEADER_LENGTH;
+
+/// The state of a [`StoreProposalsQueue`] that is saved in the store.
+#[derive(
+    Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct ProposalsQueueState {
+    proposals: Vec<StoredProposal>,
+    time_delay_policy: Option<TimeDelayPolicyState>,
+}
+
+impl ProposalsQueueState {
+    /// Returns `true` if both states hold the same proposals and policy state,
+    /// maybe in another order.
+    fn is_reordering_of(&self, other: &Self) -> bool {
+        self.time_delay_policy == other.time_delay_policy
+            && self.proposals.len() == other.proposals.len()
+            && self.proposals.iter().all(|p| other.proposals.contains(p))
+    }
+}
+
+/// A queued proposal, with its metadata, as saved in the store.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredProposal {
+    proposal: Bytes,
+    queued_at: u64,
+    should_be_dequeued_at: Option<u64>,
+}
+
+impl From<&QueuedAnchorUpdateProposal> for StoredProposal {
+    fn from(proposal: &QueuedAnchorUpdateProposal) -> Self {
+        Self {
+            proposal: proposal.to_vec().into(),
+            queued_at: proposal.metadata.queued_at(),
+            should_be_dequeued_at: proposal.metadata.should_be_dequeued_at(),
+        }
+    }
+}
+
+impl TryFrom<StoredProposal> for QueuedAnchorUpdateProposal {
+    type Error = webb_relayer_utils::Error;
+
+    fn try_from(stored: StoredProposal) -> Result<Self, Self::Error> {
+        let bytes = stored.proposal.to_vec();
+        let header: [u8; PROPOSAL_HEADER_LENGTH] = bytes
+            .get(..PROPOSAL_HEADER_LENGTH)
+            .and_then(|header| header.try_into().ok())
+            .ok_or(webb_relayer_utils::Error::Generic(
+                "stored proposal is shorter than its header",
+            ))?;
+        let metadata = QueuedProposalMetadata {
+            queued_at: Arc::new(atomic::AtomicU64::new(stored.queued_at)),
+            should_be_dequeued_at: Arc::new(atomic::AtomicU64::new(
+                stored.should_be_dequeued_at.unwrap_or_default(),
+            )),
+        };
+        Ok(Self {
+            inner: Arc::new(RestoredProposal {
+                header: ProposalHeader::from(header),
+                bytes,
+            }),
+            metadata,
+        })
+    }
+}
+
+/// A proposal restored from the store, as its raw bytes.
+struct RestoredProposal {
+    header: ProposalHeader,
+    bytes: Vec<u8>,
+}
+
+impl ProposalTrait for RestoredProposal {
+    fn header(&self) -> ProposalHeader {
+        self.header
+    }
+
+    fn to_vec(&self) -> Vec<u8> {
+        self.bytes.clone()
+    }
+}
+
+/// Store backed implementation of the proposals queue.
+///
+/// The proposals are kept in memory, and every change to the queue is saved to
+/// the store, along with the state of the [`TimeDelayPolicy`] of the queue, so
+/// the queue is restored as it was after a restart.
+///
+/// A proposal that is not due yet is put back at the end of the queue when it is
+/// dequeued, so the other ones are not held back by it. Only the order of the
+/// queue changes then, which is not saved, so polling the queue does not write to
+/// the store; the proposals may be restored in another order after a restart.
+///
+/// A dequeued proposal stays in the store until the signing backend handled it
+/// and it is [acknowledged](super::ProposalsQueue::acknowledge), so it is
+/// restored at the front of the queue if the relayer stops before. If the signing
+/// backend fails to handle it, it is [released](super::ProposalsQueue::release)
+/// back at the end of the queue.
+///
+/// Only the anchor updates of the evm anchors are queued, the substrate anchors
+/// send their anchor updates to the signing backend right away, so they do not
+/// need it.
+#[derive(Clone)]
+pub struct StoreProposalsQueue<S> {
+    store: Arc<S>,
+    queue_key: String,
+    proposals: Arc<RwLock<VecDeque<QueuedAnchorUpdateProposal>>>,
+    /// The dequeued proposals that are not acknowledged yet.
+    in_flight: Arc<RwLock<Vec<QueuedAnchorUpdateProposal>>>,
+    time_delay_policy: Option<TimeDelayPolicy>,
+    /// The last state saved in the store, also held while saving.
+    last_saved: Arc<Mutex<Option<ProposalsQueueState>>>,
+}
+
+impl<S> std::fmt::Debug for StoreProposalsQueue<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StoreProposalsQueue")
+            .field("queue_key", &self.queue_key)
+            .field("proposals", &self.proposals)
+            .field("in_flight", &self.in_flight)
+            .finish()
+    }
+}
+
+impl<S> StoreProposalsQueue<S>
+where
+    S: ProposalsQueueStore<ProposalsQueueState>,
+{
+    /// Creates a new `StoreProposalsQueue` that will save the proposals in the
+    /// store under the given key, restoring the ones saved before.
+    pub fn new(
+        store: Arc<S>,
+        queue_key: impl Into<String>,
+    ) -> webb_relayer_utils::Result<Self> {
+        let queue_key = queue_key.into();
+        let state = store.get_proposals_queue(&queue_key)?;
+        let proposals = state
+            .iter()
+            .flat_map(|state| state.proposals.iter().cloned())
+            .map(QueuedAnchorUpdateProposal::try_from)
+            .collect::<webb_relayer_utils::Result<VecDeque<_>>>()?;
+        tracing::debug!(
+            %queue_key,
+            proposals = proposals.len(),
+            "restored the proposals queue from the store",
+        );
+        Ok(Self {
+            store,
+            queue_key,
+            proposals: Arc::new(RwLock::new(proposals)),
+            in_flight: Default::default(),
+            time_delay_policy: None,
+            last_saved: Arc::new(Mutex::new(state)),
+        })
+    }
+
+    /// Saves the state of the given policy with the queue, restoring the state
+    /// that was saved before, if any.
+    ///
+    /// The policy must be the one (or a clone of the one) used with the queue.
+    pub fn with_time_delay_policy(mut self, policy: TimeDelayPolicy) -> Self {
+        let saved = self
+            .last_saved
+            .lock()
+            .as_ref()
+            .and_then(|state| state.time_delay_policy.clone());
+        if let Some(saved) = saved {
+            tracing::debug!(
+                queue_key = %self.queue_key,
+                delay = saved.current_delay,
+                "restored the time delay policy from the store",
+            );
+            policy.restore(saved);
+        }
+        self.time_delay_policy = Some(policy);
+        self
+    }
+
+    /// Saves the current state of the queue to the store, if it changed more than
+    /// the order of its proposals.
+    ///
+    /// The proposals in flight are saved first, so they are restored at the front
+    /// of the queue.
+    fn save(&self) -> webb_relayer_utils::Result<()> {
+        let mut last_saved = self.last_saved.lock();
+        let state = ProposalsQueueState {
+            proposals: self
+                .in_flight
+                .read()
+                .iter()
+                .chain(self.proposals.read().iter())
+                .map(Into::into)
+                .collect(),
+            time_delay_policy: self
+                .time_delay_policy
+                .as_ref()
+                .map(TimeDelayPolicy::state),
+        };
+        let unchanged = last_saved
+            .as_ref()
+            .map_or(false, |saved| saved.is_reordering_of(&state));
+        if unchanged {
+            return Ok(());
+        }
+        self.store
+            .save_proposals_queue(&self.queue_key, state.clone())?;
+        *last_saved = Some(state);
+        Ok(())
+    }
+}
+
+impl<S> super::ProposalsQueue for StoreProposalsQueue<S>
+where
+    S: ProposalsQueueStore<ProposalsQueueState>,
+{
+    type Proposal = QueuedAnchorUpdateProposal;
+
+    #[tracing::instrument(
+        skip_all,
+        fields(
+            queue = "store",
+            proposal = hex::encode(proposal.full_hash()),
+        )
+    )]
+    fn enqueue<Policy: ProposalPolicy>(
+        &self,
+        proposal: Self::Proposal,
+        policy: Policy,
+    ) -> webb_relayer_utils::Result<()> {
+        let accepted = policy.check(&proposal, self);
+        tracing::trace!(accepted = ?accepted, "proposal check result");
+        if let Err(e) = accepted {
+            // the policy could have changed the queue, even if it rejected it.
+            self.save()?;
+            return Err(e);
+        }
+        self.proposals.write().push_back(proposal);
+        self.save()
+    }
+
+    #[tracing::instrument(skip_all, fields(queue = "store"))]
+    fn dequeue<Policy: ProposalPolicy>(
+        &self,
+        policy: Policy,
+    ) -> webb_relayer_utils::Result<Option<Self::Proposal>> {
+        let proposal = match self.proposals.write().pop_front() {
+            Some(proposal) => proposal,
+            None => {
+                tracing::trace!("no proposals to dequeue");
+                return Ok(None);
+            }
+        };
+        let dequeued = match policy.check(&proposal, self) {
+            Ok(_) => {
+                tracing::trace!(
+                    proposal = hex::encode(proposal.full_hash()),
+                    "proposal passed policy check before dequeue",
+                );
+                // kept until the signing backend handled it.
+                self.in_flight.write().push(proposal.clone());
+                Some(proposal)
+            }
+            Err(e) => {
+                tracing::trace!(
+                    reason = %e,
+                    "proposal failed policy check before dequeue",
+                );
+                // push back the proposal if it failed the policy check
+                // so that it can be dequeued again later
+                self.proposals.write().push_back(proposal);
+                // the caller should try again later
+                None
+            }
+        };
+        self.save()?;
+        Ok(dequeued)
+    }
+
+    fn len(&self) -> webb_relayer_utils::Result<usize> {
+        let len = self.proposals.read().len();
+        Ok(len)
+    }
+
+    fn is_empty(&self) -> webb_relayer_utils::Result<bool> {
+        Ok(self.proposals.read().is_empty())
+    }
+
+    fn clear(&self) -> webb_relayer_utils::Result<()> {
+        self.proposals.write().clear();
+        self.save()
+    }
+
+    fn retain<F>(&self, f: F) -> webb_relayer_utils::Result<()>
+    where
+        F: FnMut(&Self::Proposal) -> bool,
+    {
+        self.proposals.write().retain(f);
+        self.save()
+    }
+
+    fn modify_in_place<F>(&self, f: F) -> webb_relayer_utils::Result<()>
+    where
+        F: FnMut(&mut Self::Proposal) -> webb_relayer_utils::Result<()>,
+    {
+        let result = self.proposals.write().iter_mut().try_for_each(f);
+        self.save()?;
+        result
+    }
+
+    fn find<F>(
+        &self,
+        mut f: F,
+    ) -> webb_relayer_utils::Result<Option<Self::Proposal>>
+    where
+        F: FnMut(&Self::Proposal) -> bool,
+    {
+        let v = self.proposals.read().iter().find(|v| f(v)).cloned();
+        Ok(v)
+    }
+
+    fn acknowledge(
+        &self,
+        proposal: &Self::Proposal,
+    ) -> webb_relayer_utils::Result<()> {
+        let hash = proposal.full_hash();
+        self.in_flight.write().retain(|p| p.full_hash() != hash);
+        self.save()
+    }
+
+    fn release(
+        &self,
+        proposal: Self::Proposal,
+    ) -> webb_relayer_utils::Result<()> {
+        let hash = proposal.full_hash();
+        self.in_flight.write().retain(|p| p.full_hash() != hash);
+        self.proposals.write().push_back(proposal);
+        self.save()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::test_utils::*;
+    use crate::queue::ProposalsQueue;
+    use webb::evm::ethers;
+    use webb_relayer_store::InMemoryStore;
+
+    #[test]
+    fn should_restore_the_queue_and_its_policy_from_the_store() {
+        let _guard = setup_tracing();
+        let store = Arc::new(InMemoryStore::default());
+        let make_policy = || {
+            TimeDelayPolicy::builder()
+                .initial_delay(10)
+                .min_delay(5)
+                .max_delay(100)
+                .build()
+        };
+        let policy = make_policy();
+        let queue = StoreProposalsQueue::new(store.clone(), "queue")
+            .unwrap()
+            .with_time_delay_policy(policy.clone());
+
+        let target_system = mock_target_system(ethers::types::Address::zero());
+        let r_id = mock_resourc_id(target_system, mock_typed_chain_id(1));
+        let src_system = mock_target_system(ethers::types::Address::zero());
+        let src_r_id = mock_resourc_id(src_system, mock_typed_chain_id(42));
+        for nonce in 1..=3 {
+            let header = mock_proposal_header(r_id, nonce);
+            let proposal = mock_evm_anchor_update_proposal(header, src_r_id);
+            queue.enqueue(proposal, policy.clone()).unwrap();
+        }
+        let queued = queue.find(|_| true).unwrap().unwrap();
+
+        // as if the relayer restarted.
+        let restored_policy = make_policy();
+        let restored = StoreProposalsQueue::new(store.clone(), "queue")
+            .unwrap()
+            .with_time_delay_policy(restored_policy.clone());
+        assert_eq!(restored.len().unwrap(), 3);
+        assert_eq!(restored_policy.state(), policy.state());
+        assert_ne!(restored_policy.delay(), make_policy().delay());
+        let proposal = restored.find(|_| true).unwrap().unwrap();
+        assert_eq!(proposal.to_vec(), queued.to_vec());
+        assert_eq!(proposal.header().nonce().to_u32(), 1);
+        assert_eq!(proposal.metadata.queued_at(), queued.metadata.queued_at());
+        assert_eq!(
+            proposal.metadata.should_be_dequeued_at(),
+            queued.metadata.should_be_dequeued_at()
+        );
+        // the changes of the restored queue are saved too.
+        restored.clear().unwrap();
+        let restored = StoreProposalsQueue::new(store, "queue").unwrap();
+        assert!(restored.is_empty().unwrap());
+    }
+
+    #[test]
+    fn deferred_proposals_should_not_be_saved_again() {
+        let _guard = setup_tracing();
+        let store = Arc::new(InMemoryStore::default());
+        let policy = TimeDelayPolicy::builder()
+            .initial_delay(60)
+            .min_delay(60)
+            .max_delay(60)
+            .build();
+        let queue = StoreProposalsQueue::new(store.clone(), "queue")
+            .unwrap()
+            .with_time_delay_policy(policy.clone());
+        let target_system = mock_target_system(ethers::types::Address::zero());
+        let r_id = mock_resourc_id(target_system, mock_typed_chain_id(1));
+        let src_system = mock_target_system(ethers::types::Address::zero());
+        let src_r_id = mock_resourc_id(src_system, mock_typed_chain_id(42));
+        for nonce in 1..=2 {
+            let header = mock_proposal_header(r_id, nonce);
+            let proposal = mock_evm_anchor_update_proposal(header, src_r_id);
+            queue.enqueue(proposal, policy.clone()).unwrap();
+        }
+        let saved: Option<ProposalsQueueState> =
+            store.get_proposals_queue("queue").unwrap();
+
+        // nothing is due yet, so the first one is only moved back.
+        assert!(queue.dequeue(policy.clone()).unwrap().is_none());
+        let nonce = queue.find(|_| true).unwrap().unwrap().header().nonce();
+        assert_eq!(nonce.to_u32(), 2);
+        let after: Option<ProposalsQueueState> =
+            store.get_proposals_queue("queue").unwrap();
+        assert_eq!(after, saved);
+    }
+
+    #[test]
+    fn dequeued_proposals_should_be_kept_until_acknowledged() {
+        let _guard = setup_tracing();
+        let store = Arc::new(InMemoryStore::default());
+        let queue = StoreProposalsQueue::new(store.clone(), "queue").unwrap();
+        let target_system = mock_target_system(ethers::types::Address::zero());
+        let r_id = mock_resourc_id(target_system, mock_typed_chain_id(1));
+        let src_system = mock_target_system(ethers::types::Address::zero());
+        let src_r_id = mock_resourc_id(src_system, mock_typed_chain_id(42));
+        for nonce in 1..=2 {
+            let header = mock_proposal_header(r_id, nonce);
+            let proposal = mock_evm_anchor_update_proposal(header, src_r_id);
+            queue.enqueue(proposal, ()).unwrap();
+        }
+        let first = queue.dequeue(()).unwrap().unwrap();
+        assert_eq!(queue.len().unwrap(), 1);
+
+        // as if the relayer stopped before the signing backend handled it.
+        let restored =
+            StoreProposalsQueue::new(store.clone(), "queue").unwrap();
+        assert_eq!(restored.len().unwrap(), 2);
+        let proposal = restored.find(|_| true).unwrap().unwrap();
+        assert_eq!(proposal.to_vec(), first.to_vec());
+
+        // a released proposal is put back at the end of the queue.
+        queue.release(first.clone()).unwrap();
+        let second = queue.dequeue(()).unwrap().unwrap();
+        assert_eq!(second.header().nonce().to_u32(), 2);
+        queue.acknowledge(&second).unwrap();
+        let first = queue.dequeue(()).unwrap().unwrap();
+        queue.acknowledge(&first).unwrap();
+        let restored = StoreProposalsQueue::new(store, "queue").unwrap();
+        assert!(restored.is_empty().unwrap());
+    }
+}
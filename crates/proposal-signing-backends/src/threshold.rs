@@ -0,0 +1,224 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use typed_builder::TypedBuilder;
+use webb::evm::ethers::prelude::*;
+use webb::evm::ethers::utils::keccak256;
+use webb_proposals::ResourceId;
+use webb_relayer_store::sled::SledQueueKey;
+use webb_relayer_store::{BridgeCommand, BridgeKey, QueueStore};
+use webb_relayer_utils::evm_signer::EvmSigner;
+use webb_relayer_utils::metric;
+
+use crate::proposal_kind::ProposalKindExt;
+use crate::GovernedContracts;
+
+/// A ProposalSigningBackend that collects the signatures of several signers of the
+/// Governor, and only sends a proposal to the signature bridge once `threshold` of
+/// them signed it.
+///
+/// The signatures are sent concatenated, ordered by the address of their signer, the
+/// way a multisig Governor (like a Safe) checks `n-of-m` signatures. They are not a
+/// single ECDSA signature of the Governor, so the Governor of the signature bridge
+/// must be a contract that verifies them through [EIP-1271], which is how the
+/// signature bridge watcher checks them before executing the proposal. The
+/// signature bridge must verify them the same way: the stock `SignatureBridge`
+/// recovers a single ECDSA signature, and is not supported.
+///
+/// [EIP-1271]: https://eips.ethereum.org/EIPS/eip-1271
+#[derive(TypedBuilder)]
+pub struct ThresholdProposalSigningBackend<S>
+where
+    S: QueueStore<BridgeCommand, Key = SledQueueKey>,
+{
+    /// A map between chain id and its signature bridge system.
+    #[builder(setter(into))]
+    signature_bridges: HashSet<ResourceId>,
+    /// The other contracts of the bridges, that the governance proposals target.
+    #[builder(default)]
+    governed_contracts: GovernedContracts,
+    /// Something that implements the QueueStore trait.
+    store: Arc<S>,
+    /// The signers of the governor, each with its private key or a remote signer.
+    signers: Vec<EvmSigner>,
+    /// How many of the signers must sign a proposal.
+    threshold: usize,
+}
+
+impl<S> ThresholdProposalSigningBackend<S>
+where
+    S: QueueStore<BridgeCommand, Key = SledQueueKey>,
+{
+    /// Signs the hash with all the signers at once, and returns the signatures of
+    /// the first `threshold` of them, by address, concatenated.
+    async fn threshold_signature(
+        &self,
+        hash: [u8; 32],
+    ) -> webb_relayer_utils::Result<Vec<u8>> {
+        let signatures = self.signers.iter().map(|signer| async move {
+            let signature = signer.sign_hash(TxHash(hash)).await;
+            (signer.address(), signature)
+        });
+        let mut signed = Vec::new();
+        for (address, signature) in futures::future::join_all(signatures).await
+        {
+            match signature {
+                Ok(signature) => signed.push((address, signature)),
+                Err(e) => {
+                    tracing::warn!(
+                        signer = ?address,
+                        "Failed to sign the proposal: {}",
+                        e
+                    );
+                }
+            }
+        }
+        if signed.len() < self.threshold {
+            return Err(webb_relayer_utils::Error::ThresholdNotReached {
+                signed: signed.len(),
+                threshold: self.threshold,
+            });
+        }
+        signed.sort_by_key(|(address, _)| *address);
+        let signature = signed
+            .into_iter()
+            .take(self.threshold)
+            .flat_map(|(_, signature)| signature.to_vec())
+            .collect();
+        Ok(signature)
+    }
+}
+
+#[async_trait::async_trait]
+impl<S> super::ProposalSigningBackend for ThresholdProposalSigningBackend<S>
+where
+    S: QueueStore<BridgeCommand, Key = SledQueueKey> + Send + Sync + 'static,
+{
+    async fn can_handle_proposal(
+        &self,
+        proposal: &(impl ProposalKindExt + Sync + Send + 'static),
+    ) -> webb_relayer_utils::Result<bool> {
+        let known_bridge = crate::is_known_bridge(
+            &self.signature_bridges,
+            &self.governed_contracts,
+            proposal,
+        );
+        Ok(known_bridge)
+    }
+
+    async fn handle_proposal(
+        &self,
+        proposal: &(impl ProposalKindExt + Sync + Send + 'static),
+        metrics: Arc<Mutex<metric::Metrics>>,
+    ) -> webb_relayer_utils::Result<()> {
+        let resource_id = proposal.header().resource_id();
+        let dest_chain_id = resource_id.typed_chain_id();
+        let proposal_bytes = proposal.to_vec();
+        let hash = keccak256(&proposal_bytes);
+        let signature_bytes = self.threshold_signature(hash).await?;
+        let bridge_key = BridgeKey::new(dest_chain_id);
+        tracing::debug!(
+            %bridge_key,
+            proposal = ?hex::encode(&proposal_bytes),
+            threshold = self.threshold,
+            "Signaling Signature Bridge to execute proposal",
+        );
+        tracing::event!(
+            target: webb_relayer_utils::probe::TARGET,
+            tracing::Level::DEBUG,
+            kind = %webb_relayer_utils::probe::Kind::SigningBackend,
+            backend = "Threshold",
+            signal_bridge = %bridge_key,
+            data = ?hex::encode(&proposal_bytes),
+            signature = ?hex::encode(&signature_bytes),
+        );
+        // Proposal signed metric
+        metrics.lock().await.proposals_signed.inc();
+        self.store.enqueue_item(
+            SledQueueKey::from_bridge_key(bridge_key),
+            BridgeCommand::ExecuteProposalWithSignature {
+                data: proposal_bytes,
+                signature: signature_bytes,
+            },
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::test_utils::*;
+    use crate::ProposalSigningBackend;
+    use webb::evm::ethers::core::rand::thread_rng;
+    use webb_relayer_store::InMemoryStore;
+
+    #[tokio::test]
+    async fn should_sign_once_the_threshold_is_reached() {
+        let _guard = setup_tracing();
+        let wallets = (0..3)
+            .map(|_| LocalWallet::new(&mut thread_rng()))
+            .collect::<Vec<_>>();
+        let target = mock_resourc_id(
+            mock_target_system(Address::random()),
+            mock_typed_chain_id(1),
+        );
+        let src = mock_resourc_id(
+            mock_target_system(Address::random()),
+            mock_typed_chain_id(2),
+        );
+        let proposal = mock_evm_anchor_update_proposal(
+            mock_proposal_header(target, 1),
+            src,
+        );
+        let store = Arc::new(InMemoryStore::default());
+        let backend = ThresholdProposalSigningBackend::builder()
+            .signature_bridges([target])
+            .store(store.clone())
+            .signers(wallets.iter().cloned().map(Into::into).collect())
+            .threshold(2)
+            .build();
+        backend
+            .handle_proposal(&proposal, mock_metrics())
+            .await
+            .unwrap();
+
+        let key = SledQueueKey::from_bridge_key(BridgeKey::new(
+            target.typed_chain_id(),
+        ));
+        let Some(BridgeCommand::ExecuteProposalWithSignature { data, signature }) =
+            store.dequeue_item(key).unwrap()
+        else {
+            panic!("the proposal was not sent to the signature bridge");
+        };
+        let hash = H256(keccak256(&data));
+        let signers = signature
+            .chunks(65)
+            .map(|s| Signature::try_from(s).unwrap().recover(hash).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(signers.len(), 2);
+        assert!(signers[0] < signers[1]);
+        assert!(signers
+            .iter()
+            .all(|s| wallets.iter().any(|w| w.address() == *s)));
+    }
+
+    #[tokio::test]
+    async fn should_not_sign_below_the_threshold() {
+        let store = Arc::new(InMemoryStore::default());
+        let backend = ThresholdProposalSigningBackend::builder()
+            .signature_bridges(HashSet::new())
+            .store(store)
+            .signers(vec![LocalWallet::new(&mut thread_rng()).into()])
+            .threshold(2)
+            .build();
+        assert!(matches!(
+            backend.threshold_signature([0u8; 32]).await,
+            Err(webb_relayer_utils::Error::ThresholdNotReached {
+                signed: 1,
+                threshold: 2,
+            })
+        ));
+    }
+}
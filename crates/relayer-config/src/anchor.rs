@@ -7,15 +7,29 @@ use crate::{
 use super::*;
 
 /// Linked anchor config for Evm based target system
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct RawResourceId {
     /// Raw resource Id
+    #[schemars(with = "String")]
     pub resource_id: H256,
 }
 
+/// GovernedContractConfig is a contract of the bridge, other than its anchors, that the
+/// governance proposals could target, given by its raw resource id.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum GovernedContractConfig {
+    /// A token wrapper, targeted by the token and wrapping fee proposals.
+    TokenWrapper(RawResourceId),
+    /// A treasury, targeted by the treasury handler proposals.
+    Treasury(RawResourceId),
+    /// A signature bridge, targeted by the resource id proposals.
+    SignatureBridge(RawResourceId),
+}
+
 /// LinkedAnchorConfig is configuration for the linked anchors. Linked anchor can be added in multiple ways
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type")]
 pub enum LinkedAnchorConfig {
     /// Linked anchor configuration for raw resource Id   
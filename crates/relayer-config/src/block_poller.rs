@@ -2,7 +2,7 @@ use super::*;
 use webb_relayer_types::rpc_url::RpcUrl;
 
 /// Block poller configuration
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct BlockPollerConfig {
     /// The starting block to listen at.
@@ -22,6 +22,7 @@ pub struct BlockPollerConfig {
     pub print_progress_interval: u64,
     /// Light client RPC url
     #[serde(default)]
+    #[schemars(with = "Option<String>")]
     pub light_client_rpc_url: Option<RpcUrl>,
 }
 
@@ -1,8 +1,13 @@
 use crate::WebbRelayerConfig;
 use anyhow::Context;
 use directories_next::ProjectDirs;
+use std::num::NonZeroU64;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use structopt::StructOpt;
+use webb_relayer_store::snapshot::Snapshot;
+use webb_relayer_store::{AnyStore, StoreCipher};
+use webb_relayer_types::encryption_key::EncryptionKey;
 
 /// Package identifier, where the default configuration & database are defined.
 /// If the user does not start the relayer with the `--config-dir`
@@ -29,10 +34,117 @@ pub struct Opts {
         parse(from_os_str)
     )]
     pub config_dir: Option<PathBuf>,
+    /// Reload the configuration whenever a file in the config directory changes.
+    ///
+    /// The configuration is always reloaded on `SIGHUP`, only the services of the
+    /// chains that changed are restarted.
+    #[structopt(long)]
+    pub watch_config: bool,
+    /// Reload the configuration every given number of seconds, to pick up the
+    /// secrets that were rotated in the Vault or in the keystores.
+    ///
+    /// Only the services of the chains whose secrets changed are restarted, it
+    /// must not be zero.
+    #[structopt(long, value_name = "SECONDS")]
+    pub secrets_refresh_interval: Option<NonZeroU64>,
     /// Create the Database Store in a temporary directory.
     /// and will be deleted when the process exits.
     #[structopt(long)]
     pub tmp: bool,
+    /// The storage backend used for the Database Store.
+    ///
+    /// Possible values: `sled` (default), `sqlite` (requires the `sqlite` feature)
+    /// or `memory`, which keeps everything in memory and is lost when the process exits.
+    #[structopt(long, value_name = "BACKEND", default_value = "sled")]
+    pub store_backend: StoreBackend,
+    /// The key used to encrypt the queued transactions and signatures in the
+    /// Database Store at rest.
+    ///
+    /// Could be a hex string (`0x...`), an env var (`$STORE_KEY`), a file
+    /// (`file:/path/to/key`) or a command that prints the key (`> pass show store-key`).
+    /// Ignored by the temporary and in-memory stores.
+    #[structopt(long, value_name = "KEY")]
+    pub store_key: Option<EncryptionKey>,
+    /// A maintenance command to run, instead of starting the relayer.
+    #[structopt(subcommand)]
+    pub cmd: Option<Command>,
+}
+
+/// Maintenance commands that run on the Database Store or the configuration, while
+/// the relayer is stopped.
+#[derive(Debug, Clone, StructOpt)]
+pub enum Command {
+    /// Export the leaves and encrypted outputs caches to a checksummed snapshot file.
+    ///
+    /// $ webb-relayer -c <CONFIG_FILE_PATH> export-cache <SNAPSHOT_FILE_PATH>
+    ExportCache {
+        /// The file the snapshot is written to.
+        #[structopt(value_name = "PATH", parse(from_os_str))]
+        output: PathBuf,
+    },
+    /// Seed a fresh Database Store from a snapshot file created by `export-cache`.
+    ///
+    /// The event watchers continue from the last block of the snapshot.
+    ///
+    /// $ webb-relayer -c <CONFIG_FILE_PATH> import-cache <SNAPSHOT_FILE_PATH>
+    ImportCache {
+        /// The snapshot file to import.
+        #[structopt(value_name = "PATH", parse(from_os_str))]
+        input: PathBuf,
+    },
+    /// Re-encrypt the Database Store, opened with `--store-key`, with a new key.
+    ///
+    /// Without a new key, the store is decrypted. An unencrypted store is encrypted
+    /// by running this command without `--store-key`.
+    ///
+    /// $ webb-relayer --store-key <OLD_KEY> rotate-store-key --new-key <NEW_KEY>
+    RotateStoreKey {
+        /// The new key, given like the `--store-key`.
+        #[structopt(long, value_name = "KEY")]
+        new_key: Option<EncryptionKey>,
+    },
+    /// Check the configuration, and report every problem found in it.
+    ///
+    /// $ webb-relayer -c <CONFIG_FILE_PATH> validate-config --online
+    ValidateConfig {
+        /// Also connect to every chain, to check its chain id and that the
+        /// configured contracts are deployed.
+        #[structopt(long)]
+        online: bool,
+    },
+    /// Print the JSON Schema of the config files, to validate them with an editor
+    /// or in CI.
+    ///
+    /// $ webb-relayer config-schema <SCHEMA_FILE_PATH>
+    ConfigSchema {
+        /// The file the schema is written to, instead of the standard output.
+        #[structopt(value_name = "PATH", parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+}
+
+/// The storage backend of the relayer Database Store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreBackend {
+    /// A [Sled](https://sled.rs)-based store.
+    Sled,
+    /// A [SQLite](https://sqlite.org)-based store.
+    Sqlite,
+    /// An in-memory store.
+    Memory,
+}
+
+impl FromStr for StoreBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sled" => Ok(Self::Sled),
+            "sqlite" => Ok(Self::Sqlite),
+            "memory" => Ok(Self::Memory),
+            v => Err(format!("unknown store backend: {v}")),
+        }
+    }
 }
 
 /// Loads the configuration from the given directory.
@@ -55,6 +167,21 @@ pub struct Opts {
 pub fn load_config<P>(
     config_dir: Option<P>,
 ) -> Result<WebbRelayerConfig, anyhow::Error>
+where
+    P: AsRef<Path>,
+{
+    let path = config_dir_of(config_dir)?;
+    tracing::trace!("Loading Config from {} ..", path.display());
+    let v = crate::utils::load(path)?;
+    tracing::trace!("Config loaded..");
+    Ok(v)
+}
+
+/// Returns the directory the configuration is loaded from, that is the given one
+/// or the default one of the OS.
+///
+/// Returns an error if it is not a directory.
+pub fn config_dir_of<P>(config_dir: Option<P>) -> anyhow::Result<PathBuf>
 where
     P: AsRef<Path>,
 {
@@ -69,10 +196,7 @@ where
     if !path.is_dir() {
         return Err(anyhow::anyhow!("{} is not a directory", path.display()));
     }
-    tracing::trace!("Loading Config from {} ..", path.display());
-    let v = crate::utils::load(path)?;
-    tracing::trace!("Config loaded..");
-    Ok(v)
+    Ok(path)
 }
 
 /// Sets up the logger for the relayer, based on the verbosity level passed in.
@@ -118,20 +242,41 @@ pub fn setup_logger(verbosity: i32, filter: &str) -> anyhow::Result<()> {
 
 /// Creates a database store for the relayer based on the configuration passed in.
 ///
-/// Returns `Ok(store::sled::SledStore)` on success, or `Err(anyhow::Error)` on failure.
+/// Returns `Ok(store::AnyStore)` on success, or `Err(anyhow::Error)` on failure.
 ///
 /// # Arguments
 ///
 /// * `opts` - The configuration options for the database store.
-pub async fn create_store(
-    opts: &Opts,
-) -> anyhow::Result<webb_relayer_store::SledStore> {
-    // check if we shall use the temp dir.
-    if opts.tmp {
-        tracing::debug!("Using temp dir for store");
-        let store = webb_relayer_store::SledStore::temporary()?;
-        return Ok(store);
-    }
+pub async fn create_store(opts: &Opts) -> anyhow::Result<AnyStore> {
+    let cipher = opts.store_key.as_ref().map(cipher_of);
+    let store = match opts.store_backend {
+        StoreBackend::Memory => {
+            tracing::debug!("Using in-memory store");
+            webb_relayer_store::InMemoryStore::default().into()
+        }
+        // check if we shall use the temp dir.
+        StoreBackend::Sled if opts.tmp => {
+            tracing::debug!("Using temp dir for store");
+            webb_relayer_store::SledStore::temporary()?.into()
+        }
+        StoreBackend::Sqlite if opts.tmp => {
+            tracing::debug!("Using temp dir for store");
+            create_temporary_sqlite_store()?
+        }
+        StoreBackend::Sled => webb_relayer_store::SledStore::open_with_cipher(
+            store_path(opts)?,
+            cipher,
+        )?
+        .into(),
+        StoreBackend::Sqlite => {
+            create_sqlite_store(&store_path(opts)?, cipher)?
+        }
+    };
+    Ok(store)
+}
+
+/// Returns the directory of the database store.
+fn store_path(opts: &Opts) -> anyhow::Result<PathBuf> {
     let dirs = ProjectDirs::from(PACKAGE_ID[0], PACKAGE_ID[1], PACKAGE_ID[2])
         .context("failed to get config")?;
     let p = match opts.config_dir.as_ref() {
@@ -142,7 +287,145 @@ pub async fn create_store(
         Some((_, parent)) => parent.join("store"),
         None => p.join("store"),
     };
+    Ok(db_path)
+}
 
-    let store = webb_relayer_store::SledStore::open(db_path)?;
-    Ok(store)
+/// Runs a maintenance command on the database store.
+///
+/// Returns `Ok(())` on success, or `Err(anyhow::Error)` on failure.
+///
+/// # Arguments
+///
+/// * `cmd` - The command to run.
+/// * `store` - The database store the command runs on.
+pub fn run_command(cmd: &Command, store: &mut AnyStore) -> anyhow::Result<()> {
+    match cmd {
+        Command::ExportCache { output } => {
+            let snapshot = Snapshot::export(&*store)?;
+            snapshot.write_to_file(output).with_context(|| {
+                format!("failed to write snapshot to {}", output.display())
+            })?;
+            tracing::info!(
+                entries = snapshot.entries.len(),
+                "Exported the store caches to {}",
+                output.display()
+            );
+        }
+        Command::ImportCache { input } => {
+            let snapshot =
+                Snapshot::read_from_file(input).with_context(|| {
+                    format!("failed to read snapshot from {}", input.display())
+                })?;
+            snapshot.import(&*store)?;
+            tracing::info!(
+                entries = snapshot.entries.len(),
+                "Imported the store caches from {}",
+                input.display()
+            );
+        }
+        Command::RotateStoreKey { new_key } => {
+            let count = store.rotate_key(new_key.as_ref().map(cipher_of))?;
+            if new_key.is_some() {
+                tracing::info!(
+                    "Encrypted {} store values with the new key",
+                    count
+                );
+            } else {
+                tracing::info!("Decrypted {} store values", count);
+            }
+        }
+        Command::ValidateConfig { .. } => {
+            anyhow::bail!("validate-config does not run on the Database Store")
+        }
+        Command::ConfigSchema { .. } => {
+            anyhow::bail!("config-schema does not run on the Database Store")
+        }
+    }
+    Ok(())
+}
+
+/// Loads the configuration from the given directory, and prints every problem found
+/// in it, connecting to the configured chains if `online` is set.
+///
+/// Returns an error if the configuration could not be loaded or has any problem.
+///
+/// # Arguments
+///
+/// * `config_dir` - An optional `PathBuf` representing the directory that contains the configuration.
+/// * `online` - Whether to connect to the configured chains.
+pub async fn validate_config<P>(
+    config_dir: Option<P>,
+    online: bool,
+) -> anyhow::Result<()>
+where
+    P: AsRef<Path>,
+{
+    let config = load_config(config_dir)?;
+    let mut issues = config.validate();
+    if online {
+        issues.extend(config.validate_online().await);
+    }
+    if issues.is_empty() {
+        println!("The configuration is valid");
+        return Ok(());
+    }
+    for issue in &issues {
+        println!("{issue}");
+    }
+    anyhow::bail!("found {} problem(s) in the configuration", issues.len())
+}
+
+/// Writes the JSON Schema of the config files to the given file, or prints it if
+/// there is none.
+///
+/// # Arguments
+///
+/// * `output` - An optional path of the file the schema is written to.
+pub fn write_config_schema(output: Option<&Path>) -> anyhow::Result<()> {
+    let schema = serde_json::to_string_pretty(&crate::schema::config_schema())?;
+    match output {
+        Some(path) => {
+            std::fs::write(path, schema)?;
+            tracing::info!("Wrote the config schema to {}", path.display());
+        }
+        None => println!("{schema}"),
+    }
+    Ok(())
+}
+
+/// Creates the cipher of the database store from the given key.
+fn cipher_of(key: &EncryptionKey) -> StoreCipher {
+    StoreCipher::new(key.to_bytes())
+}
+
+#[cfg(feature = "sqlite")]
+fn create_sqlite_store(
+    db_path: &Path,
+    cipher: Option<StoreCipher>,
+) -> anyhow::Result<AnyStore> {
+    std::fs::create_dir_all(db_path)?;
+    let store = webb_relayer_store::SqliteStore::open_with_cipher(
+        db_path.join("relayer.db"),
+        cipher,
+    )?;
+    Ok(store.into())
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn create_sqlite_store(
+    _db_path: &Path,
+    _cipher: Option<StoreCipher>,
+) -> anyhow::Result<AnyStore> {
+    anyhow::bail!("the relayer was built without the `sqlite` feature")
+}
+
+#[cfg(feature = "sqlite")]
+fn create_temporary_sqlite_store() -> anyhow::Result<AnyStore> {
+    let store = webb_relayer_store::SqliteStore::temporary()?;
+    Ok(store.into())
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn create_temporary_sqlite_store() -> anyhow::Result<AnyStore> {
+    anyhow::bail!("the relayer was built without the `sqlite` feature")
 }
@@ -20,6 +20,31 @@ pub const fn max_blocks_per_step() -> u64 {
 pub const fn print_progress_interval() -> u64 {
     7_000
 }
+/// The store is compacted every hour by default.
+pub const fn retention_interval() -> u64 {
+    3600
+}
+/// The finished queue entries are pruned by default.
+pub const fn prune_finished_queue_entries() -> bool {
+    true
+}
+
+/// The relayer makes a profit of 5 USD per transaction by default.
+pub const fn fee_profit_usd() -> f64 {
+    5.
+}
+/// The relayer refunds at most 5 USD per transaction by default.
+pub const fn max_refund_usd() -> f64 {
+    5.
+}
+/// The fees up to 4% lower than the estimated fee are accepted by default.
+pub const fn fee_tolerance_percent() -> f64 {
+    4.
+}
+/// The inbox of the signed proposals is checked every 5 seconds by default.
+pub const fn inbox_polling_interval() -> u64 {
+    5_000
+}
 
 /// The default unlisted assets.
 pub fn unlisted_assets() -> HashMap<String, crate::UnlistedAssetConfig> {
@@ -0,0 +1,244 @@
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+
+use sp_core::Pair;
+
+use super::*;
+use crate::evm::Contract;
+use crate::signing_backend::ProposalSigningBackendConfig;
+use crate::substrate::Pallet;
+
+/// ConfigDiff is what changed between the running configuration of the relayer
+/// and a reloaded one, as far as the running services are concerned.
+///
+/// The chains are identified by their keys in [`WebbRelayerConfig::evm`] and
+/// [`WebbRelayerConfig::substrate`], that is their chain ids once loaded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigDiff {
+    /// The EVM chains that were added, removed or changed.
+    pub evm: BTreeSet<String>,
+    /// The Substrate chains that were added, removed or changed.
+    pub substrate: BTreeSet<String>,
+    /// Whether the retention policy of the store changed.
+    pub retention: bool,
+    /// Whether the settings only used by the HTTP server, like the port, the
+    /// assets or the admin token, changed.
+    pub server: bool,
+}
+
+impl ConfigDiff {
+    /// Compares the `old` configuration with the `new` one.
+    ///
+    /// A change to the features marks all the chains as changed, since all their
+    /// services depend on them, and so does a change to the Etherscan API keys
+    /// for the EVM chains.
+    pub fn between(old: &WebbRelayerConfig, new: &WebbRelayerConfig) -> Self {
+        let features_changed = !same(&old.features, &new.features);
+        let etherscan_changed =
+            !same_map(&old.evm_etherscan, &new.evm_etherscan);
+        let evm = changed_keys(&old.evm, &new.evm, |a, b| {
+            !features_changed
+                && !etherscan_changed
+                && same(a, b)
+                && same_secret(&a.private_key, &b.private_key, |k| **k)
+                && account_keys(a) == account_keys(b)
+                && governor_keys(evm_backends(a))
+                    == governor_keys(evm_backends(b))
+        });
+        let substrate = changed_keys(&old.substrate, &new.substrate, |a, b| {
+            !features_changed
+                && same(a, b)
+                && same_secret(&a.suri, &b.suri, |suri| suri.public())
+                && governor_keys(substrate_backends(a))
+                    == governor_keys(substrate_backends(b))
+        });
+        Self {
+            evm,
+            substrate,
+            retention: !same(&old.retention, &new.retention),
+            // the admin token is not printed, so it is compared by itself.
+            server: old.port != new.port
+                || !same_map(&old.assets, &new.assets)
+                || old.proposals_admin_token != new.proposals_admin_token,
+        }
+    }
+
+    /// Returns `true` if nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.evm.is_empty()
+            && self.substrate.is_empty()
+            && !self.retention
+            && !self.server
+    }
+}
+
+/// Compares two values by their `Debug` output, which, unlike their serialized
+/// form, includes every field, except for the secrets.
+fn same<T: Debug>(a: &T, b: &T) -> bool {
+    format!("{a:?}") == format!("{b:?}")
+}
+
+/// Like [`same`], but for maps, which are not printed in any particular order.
+fn same_map<K, V>(a: &HashMap<K, V>, b: &HashMap<K, V>) -> bool
+where
+    K: Eq + std::hash::Hash,
+    V: Debug,
+{
+    a.len() == b.len()
+        && a.iter()
+            .all(|(k, v)| b.get(k).map(|other| same(v, other)).unwrap_or(false))
+}
+
+/// Compares two secrets by something that identifies them.
+fn same_secret<S, I, F>(a: &Option<S>, b: &Option<S>, id: F) -> bool
+where
+    I: PartialEq,
+    F: Fn(&S) -> I,
+{
+    a.as_ref().map(&id) == b.as_ref().map(&id)
+}
+
+/// Returns the keys of the extra accounts of an EVM chain.
+fn account_keys(chain: &EvmChainConfig) -> Vec<Option<ethereum_types::H256>> {
+    chain
+        .accounts
+        .iter()
+        .map(|account| account.private_key.as_ref().map(|key| **key))
+        .collect()
+}
+
+/// Returns the keys of the `Mocked` and `Threshold` signing backends, which are
+/// secrets too.
+fn governor_keys<'a>(
+    backends: impl Iterator<Item = &'a ProposalSigningBackendConfig>,
+) -> Vec<ethereum_types::H256> {
+    backends
+        .flat_map(|backend| match backend {
+            ProposalSigningBackendConfig::Mocked(mocked) => {
+                mocked.private_key.iter().map(|key| **key).collect()
+            }
+            ProposalSigningBackendConfig::Threshold(threshold) => threshold
+                .signers
+                .iter()
+                .filter_map(|signer| signer.private_key.as_ref())
+                .map(|key| **key)
+                .collect(),
+            ProposalSigningBackendConfig::DkgNode(_)
+            | ProposalSigningBackendConfig::AirGapped(_) => Vec::new(),
+        })
+        .collect()
+}
+
+fn evm_backends(
+    chain: &EvmChainConfig,
+) -> impl Iterator<Item = &ProposalSigningBackendConfig> {
+    chain
+        .contracts
+        .iter()
+        .filter_map(|contract| match contract {
+            Contract::VAnchor(config) => {
+                config.proposal_signing_backend.as_ref()
+            }
+            _ => None,
+        })
+}
+
+fn substrate_backends(
+    node: &SubstrateConfig,
+) -> impl Iterator<Item = &ProposalSigningBackendConfig> {
+    node.pallets.iter().filter_map(|pallet| match pallet {
+        Pallet::VAnchorBn254(config) => {
+            config.proposal_signing_backend.as_ref()
+        }
+        _ => None,
+    })
+}
+
+/// Returns the keys that are only in one of the maps, or whose values are not
+/// the `same`.
+fn changed_keys<V, F>(
+    old: &HashMap<String, V>,
+    new: &HashMap<String, V>,
+    same: F,
+) -> BTreeSet<String>
+where
+    F: Fn(&V, &V) -> bool,
+{
+    let removed = old.keys().filter(|k| !new.contains_key(*k));
+    let added_or_changed = new.iter().filter_map(|(k, v)| match old.get(k) {
+        Some(old) if same(old, v) => None,
+        _ => Some(k),
+    });
+    removed.chain(added_or_changed).cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evm_chain(chain_id: u32, polling_interval: u64) -> EvmChainConfig {
+        serde_json::from_value(serde_json::json!({
+            "name": format!("chain-{chain_id}"),
+            "enabled": true,
+            "http-endpoint": "http://localhost:8545",
+            "ws-endpoint": "ws://localhost:8545",
+            "chain-id": chain_id,
+            "tx-queue": {
+                "max-sleep-interval": 10_000,
+                "polling-interval": polling_interval,
+            },
+        }))
+        .unwrap()
+    }
+
+    fn config_of(chains: Vec<EvmChainConfig>) -> WebbRelayerConfig {
+        let mut config = WebbRelayerConfig::default();
+        for chain in chains {
+            config.evm.insert(chain.chain_id.to_string(), chain);
+        }
+        config
+    }
+
+    #[test]
+    fn diff_should_work() {
+        let old = config_of(vec![evm_chain(1, 12_000), evm_chain(2, 12_000)]);
+        assert!(ConfigDiff::between(&old, &old.clone()).is_empty());
+
+        // chain 1 changed, chain 2 removed and chain 3 added.
+        let new = config_of(vec![evm_chain(1, 6_000), evm_chain(3, 12_000)]);
+        let diff = ConfigDiff::between(&old, &new);
+        assert_eq!(
+            diff.evm,
+            ["1", "2", "3"].into_iter().map(String::from).collect()
+        );
+        assert!(diff.substrate.is_empty());
+        assert!(!diff.retention && !diff.server);
+
+        // a new private key is a change too, even if it is not printed.
+        let mut new = old.clone();
+        new.evm.get_mut("2").unwrap().private_key =
+            Some(ethereum_types::H256::repeat_byte(7).into());
+        let diff = ConfigDiff::between(&old, &new);
+        assert_eq!(diff.evm, ["2"].into_iter().map(String::from).collect());
+
+        // and all the chains depend on the features.
+        let mut new = old.clone();
+        new.features.governance_relay = !old.features.governance_relay;
+        let diff = ConfigDiff::between(&old, &new);
+        assert_eq!(diff.evm.len(), 2);
+
+        let mut new = old.clone();
+        new.port += 1;
+        let diff = ConfigDiff::between(&old, &new);
+        assert!(diff.evm.is_empty() && diff.server);
+
+        // rotating the admin token restarts the server with the new one.
+        let mut old = old;
+        old.proposals_admin_token = Some("old-token".parse().unwrap());
+        let mut new = old.clone();
+        new.proposals_admin_token = Some("new-token".parse().unwrap());
+        let diff = ConfigDiff::between(&old, &new);
+        assert!(diff.evm.is_empty() && diff.server);
+        assert!(ConfigDiff::between(&old, &old.clone()).is_empty());
+    }
+}
@@ -5,7 +5,8 @@ use url::Url;
 use webb_relayer_types::{private_key::PrivateKey, rpc_url::RpcUrl};
 
 use crate::{
-    anchor::LinkedAnchorConfig, block_poller::BlockPollerConfig,
+    anchor::{GovernedContractConfig, LinkedAnchorConfig},
+    block_poller::BlockPollerConfig,
     event_watcher::EventsWatcherConfig,
     signing_backend::ProposalSigningBackendConfig,
 };
@@ -13,7 +14,7 @@ use crate::{
 use super::*;
 
 /// EvmChainConfig is the configuration for the EVM based networks.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct EvmChainConfig {
     /// String that groups configuration for this chain on a human-readable name.
@@ -26,6 +27,7 @@ pub struct EvmChainConfig {
     pub http_endpoint: HttpEndpoint,
     /// Websocket Endpoint for long living connections
     #[serde(skip_serializing)]
+    #[schemars(with = "String")]
     pub ws_endpoint: RpcUrl,
     /// Block confirmations
     #[serde(skip_serializing, default)]
@@ -35,6 +37,7 @@ pub struct EvmChainConfig {
     /// Optional, and only used for printing a clickable links
     /// for transactions and contracts.
     #[serde(skip_serializing)]
+    #[schemars(with = "Option<String>")]
     pub explorer: Option<url::Url>,
     /// chain specific id (output of chainId opcode on EVM networks)
     pub chain_id: u32,
@@ -60,10 +63,32 @@ pub struct EvmChainConfig {
     ///
     /// 5. if it doesn't contains special characters and has 12 or 24 words in it
     ///   then we should process it as a mnemonic string: 'word two three four ...'
+    ///
+    /// 6. if it starts with 'keystore:' then it would be considered as an encrypted
+    ///   keystore (Web3 Secret Storage) file, decrypted with the password after '#'
+    ///   which is either an Environment variable or a file.
+    ///   Example: keystore:/Users/Bob/relayer/key.json#$KEYSTORE_PASSWORD
+    ///
+    /// 7. if it starts with 'vault:' then the secret would be fetched from a field of a
+    ///   Vault KV secret, using the token in the `VAULT_TOKEN` Environment variable.
+    ///   The field should include a hex encoded private key or a mnemonic word list.
+    ///   Example: vault:https://vault.local/v1/secret/data/relayer#harmony-private-key
     #[serde(skip_serializing)]
+    #[schemars(with = "Option<String>")]
     pub private_key: Option<PrivateKey>,
+    /// An external signer service that holds the key of this account, used
+    /// instead of the `private_key`.
+    #[serde(default, skip_serializing)]
+    pub remote_signer: Option<RemoteSignerConfig>,
+    /// More accounts of the relayer on this network, to spread the transactions
+    /// across, besides the primary one of the `private_key` or `remote_signer`.
+    ///
+    /// The relaying fees are always received by the primary account.
+    #[serde(default, skip_serializing)]
+    pub accounts: Vec<AccountConfig>,
     /// Optionally, a user can specify an account to receive rewards for relaying
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
     pub beneficiary: Option<Address>,
     /// Supported contracts over this chain.
     #[serde(default)]
@@ -71,19 +96,32 @@ pub struct EvmChainConfig {
     /// TxQueue configuration
     #[serde(skip_serializing, default)]
     pub tx_queue: TxQueueConfig,
+    /// The fees charged for the private transactions relayed on this chain.
+    #[serde(default)]
+    pub fee_policy: FeePolicyConfig,
     /// Block poller/listening configuration
     #[serde(skip_serializing, default)]
     pub block_poller: Option<BlockPollerConfig>,
 }
 
 /// configuration for adding http endpoints.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(untagged)]
 pub enum HttpEndpoint {
     /// Single http endpoint
-    Single(RpcUrl),
+    Single(#[schemars(with = "String")] RpcUrl),
     /// Multiple http endpoints
-    Multiple(Vec<RpcUrl>),
+    Multiple(#[schemars(with = "Vec<String>")] Vec<RpcUrl>),
+}
+
+impl HttpEndpoint {
+    /// Returns all the endpoints.
+    pub fn urls(&self) -> Vec<RpcUrl> {
+        match self {
+            HttpEndpoint::Single(url) => vec![url.clone()],
+            HttpEndpoint::Multiple(urls) => urls.clone(),
+        }
+    }
 }
 
 impl fmt::Display for HttpEndpoint {
@@ -105,18 +143,52 @@ impl From<Url> for HttpEndpoint {
     }
 }
 
+/// AccountConfig is an EVM account, like an extra account of the relayer on an
+/// EVM chain or a signer of the `Threshold` signing backend, given either its
+/// private key, in any of the formats of the primary one, or a remote signer
+/// that holds it.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct AccountConfig {
+    /// The Private Key of this account.
+    #[serde(default, skip_serializing)]
+    #[schemars(with = "Option<String>")]
+    pub private_key: Option<PrivateKey>,
+    /// An external signer service that holds the key of this account.
+    #[serde(default, skip_serializing)]
+    pub remote_signer: Option<RemoteSignerConfig>,
+}
+
+/// RemoteSignerConfig is an external signer service that holds the key of an
+/// account and signs for it over HTTP JSON-RPC.
+///
+/// The transactions are signed with `eth_signTransaction`, like web3signer or
+/// Clef do, and the proposals with `eth_signHash`, a custom method that signs
+/// a hash without any prefix and that the signer service must implement.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct RemoteSignerConfig {
+    /// The JSON-RPC endpoint of the signer.
+    #[schemars(with = "String")]
+    pub url: RpcUrl,
+    /// The address of the account to sign with.
+    #[schemars(with = "String")]
+    pub address: Address,
+}
+
 /// Linked anchor config for Evm based target system
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct EvmLinkedAnchorConfig {
     /// The chain Id
     pub chain_id: u32,
     /// The V-anchor Contract Address.
+    #[schemars(with = "String")]
     pub address: Address,
 }
 
 /// Enumerates the supported contract configurations.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "contract")]
 pub enum Contract {
     /// The VAnchor contract configuration.
@@ -126,10 +198,11 @@ pub enum Contract {
 }
 
 /// CommonContractConfig represents the common configuration for contracts.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct CommonContractConfig {
     /// The address of this contract on this chain.
+    #[schemars(with = "String")]
     pub address: Address,
     /// the block number where this contract got deployed at.
     pub deployed_at: u64,
@@ -138,7 +211,7 @@ pub struct CommonContractConfig {
 /// Smart Anchor Updates applies polices to the AnchorUpdate Proposals
 /// which helps to reduce the number of updates, hence the number of
 /// transactions and gas fees.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct SmartAnchorUpdatesConfig {
     /// Enables smart anchor updates
@@ -155,6 +228,14 @@ pub struct SmartAnchorUpdatesConfig {
     /// Time delay sliding window size
     #[serde(skip_serializing_if = "Option::is_none")]
     pub time_delay_window_size: Option<usize>,
+    /// Gas price ceiling (in wei) of the destination chain, above which the
+    /// anchor updates are deferred. No ceiling if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_gas_price: Option<u64>,
+    /// Maximum time (in seconds) an anchor update is deferred because of the
+    /// gas price, after which it is sent anyway.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_price_max_staleness: Option<u64>,
 }
 
 impl Default for SmartAnchorUpdatesConfig {
@@ -167,12 +248,14 @@ impl Default for SmartAnchorUpdatesConfig {
             max_time_delay: Some(300),
             initial_time_delay: Some(10),
             time_delay_window_size: Some(5),
+            max_gas_price: None,
+            gas_price_max_staleness: Some(3600),
         }
     }
 }
 
 /// VAnchorContractConfig represents the configuration for the VAnchor contract.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct VAnchorContractConfig {
     /// Common contract configuration.
@@ -186,13 +269,17 @@ pub struct VAnchorContractConfig {
     /// A List of linked Anchor Contracts (on other chains) to this contract.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub linked_anchors: Option<Vec<LinkedAnchorConfig>>,
+    /// The contracts of the bridge, other than the linked anchors, that the
+    /// governance proposals of this contract could target.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub governed_contracts: Vec<GovernedContractConfig>,
     /// For configuring the smart anchor updates
     #[serde(default)]
     pub smart_anchor_updates: SmartAnchorUpdatesConfig,
 }
 
 /// Signature Bridge contract configuration.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct SignatureBridgeContractConfig {
     /// Common contract configuration.
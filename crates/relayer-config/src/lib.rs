@@ -36,26 +36,34 @@ pub mod block_poller;
 pub mod cli;
 /// Module for all the default values.
 pub mod defaults;
+/// Changes between two configurations, used to reload the relayer.
+pub mod diff;
 /// Event watcher configuration
 pub mod event_watcher;
 /// EVM configuration
 pub mod evm;
+/// JSON Schema of the config files
+pub mod schema;
 /// Signing backend configuration
 pub mod signing_backend;
 /// Substrate configuration
 pub mod substrate;
 /// Utils for processing configuration
 pub mod utils;
+/// Deep validation of the configuration
+pub mod validation;
 
 use evm::EvmChainConfig;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use substrate::SubstrateConfig;
 use webb::evm::ethers::types::Chain;
+use webb_relayer_types::admin_token::AdminToken;
 use webb_relayer_types::etherscan_api::EtherscanApiKey;
 
 /// WebbRelayerConfig is the configuration for the webb relayer.
-#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[derive(Debug, Clone, Deserialize, Serialize, Default, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct WebbRelayerConfig {
     /// WebSocket Server Port number
@@ -70,12 +78,14 @@ pub struct WebbRelayerConfig {
     pub evm: HashMap<String, EvmChainConfig>,
     /// Etherscan API key configuration for evm based chains.
     #[serde(default, skip_serializing)]
+    #[schemars(with = "HashMap<String, EtherscanApiConfig>")]
     pub evm_etherscan: HashMap<Chain, EtherscanApiConfig>,
     /// ETH2 based networks and the configuration
     ///
     /// a map between chain name and its configuration
     #[cfg(feature = "eth2")]
     #[serde(default)]
+    #[schemars(with = "HashMap<String, serde_json::Value>")]
     pub eth2: HashMap<String, eth2_to_substrate_relay::config::Config>,
     /// Substrate based networks and the configuration.
     ///
@@ -91,46 +101,43 @@ pub struct WebbRelayerConfig {
     /// 3. Private transaction relaying
     #[serde(default)]
     pub features: FeaturesConfig,
+    /// The bearer token required by the admin actions on the proposals queues.
+    ///
+    /// required when the `proposals-admin` feature is enabled.
+    #[serde(default, skip_serializing)]
+    #[schemars(with = "Option<String>")]
+    pub proposals_admin_token: Option<AdminToken>,
     /// Configuration for the assets that are not listed on any exchange.
     ///
     /// it is a simple map between the asset symbol and its configuration.
     #[serde(default = "defaults::unlisted_assets")]
     pub assets: HashMap<String, UnlistedAssetConfig>,
+    /// Configuration for pruning the data the relayer no longer needs from the store.
+    ///
+    /// disabled by default.
+    #[serde(default)]
+    pub retention: RetentionConfig,
 }
 
 impl WebbRelayerConfig {
     /// Makes sure that the config is valid, by going
     /// through the whole config and doing some basic checks.
-    #[allow(unused)] // TODO(@shekohex): remove this once we convert the relayer into a crate.
+    ///
+    /// See [`WebbRelayerConfig::validate`] for the list of all the problems found.
     pub fn verify(&self) -> webb_relayer_utils::Result<()> {
-        // The first check is to make sure that the private key is there when needed.
-        // to say more on the above check, we **must** have a private key in the following conditions:
-        // 1. We are running the relayer as a private transaction relayer.
-        // 2. we are running the relayer as a governance system.
-        //
-        // However, if we are running the relayer as only a data serving relayer, we don't need a private key.
-        let check_features =
-            self.features.governance_relay || self.features.private_tx_relay;
-        let check_evm = check_features
-            && self
-                .evm
-                .iter()
-                .filter(|(_k, v)| v.enabled)
-                .all(|(_k, v)| v.private_key.is_some());
-        let check_substrate = check_features
-            && self
-                .substrate
-                .iter()
-                .filter(|(_k, v)| v.enabled)
-                .all(|(_k, v)| v.suri.is_some());
-        (check_evm && check_substrate)
-            .then_some(())
-            .ok_or(webb_relayer_utils::Error::MissingSecrets)
+        let issues = self.validate();
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(webb_relayer_utils::Error::InvalidConfig(
+                issues.iter().map(ToString::to_string).collect(),
+            ))
+        }
     }
 }
 
 /// FeaturesConfig is the configuration for running relayer with option.
-#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct FeaturesConfig {
     /// Enable data quering for leafs
@@ -139,6 +146,11 @@ pub struct FeaturesConfig {
     pub governance_relay: bool,
     /// Enable private tx relaying
     pub private_tx_relay: bool,
+    /// Enable the admin actions on the proposals queues (drop, expedite, re-sign)
+    ///
+    /// disabled by default, the admin actions require the `proposals-admin-token`.
+    #[serde(default)]
+    pub proposals_admin: bool,
 }
 
 impl Default for FeaturesConfig {
@@ -147,29 +159,90 @@ impl Default for FeaturesConfig {
             data_query: true,
             governance_relay: true,
             private_tx_relay: true,
+            proposals_admin: false,
+        }
+    }
+}
+
+/// RetentionConfig is the configuration of the background compaction task, which
+/// prunes the data the relayer no longer needs from the store.
+///
+/// The limits that are not set are not enforced.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct RetentionConfig {
+    /// Enable the background compaction task.
+    #[serde(default)]
+    pub enabled: bool,
+    /// How often the compaction task runs, in seconds.
+    #[serde(default = "defaults::retention_interval")]
+    pub interval: u64,
+    /// The maximum age, in seconds, of the hashes of the processed events.
+    #[serde(default)]
+    pub event_hashes_max_age: Option<u64>,
+    /// The maximum age, in seconds, of the cached token prices.
+    #[serde(default)]
+    pub token_prices_max_age: Option<u64>,
+    /// The maximum age, in seconds since they last failed, of the dead letters.
+    #[serde(default)]
+    pub dead_letters_max_age: Option<u64>,
+    /// How many blocks behind the last block processed the blocks of the cached
+    /// leaves and encrypted outputs are kept, to roll them back on reorgs.
+    #[serde(default)]
+    pub block_depth: Option<u64>,
+    /// Remove what is left of the queue items that were already dequeued.
+    #[serde(default = "defaults::prune_finished_queue_entries")]
+    pub finished_queue_entries: bool,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: defaults::retention_interval(),
+            event_hashes_max_age: None,
+            token_prices_max_age: None,
+            dead_letters_max_age: None,
+            block_depth: None,
+            finished_queue_entries: defaults::prune_finished_queue_entries(),
+        }
+    }
+}
+
+impl RetentionConfig {
+    /// The retention policy enforced by the compaction task.
+    pub fn policy(&self) -> webb_relayer_store::RetentionPolicy {
+        webb_relayer_store::RetentionPolicy {
+            event_hashes_max_age: self.event_hashes_max_age,
+            token_prices_max_age: self.token_prices_max_age,
+            dead_letters_max_age: self.dead_letters_max_age,
+            block_depth: self.block_depth,
+            finished_queue_entries: self.finished_queue_entries,
         }
     }
 }
 
 /// Configuration to add etherscan API key
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct EtherscanApiConfig {
     /// Chain Id
     pub chain_id: u32,
     /// A wrapper type around the `String` to allow reading it from the env.
     #[serde(skip_serializing)]
+    #[schemars(with = "String")]
     pub api_key: EtherscanApiKey,
     /// An optional URL to use for the Etherscan API instead of the default.
     ///
     /// This is useful for testing against a local Etherscan API.
     /// Or in case of testnets, the Etherscan GasOracle API is not available.
     /// So we can use the mainnet API URL to get the gas price.
+    #[schemars(with = "Option<String>")]
     pub api_url: Option<url::Url>,
 }
 
 /// TxQueueConfig is the configuration for the TxQueue.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct TxQueueConfig {
     /// Maximum number of milliseconds to wait before dequeuing a transaction from
@@ -188,8 +261,62 @@ impl Default for TxQueueConfig {
     }
 }
 
+/// FeePolicyConfig is how the relayer charges the users for the private
+/// transactions it relays on a chain.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct FeePolicyConfig {
+    /// A fixed profit, in USD, added to the fee of every transaction.
+    #[serde(default = "defaults::fee_profit_usd")]
+    pub profit_usd: f64,
+    /// A profit, in percent of the network fee, added to the fee of every
+    /// transaction, on top of the fixed profit.
+    #[serde(default)]
+    pub profit_percent: f64,
+    /// The maximum refund, in USD, the relayer pays per transaction.
+    #[serde(default = "defaults::max_refund_usd")]
+    pub max_refund_usd: f64,
+    /// How much lower, in percent, than the estimated fee the fee sent by a user
+    /// could be, since the prices may change between the estimation and the relay.
+    #[serde(default = "defaults::fee_tolerance_percent")]
+    pub tolerance_percent: f64,
+    /// The minimum fee, in USD, of a transaction.
+    #[serde(default)]
+    pub min_fee_usd: f64,
+}
+
+impl Default for FeePolicyConfig {
+    fn default() -> Self {
+        Self {
+            profit_usd: defaults::fee_profit_usd(),
+            profit_percent: 0.,
+            max_refund_usd: defaults::max_refund_usd(),
+            tolerance_percent: defaults::fee_tolerance_percent(),
+            min_fee_usd: 0.,
+        }
+    }
+}
+
+impl FeePolicyConfig {
+    /// The profit, in USD, made on a transaction with the given network fee.
+    pub fn profit_of(&self, tx_fee_usd: f64) -> f64 {
+        self.profit_usd + tx_fee_usd * self.profit_percent / 100.
+    }
+
+    /// The fee, in USD, charged for a transaction with the given network fee,
+    /// that is the network fee with the profit, and at least the minimum fee.
+    pub fn fee_of(&self, tx_fee_usd: f64) -> f64 {
+        (tx_fee_usd + self.profit_of(tx_fee_usd)).max(self.min_fee_usd)
+    }
+
+    /// The lowest fee accepted, in basis points of the estimated fee.
+    pub fn accepted_fee_bps(&self) -> u64 {
+        ((100. - self.tolerance_percent) * 100.).round() as u64
+    }
+}
+
 /// UnlistedAssetConfig is the configuration for the assets that are not listed on any exchange.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct UnlistedAssetConfig {
     /// The Price of the asset in USD.
@@ -249,4 +376,46 @@ mod tests {
                 .expect("Failed to set current directory");
         }
     }
+
+    #[test]
+    fn fee_policy_should_accept_fees_within_its_tolerance() {
+        // fees up to 4% lower are accepted by default, on substrate nodes too.
+        let node: substrate::SubstrateConfig =
+            serde_json::from_value(serde_json::json!({
+                "name": "tangle",
+                "enabled": true,
+                "http-endpoint": "http://localhost:9933",
+                "ws-endpoint": "ws://localhost:9944",
+                "chain-id": 1081,
+            }))
+            .unwrap();
+        assert_eq!(node.fee_policy.tolerance_percent, 4.);
+        assert_eq!(node.fee_policy.accepted_fee_bps(), 9_600);
+        assert_eq!(FeePolicyConfig::default().accepted_fee_bps(), 9_600);
+
+        let policy: FeePolicyConfig =
+            serde_json::from_value(serde_json::json!({
+                "tolerance-percent": 2.5,
+            }))
+            .unwrap();
+        assert_eq!(policy.accepted_fee_bps(), 9_750);
+        assert_eq!(policy.profit_usd, defaults::fee_profit_usd());
+
+        // without tolerance, only the estimated fee is accepted.
+        let exact = FeePolicyConfig {
+            tolerance_percent: 0.,
+            ..Default::default()
+        };
+        assert_eq!(exact.accepted_fee_bps(), 10_000);
+        // the tolerance is rounded to the closest basis point.
+        for (tolerance_percent, bps) in
+            [(99.99, 1), (0.004, 10_000), (0.006, 9_999)]
+        {
+            let policy = FeePolicyConfig {
+                tolerance_percent,
+                ..Default::default()
+            };
+            assert_eq!(policy.accepted_fee_bps(), bps);
+        }
+    }
 }
@@ -0,0 +1,51 @@
+use schemars::schema::RootSchema;
+
+use super::*;
+
+/// Generates the JSON Schema of the config files, that is of
+/// [`WebbRelayerConfig`] and all the types nested in it.
+///
+/// The schema describes the format the configuration is read in, with its
+/// kebab-case keys and the tags of the contracts, pallets, linked anchors and
+/// signing backends. The secrets, endpoints and addresses are plain strings,
+/// since they may also be given as environment variables.
+pub fn config_schema() -> RootSchema {
+    schemars::schema_for!(WebbRelayerConfig)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_should_describe_the_config_files() {
+        let schema = serde_json::to_value(config_schema()).unwrap();
+        let definitions = &schema["definitions"];
+        let properties_of = |name: &str| {
+            definitions[name]["properties"]
+                .as_object()
+                .unwrap()
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        assert!(schema["properties"]["evm-etherscan"].is_object());
+        let evm = properties_of("EvmChainConfig");
+        assert!(evm.contains(&"http-endpoint".to_string()));
+        assert!(evm.contains(&"private-key".to_string()));
+        assert!(evm.contains(&"tx-queue".to_string()));
+
+        // the tagged enums are told apart by their tags.
+        let definition_of =
+            |name: &str| serde_json::to_string(&definitions[name]).unwrap();
+        assert!(definition_of("Contract").contains("\"VAnchor\""));
+        assert!(definition_of("Pallet").contains("\"DKG\""));
+        assert!(definition_of("LinkedAnchorConfig").contains("\"Raw\""));
+        assert!(definition_of("ProposalSigningBackendConfig")
+            .contains("\"DKGNode\""));
+        // and the common config of the contracts is flattened into them.
+        let vanchor = properties_of("VAnchorContractConfig");
+        assert!(vanchor.contains(&"deployed-at".to_string()));
+    }
+}
@@ -1,9 +1,15 @@
+use std::path::PathBuf;
+
+use webb::evm::ethers::types::Address;
 use webb_relayer_types::private_key::PrivateKey;
+use webb_relayer_types::rpc_url::RpcUrl;
+
+use crate::evm::{AccountConfig, RemoteSignerConfig};
 
 use super::*;
 
 /// Enumerates the supported different signing backends configurations.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type")]
 pub enum ProposalSigningBackendConfig {
     /// Uses an already running and configured DKG Node for signing proposals.
@@ -11,10 +17,16 @@ pub enum ProposalSigningBackendConfig {
     DkgNode(DkgNodeProposalSigningBackendConfig),
     /// Uses the Private Key of the current Governor to sign proposals.
     Mocked(MockedProposalSigningBackendConfig),
+    /// Uses the keys of several signers of the Governor, and only signs proposals
+    /// once a threshold of them signed.
+    Threshold(ThresholdProposalSigningBackendConfig),
+    /// Exports the unsigned proposals to be signed offline by the Governor's cold key,
+    /// and imports the signed ones.
+    AirGapped(AirGappedProposalSigningBackendConfig),
 }
 
 /// DKGNodeSigningBackendConfig represents the configuration for the DKGNode signing backend.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct DkgNodeProposalSigningBackendConfig {
     /// The chain id of the DKG Node that this contract will use.
@@ -24,10 +36,56 @@ pub struct DkgNodeProposalSigningBackendConfig {
 }
 
 /// MockedSigningBackendConfig represents the configuration for the Mocked signing backend.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct MockedProposalSigningBackendConfig {
     /// The private key of the current Governor.
+    ///
+    /// It could be given in any of the formats of the private keys of the EVM chains,
+    /// including an encrypted keystore and a Vault secret.
+    #[serde(default, skip_serializing)]
+    #[schemars(with = "Option<String>")]
+    pub private_key: Option<PrivateKey>,
+    /// An external signer service that holds the key of the current Governor,
+    /// used instead of the `private_key`.
+    #[serde(default, skip_serializing)]
+    pub remote_signer: Option<RemoteSignerConfig>,
+}
+
+/// ThresholdSigningBackendConfig represents the configuration for the Threshold signing backend.
+///
+/// Every proposal is signed by each of the `signers`, and only sent to the signature
+/// bridge once `threshold` of them signed it, so no single key could sign a proposal.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct ThresholdProposalSigningBackendConfig {
+    /// How many of the signers must sign a proposal (the `n` of `n-of-m`).
+    pub threshold: usize,
+    /// The signers of the Governor, each with its private key or a remote signer.
     #[serde(skip_serializing)]
-    pub private_key: PrivateKey,
+    pub signers: Vec<AccountConfig>,
+}
+
+/// AirGappedSigningBackendConfig represents the configuration for the AirGapped signing backend.
+///
+/// The unsigned proposals are written to the `outbox` directory, or posted to the `outbox-url`,
+/// and the proposals signed offline are picked up from the `inbox` directory.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
+pub struct AirGappedProposalSigningBackendConfig {
+    /// The directory the unsigned proposals are written to.
+    #[serde(default)]
+    pub outbox: Option<PathBuf>,
+    /// An HTTP endpoint the unsigned proposals are posted to, instead of the `outbox`.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    pub outbox_url: Option<RpcUrl>,
+    /// The directory the signed proposals are picked up from.
+    pub inbox: PathBuf,
+    /// The address of the Governor's cold key, that must have signed the proposals.
+    #[schemars(with = "String")]
+    pub governor: Address,
+    /// How often, in milliseconds, the inbox is checked for signed proposals.
+    #[serde(default = "defaults::inbox_polling_interval")]
+    pub inbox_polling_interval: u64,
 }
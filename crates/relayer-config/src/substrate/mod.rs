@@ -4,11 +4,11 @@ use webb_relayer_types::{rpc_url::RpcUrl, suri::Suri};
 
 use crate::{
     anchor::LinkedAnchorConfig, event_watcher::EventsWatcherConfig,
-    signing_backend::ProposalSigningBackendConfig,
+    evm::HttpEndpoint, signing_backend::ProposalSigningBackendConfig,
 };
 
 /// SubstrateConfig is the relayer configuration for the Substrate based networks.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct SubstrateConfig {
     /// String that groups configuration for this chain on a human-readable name.
@@ -18,15 +18,19 @@ pub struct SubstrateConfig {
     pub enabled: bool,
     /// Http(s) Endpoint for quick Req/Res
     #[serde(skip_serializing)]
-    pub http_endpoint: RpcUrl,
-    /// Websocket Endpoint for long living connections
+    pub http_endpoint: HttpEndpoint,
+    /// Websocket Endpoint for long living connections.
+    ///
+    /// With multiple endpoints, the relayer connects to the first healthy one,
+    /// and fails over to the others when it is lost.
     #[serde(skip_serializing)]
-    pub ws_endpoint: RpcUrl,
+    pub ws_endpoint: WsEndpoint,
     /// Block Explorer for this Substrate node.
     ///
     /// Optional, and only used for printing a clickable links
     /// for transactions and contracts.
     #[serde(skip_serializing)]
+    #[schemars(with = "Option<String>")]
     pub explorer: Option<url::Url>,
     /// chain specific id (output of ChainIdentifier constant on LinkableTree Pallet)
     pub chain_id: u32,
@@ -38,6 +42,12 @@ pub struct SubstrateConfig {
     /// junction iterator.
     ///
     /// - If `s` begins with a `$` character it is interpreted as an environment variable.
+    /// - If `s` begins with `keystore:` it is interpreted as an encrypted keystore file holding
+    /// the seed, decrypted with the password after `#` (`keystore:<path>#$PASSWORD_VAR` or
+    /// `keystore:<path>#file:<password-path>`).
+    /// - If `s` begins with `vault:` it is fetched from a field of a Vault KV secret
+    /// (`vault:<url>#<field>`), using the token in the `VAULT_TOKEN` environment variable,
+    /// and interpreted as below.
     /// - If `s` is a possibly `0x` prefixed 64-digit hex string, then it will be interpreted
     /// directly as a `MiniSecretKey` (aka "seed" in `subkey`).
     /// - If `s` is a valid BIP-39 key phrase of 12, 15, 18, 21 or 24 words, then the key will
@@ -60,8 +70,10 @@ pub struct SubstrateConfig {
     ///
     /// `None` is returned if no matches are found.
     #[serde(skip_serializing)]
+    #[schemars(with = "Option<String>")]
     pub suri: Option<Suri>,
     /// Optionally, a user can specify an account to receive rewards for relaying
+    #[schemars(with = "Option<String>")]
     pub beneficiary: Option<Public>,
     /// Supported pallets over this substrate node.
     #[serde(default)]
@@ -69,10 +81,47 @@ pub struct SubstrateConfig {
     /// TxQueue configuration
     #[serde(skip_serializing, default)]
     pub tx_queue: TxQueueConfig,
+    /// The fees charged for the private transactions relayed on this node.
+    #[serde(default)]
+    pub fee_policy: FeePolicyConfig,
+}
+
+/// configuration for adding websocket endpoints.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum WsEndpoint {
+    /// Single websocket endpoint
+    Single(#[schemars(with = "String")] RpcUrl),
+    /// Multiple websocket endpoints
+    Multiple(#[schemars(with = "Vec<String>")] Vec<RpcUrl>),
+}
+
+impl WsEndpoint {
+    /// Returns all the endpoints, in the order they are tried.
+    pub fn urls(&self) -> Vec<RpcUrl> {
+        match self {
+            WsEndpoint::Single(url) => vec![url.clone()],
+            WsEndpoint::Multiple(urls) => urls.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for WsEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let urls: Vec<String> =
+            self.urls().iter().map(ToString::to_string).collect();
+        write!(f, "{}", urls.join(", "))
+    }
+}
+
+impl From<url::Url> for WsEndpoint {
+    fn from(url: url::Url) -> Self {
+        WsEndpoint::Single(url.into())
+    }
 }
 
 /// Linked anchor config for Substrate based target system
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct SubstrateLinkedAnchorConfig {
     /// chain Id
@@ -84,7 +133,7 @@ pub struct SubstrateLinkedAnchorConfig {
 }
 
 /// Enumerates the supported pallets configurations.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "pallet")]
 pub enum Pallet {
     /// `dkg-metadata` or as named in the runtime as `DKG` pallet.
@@ -101,7 +150,7 @@ pub enum Pallet {
 }
 
 /// DKGProposalsPalletConfig represents the configuration for the DKGProposals pallet.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct DKGProposalsPalletConfig {
     /// Controls the events watcher
@@ -109,14 +158,14 @@ pub struct DKGProposalsPalletConfig {
 }
 
 /// DKGPalletConfig represents the configuration for the DKG pallet (dkg-metadata).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct DKGPalletConfig {
     /// Controls the events watcher
     pub events_watcher: EventsWatcherConfig,
 }
 /// DKGProposalHandlerPalletConfig represents the configuration for the DKGProposalHandler pallet.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct DKGProposalHandlerPalletConfig {
     /// Controls the events watcher
@@ -124,7 +173,7 @@ pub struct DKGProposalHandlerPalletConfig {
 }
 
 /// SignatureBridgePalletConfig represents the configuration for the SignatureBridge pallet.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct SignatureBridgePalletConfig {
     /// Controls the events watcher
@@ -132,7 +181,7 @@ pub struct SignatureBridgePalletConfig {
 }
 
 /// VAnchorBn254PalletConfig represents the configuration for the VAnchorBn254 pallet.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all(serialize = "camelCase", deserialize = "kebab-case"))]
 pub struct VAnchorBn254PalletConfig {
     /// Controls the events watcher
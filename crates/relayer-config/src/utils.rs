@@ -1,5 +1,6 @@
 use config::{Config, File};
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 use crate::{anchor::LinkedAnchorConfig, evm::Contract, substrate::Pallet};
 
@@ -30,6 +31,22 @@ pub fn search_config_files<P: AsRef<Path>>(
         .collect()
 }
 
+/// Returns the config files in the given directory, along with the time they were
+/// last modified, so that any change to them could be detected.
+pub fn config_files_state<P: AsRef<Path>>(
+    base_dir: P,
+) -> webb_relayer_utils::Result<Vec<(PathBuf, Option<SystemTime>)>> {
+    let mut files = search_config_files(base_dir)?
+        .into_iter()
+        .map(|path| {
+            let modified = path.metadata().and_then(|m| m.modified()).ok();
+            (path, modified)
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+    Ok(files)
+}
+
 /// Try to parse the [`WebbRelayerConfig`] from the given config file(s).
 pub fn parse_from_files(
     files: &[PathBuf],
@@ -0,0 +1,767 @@
+use webb::evm::ethers::providers::{Http, Middleware, Provider};
+use webb::evm::ethers::types::U256;
+use webb_proposals::TypedChainId;
+
+use crate::anchor::LinkedAnchorConfig;
+use crate::event_watcher::EventsWatcherConfig;
+use crate::evm::{
+    Contract, EvmChainConfig, HttpEndpoint, SmartAnchorUpdatesConfig,
+};
+use crate::signing_backend::ProposalSigningBackendConfig;
+use crate::substrate::Pallet;
+
+use super::*;
+
+/// A problem found in the configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    /// The path of the faulty value in the configuration, like
+    /// `evm.goerli.contracts[0].linked-anchors[1]`.
+    pub path: String,
+    /// What is wrong with it.
+    pub message: String,
+}
+
+impl ConfigIssue {
+    fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+impl WebbRelayerConfig {
+    /// Checks the whole configuration, without connecting to any chain, and
+    /// returns every problem found in it.
+    ///
+    /// The disabled chains are not checked, since they are not used.
+    pub fn validate(&self) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+        let chains = self.typed_chain_ids();
+        let secrets_required =
+            self.features.governance_relay || self.features.private_tx_relay;
+        for chain in self.evm.values().filter(|c| c.enabled) {
+            let path = format!("evm.{}", chain.name);
+            let has_signer =
+                chain.private_key.is_some() || chain.remote_signer.is_some();
+            if secrets_required && !has_signer {
+                issues.push(ConfigIssue::new(
+                    format!("{path}.private-key"),
+                    "or a remote-signer is required by the governance-relay and private-tx-relay features",
+                ));
+            }
+            validate_signer(
+                &path,
+                chain.private_key.is_some(),
+                chain.remote_signer.is_some(),
+                &mut issues,
+            );
+            if !has_signer && !chain.accounts.is_empty() {
+                issues.push(ConfigIssue::new(
+                    format!("{path}.accounts"),
+                    "need the primary private-key or remote-signer too",
+                ));
+            }
+            for (i, account) in chain.accounts.iter().enumerate() {
+                let path = format!("{path}.accounts[{i}]");
+                if account.private_key.is_none()
+                    && account.remote_signer.is_none()
+                {
+                    issues.push(ConfigIssue::new(
+                        format!("{path}.private-key"),
+                        "or a remote-signer is required",
+                    ));
+                }
+                validate_signer(
+                    &path,
+                    account.private_key.is_some(),
+                    account.remote_signer.is_some(),
+                    &mut issues,
+                );
+            }
+            if chain.http_endpoint.urls().is_empty() {
+                issues.push(ConfigIssue::new(
+                    format!("{path}.http-endpoint"),
+                    "must have at least one endpoint",
+                ));
+            }
+            validate_tx_queue(&path, &chain.tx_queue, &mut issues);
+            validate_fee_policy(&path, &chain.fee_policy, &mut issues);
+            if let Some(poller) = &chain.block_poller {
+                let path = format!("{path}.block-poller");
+                validate_polling(
+                    &path,
+                    poller.polling_interval,
+                    poller.max_blocks_per_step,
+                    &mut issues,
+                );
+            }
+            for (i, contract) in chain.contracts.iter().enumerate() {
+                let path = format!("{path}.contracts[{i}]");
+                match contract {
+                    Contract::VAnchor(config) => {
+                        validate_events_watcher(
+                            &path,
+                            &config.events_watcher,
+                            &mut issues,
+                        );
+                        self.validate_signing_backend(
+                            &path,
+                            config.proposal_signing_backend.as_ref(),
+                            &mut issues,
+                        );
+                        validate_linked_anchors(
+                            &path,
+                            config.linked_anchors.as_deref(),
+                            &chains,
+                            &mut issues,
+                        );
+                        validate_smart_anchor_updates(
+                            &path,
+                            &config.smart_anchor_updates,
+                            &mut issues,
+                        );
+                    }
+                    Contract::SignatureBridge(config) => {
+                        validate_events_watcher(
+                            &path,
+                            &config.events_watcher,
+                            &mut issues,
+                        );
+                    }
+                }
+            }
+        }
+        for node in self.substrate.values().filter(|n| n.enabled) {
+            let path = format!("substrate.{}", node.name);
+            if secrets_required && node.suri.is_none() {
+                issues.push(ConfigIssue::new(
+                    format!("{path}.suri"),
+                    "is required by the governance-relay and private-tx-relay features",
+                ));
+            }
+            if node.ws_endpoint.urls().is_empty() {
+                issues.push(ConfigIssue::new(
+                    format!("{path}.ws-endpoint"),
+                    "must have at least one endpoint",
+                ));
+            }
+            validate_tx_queue(&path, &node.tx_queue, &mut issues);
+            validate_fee_policy(&path, &node.fee_policy, &mut issues);
+            for (i, pallet) in node.pallets.iter().enumerate() {
+                let path = format!("{path}.pallets[{i}]");
+                let events_watcher = match pallet {
+                    Pallet::Dkg(config) => &config.events_watcher,
+                    Pallet::DKGProposals(config) => &config.events_watcher,
+                    Pallet::DKGProposalHandler(config) => {
+                        &config.events_watcher
+                    }
+                    Pallet::SignatureBridge(config) => &config.events_watcher,
+                    Pallet::VAnchorBn254(config) => {
+                        self.validate_signing_backend(
+                            &path,
+                            config.proposal_signing_backend.as_ref(),
+                            &mut issues,
+                        );
+                        validate_linked_anchors(
+                            &path,
+                            config.linked_anchors.as_deref(),
+                            &chains,
+                            &mut issues,
+                        );
+                        &config.events_watcher
+                    }
+                };
+                validate_events_watcher(&path, events_watcher, &mut issues);
+            }
+        }
+        if self.features.proposals_admin && self.proposals_admin_token.is_none()
+        {
+            issues.push(ConfigIssue::new(
+                "proposals-admin-token",
+                "is required by the proposals-admin feature",
+            ));
+        }
+        if self.retention.enabled && self.retention.interval == 0 {
+            issues.push(ConfigIssue::new(
+                "retention.interval",
+                "must be greater than zero",
+            ));
+        }
+        issues
+    }
+
+    /// Connects to every enabled chain, and returns the problems found with
+    /// them, that is the endpoints that report another chain id, and the
+    /// contracts that have no code at their address.
+    ///
+    /// The chains that could not be reached are reported as well.
+    pub async fn validate_online(&self) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+        for chain in self.evm.values().filter(|c| c.enabled) {
+            validate_evm_chain_online(chain, &mut issues).await;
+        }
+        #[cfg(feature = "substrate-runtime")]
+        for node in self.substrate.values().filter(|n| n.enabled) {
+            validate_substrate_node_online(node, &mut issues).await;
+        }
+        issues
+    }
+
+    /// Returns the chain ids of all the configured chains.
+    fn typed_chain_ids(&self) -> HashSet<TypedChainId> {
+        let evm = self.evm.values().map(|c| TypedChainId::Evm(c.chain_id));
+        let substrate = self
+            .substrate
+            .values()
+            .map(|c| TypedChainId::Substrate(c.chain_id));
+        evm.chain(substrate).collect()
+    }
+
+    /// Checks that the DKG node of a signing backend is configured.
+    fn validate_signing_backend(
+        &self,
+        path: &str,
+        backend: Option<&ProposalSigningBackendConfig>,
+        issues: &mut Vec<ConfigIssue>,
+    ) {
+        match backend {
+            Some(ProposalSigningBackendConfig::DkgNode(c)) => {
+                let defined =
+                    self.substrate.values().any(|n| n.chain_id == c.chain_id);
+                if !defined {
+                    issues.push(ConfigIssue::new(
+                        format!("{path}.proposal-signing-backend.chain-id"),
+                        format!(
+                            "no substrate node is defined for chain {}",
+                            c.chain_id
+                        ),
+                    ));
+                }
+            }
+            Some(ProposalSigningBackendConfig::Mocked(c)) => {
+                let path = format!("{path}.proposal-signing-backend");
+                if c.private_key.is_none() && c.remote_signer.is_none() {
+                    issues.push(ConfigIssue::new(
+                        format!("{path}.private-key"),
+                        "or a remote-signer is required by the Mocked backend",
+                    ));
+                }
+                validate_signer(
+                    &path,
+                    c.private_key.is_some(),
+                    c.remote_signer.is_some(),
+                    issues,
+                );
+            }
+            Some(ProposalSigningBackendConfig::Threshold(c)) => {
+                let path = format!("{path}.proposal-signing-backend");
+                if c.threshold == 0 || c.threshold > c.signers.len() {
+                    issues.push(ConfigIssue::new(
+                        format!("{path}.threshold"),
+                        format!(
+                            "must be between 1 and the {} signers",
+                            c.signers.len()
+                        ),
+                    ));
+                }
+                for (i, signer) in c.signers.iter().enumerate() {
+                    let path = format!("{path}.signers[{i}]");
+                    if signer.private_key.is_none()
+                        && signer.remote_signer.is_none()
+                    {
+                        issues.push(ConfigIssue::new(
+                            format!("{path}.private-key"),
+                            "or a remote-signer is required",
+                        ));
+                    }
+                    validate_signer(
+                        &path,
+                        signer.private_key.is_some(),
+                        signer.remote_signer.is_some(),
+                        issues,
+                    );
+                }
+            }
+            Some(ProposalSigningBackendConfig::AirGapped(c)) => {
+                let path = format!("{path}.proposal-signing-backend");
+                match (&c.outbox, &c.outbox_url) {
+                    (None, None) => issues.push(ConfigIssue::new(
+                        format!("{path}.outbox"),
+                        "or an outbox-url is required by the AirGapped backend",
+                    )),
+                    (Some(_), Some(_)) => issues.push(ConfigIssue::new(
+                        format!("{path}.outbox-url"),
+                        "cannot be used along with an outbox",
+                    )),
+                    _ => {}
+                }
+                if c.inbox_polling_interval == 0 {
+                    issues.push(ConfigIssue::new(
+                        format!("{path}.inbox-polling-interval"),
+                        "must be greater than zero",
+                    ));
+                }
+            }
+            None => {}
+        }
+    }
+}
+
+/// Checks that an account is not given both a private key and a remote signer.
+fn validate_signer(
+    path: &str,
+    has_private_key: bool,
+    has_remote_signer: bool,
+    issues: &mut Vec<ConfigIssue>,
+) {
+    if has_private_key && has_remote_signer {
+        issues.push(ConfigIssue::new(
+            format!("{path}.remote-signer"),
+            "cannot be used along with a private-key",
+        ));
+    }
+}
+
+/// Checks that the linked anchors point at configured chains.
+fn validate_linked_anchors(
+    path: &str,
+    linked_anchors: Option<&[LinkedAnchorConfig]>,
+    chains: &HashSet<TypedChainId>,
+    issues: &mut Vec<ConfigIssue>,
+) {
+    for (i, anchor) in linked_anchors.unwrap_or_default().iter().enumerate() {
+        let typed_chain_id = match anchor {
+            LinkedAnchorConfig::Raw(raw) => {
+                let bytes: [u8; 32] = raw.resource_id.into();
+                webb_proposals::ResourceId::from(bytes).typed_chain_id()
+            }
+            LinkedAnchorConfig::Evm(c) => TypedChainId::Evm(c.chain_id),
+            LinkedAnchorConfig::Substrate(c) => {
+                TypedChainId::Substrate(c.chain_id)
+            }
+        };
+        if !chains.contains(&typed_chain_id) {
+            issues.push(ConfigIssue::new(
+                format!("{path}.linked-anchors[{i}]"),
+                format!(
+                    "points at {typed_chain_id:?}, which is not configured"
+                ),
+            ));
+        }
+    }
+}
+
+fn validate_events_watcher(
+    path: &str,
+    config: &EventsWatcherConfig,
+    issues: &mut Vec<ConfigIssue>,
+) {
+    if config.enabled {
+        let path = format!("{path}.events-watcher");
+        validate_polling(
+            &path,
+            config.polling_interval,
+            config.max_blocks_per_step,
+            issues,
+        );
+    }
+}
+
+fn validate_tx_queue(
+    path: &str,
+    config: &TxQueueConfig,
+    issues: &mut Vec<ConfigIssue>,
+) {
+    if config.polling_interval == 0 {
+        issues.push(ConfigIssue::new(
+            format!("{path}.tx-queue.polling-interval"),
+            "must be greater than zero",
+        ));
+    }
+    if config.max_sleep_interval == 0 {
+        issues.push(ConfigIssue::new(
+            format!("{path}.tx-queue.max-sleep-interval"),
+            "must be greater than zero",
+        ));
+    }
+}
+
+fn validate_fee_policy(
+    path: &str,
+    config: &FeePolicyConfig,
+    issues: &mut Vec<ConfigIssue>,
+) {
+    let amounts = [
+        ("profit-usd", config.profit_usd),
+        ("profit-percent", config.profit_percent),
+        ("max-refund-usd", config.max_refund_usd),
+        ("min-fee-usd", config.min_fee_usd),
+    ];
+    for (key, amount) in amounts {
+        if !amount.is_finite() || amount < 0. {
+            issues.push(ConfigIssue::new(
+                format!("{path}.fee-policy.{key}"),
+                "must not be negative",
+            ));
+        }
+    }
+    if !(0. ..100.).contains(&config.tolerance_percent) {
+        issues.push(ConfigIssue::new(
+            format!("{path}.fee-policy.tolerance-percent"),
+            "must be at least 0 and less than 100",
+        ));
+    }
+}
+
+/// Checks the settings shared by the event watchers and the block pollers.
+fn validate_polling(
+    path: &str,
+    polling_interval: u64,
+    max_blocks_per_step: u64,
+    issues: &mut Vec<ConfigIssue>,
+) {
+    if polling_interval == 0 {
+        issues.push(ConfigIssue::new(
+            format!("{path}.polling-interval"),
+            "must be greater than zero",
+        ));
+    }
+    if max_blocks_per_step == 0 {
+        issues.push(ConfigIssue::new(
+            format!("{path}.max-blocks-per-step"),
+            "must be greater than zero",
+        ));
+    }
+}
+
+fn validate_smart_anchor_updates(
+    path: &str,
+    config: &SmartAnchorUpdatesConfig,
+    issues: &mut Vec<ConfigIssue>,
+) {
+    if !config.enabled {
+        return;
+    }
+    if let (Some(min), Some(max)) =
+        (config.min_time_delay, config.max_time_delay)
+    {
+        if min > max {
+            issues.push(ConfigIssue::new(
+                format!("{path}.smart-anchor-updates.min-time-delay"),
+                format!("must not be greater than the max-time-delay ({max})"),
+            ));
+        }
+    }
+    if config.time_delay_window_size == Some(0) {
+        issues.push(ConfigIssue::new(
+            format!("{path}.smart-anchor-updates.time-delay-window-size"),
+            "must be greater than zero",
+        ));
+    }
+    if config.max_gas_price == Some(0) {
+        issues.push(ConfigIssue::new(
+            format!("{path}.smart-anchor-updates.max-gas-price"),
+            "must be greater than zero",
+        ));
+    }
+    if config.max_gas_price.is_some()
+        && config.gas_price_max_staleness == Some(0)
+    {
+        issues.push(ConfigIssue::new(
+            format!("{path}.smart-anchor-updates.gas-price-max-staleness"),
+            "must be greater than zero, or the gas price is never checked",
+        ));
+    }
+}
+
+async fn validate_evm_chain_online(
+    chain: &EvmChainConfig,
+    issues: &mut Vec<ConfigIssue>,
+) {
+    let path = format!("evm.{}", chain.name);
+    let endpoints = chain.http_endpoint.urls();
+    let mut reachable = None;
+    for (i, url) in endpoints.iter().enumerate() {
+        let path = match chain.http_endpoint {
+            HttpEndpoint::Single(_) => format!("{path}.http-endpoint"),
+            HttpEndpoint::Multiple(_) => format!("{path}.http-endpoint[{i}]"),
+        };
+        let provider = Provider::new(Http::new(url.clone()));
+        match provider.get_chainid().await {
+            Ok(chain_id) if chain_id == U256::from(chain.chain_id) => {
+                reachable.get_or_insert(provider);
+            }
+            Ok(chain_id) => issues.push(ConfigIssue::new(
+                path,
+                format!(
+                    "reports chain id {chain_id}, but {} is configured",
+                    chain.chain_id
+                ),
+            )),
+            Err(e) => issues.push(ConfigIssue::new(
+                path,
+                format!("could not be reached: {e}"),
+            )),
+        }
+    }
+    // the contracts could only be checked on the right chain.
+    let Some(provider) = reachable else {
+        return;
+    };
+    for (i, contract) in chain.contracts.iter().enumerate() {
+        let address = match contract {
+            Contract::VAnchor(c) => c.common.address,
+            Contract::SignatureBridge(c) => c.common.address,
+        };
+        let path = format!("{path}.contracts[{i}].address");
+        match provider.get_code(address, None).await {
+            Ok(code) if code.is_empty() => {
+                issues.push(ConfigIssue::new(
+                    path,
+                    format!("no contract is deployed at {address:?}"),
+                ));
+            }
+            Ok(_) => {}
+            Err(e) => issues.push(ConfigIssue::new(
+                path,
+                format!("could not get the code of {address:?}: {e}"),
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "substrate-runtime")]
+async fn validate_substrate_node_online(
+    node: &crate::substrate::SubstrateConfig,
+    issues: &mut Vec<ConfigIssue>,
+) {
+    use crate::substrate::WsEndpoint;
+    use webb::substrate::subxt::{OnlineClient, PolkadotConfig};
+    let path = format!("substrate.{}.ws-endpoint", node.name);
+    for (i, url) in node.ws_endpoint.urls().iter().enumerate() {
+        let path = match node.ws_endpoint {
+            WsEndpoint::Single(_) => path.clone(),
+            WsEndpoint::Multiple(_) => format!("{path}[{i}]"),
+        };
+        let url = url.to_string();
+        match OnlineClient::<PolkadotConfig>::from_url(url).await {
+            Ok(client) => match substrate_chain_id(&client) {
+                Some(chain_id) if chain_id != u64::from(node.chain_id) => {
+                    issues.push(ConfigIssue::new(
+                        path,
+                        format!(
+                            "reports chain id {chain_id}, but {} is configured",
+                            node.chain_id
+                        ),
+                    ))
+                }
+                // the nodes without these pallets have no chain id to check.
+                _ => {}
+            },
+            Err(e) => issues.push(ConfigIssue::new(
+                path,
+                format!("could not be reached: {e}"),
+            )),
+        }
+    }
+}
+
+/// Returns the chain id of a substrate node, that is the `ChainIdentifier`
+/// constant of its `LinkableTreeBn254` pallet, or of its `DKGProposals` pallet.
+#[cfg(feature = "substrate-runtime")]
+fn substrate_chain_id(
+    client: &webb::substrate::subxt::OnlineClient<
+        webb::substrate::subxt::PolkadotConfig,
+    >,
+) -> Option<u64> {
+    use webb::substrate::tangle_runtime::api as RuntimeApi;
+    use webb::substrate::tangle_runtime::api::runtime_types::webb_proposals::header::TypedChainId as DkgTypedChainId;
+    let linkable_tree_chain_id = RuntimeApi::constants()
+        .linkable_tree_bn254()
+        .chain_identifier();
+    if let Ok(chain_id) = client.constants().at(&linkable_tree_chain_id) {
+        return Some(chain_id);
+    }
+    let dkg_chain_id =
+        RuntimeApi::constants().dkg_proposals().chain_identifier();
+    match client.constants().at(&dkg_chain_id).ok()? {
+        DkgTypedChainId::Substrate(chain_id)
+        | DkgTypedChainId::PolkadotParachain(chain_id)
+        | DkgTypedChainId::KusamaParachain(chain_id) => Some(chain_id.into()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evm::RemoteSignerConfig;
+
+    #[test]
+    fn validate_should_report_every_issue() {
+        let chain: EvmChainConfig = serde_json::from_value(serde_json::json!({
+            "name": "hermes",
+            "enabled": true,
+            "http-endpoint": "http://localhost:5001",
+            "ws-endpoint": "ws://localhost:5001",
+            "chain-id": 5001,
+            "fee-policy": { "tolerance-percent": 100 },
+            "contracts": [{
+                "contract": "VAnchor",
+                "address": "0x0000000000000000000000000000000000000001",
+                "deployed-at": 1,
+                "events-watcher": {
+                    "enabled": true,
+                    "polling-interval": 0,
+                },
+                "proposal-signing-backend": {
+                    "type": "DKGNode",
+                    "chain-id": 1080,
+                },
+                "linked-anchors": [
+                    {
+                        "type": "Evm",
+                        "chain-id": 5001,
+                        "address": "0x0000000000000000000000000000000000000002",
+                    },
+                    {
+                        "type": "Evm",
+                        "chain-id": 5002,
+                        "address": "0x0000000000000000000000000000000000000003",
+                    },
+                ],
+            }],
+        }))
+        .unwrap();
+        let mut config = WebbRelayerConfig::default();
+        config.evm.insert(chain.chain_id.to_string(), chain);
+        let issues = config.validate();
+        let paths = issues.iter().map(|i| i.path.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                "evm.hermes.private-key",
+                "evm.hermes.fee-policy.tolerance-percent",
+                "evm.hermes.contracts[0].events-watcher.polling-interval",
+                "evm.hermes.contracts[0].proposal-signing-backend.chain-id",
+                "evm.hermes.contracts[0].linked-anchors[1]",
+            ]
+        );
+        assert!(config.verify().is_err());
+
+        let mut config = config.clone();
+        config.features.governance_relay = false;
+        config.features.private_tx_relay = false;
+        assert_eq!(config.validate().len(), 4);
+
+        // the admin actions are never left without a credential.
+        config.features.proposals_admin = true;
+        let issues = config.validate();
+        assert!(issues.iter().any(|i| i.path == "proposals-admin-token"));
+        config.proposals_admin_token = Some("s3cr3t".parse().unwrap());
+        assert_eq!(config.validate().len(), 4);
+
+        // an account is signed for either by its key or by a remote signer.
+        let chain = config.evm.get_mut("5001").unwrap();
+        chain.private_key = Some(ethereum_types::H256::repeat_byte(7).into());
+        chain.remote_signer = Some(RemoteSignerConfig {
+            url: "http://localhost:9000".parse::<url::Url>().unwrap().into(),
+            address: Default::default(),
+        });
+        let issues = config.validate();
+        assert!(issues.iter().any(|i| i.path == "evm.hermes.remote-signer"));
+
+        // a threshold cannot be met by fewer signers.
+        let chain = config.evm.get_mut("5001").unwrap();
+        let Contract::VAnchor(vanchor) = &mut chain.contracts[0] else {
+            unreachable!()
+        };
+        vanchor.proposal_signing_backend = Some(
+            serde_json::from_value(serde_json::json!({
+                "type": "Threshold",
+                "threshold": 3,
+                "signers": [
+                    { "private-key": format!("0x{}", "07".repeat(32)) },
+                    {},
+                ],
+            }))
+            .unwrap(),
+        );
+        let issues = config.validate();
+        let backend = "evm.hermes.contracts[0].proposal-signing-backend";
+        for path in ["threshold", "signers[1].private-key"] {
+            let path = format!("{backend}.{path}");
+            assert!(issues.iter().any(|i| i.path == path));
+        }
+
+        // the unsigned proposals must be exported somewhere.
+        let chain = config.evm.get_mut("5001").unwrap();
+        let Contract::VAnchor(vanchor) = &mut chain.contracts[0] else {
+            unreachable!()
+        };
+        vanchor.proposal_signing_backend = Some(
+            serde_json::from_value(serde_json::json!({
+                "type": "AirGapped",
+                "inbox": "/var/relayer/inbox",
+                "governor": "0x0000000000000000000000000000000000000004",
+            }))
+            .unwrap(),
+        );
+        let issues = config.validate();
+        let path = format!("{backend}.outbox");
+        assert!(issues.iter().any(|i| i.path == path));
+
+        // the anchor updates are never sent below a zero gas price.
+        let chain = config.evm.get_mut("5001").unwrap();
+        let Contract::VAnchor(vanchor) = &mut chain.contracts[0] else {
+            unreachable!()
+        };
+        vanchor.smart_anchor_updates.enabled = true;
+        vanchor.smart_anchor_updates.max_gas_price = Some(0);
+        let issues = config.validate();
+        assert!(issues.iter().any(|i| {
+            i.path == "evm.hermes.contracts[0].smart-anchor-updates.max-gas-price"
+        }));
+    }
+
+    #[test]
+    fn validate_should_skip_disabled_chains() {
+        let chain: EvmChainConfig = serde_json::from_value(serde_json::json!({
+            "name": "hermes",
+            "enabled": false,
+            "http-endpoint": "http://localhost:5001",
+            "ws-endpoint": "ws://localhost:5001",
+            "chain-id": 5001,
+        }))
+        .unwrap();
+        let node: crate::substrate::SubstrateConfig =
+            serde_json::from_value(serde_json::json!({
+                "name": "tangle",
+                "enabled": false,
+                "http-endpoint": "http://localhost:9933",
+                "ws-endpoint": "ws://localhost:9944",
+                "chain-id": 1081,
+            }))
+            .unwrap();
+        let mut config = WebbRelayerConfig::default();
+        config.evm.insert(chain.chain_id.to_string(), chain);
+        config.substrate.insert(node.chain_id.to_string(), node);
+        assert!(config.features.governance_relay);
+        assert!(config.validate().is_empty());
+
+        // once enabled, they need their keys.
+        config.evm.get_mut("5001").unwrap().enabled = true;
+        config.substrate.get_mut("1081").unwrap().enabled = true;
+        let issues = config.validate();
+        let paths = issues.iter().map(|i| i.path.as_str()).collect::<Vec<_>>();
+        assert_eq!(paths, ["evm.hermes.private-key", "substrate.tangle.suri"]);
+    }
+}
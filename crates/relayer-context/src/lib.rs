@@ -16,6 +16,7 @@
 //! # Relayer Context Module 🕸️
 //!
 //! A module for managing the context of the relayer.
+use std::collections::BTreeMap;
 use std::time::Duration;
 use std::{collections::HashMap, sync::Arc};
 use tokio::sync::{broadcast, Mutex};
@@ -35,21 +36,84 @@ use webb::evm::ethers::middleware::gas_oracle::{
 #[cfg(feature = "substrate")]
 use webb::substrate::subxt;
 
+use webb_proposal_signing_backends::queue::control::ProposalsQueueRegistry;
+
 use webb_price_oracle_backends::{
-    CachedPriceBackend, CoinGeckoBackend, DummyPriceBackend, PriceOracleMerger,
+    CachedPrice, CachedPriceBackend, CoinGeckoBackend, DummyPriceBackend,
+    PriceOracleMerger,
+};
+use webb_proposal_signing_backends::queue::store::ProposalsQueueState;
+#[cfg(feature = "evm")]
+use webb_relayer_config::evm::{EvmChainConfig, RemoteSignerConfig};
+use webb_relayer_store::merkle_tree::MerkleTreeCache;
+use webb_relayer_store::{
+    BatchStore, BridgeCommand, EncryptedOutputCacheStore, EventHashStore,
+    HistoryStore, LeafCacheStore, ProposalsQueueStore, QueueStore, ReorgStore,
+    RetentionStore, SledQueueKey, TokenPriceCacheStore,
 };
-use webb_relayer_store::SledStore;
+#[cfg(feature = "evm")]
+use webb_relayer_utils::evm_signer::{EvmSigner, RemoteSigner};
 use webb_relayer_utils::metric::{self, Metrics};
+use webb_relayer_utils::static_tx_payload::TypeErasedStaticTxPayload;
 
 mod ethers_retry_policy;
 use ethers_retry_policy::WebbHttpRetryPolicy;
 use webb_relayer_utils::multi_provider::MultiProvider;
+use webb_relayer_utils::substrate_endpoints::SubstrateEndpoints;
+use webb_relayer_utils::wallet_pool::WalletPool;
 
 type EthersClient = Provider<RetryClient<MultiProvider<Http>>>;
 
+/// The store of the relayer.
+///
+/// It is implemented for every store that implements the store traits used by
+/// the services of the relayer, so any backend, even one that is not part of
+/// `webb-relayer-store`, could be used as one.
+pub trait RelayerStore:
+    HistoryStore
+    + EventHashStore
+    + LeafCacheStore<Output = BTreeMap<u32, types::H256>>
+    + EncryptedOutputCacheStore<Output = Vec<Vec<u8>>>
+    + ReorgStore
+    + QueueStore<
+        types::transaction::eip2718::TypedTransaction,
+        Key = SledQueueKey,
+    > + QueueStore<TypeErasedStaticTxPayload, Key = SledQueueKey>
+    + QueueStore<BridgeCommand, Key = SledQueueKey>
+    + TokenPriceCacheStore<CachedPrice>
+    + ProposalsQueueStore<ProposalsQueueState>
+    + RetentionStore
+    + BatchStore
+    + std::fmt::Debug
+    + 'static
+{
+}
+
+impl<S> RelayerStore for S where
+    S: HistoryStore
+        + EventHashStore
+        + LeafCacheStore<Output = BTreeMap<u32, types::H256>>
+        + EncryptedOutputCacheStore<Output = Vec<Vec<u8>>>
+        + ReorgStore
+        + QueueStore<
+            types::transaction::eip2718::TypedTransaction,
+            Key = SledQueueKey,
+        > + QueueStore<TypeErasedStaticTxPayload, Key = SledQueueKey>
+        + QueueStore<BridgeCommand, Key = SledQueueKey>
+        + TokenPriceCacheStore<CachedPrice>
+        + ProposalsQueueStore<ProposalsQueueState>
+        + RetentionStore
+        + BatchStore
+        + std::fmt::Debug
+        + 'static
+{
+}
+
 /// RelayerContext contains Relayer's configuration and shutdown signal.
+///
+/// It is generic over the store `S` of the relayer, see [`RelayerStore`].
 #[derive(Clone)]
-pub struct RelayerContext {
+pub struct RelayerContext<S> {
     /// The configuration of the relayer.
     pub config: webb_relayer_config::WebbRelayerConfig,
     /// Broadcasts a shutdown signal to all active connections.
@@ -63,7 +127,11 @@ pub struct RelayerContext {
     notify_shutdown: broadcast::Sender<()>,
     /// Represents the metrics for the relayer
     pub metrics: Arc<Mutex<metric::Metrics>>,
-    store: SledStore,
+    store: S,
+    /// The Merkle trees of the VAnchor resources, kept up to date by the event watchers.
+    merkle_trees: MerkleTreeCache,
+    /// The proposals queues of the VAnchor resources, registered by the event watchers.
+    proposals_queues: ProposalsQueueRegistry,
     /// Price backend for fetching prices.
     price_oracle: Arc<PriceOracleMerger>,
     /// Hashmap of <ChainID, Etherscan Client>
@@ -71,17 +139,66 @@ pub struct RelayerContext {
 
     /// Evm Providers Cache.
     evm_providers: Arc<HashMap<types::U256, Arc<EthersClient>>>,
+    /// The accounts of the relayer on every EVM chain.
+    evm_wallet_pools: Arc<HashMap<types::U256, WalletPool>>,
+    /// The websocket endpoints of the Substrate nodes.
+    substrate_endpoints: Arc<HashMap<types::U256, SubstrateEndpoints>>,
 }
 
-impl RelayerContext {
+impl<S> RelayerContext<S>
+where
+    S: TokenPriceCacheStore<CachedPrice> + Clone + Send + Sync + 'static,
+{
     /// Creates a new RelayerContext.
     pub fn new(
         config: webb_relayer_config::WebbRelayerConfig,
-        store: SledStore,
+        store: S,
     ) -> webb_relayer_utils::Result<Self> {
         let (notify_shutdown, _) = broadcast::channel(2);
         let metrics = Arc::new(Mutex::new(Metrics::new()?));
+        Self::build(
+            config,
+            store,
+            notify_shutdown,
+            metrics,
+            MerkleTreeCache::default(),
+            ProposalsQueueRegistry::default(),
+            &HashMap::new(),
+        )
+    }
+
+    /// Creates a new RelayerContext for the given configuration, which shares the
+    /// store, the metrics, the Merkle trees, the proposals queues and the shutdown
+    /// signal of this one.
+    ///
+    /// Used to reload the configuration of a running relayer. The accounts
+    /// that are in both configurations share their nonces, since the services
+    /// of the unchanged chains keep using this context.
+    pub fn with_config(
+        &self,
+        config: webb_relayer_config::WebbRelayerConfig,
+    ) -> webb_relayer_utils::Result<Self> {
+        Self::build(
+            config,
+            self.store.clone(),
+            self.notify_shutdown.clone(),
+            self.metrics.clone(),
+            self.merkle_trees.clone(),
+            self.proposals_queues.clone(),
+            &self.evm_wallet_pools,
+        )
+    }
 
+    /// Creates the providers and oracles of the given configuration.
+    fn build(
+        config: webb_relayer_config::WebbRelayerConfig,
+        store: S,
+        notify_shutdown: broadcast::Sender<()>,
+        metrics: Arc<Mutex<metric::Metrics>>,
+        merkle_trees: MerkleTreeCache,
+        proposals_queues: ProposalsQueueRegistry,
+        previous_pools: &HashMap<types::U256, WalletPool>,
+    ) -> webb_relayer_utils::Result<Self> {
         let dummy_backend = {
             let price_map = config
                 .assets
@@ -148,16 +265,50 @@ impl RelayerContext {
                 .insert(chain_config.chain_id.into(), Arc::new(provider));
         }
 
+        let mut evm_wallet_pools = HashMap::new();
+        for chain_config in config.evm.values() {
+            let chain_id = types::U256::from(chain_config.chain_id);
+            let Some(pool) = wallet_pool_of(chain_config)? else {
+                continue;
+            };
+            let pool = match previous_pools.get(&chain_id) {
+                Some(previous) => pool.sharing_nonces_with(previous),
+                None => pool,
+            };
+            evm_wallet_pools.insert(chain_id, pool);
+        }
+
+        let substrate_endpoints = config
+            .substrate
+            .values()
+            .map(|node_config| {
+                let urls = node_config
+                    .ws_endpoint
+                    .urls()
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect();
+                (node_config.chain_id.into(), SubstrateEndpoints::new(urls))
+            })
+            .collect();
+
         Ok(Self {
             config,
             notify_shutdown,
             metrics,
             store,
+            merkle_trees,
+            proposals_queues,
             price_oracle,
             etherscan_clients: Arc::new(etherscan_clients),
             evm_providers: Arc::new(evm_providers),
+            evm_wallet_pools: Arc::new(evm_wallet_pools),
+            substrate_endpoints: Arc::new(substrate_endpoints),
         })
     }
+}
+
+impl<S> RelayerContext<S> {
     /// Returns a broadcast receiver handle for the shutdown signal.
     pub fn shutdown_signal(&self) -> Shutdown {
         Shutdown::new(self.notify_shutdown.subscribe())
@@ -166,6 +317,31 @@ impl RelayerContext {
     pub fn shutdown(&self) {
         let _ = self.notify_shutdown.send(());
     }
+    /// Returns a copy of this context with its own shutdown signal, so the tasks
+    /// started with it could be shut down without the others.
+    ///
+    /// The returned context is still shut down along with this one.
+    pub fn scoped(&self) -> Self
+    where
+        S: Clone,
+    {
+        let (notify_shutdown, _) = broadcast::channel(2);
+        let mut parent = self.shutdown_signal();
+        let mut this = Shutdown::new(notify_shutdown.subscribe());
+        let notify = notify_shutdown.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = parent.recv() => {
+                    let _ = notify.send(());
+                },
+                _ = this.recv() => {},
+            }
+        });
+        Self {
+            notify_shutdown,
+            ..self.clone()
+        }
+    }
     /// Returns a new `EthereumProvider` for the relayer.
     ///
     /// # Arguments
@@ -186,7 +362,10 @@ impl RelayerContext {
             })
         }
     }
-    /// Sets up and returns an EVM wallet for the relayer.
+    /// Sets up and returns an EVM wallet for the relayer, which signs either
+    /// with the private key of the chain, or with its remote signer.
+    ///
+    /// This is the primary account of the [`WalletPool`] of the chain.
     ///
     /// # Arguments
     ///
@@ -195,26 +374,37 @@ impl RelayerContext {
     pub async fn evm_wallet<I: Into<types::U256>>(
         &self,
         chain_id: I,
-    ) -> webb_relayer_utils::Result<LocalWallet> {
+    ) -> webb_relayer_utils::Result<EvmSigner> {
+        let pool = self.evm_wallet_pool(chain_id)?;
+        Ok(pool.primary().clone())
+    }
+    /// Returns the pool of the accounts of the relayer on an EVM chain, to
+    /// send transactions from, with their nonces managed locally.
+    ///
+    /// # Arguments
+    ///
+    /// * `chain_id` - A string representing the chain id.
+    #[cfg(feature = "evm")]
+    pub fn evm_wallet_pool<I: Into<types::U256>>(
+        &self,
+        chain_id: I,
+    ) -> webb_relayer_utils::Result<WalletPool> {
         let chain_id: types::U256 = chain_id.into();
-        let chain_name = chain_id.to_string();
-        let chain_config =
-            self.config.evm.get(&chain_name).ok_or_else(|| {
-                webb_relayer_utils::Error::ChainNotFound {
-                    chain_id: chain_name.to_string(),
-                }
-            })?;
-        let private_key = chain_config
-            .private_key
-            .as_ref()
-            .ok_or(webb_relayer_utils::Error::MissingSecrets)?;
-        let key = SecretKey::from_bytes(private_key.as_bytes().into())?;
-        let chain_id = chain_config.chain_id;
-        let wallet = LocalWallet::from(key).with_chain_id(chain_id);
-        Ok(wallet)
+        if !self.config.evm.contains_key(&chain_id.to_string()) {
+            return Err(webb_relayer_utils::Error::ChainNotFound {
+                chain_id: chain_id.to_string(),
+            });
+        }
+        self.evm_wallet_pools
+            .get(&chain_id)
+            .cloned()
+            .ok_or(webb_relayer_utils::Error::MissingSecrets)
     }
     /// Sets up and returns a Substrate client for the relayer.
     ///
+    /// The client is connected to the first healthy endpoint of the node, so
+    /// calling this again after losing an endpoint fails over to the next one.
+    ///
     /// # Arguments
     ///
     /// * `chain_id` - A string representing the chain ID.
@@ -223,19 +413,27 @@ impl RelayerContext {
         &self,
         chain_id: I,
     ) -> webb_relayer_utils::Result<subxt::OnlineClient<C>> {
+        let endpoints = self.substrate_endpoints(chain_id)?;
+        endpoints.connect().await
+    }
+    /// Returns the websocket endpoints of a Substrate node, to connect to it
+    /// again whenever it is lost.
+    ///
+    /// # Arguments
+    ///
+    /// * `chain_id` - A string representing the chain ID.
+    #[cfg(feature = "substrate")]
+    pub fn substrate_endpoints<I: Into<types::U256>>(
+        &self,
+        chain_id: I,
+    ) -> webb_relayer_utils::Result<SubstrateEndpoints> {
         let chain_id: types::U256 = chain_id.into();
-        let chain_name = chain_id.to_string();
-        let node_config =
-            self.config.substrate.get(&chain_name).ok_or_else(|| {
-                webb_relayer_utils::Error::NodeNotFound {
-                    chain_id: chain_id.to_string(),
-                }
-            })?;
-        let client = subxt::OnlineClient::<C>::from_url(
-            node_config.ws_endpoint.to_string(),
-        )
-        .await?;
-        Ok(client)
+        self.substrate_endpoints
+            .get(&chain_id)
+            .cloned()
+            .ok_or_else(|| webb_relayer_utils::Error::NodeNotFound {
+                chain_id: chain_id.to_string(),
+            })
     }
     /// Sets up and returns a Substrate wallet for the relayer.
     ///
@@ -261,11 +459,21 @@ impl RelayerContext {
         Ok(suri_key.into())
     }
 
-    /// Returns [Sled](https://sled.rs)-based database store
-    pub fn store(&self) -> &SledStore {
+    /// Returns the database store.
+    pub fn store(&self) -> &S {
         &self.store
     }
 
+    /// Returns the Merkle trees of the VAnchor resources.
+    pub fn merkle_trees(&self) -> &MerkleTreeCache {
+        &self.merkle_trees
+    }
+
+    /// Returns the proposals queues of the VAnchor resources.
+    pub fn proposals_queues(&self) -> &ProposalsQueueRegistry {
+        &self.proposals_queues
+    }
+
     /// Returns a price oracle for fetching token prices.
     pub fn price_oracle(&self) -> Arc<PriceOracleMerger> {
         self.price_oracle.clone()
@@ -302,6 +510,55 @@ impl RelayerContext {
     }
 }
 
+/// Returns the pool of the accounts of an EVM chain, the primary one first, or
+/// `None` if the chain has no primary account.
+#[cfg(feature = "evm")]
+fn wallet_pool_of(
+    chain_config: &EvmChainConfig,
+) -> webb_relayer_utils::Result<Option<WalletPool>> {
+    let primary = match evm_signer(
+        chain_config.private_key.as_deref(),
+        chain_config.remote_signer.as_ref(),
+    ) {
+        Ok(signer) => signer,
+        Err(webb_relayer_utils::Error::MissingSecrets) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let mut signers = vec![primary];
+    for account in &chain_config.accounts {
+        let signer = evm_signer(
+            account.private_key.as_deref(),
+            account.remote_signer.as_ref(),
+        )?;
+        signers.push(signer);
+    }
+    let signers = signers
+        .into_iter()
+        .map(|signer| signer.with_chain_id(chain_config.chain_id))
+        .collect();
+    Ok(Some(WalletPool::new(signers)))
+}
+
+/// Returns the signer of an EVM account, that is given either its private key
+/// or a remote signer that holds it.
+#[cfg(feature = "evm")]
+pub fn evm_signer(
+    private_key: Option<&types::H256>,
+    remote_signer: Option<&RemoteSignerConfig>,
+) -> webb_relayer_utils::Result<EvmSigner> {
+    match (private_key, remote_signer) {
+        (_, Some(remote)) => {
+            let url = remote.url.as_url().clone();
+            Ok(RemoteSigner::new(url, remote.address).into())
+        }
+        (Some(private_key), None) => {
+            let key = SecretKey::from_bytes(private_key.as_bytes().into())?;
+            Ok(LocalWallet::from(key).into())
+        }
+        (None, None) => Err(webb_relayer_utils::Error::MissingSecrets),
+    }
+}
+
 /// Listens for the server shutdown signal.
 ///
 /// Shutdown is signalled using a `broadcast::Receiver`. Only a single value is
@@ -50,10 +50,13 @@ use webb_relayer_utils::HandlerError;
 pub mod routes;
 
 /// Wait for websocket connection upgrade
-pub async fn websocket_handler(
+pub async fn websocket_handler<S>(
     ws: WebSocketUpgrade,
-    State(ctx): State<Arc<RelayerContext>>,
-) -> Response {
+    State(ctx): State<Arc<RelayerContext<S>>>,
+) -> Response
+where
+    S: Clone + Send + Sync + 'static,
+{
     ws.on_upgrade(move |socket| accept_websocket_connection(socket, ctx))
 }
 
@@ -63,7 +66,12 @@ pub async fn websocket_handler(
 ///
 /// * `ctx` - RelayContext reference that holds the configuration
 /// * `stream` - Websocket stream
-async fn accept_websocket_connection(ws: WebSocket, ctx: Arc<RelayerContext>) {
+async fn accept_websocket_connection<S>(
+    ws: WebSocket,
+    ctx: Arc<RelayerContext<S>>,
+) where
+    S: Clone + Send + Sync + 'static,
+{
     let (mut tx, mut rx) = ws.split();
 
     // Wait for client to send over text (such as relay transaction requests)
@@ -100,14 +108,15 @@ async fn accept_websocket_connection(ws: WebSocket, ctx: Arc<RelayerContext>) {
 /// * `ctx` - RelayContext reference that holds the configuration
 /// * `v` - The text (usually in a JSON form) message to be handled.
 /// * `tx` - A mutable Trait implementation of the `warp::ws::Sender` trait
-pub async fn handle_text<TX>(
-    ctx: &RelayerContext,
+pub async fn handle_text<TX, S>(
+    ctx: &RelayerContext<S>,
     v: &str,
     tx: &mut TX,
 ) -> webb_relayer_utils::Result<()>
 where
     TX: Sink<Message> + Unpin,
     TX::Error: Error + Send + Sync + 'static,
+    S: Clone,
 {
     // for every connection, we create a new channel, where we will use to send messages
     // over it.
@@ -164,8 +173,8 @@ pub async fn handle_socket_info(
 /// * `ctx` - RelayContext reference that holds the configuration
 /// * `cmd` - The command to execute
 /// * `stream` - The stream to write the response to
-pub async fn handle_cmd(
-    ctx: RelayerContext,
+pub async fn handle_cmd<S>(
+    ctx: RelayerContext<S>,
     cmd: Command,
     stream: CommandStream,
 ) -> Result<(), CommandResponse> {
@@ -201,8 +210,8 @@ pub async fn handle_cmd(
 /// * `vanchor` - Address of the smart contract
 /// * `gas_amount` - How much gas the transaction needs. Don't use U256 here because it
 ///                  gets parsed incorrectly.
-pub async fn handle_evm_fee_info(
-    State(ctx): State<Arc<RelayerContext>>,
+pub async fn handle_evm_fee_info<S>(
+    State(ctx): State<Arc<RelayerContext<S>>>,
     Path((chain_id, vanchor, gas_amount)): Path<(u32, Address, u64)>,
 ) -> Result<Json<EvmFeeInfo>, HandlerError> {
     let chain_id = TypedChainId::Evm(chain_id);
@@ -220,8 +229,8 @@ pub async fn handle_evm_fee_info(
 /// * `chain_id` - ID of the blockchain
 /// * `estimated_tx_fees` - Estimated transaction fees
 /// * `ctx` - RelayContext reference that holds the configuration
-pub async fn handle_substrate_fee_info(
-    State(ctx): State<Arc<RelayerContext>>,
+pub async fn handle_substrate_fee_info<S>(
+    State(ctx): State<Arc<RelayerContext<S>>>,
     Path((chain_id, estimated_tx_fees)): Path<(u64, u128)>,
 ) -> Result<Json<SubstrateFeeInfo>, HandlerError> {
     get_substrate_fee_info(chain_id, estimated_tx_fees.into(), ctx.as_ref())
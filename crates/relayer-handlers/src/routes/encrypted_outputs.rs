@@ -42,11 +42,14 @@ pub struct EncryptedOutputsCacheResponse {
 /// * `chain_id` - An U256 representing the chain id of the chain to query
 /// * `contract` - An address of the contract to query
 /// * `query_range` - An optional range query
-pub async fn handle_encrypted_outputs_cache_evm(
-    State(ctx): State<Arc<RelayerContext>>,
+pub async fn handle_encrypted_outputs_cache_evm<S>(
+    State(ctx): State<Arc<RelayerContext<S>>>,
     Path((chain_id, contract)): Path<(u32, Address)>,
     Query(query_range): Query<OptionalRangeQuery>,
-) -> Result<Json<EncryptedOutputsCacheResponse>, HandlerError> {
+) -> Result<Json<EncryptedOutputsCacheResponse>, HandlerError>
+where
+    S: EncryptedOutputCacheStore<Output = Vec<Vec<u8>>>,
+{
     let config = ctx.config.clone();
 
     // check if data query is enabled for relayer
@@ -4,10 +4,7 @@ use std::sync::Arc;
 
 use serde::Serialize;
 use sp_core::Pair;
-use webb::evm::ethers::{
-    prelude::k256::SecretKey,
-    signers::{LocalWallet, Signer},
-};
+use webb::evm::ethers::signers::Signer;
 use webb_relayer_context::RelayerContext;
 
 /// Build info data
@@ -43,8 +40,8 @@ pub struct RelayerInformationResponse {
 /// Handles relayer configuration requests
 ///
 /// Returns a Result with the `RelayerConfigurationResponse` on success
-pub async fn handle_relayer_info(
-    State(ctx): State<Arc<RelayerContext>>,
+pub async fn handle_relayer_info<S>(
+    State(ctx): State<Arc<RelayerContext<S>>>,
 ) -> Json<RelayerInformationResponse> {
     // clone the original config, to update it with accounts.
     let mut config = ctx.config.clone();
@@ -54,13 +51,11 @@ pub async fn handle_relayer_info(
         .values_mut()
         .filter(|v| v.beneficiary.is_none())
         .try_for_each(|v| {
-            let key = v
-                .private_key
-                .as_ref()
-                .ok_or(webb_relayer_utils::Error::MissingSecrets)?;
-            let key = SecretKey::from_bytes(key.as_bytes().into())?;
-            let wallet = LocalWallet::from(key);
-            v.beneficiary = Some(wallet.address());
+            let signer = webb_relayer_context::evm_signer(
+                v.private_key.as_deref(),
+                v.remote_signer.as_ref(),
+            )?;
+            v.beneficiary = Some(signer.address());
             webb_relayer_utils::Result::Ok(())
         });
     let _ = config
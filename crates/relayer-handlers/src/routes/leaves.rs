@@ -15,15 +15,17 @@
 use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
 use axum::Json;
-use std::{collections::HashMap, sync::Arc};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 use webb::evm::ethers::types;
 
 use ethereum_types::Address;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use webb_proposals::{
     ResourceId, SubstrateTargetSystem, TargetSystem, TypedChainId,
 };
 use webb_relayer_context::RelayerContext;
+use webb_relayer_store::merkle_tree::{MerkleProof, SharedMerkleTree};
 use webb_relayer_store::LeafCacheStore;
 use webb_relayer_utils::HandlerError;
 
@@ -37,6 +39,25 @@ pub struct LeavesCacheResponse {
     last_queried_block: u64,
 }
 
+/// Merkle root response
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MerkleRootResponse {
+    root: types::H256,
+    leaf_count: u32,
+    last_queried_block: u64,
+}
+
+/// Selects the leaf of a membership proof, either by its index or by its commitment.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MerkleProofQuery {
+    /// The index of the leaf.
+    leaf_index: Option<u32>,
+    /// The leaf (commitment).
+    commitment: Option<types::H256>,
+}
+
 /// Handles leaf data requests for evm
 ///
 /// Returns a Result with the `LeafDataResponse` on success
@@ -46,11 +67,102 @@ pub struct LeavesCacheResponse {
 /// * `chain_id` - An u32 representing the chain id of the chain to query
 /// * `contract` - An address of the contract to query
 /// * `query_range` - An Optinal Query range.
-pub async fn handle_leaves_cache_evm(
-    State(ctx): State<Arc<RelayerContext>>,
+pub async fn handle_leaves_cache_evm<S>(
+    State(ctx): State<Arc<RelayerContext<S>>>,
     Path((chain_id, contract)): Path<(u32, Address)>,
     Query(query_range): Query<OptionalRangeQuery>,
-) -> Result<Json<LeavesCacheResponse>, HandlerError> {
+) -> Result<Json<LeavesCacheResponse>, HandlerError>
+where
+    S: LeafCacheStore<Output = BTreeMap<u32, types::H256>>,
+{
+    let history_store_key = evm_history_store_key(&ctx, chain_id, contract)?;
+    let leaves = ctx
+        .store()
+        .get_leaves_with_range(history_store_key, query_range.into())
+        .map(|tree| tree.into_values().collect::<Vec<_>>())?;
+    let last_queried_block = ctx
+        .store()
+        .get_last_deposit_block_number(history_store_key)?;
+
+    Ok(Json(LeavesCacheResponse {
+        leaves,
+        last_queried_block,
+    }))
+}
+
+/// Handles merkle root requests for evm
+///
+/// Returns a Result with the `MerkleRootResponse` on success
+///
+/// # Arguments
+///
+/// * `chain_id` - An u32 representing the chain id of the chain to query
+/// * `contract` - An address of the contract to query
+pub async fn handle_merkle_root_evm<S>(
+    State(ctx): State<Arc<RelayerContext<S>>>,
+    Path((chain_id, contract)): Path<(u32, Address)>,
+) -> Result<Json<MerkleRootResponse>, HandlerError>
+where
+    S: LeafCacheStore,
+{
+    let history_store_key = evm_history_store_key(&ctx, chain_id, contract)?;
+    let tree = merkle_tree_of(&ctx, history_store_key)?;
+    let (root, leaf_count) = {
+        let tree = tree.lock();
+        (tree.root(), tree.len())
+    };
+    let last_queried_block = ctx
+        .store()
+        .get_last_deposit_block_number(history_store_key)?;
+
+    Ok(Json(MerkleRootResponse {
+        root,
+        leaf_count,
+        last_queried_block,
+    }))
+}
+
+/// Handles membership proof requests for evm
+///
+/// Returns a Result with the `MerkleProof` of the leaf on success
+///
+/// # Arguments
+///
+/// * `chain_id` - An u32 representing the chain id of the chain to query
+/// * `contract` - An address of the contract to query
+/// * `query` - The index or the commitment of the leaf.
+pub async fn handle_merkle_proof_evm<S>(
+    State(ctx): State<Arc<RelayerContext<S>>>,
+    Path((chain_id, contract)): Path<(u32, Address)>,
+    Query(query): Query<MerkleProofQuery>,
+) -> Result<Json<MerkleProof>, HandlerError> {
+    let history_store_key = evm_history_store_key(&ctx, chain_id, contract)?;
+    let tree = merkle_tree_of(&ctx, history_store_key)?;
+    let tree = tree.lock();
+    let leaf_index = match (query.leaf_index, query.commitment) {
+        (Some(leaf_index), None) => Some(leaf_index),
+        (None, Some(commitment)) => tree.index_of(&commitment),
+        _ => {
+            return Err(HandlerError(
+                StatusCode::BAD_REQUEST,
+                "Expected either a leafIndex or a commitment".to_string(),
+            ));
+        }
+    };
+    leaf_index
+        .and_then(|leaf_index| tree.proof(leaf_index))
+        .map(Json)
+        .ok_or_else(|| {
+            HandlerError(StatusCode::NOT_FOUND, "Leaf not found".to_string())
+        })
+}
+
+/// Checks that data query is enabled for the given contract, and returns its history store key.
+fn evm_history_store_key<S>(
+    ctx: &RelayerContext<S>,
+    chain_id: u32,
+    contract: Address,
+) -> Result<ResourceId, HandlerError> {
     let config = ctx.config.clone();
     // check if data query is enabled for relayer
     if !config.features.data_query {
@@ -112,20 +224,20 @@ pub async fn handle_leaves_cache_evm(
     let src_target_system =
         TargetSystem::new_contract_address(contract.to_fixed_bytes());
     let src_typed_chain_id = TypedChainId::Evm(chain_id);
-    let history_store_key =
-        ResourceId::new(src_target_system, src_typed_chain_id);
-    let leaves = ctx
-        .store()
-        .get_leaves_with_range(history_store_key, query_range.into())
-        .map(|tree| tree.into_values().collect::<Vec<_>>())?;
-    let last_queried_block = ctx
-        .store()
-        .get_last_deposit_block_number(history_store_key)?;
+    Ok(ResourceId::new(src_target_system, src_typed_chain_id))
+}
 
-    Ok(Json(LeavesCacheResponse {
-        leaves,
-        last_queried_block,
-    }))
+/// Returns the merkle tree of the given key, if its event watcher is running.
+fn merkle_tree_of<S>(
+    ctx: &RelayerContext<S>,
+    history_store_key: ResourceId,
+) -> Result<SharedMerkleTree, HandlerError> {
+    ctx.merkle_trees().get(history_store_key).ok_or_else(|| {
+        HandlerError(
+            StatusCode::NOT_FOUND,
+            format!("No merkle tree for {history_store_key:?}"),
+        )
+    })
 }
 
 /// Handles leaf data requests for substrate
@@ -139,11 +251,14 @@ pub async fn handle_leaves_cache_evm(
 /// * `pallet_id` - Pallet id of the the source system to query
 /// * `query_range` - An Optional Query range.
 /// * `ctx` - RelayContext reference that holds the configuration
-pub async fn handle_leaves_cache_substrate(
-    State(ctx): State<Arc<RelayerContext>>,
+pub async fn handle_leaves_cache_substrate<S>(
+    State(ctx): State<Arc<RelayerContext<S>>>,
     Path((chain_id, tree_id, pallet_id)): Path<(u32, u32, u8)>,
     Query(query_range): Query<OptionalRangeQuery>,
-) -> Result<Json<LeavesCacheResponse>, HandlerError> {
+) -> Result<Json<LeavesCacheResponse>, HandlerError>
+where
+    S: LeafCacheStore<Output = BTreeMap<u32, types::H256>>,
+{
     let config = ctx.config.clone();
     // check if data querying is enabled
     if !config.features.data_query {
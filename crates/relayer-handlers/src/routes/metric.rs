@@ -36,8 +36,8 @@ pub async fn handle_metric_info() -> Result<String, HandlerError> {
 /// Handles relayer metric requests for evm based resource
 ///
 /// Returns a Result with the `ResourceMetricResponse` on success
-pub async fn handle_evm_metric_info(
-    State(ctx): State<Arc<RelayerContext>>,
+pub async fn handle_evm_metric_info<S>(
+    State(ctx): State<Arc<RelayerContext<S>>>,
     Path((chain_id, contract)): Path<(u32, Address)>,
 ) -> Json<ResourceMetricResponse> {
     let mut metrics = ctx.metrics.lock().await;
@@ -63,8 +63,8 @@ pub async fn handle_evm_metric_info(
 /// Handles relayer metric requests for substrate based resource
 ///
 /// Returns a Result with the `ResourceMetricResponse` on success
-pub async fn handle_substrate_metric_info(
-    State(ctx): State<Arc<RelayerContext>>,
+pub async fn handle_substrate_metric_info<S>(
+    State(ctx): State<Arc<RelayerContext<S>>>,
     Path((chain_id, tree_id, pallet_id)): Path<(u32, u32, u8)>,
 ) -> Json<ResourceMetricResponse> {
     let mut metrics = ctx.metrics.lock().await;
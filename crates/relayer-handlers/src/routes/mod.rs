@@ -12,6 +12,9 @@ pub mod metric;
 /// Module for handling relayer info API
 pub mod info;
 
+/// Module for handling proposals queue API
+pub mod proposals;
+
 /// A (half-open) range bounded inclusively below and exclusively above
 /// (`start..end`).
 ///
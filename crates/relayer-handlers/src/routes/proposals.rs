@@ -0,0 +1,234 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::Json;
+use ethereum_types::Address;
+use std::sync::Arc;
+use webb::evm::ethers::types::H256;
+use webb::evm::ethers::utils::keccak256;
+use webb_proposal_signing_backends::governance::GovernanceAction;
+use webb_proposal_signing_backends::proposal_kind::{
+    ProposalKind, ProposalKindExt,
+};
+use webb_proposal_signing_backends::queue::control::{
+    ProposalsQueueControl, ProposalsQueueInfo,
+};
+use webb_proposals::{ProposalTrait, ResourceId, TargetSystem, TypedChainId};
+use webb_relayer_context::RelayerContext;
+use webb_relayer_utils::HandlerError;
+
+/// Handles the requests for the proposals queued for an evm `vanchor`.
+///
+/// Returns a Result with the `ProposalsQueueInfo` on success
+///
+/// # Arguments
+///
+/// * `chain_id` - An u32 representing the chain id of the `vanchor`
+/// * `contract` - The address of the `vanchor`
+pub async fn handle_proposals_queue_evm<S>(
+    State(ctx): State<Arc<RelayerContext<S>>>,
+    Path((chain_id, contract)): Path<(u32, Address)>,
+) -> Result<Json<ProposalsQueueInfo>, HandlerError> {
+    let queue = evm_proposals_queue(&ctx, chain_id, contract)?;
+    Ok(queue.info().map(Json)?)
+}
+
+/// Handles the requests to drop a proposal from the queue of an evm `vanchor`.
+///
+/// Returns a Result with the `ProposalsQueueInfo` left on success
+///
+/// # Arguments
+///
+/// * `chain_id` - An u32 representing the chain id of the `vanchor`
+/// * `contract` - The address of the `vanchor`
+/// * `hash` - The hash of the full proposal
+/// * `headers` - The headers of the request, carrying the admin token
+pub async fn handle_drop_proposal_evm<S>(
+    State(ctx): State<Arc<RelayerContext<S>>>,
+    Path((chain_id, contract, hash)): Path<(u32, Address, H256)>,
+    headers: HeaderMap,
+) -> Result<Json<ProposalsQueueInfo>, HandlerError> {
+    ensure_proposals_admin(&ctx, &headers)?;
+    let queue = evm_proposals_queue(&ctx, chain_id, contract)?;
+    let found = queue.drop_proposal(hash)?;
+    proposal_found(found, hash)?;
+    Ok(queue.info().map(Json)?)
+}
+
+/// Handles the requests to expedite a proposal of the queue of an evm `vanchor`,
+/// so it is sent without waiting for the delay of the queue.
+///
+/// Returns a Result with the `ProposalsQueueInfo` on success
+///
+/// # Arguments
+///
+/// * `chain_id` - An u32 representing the chain id of the `vanchor`
+/// * `contract` - The address of the `vanchor`
+/// * `hash` - The hash of the full proposal
+/// * `headers` - The headers of the request, carrying the admin token
+pub async fn handle_expedite_proposal_evm<S>(
+    State(ctx): State<Arc<RelayerContext<S>>>,
+    Path((chain_id, contract, hash)): Path<(u32, Address, H256)>,
+    headers: HeaderMap,
+) -> Result<Json<ProposalsQueueInfo>, HandlerError> {
+    ensure_proposals_admin(&ctx, &headers)?;
+    let queue = evm_proposals_queue(&ctx, chain_id, contract)?;
+    let found = queue.expedite(hash)?;
+    proposal_found(found, hash)?;
+    Ok(queue.info().map(Json)?)
+}
+
+/// Handles the requests to send a proposal of the queue of an evm `vanchor` to
+/// the signing backend right away.
+///
+/// Returns a Result with the `ProposalsQueueInfo` left on success
+///
+/// # Arguments
+///
+/// * `chain_id` - An u32 representing the chain id of the `vanchor`
+/// * `contract` - The address of the `vanchor`
+/// * `hash` - The hash of the full proposal
+/// * `headers` - The headers of the request, carrying the admin token
+pub async fn handle_resign_proposal_evm<S>(
+    State(ctx): State<Arc<RelayerContext<S>>>,
+    Path((chain_id, contract, hash)): Path<(u32, Address, H256)>,
+    headers: HeaderMap,
+) -> Result<Json<ProposalsQueueInfo>, HandlerError> {
+    ensure_proposals_admin(&ctx, &headers)?;
+    let queue = evm_proposals_queue(&ctx, chain_id, contract)?;
+    let found = queue.resign(hash).await?;
+    proposal_found(found, hash)?;
+    Ok(queue.info().map(Json)?)
+}
+
+/// A governance action to propose, through the signing backend of an evm `vanchor`.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GovernanceRequest {
+    /// The resource id of the contract targeted by the proposal.
+    pub target: H256,
+    /// The nonce of the proposal.
+    pub nonce: u32,
+    /// The governance action to propose.
+    pub action: GovernanceAction,
+}
+
+/// The governance proposal sent to the signing backend.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GovernanceProposalInfo {
+    /// The hash of the full proposal.
+    pub hash: H256,
+    /// The kind of the proposal.
+    pub kind: ProposalKind,
+}
+
+/// Handles the requests to propose a governance action (like adding a token to a
+/// token wrapper) through the signing backend of an evm `vanchor`.
+///
+/// Returns a Result with the `GovernanceProposalInfo` on success
+///
+/// # Arguments
+///
+/// * `chain_id` - An u32 representing the chain id of the `vanchor`
+/// * `contract` - The address of the `vanchor`
+/// * `headers` - The headers of the request, carrying the admin token
+/// * `request` - The target, nonce and action of the proposal
+pub async fn handle_governance_proposal_evm<S>(
+    State(ctx): State<Arc<RelayerContext<S>>>,
+    Path((chain_id, contract)): Path<(u32, Address)>,
+    headers: HeaderMap,
+    Json(request): Json<GovernanceRequest>,
+) -> Result<Json<GovernanceProposalInfo>, HandlerError> {
+    ensure_proposals_admin(&ctx, &headers)?;
+    let queue = evm_proposals_queue(&ctx, chain_id, contract)?;
+    let target = ResourceId::from(request.target.to_fixed_bytes());
+    let proposal = request.action.into_proposal(target, request.nonce);
+    let info = GovernanceProposalInfo {
+        hash: H256(keccak256(proposal.to_vec())),
+        kind: proposal.kind(),
+    };
+    if !queue.propose(proposal).await? {
+        return Err(HandlerError(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "The signing backend cannot handle a {} proposal to {target:?}",
+                info.kind
+            ),
+        ));
+    }
+    Ok(Json(info))
+}
+
+/// Checks that the admin actions on the proposals queues are enabled, and that
+/// the request carries the admin token.
+fn ensure_proposals_admin<S>(
+    ctx: &RelayerContext<S>,
+    headers: &HeaderMap,
+) -> Result<(), HandlerError> {
+    if !ctx.config.features.proposals_admin {
+        tracing::warn!("Proposals admin is not enabled for relayer.");
+        return Err(HandlerError(
+            StatusCode::FORBIDDEN,
+            "Proposals admin is not enabled for relayer.".to_string(),
+        ));
+    }
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let authorized = match (&ctx.config.proposals_admin_token, token) {
+        (Some(expected), Some(token)) => expected.verify(token.trim()),
+        _ => false,
+    };
+    if !authorized {
+        tracing::warn!("Rejected an unauthorized proposals admin request.");
+        return Err(HandlerError(
+            StatusCode::UNAUTHORIZED,
+            "Missing or invalid proposals admin token.".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the proposals queue of the given `vanchor`, if its event watcher is running.
+fn evm_proposals_queue<S>(
+    ctx: &RelayerContext<S>,
+    chain_id: u32,
+    contract: Address,
+) -> Result<Arc<dyn ProposalsQueueControl>, HandlerError> {
+    let target_system =
+        TargetSystem::new_contract_address(contract.to_fixed_bytes());
+    let resource_id =
+        ResourceId::new(target_system, TypedChainId::Evm(chain_id));
+    ctx.proposals_queues().get(&resource_id).ok_or_else(|| {
+        HandlerError(
+            StatusCode::NOT_FOUND,
+            format!("No proposals queue for {resource_id:?}"),
+        )
+    })
+}
+
+fn proposal_found(found: bool, hash: H256) -> Result<(), HandlerError> {
+    if found {
+        Ok(())
+    } else {
+        Err(HandlerError(
+            StatusCode::NOT_FOUND,
+            format!("No queued proposal with hash {hash:?}"),
+        ))
+    }
+}
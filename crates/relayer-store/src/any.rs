@@ -0,0 +1,448 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use webb::evm::ethers::types;
+
+use super::{
+    BatchStore, BlockInfo, DeadLetter, DequeuedItem, EncryptedOutputCacheStore,
+    EnqueueOptions, EventHashStore, HistoryStore, HistoryStoreKey,
+    LeafCacheStore, ProposalsQueueStore, PruneReport, QueueStore, ReorgStore,
+    RetentionPolicy, RetentionStore, StoreBatch, TokenPriceCacheStore,
+};
+use crate::crypto::StoreCipher;
+use crate::mem::InMemoryStore;
+#[cfg(feature = "sled")]
+use crate::sled::SledStore;
+#[cfg(feature = "sqlite")]
+use crate::sqlite::SqliteStore;
+use crate::SledQueueKey;
+
+/// AnyStore is a store that could be backed by any of the supported storage backends.
+///
+/// The backend is selected at runtime (for example, from the command line),
+/// and every call is forwarded to the selected backend. It is only the default
+/// store of the relayer binaries: the relayer itself is generic over its store,
+/// so an embedder could use its own backend instead.
+#[derive(Clone, Debug)]
+pub enum AnyStore {
+    /// A [Sled](https://sled.rs)-based store.
+    #[cfg(feature = "sled")]
+    Sled(SledStore),
+    /// A [SQLite](https://sqlite.org)-based store.
+    #[cfg(feature = "sqlite")]
+    Sqlite(SqliteStore),
+    /// A store that keeps everything in memory, mostly useful for tests.
+    InMemory(InMemoryStore),
+}
+
+/// Forwards a call to the underlying store.
+macro_rules! dispatch {
+    ($self:expr, $store:ident => $body:expr) => {
+        match $self {
+            #[cfg(feature = "sled")]
+            AnyStore::Sled($store) => $body,
+            #[cfg(feature = "sqlite")]
+            AnyStore::Sqlite($store) => $body,
+            AnyStore::InMemory($store) => $body,
+        }
+    };
+}
+
+impl AnyStore {
+    /// Gets the total amount of data stored on disk
+    pub fn get_data_stored_size(&self) -> u64 {
+        match self {
+            #[cfg(feature = "sled")]
+            AnyStore::Sled(store) => store.get_data_stored_size(),
+            #[cfg(feature = "sqlite")]
+            AnyStore::Sqlite(store) => store.get_data_stored_size(),
+            // nothing is stored on disk.
+            AnyStore::InMemory(_) => 0,
+        }
+    }
+
+    /// Re-encrypts the sensitive values of the store with the given cipher,
+    /// or decrypts them if there is none.
+    ///
+    /// Returns the number of values rewritten.
+    pub fn rotate_key(
+        &mut self,
+        new_cipher: Option<StoreCipher>,
+    ) -> crate::Result<usize> {
+        match self {
+            #[cfg(feature = "sled")]
+            AnyStore::Sled(store) => store.rotate_key(new_cipher),
+            #[cfg(feature = "sqlite")]
+            AnyStore::Sqlite(store) => store.rotate_key(new_cipher),
+            // nothing is stored on disk.
+            AnyStore::InMemory(_) => Ok(0),
+        }
+    }
+}
+
+impl Default for AnyStore {
+    fn default() -> Self {
+        Self::InMemory(InMemoryStore::default())
+    }
+}
+
+impl From<InMemoryStore> for AnyStore {
+    fn from(store: InMemoryStore) -> Self {
+        Self::InMemory(store)
+    }
+}
+
+#[cfg(feature = "sled")]
+impl From<SledStore> for AnyStore {
+    fn from(store: SledStore) -> Self {
+        Self::Sled(store)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<SqliteStore> for AnyStore {
+    fn from(store: SqliteStore) -> Self {
+        Self::Sqlite(store)
+    }
+}
+
+impl HistoryStore for AnyStore {
+    fn set_last_block_number<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        block_number: u64,
+    ) -> crate::Result<u64> {
+        dispatch!(self, store => store.set_last_block_number(key, block_number))
+    }
+
+    fn get_last_block_number<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        default_block_number: u64,
+    ) -> crate::Result<u64> {
+        dispatch!(self, store => store.get_last_block_number(key, default_block_number))
+    }
+
+    fn set_target_block_number<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        block_number: u64,
+    ) -> crate::Result<u64> {
+        dispatch!(self, store => store.set_target_block_number(key, block_number))
+    }
+
+    fn get_target_block_number<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        default_block_number: u64,
+    ) -> crate::Result<u64> {
+        dispatch!(self, store => store.get_target_block_number(key, default_block_number))
+    }
+}
+
+impl LeafCacheStore for AnyStore {
+    type Output = BTreeMap<u32, types::H256>;
+
+    fn clear_leaves_cache<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<()> {
+        dispatch!(self, store => store.clear_leaves_cache(key))
+    }
+
+    fn get_leaves<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<Self::Output> {
+        dispatch!(self, store => store.get_leaves(key))
+    }
+
+    fn get_leaves_with_range<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        range: core::ops::Range<u32>,
+    ) -> crate::Result<Self::Output> {
+        dispatch!(self, store => store.get_leaves_with_range(key, range))
+    }
+
+    fn get_last_deposit_block_number<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<u64> {
+        dispatch!(self, store => store.get_last_deposit_block_number(key))
+    }
+
+    fn insert_leaves_and_last_deposit_block_number<
+        K: Into<HistoryStoreKey> + Debug + Clone,
+    >(
+        &self,
+        key: K,
+        leaves: &[(u32, Vec<u8>)],
+        block_number: u64,
+        block_hash: types::H256,
+    ) -> crate::Result<()> {
+        dispatch!(self, store => store.insert_leaves_and_last_deposit_block_number(
+            key,
+            leaves,
+            block_number,
+            block_hash,
+        ))
+    }
+
+    fn get_leaves_cache_keys(&self) -> crate::Result<Vec<HistoryStoreKey>> {
+        dispatch!(self, store => store.get_leaves_cache_keys())
+    }
+}
+
+impl EncryptedOutputCacheStore for AnyStore {
+    type Output = Vec<Vec<u8>>;
+
+    fn get_encrypted_output<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<Self::Output> {
+        dispatch!(self, store => store.get_encrypted_output(key))
+    }
+
+    fn get_encrypted_output_with_range<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        range: core::ops::Range<u32>,
+    ) -> crate::Result<Self::Output> {
+        dispatch!(self, store => store.get_encrypted_output_with_range(key, range))
+    }
+
+    fn get_encrypted_output_with_indices<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<BTreeMap<u32, Vec<u8>>> {
+        dispatch!(self, store => store.get_encrypted_output_with_indices(key))
+    }
+
+    fn get_last_deposit_block_number_for_encrypted_output<
+        K: Into<HistoryStoreKey> + Debug,
+    >(
+        &self,
+        key: K,
+    ) -> crate::Result<u64> {
+        dispatch!(self, store => store.get_last_deposit_block_number_for_encrypted_output(key))
+    }
+
+    fn insert_encrypted_output_and_last_deposit_block_number<
+        K: Into<HistoryStoreKey> + Debug + Clone,
+    >(
+        &self,
+        key: K,
+        encrypted_output: &[(u32, Vec<u8>)],
+        block_number: u64,
+        block_hash: types::H256,
+    ) -> crate::Result<()> {
+        dispatch!(self, store => store.insert_encrypted_output_and_last_deposit_block_number(
+            key,
+            encrypted_output,
+            block_number,
+            block_hash,
+        ))
+    }
+
+    fn get_encrypted_output_cache_keys(
+        &self,
+    ) -> crate::Result<Vec<HistoryStoreKey>> {
+        dispatch!(self, store => store.get_encrypted_output_cache_keys())
+    }
+}
+
+impl ReorgStore for AnyStore {
+    fn set_last_block<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        block: BlockInfo,
+    ) -> crate::Result<()> {
+        dispatch!(self, store => store.set_last_block(key, block))
+    }
+
+    fn get_last_block_hash<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<Option<types::H256>> {
+        dispatch!(self, store => store.get_last_block_hash(key))
+    }
+
+    fn get_leaf_block<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        leaf_index: u32,
+    ) -> crate::Result<Option<BlockInfo>> {
+        dispatch!(self, store => store.get_leaf_block(key, leaf_index))
+    }
+
+    fn get_encrypted_output_block<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        index: u32,
+    ) -> crate::Result<Option<BlockInfo>> {
+        dispatch!(self, store => store.get_encrypted_output_block(key, index))
+    }
+
+    fn get_cached_blocks<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<Vec<BlockInfo>> {
+        dispatch!(self, store => store.get_cached_blocks(key))
+    }
+
+    fn rollback_to_block<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        block: BlockInfo,
+    ) -> crate::Result<()> {
+        dispatch!(self, store => store.rollback_to_block(key, block))
+    }
+}
+
+impl EventHashStore for AnyStore {
+    fn store_event(&self, event: &[u8]) -> crate::Result<()> {
+        dispatch!(self, store => store.store_event(event))
+    }
+
+    fn contains_event(&self, event: &[u8]) -> crate::Result<bool> {
+        dispatch!(self, store => store.contains_event(event))
+    }
+
+    fn delete_event(&self, event: &[u8]) -> crate::Result<()> {
+        dispatch!(self, store => store.delete_event(event))
+    }
+}
+
+impl<T> QueueStore<T> for AnyStore
+where
+    T: Serialize + DeserializeOwned + Clone,
+{
+    type Key = SledQueueKey;
+
+    fn enqueue_item_with_options(
+        &self,
+        key: Self::Key,
+        item: T,
+        options: EnqueueOptions,
+    ) -> crate::Result<()> {
+        dispatch!(self, store => store.enqueue_item_with_options(key, item, options))
+    }
+
+    fn dequeue_item_with_options(
+        &self,
+        key: Self::Key,
+    ) -> crate::Result<Option<DequeuedItem<T>>> {
+        dispatch!(self, store => store.dequeue_item_with_options(key))
+    }
+
+    fn dequeue_item_if<F>(
+        &self,
+        key: Self::Key,
+        predicate: F,
+    ) -> crate::Result<Option<DequeuedItem<T>>>
+    where
+        F: FnOnce(&T) -> bool,
+    {
+        dispatch!(self, store => store.dequeue_item_if(key, predicate))
+    }
+
+    fn peek_item(&self, key: Self::Key) -> crate::Result<Option<T>> {
+        dispatch!(self, store => store.peek_item(key))
+    }
+
+    fn has_item(&self, key: Self::Key) -> crate::Result<bool> {
+        dispatch!(self, store => QueueStore::<T>::has_item(store, key))
+    }
+
+    fn remove_item(&self, key: Self::Key) -> crate::Result<Option<T>> {
+        dispatch!(self, store => store.remove_item(key))
+    }
+
+    fn dead_letter_item_with_options(
+        &self,
+        key: Self::Key,
+        item: T,
+        options: EnqueueOptions,
+        reason: String,
+    ) -> crate::Result<DeadLetter<T>> {
+        dispatch!(self, store => store.dead_letter_item_with_options(key, item, options, reason))
+    }
+
+    fn get_dead_letters(
+        &self,
+        key: Self::Key,
+    ) -> crate::Result<Vec<DeadLetter<T>>> {
+        dispatch!(self, store => QueueStore::<T>::get_dead_letters(store, key))
+    }
+
+    fn requeue_dead_letter(
+        &self,
+        key: Self::Key,
+        id: types::H256,
+    ) -> crate::Result<bool> {
+        dispatch!(self, store => QueueStore::<T>::requeue_dead_letter(store, key, id))
+    }
+
+    fn purge_dead_letters(&self, key: Self::Key) -> crate::Result<usize> {
+        dispatch!(self, store => QueueStore::<T>::purge_dead_letters(store, key))
+    }
+}
+
+impl<T> TokenPriceCacheStore<T> for AnyStore
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn get_price(&self, token_key: &str) -> crate::Result<Option<T>> {
+        dispatch!(self, store => store.get_price(token_key))
+    }
+
+    fn insert_price(&self, token_key: &str, value: T) -> crate::Result<()> {
+        dispatch!(self, store => store.insert_price(token_key, value))
+    }
+}
+
+impl<T> ProposalsQueueStore<T> for AnyStore
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn get_proposals_queue(&self, queue_key: &str) -> crate::Result<Option<T>> {
+        dispatch!(self, store => store.get_proposals_queue(queue_key))
+    }
+
+    fn save_proposals_queue(
+        &self,
+        queue_key: &str,
+        state: T,
+    ) -> crate::Result<()> {
+        dispatch!(self, store => store.save_proposals_queue(queue_key, state))
+    }
+}
+
+impl RetentionStore for AnyStore {
+    fn prune(&self, policy: &RetentionPolicy) -> crate::Result<PruneReport> {
+        dispatch!(self, store => store.prune(policy))
+    }
+}
+
+impl BatchStore for AnyStore {
+    fn apply_batch(&self, batch: StoreBatch) -> crate::Result<()> {
+        dispatch!(self, store => store.apply_batch(batch))
+    }
+}
@@ -0,0 +1,245 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Encryption at rest of the values the store keeps.
+//!
+//! Only the values that could be sensitive are encrypted, that is the queued items
+//! (signed transactions, [`BridgeCommand`](crate::BridgeCommand) signatures, ..etc)
+//! and the dead letters. The caches of public on-chain data are kept in plaintext.
+//!
+//! Every encrypted value starts with a magic prefix, followed by a random nonce and
+//! the ciphertext, so values written before the store was encrypted are still
+//! readable as plaintext.
+
+use std::sync::Arc;
+
+use ring::aead::{
+    Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN,
+};
+use ring::rand::{SecureRandom, SystemRandom};
+use webb_relayer_utils::Error;
+
+/// The prefix of the encrypted binary values.
+const MAGIC: &[u8] = b"wenc\x01";
+/// The prefix of the encrypted text values, followed by the hex of the nonce and
+/// the ciphertext.
+const TEXT_MAGIC: &str = "wenc1:";
+/// The plaintext of the value used to check that the store is opened with the right key.
+const KEY_CHECK: &[u8] = b"webb-relayer-store";
+
+/// StoreCipher encrypts and decrypts the values of the store, using
+/// ChaCha20-Poly1305 with a 256-bit key.
+#[derive(Clone)]
+pub struct StoreCipher {
+    key: Arc<LessSafeKey>,
+    rng: SystemRandom,
+}
+
+impl std::fmt::Debug for StoreCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StoreCipher").finish()
+    }
+}
+
+impl StoreCipher {
+    /// Creates a new cipher from the given 256-bit key.
+    pub fn new(key: [u8; 32]) -> Self {
+        let key = UnboundKey::new(&CHACHA20_POLY1305, &key)
+            .expect("the key has the length of the algorithm");
+        Self {
+            key: Arc::new(LessSafeKey::new(key)),
+            rng: SystemRandom::new(),
+        }
+    }
+
+    /// Encrypts the given plaintext.
+    pub fn encrypt(&self, plaintext: &[u8]) -> crate::Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce)
+            .map_err(|_| Error::Generic("failed to generate a nonce"))?;
+        let mut in_out = plaintext.to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::empty(),
+                &mut in_out,
+            )
+            .map_err(|_| Error::Generic("failed to encrypt a store value"))?;
+        let mut output =
+            Vec::with_capacity(MAGIC.len() + NONCE_LEN + in_out.len());
+        output.extend_from_slice(MAGIC);
+        output.extend_from_slice(&nonce);
+        output.extend_from_slice(&in_out);
+        Ok(output)
+    }
+
+    /// Decrypts a value returned by [`StoreCipher::encrypt`].
+    pub fn decrypt(&self, value: &[u8]) -> crate::Result<Vec<u8>> {
+        let sealed = value.strip_prefix(MAGIC).ok_or(Error::WrongStoreKey)?;
+        if sealed.len() < NONCE_LEN {
+            return Err(Error::WrongStoreKey);
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce)
+            .map_err(|_| Error::WrongStoreKey)?;
+        let mut in_out = ciphertext.to_vec();
+        let plaintext = self
+            .key
+            .open_in_place(nonce, Aad::empty(), &mut in_out)
+            .map_err(|_| Error::WrongStoreKey)?;
+        Ok(plaintext.to_vec())
+    }
+}
+
+/// Returns `true` if the given value was encrypted.
+pub(crate) fn is_encrypted(value: &[u8]) -> bool {
+    value.starts_with(MAGIC)
+}
+
+/// Encrypts the given value, if the store is encrypted.
+pub(crate) fn seal(
+    cipher: Option<&StoreCipher>,
+    value: Vec<u8>,
+) -> crate::Result<Vec<u8>> {
+    match cipher {
+        Some(cipher) => cipher.encrypt(&value),
+        None => Ok(value),
+    }
+}
+
+/// Decrypts the given value, unless it was stored in plaintext.
+pub(crate) fn unseal(
+    cipher: Option<&StoreCipher>,
+    value: &[u8],
+) -> crate::Result<Vec<u8>> {
+    match (cipher, is_encrypted(value)) {
+        (Some(cipher), true) => cipher.decrypt(value),
+        (None, true) => Err(Error::StoreKeyMissing),
+        (_, false) => Ok(value.to_vec()),
+    }
+}
+
+/// Like [`seal`], but for the values stored as text.
+pub(crate) fn seal_text(
+    cipher: Option<&StoreCipher>,
+    value: String,
+) -> crate::Result<String> {
+    match cipher {
+        Some(cipher) => {
+            let sealed = cipher.encrypt(value.as_bytes())?;
+            Ok(format!(
+                "{TEXT_MAGIC}{}",
+                hex::encode(&sealed[MAGIC.len()..])
+            ))
+        }
+        None => Ok(value),
+    }
+}
+
+/// Like [`unseal`], but for the values stored as text.
+pub(crate) fn unseal_text(
+    cipher: Option<&StoreCipher>,
+    value: String,
+) -> crate::Result<String> {
+    let sealed = match value.strip_prefix(TEXT_MAGIC) {
+        Some(sealed) => sealed,
+        None => return Ok(value),
+    };
+    let cipher = cipher.ok_or(Error::StoreKeyMissing)?;
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend(hex::decode(sealed).map_err(|_| Error::WrongStoreKey)?);
+    let plaintext = cipher.decrypt(&bytes)?;
+    String::from_utf8(plaintext).map_err(|_| Error::WrongStoreKey)
+}
+
+/// Creates the value stored alongside the data, to check the key it is opened with.
+pub(crate) fn key_check(cipher: &StoreCipher) -> crate::Result<Vec<u8>> {
+    cipher.encrypt(KEY_CHECK)
+}
+
+/// Checks that the store is opened with the key it was encrypted with, given
+/// the key check stored in it, if any.
+pub(crate) fn verify_key_check(
+    cipher: Option<&StoreCipher>,
+    key_check: Option<&[u8]>,
+) -> crate::Result<()> {
+    match (cipher, key_check) {
+        (Some(cipher), Some(key_check)) => {
+            if cipher.decrypt(key_check)? == KEY_CHECK {
+                Ok(())
+            } else {
+                Err(Error::WrongStoreKey)
+            }
+        }
+        (None, Some(_)) => Err(Error::StoreKeyMissing),
+        // a store that was not encrypted yet.
+        (_, None) => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypt_and_decrypt_should_work() {
+        let cipher = StoreCipher::new([1u8; 32]);
+        let value = br#"{"foo":"bar"}"#;
+        let sealed = seal(Some(&cipher), value.to_vec()).unwrap();
+        assert!(is_encrypted(&sealed));
+        assert_ne!(&sealed[MAGIC.len()..], value.as_slice());
+        // the same value is never encrypted twice the same way.
+        assert_ne!(sealed, seal(Some(&cipher), value.to_vec()).unwrap());
+        assert_eq!(unseal(Some(&cipher), &sealed).unwrap(), value.to_vec());
+        // plaintext values are read as they are.
+        assert_eq!(unseal(Some(&cipher), value).unwrap(), value.to_vec());
+        assert_eq!(unseal(None, value).unwrap(), value.to_vec());
+
+        let other = StoreCipher::new([2u8; 32]);
+        assert!(matches!(
+            unseal(Some(&other), &sealed),
+            Err(Error::WrongStoreKey)
+        ));
+        assert!(matches!(unseal(None, &sealed), Err(Error::StoreKeyMissing)));
+
+        let text = String::from(r#"{"foo":"bar"}"#);
+        let sealed = seal_text(Some(&cipher), text.clone()).unwrap();
+        assert!(sealed.starts_with(TEXT_MAGIC));
+        assert_eq!(unseal_text(Some(&cipher), sealed.clone()).unwrap(), text);
+        assert_eq!(unseal_text(None, text.clone()).unwrap(), text);
+        assert!(matches!(
+            unseal_text(Some(&other), sealed),
+            Err(Error::WrongStoreKey)
+        ));
+    }
+
+    #[test]
+    fn key_check_should_work() {
+        let cipher = StoreCipher::new([1u8; 32]);
+        let other = StoreCipher::new([2u8; 32]);
+        let check = key_check(&cipher).unwrap();
+        verify_key_check(Some(&cipher), Some(&check)).unwrap();
+        verify_key_check(Some(&cipher), None).unwrap();
+        verify_key_check(None, None).unwrap();
+        assert!(matches!(
+            verify_key_check(Some(&other), Some(&check)),
+            Err(Error::WrongStoreKey)
+        ));
+        assert!(matches!(
+            verify_key_check(None, Some(&check)),
+            Err(Error::StoreKeyMissing)
+        ));
+    }
+}
@@ -21,23 +21,42 @@
 //! The relayer store module stores the history of events. Manages the setting
 //! and retrieving operations of events.
 //!
+use std::collections::BTreeMap;
 use std::fmt::{Debug, Display};
 use std::sync::Arc;
 
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use webb::evm::ethers::types;
+use webb::evm::ethers::{self, types};
 use webb_proposals::{ResourceId, TargetSystem, TypedChainId};
 use webb_relayer_utils::Result;
+/// A module for selecting the storage backend at runtime.
+pub mod any;
+/// A module for encrypting the sensitive values of the store at rest.
+pub mod crypto;
 /// A module for managing in-memory storage of the relayer.
 pub mod mem;
+/// A module for the incremental Merkle trees built from the cached leaves.
+pub mod merkle_tree;
 /// A module for setting up and managing a [Sled](https://sled.rs)-based database.
 #[cfg(feature = "sled")]
 pub mod sled;
+/// A module for exporting and importing the leaves and encrypted outputs caches.
+pub mod snapshot;
+/// A module for setting up and managing a [SQLite](https://sqlite.org)-based database.
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
 
+/// A store that could be backed by any of the enabled backends.
+pub use self::any::AnyStore;
+/// The cipher used to encrypt the store at rest.
+pub use self::crypto::StoreCipher;
 /// A store that uses [`sled`](https://sled.rs) as the backend.
 #[cfg(feature = "sled")]
 pub use self::sled::SledStore;
+/// A store that uses [`SQLite`](https://sqlite.org) as the backend.
+#[cfg(feature = "sqlite")]
+pub use self::sqlite::SqliteStore;
 /// A store that uses in memory data structures as the backend.
 pub use mem::InMemoryStore;
 
@@ -140,6 +159,29 @@ impl HistoryStoreKey {
         }
         vec
     }
+
+    /// Creates a key from its bytes, as returned by [`Self::to_bytes`].
+    ///
+    /// Returns `None` if the bytes are not a valid key.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        match bytes.len() {
+            4 => {
+                let mut chain_id = [0u8; 4];
+                chain_id.copy_from_slice(bytes);
+                Some(Self::Block {
+                    chain_id: u32::from_be_bytes(chain_id),
+                })
+            }
+            32 => {
+                let mut resource_id = [0u8; 32];
+                resource_id.copy_from_slice(bytes);
+                Some(Self::ResourceId {
+                    resource_id: ResourceId::from(resource_id),
+                })
+            }
+            _ => None,
+        }
+    }
 }
 
 impl Display for HistoryStoreKey {
@@ -285,6 +327,12 @@ pub trait LeafCacheStore: HistoryStore {
         range: core::ops::Range<u32>,
     ) -> crate::Result<Self::Output>;
 
+    /// Get all the encrypted output for the given key, by their index.
+    fn get_encrypted_output_with_indices<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<BTreeMap<u32, Vec<u8>>>;
+
     /// The last deposit info is sent to the client on leaf request
     /// So they can verify when the last transaction was sent to maintain
     /// their own state of vanchors.
@@ -294,6 +342,10 @@ pub trait LeafCacheStore: HistoryStore {
     ) -> crate::Result<u64>;
 
     /// Insert leaves and last deposit block number for the given key.
+    ///
+    /// The leaves are recorded as found in the block with the given number and hash,
+    /// which is also the last block processed for the given key. A zero hash means the
+    /// block is unknown, and the leaves are then assumed to be final.
     fn insert_leaves_and_last_deposit_block_number<
         K: Into<HistoryStoreKey> + Debug + Clone,
     >(
@@ -301,7 +353,12 @@ pub trait LeafCacheStore: HistoryStore {
         key: K,
         leaves: &[(u32, Vec<u8>)],
         block_number: u64,
+        block_hash: types::H256,
     ) -> crate::Result<()>;
+
+    /// Get all the keys that have a last deposit block number, that is, every key
+    /// leaves were inserted for.
+    fn get_leaves_cache_keys(&self) -> crate::Result<Vec<HistoryStoreKey>>;
 }
 
 /// An Encrypted Output Cache Store is a simple trait that would help in
@@ -334,6 +391,10 @@ pub trait EncryptedOutputCacheStore: HistoryStore {
     ) -> crate::Result<u64>;
 
     /// Insert encrypted output and last deposit block number for the given key.
+    ///
+    /// The encrypted outputs are recorded as found in the block with the given number and hash.
+    /// A zero hash means the block is unknown, and the encrypted outputs are then assumed
+    /// to be final.
     fn insert_encrypted_output_and_last_deposit_block_number<
         K: Into<HistoryStoreKey> + Debug + Clone,
     >(
@@ -341,6 +402,103 @@ pub trait EncryptedOutputCacheStore: HistoryStore {
         key: K,
         encrypted_output: &[(u32, Vec<u8>)],
         block_number: u64,
+        block_hash: types::H256,
+    ) -> crate::Result<()>;
+
+    /// Get all the keys that have a last deposit block number for the encrypted outputs,
+    /// that is, every key encrypted outputs were inserted for.
+    fn get_encrypted_output_cache_keys(
+        &self,
+    ) -> crate::Result<Vec<HistoryStoreKey>>;
+}
+
+/// A block of the chain, identified by its number and hash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BlockInfo {
+    /// The block number.
+    pub number: u64,
+    /// The block hash.
+    pub hash: types::H256,
+}
+
+impl BlockInfo {
+    /// Creates a new BlockInfo.
+    pub fn new(number: u64, hash: types::H256) -> Self {
+        Self { number, hash }
+    }
+
+    /// Encodes the block as 8 bytes of the block number (big-endian) followed by the hash.
+    pub fn to_bytes(&self) -> [u8; 40] {
+        let mut bytes = [0u8; 40];
+        bytes[..8].copy_from_slice(&self.number.to_be_bytes());
+        bytes[8..].copy_from_slice(self.hash.as_bytes());
+        bytes
+    }
+
+    /// Decodes the block from the bytes returned by [`Self::to_bytes`].
+    ///
+    /// Returns `None` if the bytes are not a valid block.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != 40 {
+            return None;
+        }
+        let mut number = [0u8; 8];
+        number.copy_from_slice(&bytes[..8]);
+        Some(Self {
+            number: u64::from_be_bytes(number),
+            hash: types::H256::from_slice(&bytes[8..]),
+        })
+    }
+}
+
+/// A Reorg Store keeps track of the blocks where the cached leaves and encrypted outputs
+/// were found, so the caches could be rolled back to a common ancestor when the chain reorganizes.
+///
+/// Leaves and encrypted outputs cached before their blocks were tracked (or imported from a
+/// snapshot) have no known block, and are assumed to be final.
+pub trait ReorgStore: LeafCacheStore + EncryptedOutputCacheStore {
+    /// Sets the last block processed for the given key, together with its hash.
+    fn set_last_block<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        block: BlockInfo,
+    ) -> crate::Result<()>;
+
+    /// Get the hash of the last block processed for the given key, if it is known.
+    fn get_last_block_hash<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<Option<types::H256>>;
+
+    /// Get the block where the leaf with the given index was found, if it is known.
+    fn get_leaf_block<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        leaf_index: u32,
+    ) -> crate::Result<Option<BlockInfo>>;
+
+    /// Get the block where the encrypted output with the given index was found, if it is known.
+    fn get_encrypted_output_block<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        index: u32,
+    ) -> crate::Result<Option<BlockInfo>>;
+
+    /// Get all the blocks where leaves or encrypted outputs were found for the given key,
+    /// newest first.
+    fn get_cached_blocks<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<Vec<BlockInfo>>;
+
+    /// Rolls back the caches of the given key to the given block.
+    ///
+    /// Every leaf and encrypted output found after that block is removed, and the block
+    /// becomes the last block processed, so the event watcher re-indexes from there.
+    fn rollback_to_block<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        block: BlockInfo,
     ) -> crate::Result<()>;
 }
 
@@ -379,8 +537,234 @@ pub trait QueueKey {
     fn item_key(&self) -> Option<[u8; 64]>;
 }
 
+/// SledQueueKey is a key for a queue in the store.
+///
+/// While it was first introduced for the sled backend, it is used by all the
+/// store backends to identify their queues.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SledQueueKey {
+    /// Queue Key for EVM based Transaction Queue.
+    EvmTx {
+        /// EVM Chain Id.
+        chain_id: u32,
+        /// an optional key for this transaction.
+        optional_key: Option<[u8; 64]>,
+    },
+    /// Queue Key for Substrate based Transaction Queue.
+    SubstrateTx {
+        /// Substrate Chain Id.
+        chain_id: u32,
+        /// an optional key for this transaction.
+        optional_key: Option<[u8; 64]>,
+    },
+    /// Queue Key for Bridge Watcher Command Queue.
+    BridgeCmd {
+        /// Specific Bridge Key.
+        bridge_key: BridgeKey,
+    },
+}
+
+impl SledQueueKey {
+    /// Create a new SledQueueKey from an evm chain id.
+    pub fn from_evm_chain_id(chain_id: u32) -> Self {
+        Self::EvmTx {
+            chain_id,
+            optional_key: None,
+        }
+    }
+
+    /// from_evm_with_custom_key returns an EVM specific SledQueueKey.
+    pub fn from_evm_with_custom_key(chain_id: u32, key: [u8; 64]) -> Self {
+        Self::EvmTx {
+            chain_id,
+            optional_key: Some(key),
+        }
+    }
+
+    /// Create a new SledQueueKey from an substrate chain id.
+    pub fn from_substrate_chain_id(chain_id: u32) -> Self {
+        Self::SubstrateTx {
+            chain_id,
+            optional_key: None,
+        }
+    }
+
+    /// from_substrate_with_custom_key returns an Substrate specific SledQueueKey.
+    pub fn from_substrate_with_custom_key(
+        chain_id: u32,
+        key: [u8; 64],
+    ) -> Self {
+        Self::SubstrateTx {
+            chain_id,
+            optional_key: Some(key),
+        }
+    }
+
+    /// from_bridge_key returns a Bridge specific SledQueueKey.
+    pub fn from_bridge_key(bridge_key: BridgeKey) -> Self {
+        Self::BridgeCmd { bridge_key }
+    }
+
+    /// Returns the same queue key, with the given item key.
+    ///
+    /// Bridge command queues have no item keys, so it is ignored for them.
+    pub fn with_item_key(self, item_key: Option<[u8; 64]>) -> Self {
+        match self {
+            Self::EvmTx { chain_id, .. } => Self::EvmTx {
+                chain_id,
+                optional_key: item_key,
+            },
+            Self::SubstrateTx { chain_id, .. } => Self::SubstrateTx {
+                chain_id,
+                optional_key: item_key,
+            },
+            Self::BridgeCmd { bridge_key } => Self::BridgeCmd { bridge_key },
+        }
+    }
+}
+
+impl Display for SledQueueKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EvmTx {
+                chain_id,
+                optional_key,
+            } => write!(
+                f,
+                "EvmTx({}, {:?})",
+                chain_id,
+                optional_key.map(hex::encode)
+            ),
+            Self::SubstrateTx {
+                chain_id,
+                optional_key,
+            } => write!(
+                f,
+                "SubstrateTx({}, {:?})",
+                chain_id,
+                optional_key.map(hex::encode)
+            ),
+            Self::BridgeCmd { bridge_key } => {
+                write!(f, "BridgeCmd({bridge_key})")
+            }
+        }
+    }
+}
+
+impl QueueKey for SledQueueKey {
+    fn queue_name(&self) -> String {
+        match self {
+            Self::EvmTx { chain_id, .. } => format!("evm_tx_{chain_id}"),
+            Self::SubstrateTx { chain_id, .. } => {
+                format!("substrate_tx_{chain_id}")
+            }
+            Self::BridgeCmd { bridge_key, .. } => {
+                format!("bridge_cmd_{}", bridge_key.chain_id.chain_id())
+            }
+        }
+    }
+
+    fn item_key(&self) -> Option<[u8; 64]> {
+        match self {
+            Self::EvmTx { optional_key, .. } => *optional_key,
+            Self::SubstrateTx { optional_key, .. } => *optional_key,
+            Self::BridgeCmd { .. } => None,
+        }
+    }
+}
+
+/// The priority of an item in a queue.
+///
+/// Items with a higher priority are dequeued first, items with the same priority
+/// are dequeued in the order they were enqueued.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum QueuePriority {
+    /// Served after everything else.
+    Low = 0,
+    /// The priority of routine items.
+    #[default]
+    Normal = 1,
+    /// Served before everything else, for example ownership transfers.
+    High = 2,
+}
+
+impl QueuePriority {
+    /// Returns the priority of the given numeric value, if any.
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Low),
+            1 => Some(Self::Normal),
+            2 => Some(Self::High),
+            _ => None,
+        }
+    }
+}
+
+/// Options of an item inserted into a queue.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EnqueueOptions {
+    /// The priority of the item.
+    pub priority: QueuePriority,
+    /// The item is not dequeued before this time, in seconds since the unix epoch.
+    pub not_before: Option<u64>,
+}
+
+impl EnqueueOptions {
+    /// Options of an item with the given priority.
+    pub fn with_priority(priority: QueuePriority) -> Self {
+        Self {
+            priority,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the time before which the item is not dequeued.
+    pub fn not_before(mut self, timestamp: u64) -> Self {
+        self.not_before = Some(timestamp);
+        self
+    }
+
+    /// Returns `true` if the item could be dequeued at the given time.
+    pub fn is_ready(&self, now: u64) -> bool {
+        self.not_before.map(|t| t <= now).unwrap_or(true)
+    }
+}
+
+/// An item dequeued from a queue, along with how it was enqueued.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DequeuedItem<Item> {
+    /// The item itself.
+    pub item: Item,
+    /// The _optional_ key the item was enqueued with.
+    pub item_key: Option<[u8; 64]>,
+    /// The priority and not-before time the item was enqueued with.
+    pub options: EnqueueOptions,
+}
+
+/// The current time, in seconds since the unix epoch.
+pub(crate) fn unix_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
 /// A Queue Store is a simple trait that help storing items in a queue.
-/// The queue is a FIFO queue, that can be used to store anything that can be serialized.
+/// The queue is a priority queue, FIFO within the same priority, that can be used
+/// to store anything that can be serialized.
 ///
 /// There is a simple API to get the items from the queue, from a background task for example.
 pub trait QueueStore<Item>
@@ -389,16 +773,161 @@ where
 {
     /// The type of the queue key.
     type Key: QueueKey;
-    /// Insert an item into the queue.
-    fn enqueue_item(&self, key: Self::Key, item: Item) -> crate::Result<()>;
-    /// Get an item from the queue, and removes it.
-    fn dequeue_item(&self, key: Self::Key) -> crate::Result<Option<Item>>;
-    /// Get an item from the queue, without removing it.
+    /// Insert an item into the queue, with the default options.
+    fn enqueue_item(&self, key: Self::Key, item: Item) -> crate::Result<()> {
+        self.enqueue_item_with_options(key, item, EnqueueOptions::default())
+    }
+    /// Insert an item into the queue, with the given priority and not-before time.
+    fn enqueue_item_with_options(
+        &self,
+        key: Self::Key,
+        item: Item,
+        options: EnqueueOptions,
+    ) -> crate::Result<()>;
+    /// Get the item with the highest priority that is ready from the queue, and removes it.
+    fn dequeue_item(&self, key: Self::Key) -> crate::Result<Option<Item>> {
+        let dequeued = self.dequeue_item_with_options(key)?;
+        Ok(dequeued.map(|dequeued| dequeued.item))
+    }
+    /// Same as [`QueueStore::dequeue_item`], but also returns the item key and the options
+    /// the item was enqueued with, so it could be enqueued again or dead-lettered as it was.
+    fn dequeue_item_with_options(
+        &self,
+        key: Self::Key,
+    ) -> crate::Result<Option<DequeuedItem<Item>>> {
+        self.dequeue_item_if(key, |_| true)
+    }
+    /// Same as [`QueueStore::dequeue_item_with_options`], but only removes the item if it
+    /// matches the given predicate, otherwise it is left at the head of the queue.
+    ///
+    /// Unlike a peek followed by a dequeue, the item that is checked is the one removed,
+    /// even if another item was enqueued ahead of it in the meantime.
+    fn dequeue_item_if<F>(
+        &self,
+        key: Self::Key,
+        predicate: F,
+    ) -> crate::Result<Option<DequeuedItem<Item>>>
+    where
+        F: FnOnce(&Item) -> bool;
+    /// Get the item with the highest priority that is ready from the queue, without removing it.
     fn peek_item(&self, key: Self::Key) -> crate::Result<Option<Item>>;
     /// Check if the item is in the queue.
     fn has_item(&self, key: Self::Key) -> crate::Result<bool>;
     /// Remove an item from the queue.
     fn remove_item(&self, key: Self::Key) -> crate::Result<Option<Item>>;
+    /// Moves an item that failed to be processed into the dead-letter queue of its queue,
+    /// with the default options.
+    ///
+    /// If the same item already failed before, its attempts are incremented.
+    fn dead_letter_item(
+        &self,
+        key: Self::Key,
+        item: Item,
+        reason: String,
+    ) -> crate::Result<DeadLetter<Item>> {
+        self.dead_letter_item_with_options(
+            key,
+            item,
+            EnqueueOptions::default(),
+            reason,
+        )
+    }
+    /// Same as [`QueueStore::dead_letter_item`], but keeps the priority the item was
+    /// enqueued with, so it is requeued with the same priority.
+    fn dead_letter_item_with_options(
+        &self,
+        key: Self::Key,
+        item: Item,
+        options: EnqueueOptions,
+        reason: String,
+    ) -> crate::Result<DeadLetter<Item>>;
+    /// Get the dead letters of the queue, the oldest first.
+    fn get_dead_letters(
+        &self,
+        key: Self::Key,
+    ) -> crate::Result<Vec<DeadLetter<Item>>>;
+    /// Moves a dead letter back to the end of its queue, with the priority it was
+    /// enqueued with.
+    ///
+    /// Returns `false` if there is no dead letter with this id.
+    fn requeue_dead_letter(
+        &self,
+        key: Self::Key,
+        id: types::H256,
+    ) -> crate::Result<bool>;
+    /// Removes all the dead letters of the queue, and returns how many were removed.
+    fn purge_dead_letters(&self, key: Self::Key) -> crate::Result<usize>;
+}
+
+/// An item that failed to be processed, kept aside so it is not lost.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadLetter<Item> {
+    /// The id of the dead letter, the keccak256 hash of the JSON encoded item.
+    pub id: types::H256,
+    /// The _optional_ key used to find the item in its queue.
+    pub item_key: Option<types::Bytes>,
+    /// The item itself.
+    pub item: Item,
+    /// The priority the item was enqueued with.
+    #[serde(default)]
+    pub priority: QueuePriority,
+    /// The error of the last failure.
+    pub reason: String,
+    /// How many times the item failed.
+    pub attempts: u32,
+    /// The first time the item failed, in seconds since the unix epoch.
+    pub first_failed_at: u64,
+    /// The last time the item failed, in seconds since the unix epoch.
+    pub last_failed_at: u64,
+}
+
+impl<Item> DeadLetter<Item>
+where
+    Item: Serialize,
+{
+    /// Returns the id of the dead letter of the given item.
+    pub fn id_of(item: &Item) -> crate::Result<types::H256> {
+        let bytes = serde_json::to_vec(item)?;
+        Ok(types::H256::from(ethers::utils::keccak256(bytes)))
+    }
+
+    /// Records a new failure of the item, on top of the `previous` ones.
+    pub(crate) fn failed(
+        previous: Option<Self>,
+        id: types::H256,
+        item_key: Option<[u8; 64]>,
+        item: Item,
+        options: EnqueueOptions,
+        reason: String,
+    ) -> Self {
+        let now = unix_timestamp();
+        let (attempts, first_failed_at) = previous
+            .map(|p| (p.attempts.saturating_add(1), p.first_failed_at))
+            .unwrap_or((1, now));
+        Self {
+            id,
+            item_key: item_key.map(|k| types::Bytes::from(k.to_vec())),
+            item,
+            priority: options.priority,
+            reason,
+            attempts,
+            first_failed_at,
+            last_failed_at: now,
+        }
+    }
+
+    /// The key used to find the item in its queue, if any.
+    pub(crate) fn queue_item_key(&self) -> Option<[u8; 64]> {
+        self.item_key
+            .as_ref()
+            .and_then(|k| k.as_ref().try_into().ok())
+    }
+
+    /// The options to enqueue the item again with.
+    pub(crate) fn requeue_options(&self) -> EnqueueOptions {
+        EnqueueOptions::with_priority(self.priority)
+    }
 }
 
 impl<S, T> QueueStore<T> for Arc<S>
@@ -412,8 +941,31 @@ where
         S::enqueue_item(self, key, item)
     }
 
-    fn dequeue_item(&self, key: Self::Key) -> crate::Result<Option<T>> {
-        S::dequeue_item(self, key)
+    fn enqueue_item_with_options(
+        &self,
+        key: Self::Key,
+        item: T,
+        options: EnqueueOptions,
+    ) -> crate::Result<()> {
+        S::enqueue_item_with_options(self, key, item, options)
+    }
+
+    fn dequeue_item_with_options(
+        &self,
+        key: Self::Key,
+    ) -> crate::Result<Option<DequeuedItem<T>>> {
+        S::dequeue_item_with_options(self, key)
+    }
+
+    fn dequeue_item_if<F>(
+        &self,
+        key: Self::Key,
+        predicate: F,
+    ) -> crate::Result<Option<DequeuedItem<T>>>
+    where
+        F: FnOnce(&T) -> bool,
+    {
+        S::dequeue_item_if(self, key, predicate)
     }
 
     fn peek_item(&self, key: Self::Key) -> crate::Result<Option<T>> {
@@ -427,6 +979,44 @@ where
     fn remove_item(&self, key: Self::Key) -> crate::Result<Option<T>> {
         S::remove_item(self, key)
     }
+
+    fn dead_letter_item(
+        &self,
+        key: Self::Key,
+        item: T,
+        reason: String,
+    ) -> crate::Result<DeadLetter<T>> {
+        S::dead_letter_item(self, key, item, reason)
+    }
+
+    fn dead_letter_item_with_options(
+        &self,
+        key: Self::Key,
+        item: T,
+        options: EnqueueOptions,
+        reason: String,
+    ) -> crate::Result<DeadLetter<T>> {
+        S::dead_letter_item_with_options(self, key, item, options, reason)
+    }
+
+    fn get_dead_letters(
+        &self,
+        key: Self::Key,
+    ) -> crate::Result<Vec<DeadLetter<T>>> {
+        S::get_dead_letters(self, key)
+    }
+
+    fn requeue_dead_letter(
+        &self,
+        key: Self::Key,
+        id: types::H256,
+    ) -> crate::Result<bool> {
+        S::requeue_dead_letter(self, key, id)
+    }
+
+    fn purge_dead_letters(&self, key: Self::Key) -> crate::Result<usize> {
+        S::purge_dead_letters(self, key)
+    }
 }
 
 /// A trait for Cached Token Price.
@@ -449,3 +1039,281 @@ where
         value: CachedTokenPrice,
     ) -> crate::Result<()>;
 }
+
+/// A Proposals Queue Store keeps the state of the proposals queues, like the
+/// queued proposals and the state of their policies, so they survive a restart.
+pub trait ProposalsQueueStore<QueueState>
+where
+    QueueState: Serialize + DeserializeOwned,
+{
+    /// Get the saved state of the queue with the given key.
+    /// If the queue was never saved, it will return `None`.
+    fn get_proposals_queue(
+        &self,
+        queue_key: &str,
+    ) -> crate::Result<Option<QueueState>>;
+    /// Save the state of the queue with the given key.
+    ///
+    /// **Note**: this will override the previous state.
+    fn save_proposals_queue(
+        &self,
+        queue_key: &str,
+        state: QueueState,
+    ) -> crate::Result<()>;
+}
+
+/// How long the store keeps the data that is only needed for a while.
+///
+/// Every limit is optional, nothing is pruned for the limits that are not set.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// The maximum age, in seconds, of the hashes of the processed events.
+    pub event_hashes_max_age: Option<u64>,
+    /// The maximum age, in seconds, of the cached token prices.
+    ///
+    /// Prices cached before their age was recorded are always pruned.
+    pub token_prices_max_age: Option<u64>,
+    /// The maximum age, in seconds since they last failed, of the dead letters.
+    pub dead_letters_max_age: Option<u64>,
+    /// How many blocks behind the last block processed the blocks of the cached
+    /// leaves and encrypted outputs are kept.
+    ///
+    /// The leaves and encrypted outputs of older blocks are then assumed to be
+    /// final, and are no longer rolled back on chain reorganizations.
+    pub block_depth: Option<u64>,
+    /// Whether to remove what is left of the queue items that were already dequeued.
+    pub finished_queue_entries: bool,
+}
+
+/// What was removed by [`RetentionStore::prune`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    /// The number of processed-event hashes removed.
+    pub event_hashes: usize,
+    /// The number of cached token prices removed.
+    pub token_prices: usize,
+    /// The number of dead letters removed.
+    pub dead_letters: usize,
+    /// The number of blocks of leaves and encrypted outputs forgotten.
+    pub blocks: usize,
+    /// The number of entries of finished queue items removed.
+    pub queue_entries: usize,
+    /// The size, in bytes, of the keys and values removed.
+    pub reclaimed_bytes: u64,
+}
+
+impl PruneReport {
+    /// The total number of entries removed.
+    pub fn total(&self) -> usize {
+        self.event_hashes
+            + self.token_prices
+            + self.dead_letters
+            + self.blocks
+            + self.queue_entries
+    }
+}
+
+/// Returns `true` if the given time, in seconds since the unix epoch, is older than
+/// the given maximum age.
+pub(crate) fn is_expired(timestamp: u64, max_age: u64, now: u64) -> bool {
+    now.saturating_sub(timestamp) > max_age
+}
+
+/// A Retention Store could remove the data that is no longer needed, following
+/// a [`RetentionPolicy`].
+pub trait RetentionStore: Send + Sync {
+    /// Removes everything the given policy does not keep, and reports what was removed.
+    fn prune(&self, policy: &RetentionPolicy) -> crate::Result<PruneReport>;
+}
+
+/// A single write of a [`StoreBatch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum BatchOp {
+    /// See [`HistoryStore::set_last_block_number`].
+    SetLastBlockNumber {
+        key: HistoryStoreKey,
+        block_number: u64,
+    },
+    /// See [`HistoryStore::set_target_block_number`].
+    SetTargetBlockNumber {
+        key: HistoryStoreKey,
+        block_number: u64,
+    },
+    /// See [`LeafCacheStore::insert_leaves_and_last_deposit_block_number`].
+    InsertLeaves {
+        key: HistoryStoreKey,
+        leaves: Vec<(u32, Vec<u8>)>,
+        block_number: u64,
+        block_hash: types::H256,
+    },
+    /// See [`EncryptedOutputCacheStore::insert_encrypted_output_and_last_deposit_block_number`].
+    InsertEncryptedOutputs {
+        key: HistoryStoreKey,
+        encrypted_outputs: Vec<(u32, Vec<u8>)>,
+        block_number: u64,
+        block_hash: types::H256,
+    },
+    /// See [`EventHashStore::store_event`].
+    StoreEvent { event: Vec<u8> },
+    /// See [`EventHashStore::delete_event`].
+    DeleteEvent { event: Vec<u8> },
+    /// See [`QueueStore::enqueue_item_with_options`], the item is serialized as JSON.
+    EnqueueItem {
+        key: SledQueueKey,
+        item: String,
+        options: EnqueueOptions,
+    },
+}
+
+/// A group of writes, to the different stores, that are applied together by a
+/// [`BatchStore`].
+///
+/// Nothing is written until the batch is applied, so the writes of a batch could
+/// not be read back before that.
+#[derive(Debug, Clone, Default)]
+pub struct StoreBatch {
+    pub(crate) ops: Vec<BatchOp>,
+}
+
+impl StoreBatch {
+    /// Creates an empty batch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if the batch has no writes.
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// The number of writes in the batch.
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Sets the last block number processed for the given key.
+    pub fn set_last_block_number<K: Into<HistoryStoreKey>>(
+        &mut self,
+        key: K,
+        block_number: u64,
+    ) -> &mut Self {
+        self.ops.push(BatchOp::SetLastBlockNumber {
+            key: key.into(),
+            block_number,
+        });
+        self
+    }
+
+    /// Sets the target block number for the given key.
+    pub fn set_target_block_number<K: Into<HistoryStoreKey>>(
+        &mut self,
+        key: K,
+        block_number: u64,
+    ) -> &mut Self {
+        self.ops.push(BatchOp::SetTargetBlockNumber {
+            key: key.into(),
+            block_number,
+        });
+        self
+    }
+
+    /// Inserts the leaves, found in the given block, and sets the last deposit
+    /// block number for the given key.
+    pub fn insert_leaves_and_last_deposit_block_number<
+        K: Into<HistoryStoreKey>,
+    >(
+        &mut self,
+        key: K,
+        leaves: &[(u32, Vec<u8>)],
+        block_number: u64,
+        block_hash: types::H256,
+    ) -> &mut Self {
+        self.ops.push(BatchOp::InsertLeaves {
+            key: key.into(),
+            leaves: leaves.to_vec(),
+            block_number,
+            block_hash,
+        });
+        self
+    }
+
+    /// Inserts the encrypted outputs, found in the given block, and sets the last
+    /// deposit block number of the encrypted outputs for the given key.
+    pub fn insert_encrypted_output_and_last_deposit_block_number<
+        K: Into<HistoryStoreKey>,
+    >(
+        &mut self,
+        key: K,
+        encrypted_outputs: &[(u32, Vec<u8>)],
+        block_number: u64,
+        block_hash: types::H256,
+    ) -> &mut Self {
+        self.ops.push(BatchOp::InsertEncryptedOutputs {
+            key: key.into(),
+            encrypted_outputs: encrypted_outputs.to_vec(),
+            block_number,
+            block_hash,
+        });
+        self
+    }
+
+    /// Stores the hash of the given event.
+    pub fn store_event(&mut self, event: &[u8]) -> &mut Self {
+        self.ops.push(BatchOp::StoreEvent {
+            event: event.to_vec(),
+        });
+        self
+    }
+
+    /// Deletes the hash of the given event.
+    pub fn delete_event(&mut self, event: &[u8]) -> &mut Self {
+        self.ops.push(BatchOp::DeleteEvent {
+            event: event.to_vec(),
+        });
+        self
+    }
+
+    /// Inserts an item into the queue.
+    pub fn enqueue_item<T: Serialize>(
+        &mut self,
+        key: SledQueueKey,
+        item: &T,
+    ) -> crate::Result<&mut Self> {
+        self.enqueue_item_with_options(key, item, EnqueueOptions::default())
+    }
+
+    /// Inserts an item into the queue, with the given priority and not-before time.
+    pub fn enqueue_item_with_options<T: Serialize>(
+        &mut self,
+        key: SledQueueKey,
+        item: &T,
+        options: EnqueueOptions,
+    ) -> crate::Result<&mut Self> {
+        self.ops.push(BatchOp::EnqueueItem {
+            key,
+            item: serde_json::to_string(item)?,
+            options,
+        });
+        Ok(self)
+    }
+}
+
+/// A Batch Store applies a [`StoreBatch`] atomically, that is either all of its
+/// writes are stored or none of them, even if the relayer crashes meanwhile.
+pub trait BatchStore: Send + Sync {
+    /// Applies all the writes of the given batch, in order.
+    fn apply_batch(&self, batch: StoreBatch) -> crate::Result<()>;
+
+    /// Builds a batch with the given closure, then applies it.
+    ///
+    /// Nothing is written if the closure fails.
+    fn batch<R, F>(&self, f: F) -> crate::Result<R>
+    where
+        F: FnOnce(&mut StoreBatch) -> crate::Result<R>,
+    {
+        let mut batch = StoreBatch::new();
+        let output = f(&mut batch)?;
+        self.apply_batch(batch)?;
+        Ok(output)
+    }
+}
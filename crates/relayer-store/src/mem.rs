@@ -12,22 +12,44 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::fmt::Debug;
 use std::sync::Arc;
 
 use parking_lot::RwLock;
-use webb::evm::ethers::types;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use webb::evm::ethers::{self, types};
 
-use crate::TokenPriceCacheStore;
+use crate::{
+    is_expired, unix_timestamp, BatchOp, BatchStore, EnqueueOptions,
+    ProposalsQueueStore, PruneReport, QueueKey, RetentionPolicy,
+    RetentionStore, SledQueueKey, StoreBatch, TokenPriceCacheStore,
+};
 
 use super::{
-    EncryptedOutputCacheStore, HistoryStore, HistoryStoreKey, LeafCacheStore,
+    BlockInfo, DeadLetter, DequeuedItem, EncryptedOutputCacheStore,
+    EventHashStore, HistoryStore, HistoryStoreKey, LeafCacheStore, QueueStore,
+    ReorgStore,
 };
 
 type MemStore = HashMap<HistoryStoreKey, Vec<types::H256>>;
-type MemStoreForVec = HashMap<HistoryStoreKey, Vec<Vec<u8>>>;
+type MemStoreForVec = HashMap<HistoryStoreKey, BTreeMap<u32, Vec<u8>>>;
 type MemStoreForMap = HashMap<HistoryStoreKey, BTreeMap<u32, types::H256>>;
+type MemQueues = HashMap<String, VecDeque<MemQueueItem>>;
+type MemBlocks = HashMap<HistoryStoreKey, BTreeMap<u32, BlockInfo>>;
+type MemDeadLetters = HashMap<String, HashMap<types::H256, Vec<u8>>>;
+
+/// An item stored in one of the in-memory queues.
+#[derive(Clone, Debug)]
+struct MemQueueItem {
+    /// an _optional_ key used to find this item directly.
+    key: Option<[u8; 64]>,
+    /// The item, serialized as JSON.
+    value: Vec<u8>,
+    /// The priority and not-before time of the item.
+    options: EnqueueOptions,
+}
 /// InMemoryStore is a store that stores the history of events in memory.
 #[derive(Clone, Default)]
 pub struct InMemoryStore {
@@ -39,7 +61,17 @@ pub struct InMemoryStore {
     last_deposit_block_numbers: Arc<RwLock<HashMap<HistoryStoreKey, u64>>>,
     encrypted_output_last_deposit_block_numbers:
         Arc<RwLock<HashMap<HistoryStoreKey, u64>>>,
-    token_prices_cache: Arc<RwLock<HashMap<String, Vec<u8>>>>,
+    /// the cached prices, with the time they were cached.
+    token_prices_cache: Arc<RwLock<HashMap<String, (u64, Vec<u8>)>>>,
+    /// the processed-event hashes, with the time they were stored.
+    event_hashes: Arc<RwLock<HashMap<[u8; 32], u64>>>,
+    queues: Arc<RwLock<MemQueues>>,
+    dead_letters: Arc<RwLock<MemDeadLetters>>,
+    leaf_blocks: Arc<RwLock<MemBlocks>>,
+    encrypted_output_blocks: Arc<RwLock<MemBlocks>>,
+    last_block_hashes: Arc<RwLock<HashMap<HistoryStoreKey, types::H256>>>,
+    /// the saved states of the proposals queues.
+    proposals_queues: Arc<RwLock<HashMap<String, Vec<u8>>>>,
 }
 
 impl std::fmt::Debug for InMemoryStore {
@@ -119,8 +151,9 @@ impl LeafCacheStore for InMemoryStore {
         &self,
         key: K,
     ) -> crate::Result<()> {
-        let mut guard = self.leaf_store.write();
-        guard.clear();
+        let key = key.into();
+        self.leaf_store.write().remove(&key);
+        self.leaf_blocks.write().remove(&key);
         Ok(())
     }
 
@@ -140,12 +173,11 @@ impl LeafCacheStore for InMemoryStore {
         range: core::ops::Range<u32>,
     ) -> crate::Result<Self::Output> {
         let guard = self.leaf_store.read();
-        let val = guard.get(&key.into()).cloned().unwrap_or_default();
-        let iter = val
-            .into_iter()
-            .skip(range.start as usize)
-            .take((range.end - range.start) as usize);
-        Ok(iter.collect())
+        let val: Self::Output = guard
+            .get(&key.into())
+            .map(|leaves| leaves.range(range).map(|(k, v)| (*k, *v)).collect())
+            .unwrap_or_default();
+        Ok(val)
     }
 
     #[tracing::instrument(skip(self))]
@@ -170,10 +202,13 @@ impl LeafCacheStore for InMemoryStore {
         key: K,
         leaves: &[(u32, Vec<u8>)],
         block_number: u64,
+        block_hash: types::H256,
     ) -> crate::Result<()> {
         let mut guard1 = self.leaf_store.write();
         let mut guard2 = self.last_deposit_block_numbers.write();
         let mut guard3 = self.last_block_numbers.write();
+        let mut guard4 = self.leaf_blocks.write();
+        let mut guard5 = self.last_block_hashes.write();
         {
             // 1. Insert leaves
             guard1
@@ -193,10 +228,26 @@ impl LeafCacheStore for InMemoryStore {
             // 2. Insert last deposit block number
             guard2.insert(key.clone().into(), block_number);
             // 3. Insert last block number
-            guard3.entry(key.into()).or_insert(block_number);
+            guard3.insert(key.clone().into(), block_number);
+            // 4. Record the block of the leaves, if it is known
+            if block_hash.is_zero() {
+                guard5.remove(&key.into());
+            } else {
+                let block = BlockInfo::new(block_number, block_hash);
+                let blocks = guard4.entry(key.clone().into()).or_default();
+                for (index, _) in leaves {
+                    blocks.insert(*index, block);
+                }
+                guard5.insert(key.into(), block_hash);
+            }
         }
         Ok(())
     }
+
+    fn get_leaves_cache_keys(&self) -> crate::Result<Vec<HistoryStoreKey>> {
+        let guard = self.last_deposit_block_numbers.read();
+        Ok(guard.keys().copied().collect())
+    }
 }
 
 impl EncryptedOutputCacheStore for InMemoryStore {
@@ -208,7 +259,10 @@ impl EncryptedOutputCacheStore for InMemoryStore {
         key: K,
     ) -> crate::Result<Self::Output> {
         let guard = self.encrypted_output_store.read();
-        let val = guard.get(&key.into()).cloned().unwrap_or_default();
+        let val: Self::Output = guard
+            .get(&key.into())
+            .map(|outputs| outputs.values().cloned().collect())
+            .unwrap_or_default();
         Ok(val)
     }
 
@@ -219,12 +273,22 @@ impl EncryptedOutputCacheStore for InMemoryStore {
         range: core::ops::Range<u32>,
     ) -> crate::Result<Self::Output> {
         let guard = self.encrypted_output_store.read();
-        let val = guard.get(&key.into()).cloned().unwrap_or_default();
-        let iter = val
-            .into_iter()
-            .skip(range.start as usize)
-            .take((range.end - range.start) as usize);
-        Ok(iter.collect())
+        let val: Self::Output = guard
+            .get(&key.into())
+            .map(|outputs| {
+                outputs.range(range).map(|(_, v)| v.clone()).collect()
+            })
+            .unwrap_or_default();
+        Ok(val)
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_encrypted_output_with_indices<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<BTreeMap<u32, Vec<u8>>> {
+        let guard = self.encrypted_output_store.read();
+        Ok(guard.get(&key.into()).cloned().unwrap_or_default())
     }
 
     #[tracing::instrument(skip(self))]
@@ -251,21 +315,502 @@ impl EncryptedOutputCacheStore for InMemoryStore {
         key: K,
         encrypted_outputs: &[(u32, Vec<u8>)],
         block_number: u64,
+        block_hash: types::H256,
     ) -> crate::Result<()> {
         let mut guard1 = self.encrypted_output_store.write();
-        let mut guard2 = self.last_deposit_block_numbers.write();
+        let mut guard2 =
+            self.encrypted_output_last_deposit_block_numbers.write();
+        let mut guard3 = self.encrypted_output_blocks.write();
         {
-            guard1
-                .entry(key.clone().into())
-                .and_modify(|v| {
-                    for (index, encrypted_output) in encrypted_outputs {
-                        v.insert(*index as usize, encrypted_output.clone());
+            let outputs = guard1.entry(key.clone().into()).or_default();
+            for (index, encrypted_output) in encrypted_outputs {
+                outputs.insert(*index, encrypted_output.clone());
+            }
+            if !block_hash.is_zero() {
+                let block = BlockInfo::new(block_number, block_hash);
+                let blocks = guard3.entry(key.clone().into()).or_default();
+                for (index, _) in encrypted_outputs {
+                    blocks.insert(*index, block);
+                }
+            }
+            guard2.insert(key.into(), block_number);
+        }
+        Ok(())
+    }
+
+    fn get_encrypted_output_cache_keys(
+        &self,
+    ) -> crate::Result<Vec<HistoryStoreKey>> {
+        let guard = self.encrypted_output_last_deposit_block_numbers.read();
+        Ok(guard.keys().copied().collect())
+    }
+}
+
+impl ReorgStore for InMemoryStore {
+    #[tracing::instrument(skip(self))]
+    fn set_last_block<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        block: BlockInfo,
+    ) -> crate::Result<()> {
+        let key = key.into();
+        let mut guard1 = self.last_block_numbers.write();
+        let mut guard2 = self.last_block_hashes.write();
+        guard1.insert(key, block.number);
+        guard2.insert(key, block.hash);
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_last_block_hash<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<Option<types::H256>> {
+        let guard = self.last_block_hashes.read();
+        Ok(guard.get(&key.into()).cloned())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_leaf_block<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        leaf_index: u32,
+    ) -> crate::Result<Option<BlockInfo>> {
+        let guard = self.leaf_blocks.read();
+        let val = guard
+            .get(&key.into())
+            .and_then(|blocks| blocks.get(&leaf_index).cloned());
+        Ok(val)
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_encrypted_output_block<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        index: u32,
+    ) -> crate::Result<Option<BlockInfo>> {
+        let guard = self.encrypted_output_blocks.read();
+        let val = guard
+            .get(&key.into())
+            .and_then(|blocks| blocks.get(&index).cloned());
+        Ok(val)
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_cached_blocks<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<Vec<BlockInfo>> {
+        let key = key.into();
+        let guard1 = self.leaf_blocks.read();
+        let guard2 = self.encrypted_output_blocks.read();
+        let blocks: BTreeMap<_, _> = guard1
+            .get(&key)
+            .into_iter()
+            .chain(guard2.get(&key))
+            .flat_map(|blocks| blocks.values())
+            .map(|block| ((block.number, block.hash), *block))
+            .collect();
+        Ok(blocks.into_values().rev().collect())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn rollback_to_block<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        block: BlockInfo,
+    ) -> crate::Result<()> {
+        let key = key.into();
+        let mut leaf_store = self.leaf_store.write();
+        let mut leaf_blocks = self.leaf_blocks.write();
+        let mut encrypted_output_store = self.encrypted_output_store.write();
+        let mut encrypted_output_blocks = self.encrypted_output_blocks.write();
+        let mut last_deposit_block_numbers =
+            self.last_deposit_block_numbers.write();
+        let mut encrypted_output_last_deposit_block_numbers =
+            self.encrypted_output_last_deposit_block_numbers.write();
+        let mut last_block_numbers = self.last_block_numbers.write();
+        let mut last_block_hashes = self.last_block_hashes.write();
+
+        if let Some(blocks) = leaf_blocks.get_mut(&key) {
+            let leaves = leaf_store.entry(key).or_default();
+            blocks.retain(|index, b| {
+                let orphaned = b.number > block.number;
+                if orphaned {
+                    leaves.remove(index);
+                }
+                !orphaned
+            });
+        }
+        if let Some(blocks) = encrypted_output_blocks.get_mut(&key) {
+            let outputs = encrypted_output_store.entry(key).or_default();
+            blocks.retain(|index, b| {
+                let orphaned = b.number > block.number;
+                if orphaned {
+                    outputs.remove(index);
+                }
+                !orphaned
+            });
+        }
+        // never move the last deposit block numbers forward.
+        for block_numbers in [
+            &mut *last_deposit_block_numbers,
+            &mut *encrypted_output_last_deposit_block_numbers,
+        ] {
+            if let Some(v) = block_numbers.get_mut(&key) {
+                *v = (*v).min(block.number);
+            }
+        }
+        last_block_numbers.insert(key, block.number);
+        last_block_hashes.insert(key, block.hash);
+        Ok(())
+    }
+}
+
+impl EventHashStore for InMemoryStore {
+    fn store_event(&self, event: &[u8]) -> crate::Result<()> {
+        let hash = ethers::utils::keccak256(event);
+        self.event_hashes.write().insert(hash, unix_timestamp());
+        Ok(())
+    }
+
+    fn contains_event(&self, event: &[u8]) -> crate::Result<bool> {
+        let hash = ethers::utils::keccak256(event);
+        Ok(self.event_hashes.read().contains_key(&hash))
+    }
+
+    fn delete_event(&self, event: &[u8]) -> crate::Result<()> {
+        let hash = ethers::utils::keccak256(event);
+        self.event_hashes.write().remove(&hash);
+        Ok(())
+    }
+}
+
+impl RetentionStore for InMemoryStore {
+    #[tracing::instrument(skip(self))]
+    fn prune(&self, policy: &RetentionPolicy) -> crate::Result<PruneReport> {
+        let now = unix_timestamp();
+        let mut report = PruneReport::default();
+        if let Some(max_age) = policy.event_hashes_max_age {
+            self.event_hashes.write().retain(|hash, stored_at| {
+                let expired = is_expired(*stored_at, max_age, now);
+                if expired {
+                    report.event_hashes += 1;
+                    report.reclaimed_bytes += (hash.len() + 8) as u64;
+                }
+                !expired
+            });
+        }
+        if let Some(max_age) = policy.token_prices_max_age {
+            self.token_prices_cache.write().retain(
+                |token, (updated_at, value)| {
+                    let expired = is_expired(*updated_at, max_age, now);
+                    if expired {
+                        report.token_prices += 1;
+                        report.reclaimed_bytes +=
+                            (token.len() + value.len() + 8) as u64;
                     }
-                })
-                .or_insert_with(|| {
-                    encrypted_outputs.iter().map(|v| v.1.clone()).collect()
+                    !expired
+                },
+            );
+        }
+        if let Some(max_age) = policy.dead_letters_max_age {
+            let mut dead_letters = self.dead_letters.write();
+            for queue in dead_letters.values_mut() {
+                queue.retain(|id, value| {
+                    let expired = serde_json::from_slice::<
+                        DeadLetter<serde_json::Value>,
+                    >(value)
+                    .map(|d| is_expired(d.last_failed_at, max_age, now))
+                    .unwrap_or(false);
+                    if expired {
+                        report.dead_letters += 1;
+                        report.reclaimed_bytes +=
+                            (id.as_bytes().len() + value.len()) as u64;
+                    }
+                    !expired
                 });
-            guard2.insert(key.into(), block_number);
+            }
+            dead_letters.retain(|_, queue| !queue.is_empty());
+        }
+        if let Some(depth) = policy.block_depth {
+            // copy the block numbers, so that no lock on them is held while
+            // the blocks are locked, `rollback_to_block` locks them the other
+            // way around.
+            let last_block_numbers = self.last_block_numbers.read().clone();
+            for store in [
+                &mut *self.leaf_blocks.write(),
+                &mut *self.encrypted_output_blocks.write(),
+            ] {
+                for (key, blocks) in store.iter_mut() {
+                    let last_block_number = match last_block_numbers.get(key) {
+                        Some(v) => *v,
+                        None => continue,
+                    };
+                    blocks.retain(|_, b| {
+                        let is_final =
+                            b.number.saturating_add(depth) < last_block_number;
+                        if is_final {
+                            report.blocks += 1;
+                            // the index and the encoded block.
+                            report.reclaimed_bytes += 4 + 40;
+                        }
+                        !is_final
+                    });
+                }
+            }
+        }
+        // the in-memory queues keep nothing once an item is dequeued.
+        Ok(report)
+    }
+}
+
+impl InMemoryStore {
+    /// Inserts an item, serialized as JSON, into the queue.
+    fn push_item(
+        &self,
+        key: SledQueueKey,
+        value: Vec<u8>,
+        options: EnqueueOptions,
+    ) {
+        let mut guard = self.queues.write();
+        let queue = guard.entry(key.queue_name()).or_default();
+        // the queue is kept sorted by priority, FIFO within the same priority.
+        let position = queue
+            .iter()
+            .position(|item| item.options.priority < options.priority)
+            .unwrap_or(queue.len());
+        queue.insert(
+            position,
+            MemQueueItem {
+                key: key.item_key(),
+                value,
+                options,
+            },
+        );
+        tracing::trace!("enqueue item under key = {}", key);
+    }
+}
+
+impl<T> QueueStore<T> for InMemoryStore
+where
+    T: Serialize + DeserializeOwned + Clone,
+{
+    type Key = SledQueueKey;
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn enqueue_item_with_options(
+        &self,
+        key: Self::Key,
+        item: T,
+        options: EnqueueOptions,
+    ) -> crate::Result<()> {
+        self.push_item(key, serde_json::to_vec(&item)?, options);
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn dequeue_item_if<F>(
+        &self,
+        key: Self::Key,
+        predicate: F,
+    ) -> crate::Result<Option<DequeuedItem<T>>>
+    where
+        F: FnOnce(&T) -> bool,
+    {
+        let now = unix_timestamp();
+        let mut guard = self.queues.write();
+        let Some(queue) = guard.get_mut(&key.queue_name()) else {
+            return Ok(None);
+        };
+        let Some(position) =
+            queue.iter().position(|item| item.options.is_ready(now))
+        else {
+            return Ok(None);
+        };
+        // only remove the item once it is decoded, so it is not lost on error.
+        let value = serde_json::from_slice(&queue[position].value)?;
+        if !predicate(&value) {
+            return Ok(None);
+        }
+        let item = queue.remove(position).expect("the item is in the queue");
+        Ok(Some(DequeuedItem {
+            item: value,
+            item_key: item.key,
+            options: item.options,
+        }))
+    }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn peek_item(&self, key: Self::Key) -> crate::Result<Option<T>> {
+        let now = unix_timestamp();
+        let guard = self.queues.read();
+        let item = guard.get(&key.queue_name()).and_then(|queue| {
+            queue.iter().find(|item| item.options.is_ready(now))
+        });
+        match item {
+            Some(item) => Ok(Some(serde_json::from_slice(&item.value)?)),
+            None => Ok(None),
+        }
+    }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn has_item(&self, key: Self::Key) -> crate::Result<bool> {
+        let item_key = match key.item_key() {
+            Some(k) => k,
+            None => return Ok(false),
+        };
+        let guard = self.queues.read();
+        let exists = guard
+            .get(&key.queue_name())
+            .map(|queue| queue.iter().any(|item| item.key == Some(item_key)))
+            .unwrap_or(false);
+        Ok(exists)
+    }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn remove_item(&self, key: Self::Key) -> crate::Result<Option<T>> {
+        let item_key = match key.item_key() {
+            Some(k) => k,
+            None => return Ok(None),
+        };
+        let mut guard = self.queues.write();
+        let queue = match guard.get_mut(&key.queue_name()) {
+            Some(queue) => queue,
+            None => return Ok(None),
+        };
+        let position = queue.iter().position(|item| item.key == Some(item_key));
+        match position.and_then(|i| queue.remove(i)) {
+            Some(item) => {
+                tracing::trace!("removed item from the queue..");
+                Ok(serde_json::from_slice(&item.value).ok())
+            }
+            None => {
+                tracing::trace!("item with key {} not found in queue", key);
+                Ok(None)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn dead_letter_item_with_options(
+        &self,
+        key: Self::Key,
+        item: T,
+        options: EnqueueOptions,
+        reason: String,
+    ) -> crate::Result<DeadLetter<T>> {
+        let id = DeadLetter::id_of(&item)?;
+        let mut guard = self.dead_letters.write();
+        let dead_letters = guard.entry(key.queue_name()).or_default();
+        let previous = dead_letters
+            .get(&id)
+            .map(|v| serde_json::from_slice(v))
+            .transpose()?;
+        let dead_letter = DeadLetter::failed(
+            previous,
+            id,
+            key.item_key(),
+            item,
+            options,
+            reason,
+        );
+        dead_letters.insert(id, serde_json::to_vec(&dead_letter)?);
+        tracing::trace!(
+            attempts = dead_letter.attempts,
+            "moved item to the dead-letter queue"
+        );
+        Ok(dead_letter)
+    }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn get_dead_letters(
+        &self,
+        key: Self::Key,
+    ) -> crate::Result<Vec<DeadLetter<T>>> {
+        let guard = self.dead_letters.read();
+        let mut dead_letters = guard
+            .get(&key.queue_name())
+            .into_iter()
+            .flat_map(|d| d.values())
+            .map(|v| serde_json::from_slice::<DeadLetter<T>>(v))
+            .collect::<Result<Vec<_>, _>>()?;
+        dead_letters.sort_by_key(|d| d.first_failed_at);
+        Ok(dead_letters)
+    }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn requeue_dead_letter(
+        &self,
+        key: Self::Key,
+        id: types::H256,
+    ) -> crate::Result<bool> {
+        let removed = self
+            .dead_letters
+            .write()
+            .get_mut(&key.queue_name())
+            .and_then(|d| d.remove(&id));
+        let dead_letter: DeadLetter<T> = match removed {
+            Some(v) => serde_json::from_slice(&v)?,
+            None => return Ok(false),
+        };
+        let queue_key = key.with_item_key(dead_letter.queue_item_key());
+        let options = dead_letter.requeue_options();
+        self.enqueue_item_with_options(queue_key, dead_letter.item, options)?;
+        Ok(true)
+    }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn purge_dead_letters(&self, key: Self::Key) -> crate::Result<usize> {
+        let removed = self.dead_letters.write().remove(&key.queue_name());
+        Ok(removed.map(|d| d.len()).unwrap_or_default())
+    }
+}
+
+impl BatchStore for InMemoryStore {
+    fn apply_batch(&self, batch: StoreBatch) -> crate::Result<()> {
+        // none of these writes could fail, and nothing is persisted,
+        // so the batch could not be left half-written.
+        for op in batch.ops {
+            match op {
+                BatchOp::SetLastBlockNumber { key, block_number } => {
+                    self.set_last_block_number(key, block_number)?;
+                }
+                BatchOp::SetTargetBlockNumber { key, block_number } => {
+                    self.set_target_block_number(key, block_number)?;
+                }
+                BatchOp::InsertLeaves {
+                    key,
+                    leaves,
+                    block_number,
+                    block_hash,
+                } => {
+                    self.insert_leaves_and_last_deposit_block_number(
+                        key,
+                        &leaves,
+                        block_number,
+                        block_hash,
+                    )?;
+                }
+                BatchOp::InsertEncryptedOutputs {
+                    key,
+                    encrypted_outputs,
+                    block_number,
+                    block_hash,
+                } => {
+                    self.insert_encrypted_output_and_last_deposit_block_number(
+                        key,
+                        &encrypted_outputs,
+                        block_number,
+                        block_hash,
+                    )?;
+                }
+                BatchOp::StoreEvent { event } => self.store_event(&event)?,
+                BatchOp::DeleteEvent { event } => self.delete_event(&event)?,
+                BatchOp::EnqueueItem { key, item, options } => {
+                    self.push_item(key, item.into_bytes(), options);
+                }
+            }
         }
         Ok(())
     }
@@ -273,20 +818,48 @@ impl EncryptedOutputCacheStore for InMemoryStore {
 
 impl<T> TokenPriceCacheStore<T> for InMemoryStore
 where
-    T: serde::Serialize + serde::de::DeserializeOwned + Clone + Debug,
+    T: Serialize + DeserializeOwned,
 {
     fn get_price(&self, token: &str) -> crate::Result<Option<T>> {
         self.token_prices_cache
             .read()
             .get(token)
-            .map(|v| serde_json::from_slice(v))
+            .map(|(_, v)| serde_json::from_slice(v))
             .transpose()
             .map_err(Into::into)
     }
 
     fn insert_price(&self, token: &str, value: T) -> crate::Result<()> {
         let v = serde_json::to_vec(&value)?;
-        self.token_prices_cache.write().insert(token.to_string(), v);
+        self.token_prices_cache
+            .write()
+            .insert(token.to_string(), (unix_timestamp(), v));
+        Ok(())
+    }
+}
+
+impl<T> ProposalsQueueStore<T> for InMemoryStore
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn get_proposals_queue(&self, queue_key: &str) -> crate::Result<Option<T>> {
+        self.proposals_queues
+            .read()
+            .get(queue_key)
+            .map(|v| serde_json::from_slice(v))
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    fn save_proposals_queue(
+        &self,
+        queue_key: &str,
+        state: T,
+    ) -> crate::Result<()> {
+        let v = serde_json::to_vec(&state)?;
+        self.proposals_queues
+            .write()
+            .insert(queue_key.to_string(), v);
         Ok(())
     }
 }
@@ -294,6 +867,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::QueuePriority;
 
     #[test]
     fn it_gets_all_leaves() {
@@ -309,6 +883,7 @@ mod tests {
                 key,
                 &generated_leaves,
                 block_number,
+                types::H256::random(),
             )
             .unwrap();
         let leaves = store.get_leaves(key).unwrap();
@@ -338,6 +913,7 @@ mod tests {
                 key,
                 &generated_leaves,
                 block_number,
+                types::H256::random(),
             )
             .unwrap();
         let leaves = store.get_leaves_with_range(key, 5..10).unwrap();
@@ -369,6 +945,7 @@ mod tests {
                 key,
                 &generated_leaves,
                 block_number,
+                types::H256::random(),
             )
             .unwrap();
         let last_deposit_block_number =
@@ -386,4 +963,400 @@ mod tests {
                 .collect::<Vec<_>>()
                 .iter()));
     }
+
+    #[test]
+    fn rollback_to_block_should_work() {
+        let store = InMemoryStore::default();
+        let key = HistoryStoreKey::from(1u32);
+        let old_block = BlockInfo::new(10, types::H256::random());
+        let orphaned_block = BlockInfo::new(11, types::H256::random());
+        for (i, block) in [old_block, orphaned_block].iter().enumerate() {
+            let leaves = vec![(i as u32, types::H256::random().0.to_vec())];
+            store
+                .insert_leaves_and_last_deposit_block_number(
+                    key,
+                    &leaves,
+                    block.number,
+                    block.hash,
+                )
+                .unwrap();
+        }
+        store.rollback_to_block(key, old_block).unwrap();
+        assert_eq!(
+            store.get_leaves(key).unwrap().keys().collect::<Vec<_>>(),
+            vec![&0]
+        );
+        assert_eq!(store.get_cached_blocks(key).unwrap(), vec![old_block]);
+        assert_eq!(store.get_last_block_number(key, 0).unwrap(), 10);
+        assert_eq!(
+            store.get_last_block_hash(key).unwrap(),
+            Some(old_block.hash)
+        );
+    }
+
+    #[test]
+    fn queue_should_work() {
+        let store = InMemoryStore::default();
+        let chain_id = 1u32;
+        let make_key =
+            |i: u8| SledQueueKey::from_evm_with_custom_key(chain_id, [i; 64]);
+        // it is now empty
+        assert_eq!(
+            store
+                .dequeue_item(SledQueueKey::from_evm_chain_id(chain_id))
+                .unwrap(),
+            Option::<u32>::None
+        );
+        for i in 1..=3u8 {
+            store.enqueue_item(make_key(i), u32::from(i)).unwrap();
+        }
+        assert_eq!(
+            store
+                .dequeue_item(SledQueueKey::from_evm_chain_id(chain_id))
+                .unwrap(),
+            Some(1u32)
+        );
+        assert!(QueueStore::<u32>::has_item(&store, make_key(3)).unwrap());
+        assert_eq!(
+            QueueStore::<u32>::remove_item(&store, make_key(3)).unwrap(),
+            Some(3u32)
+        );
+        assert!(!QueueStore::<u32>::has_item(&store, make_key(3)).unwrap());
+        assert_eq!(
+            store
+                .peek_item(SledQueueKey::from_evm_chain_id(chain_id))
+                .unwrap(),
+            Some(2u32)
+        );
+        assert_eq!(
+            store
+                .dequeue_item(SledQueueKey::from_evm_chain_id(chain_id))
+                .unwrap(),
+            Some(2u32)
+        );
+        assert_eq!(
+            store
+                .dequeue_item(SledQueueKey::from_evm_chain_id(chain_id))
+                .unwrap(),
+            Option::<u32>::None
+        );
+    }
+
+    #[test]
+    fn dequeue_should_keep_the_items_it_fails_to_decode() {
+        let store = InMemoryStore::default();
+        let key = SledQueueKey::from_evm_chain_id(1);
+        store
+            .enqueue_item(key, String::from("not a number"))
+            .unwrap();
+        assert!(QueueStore::<u32>::dequeue_item(&store, key).is_err());
+        assert_eq!(
+            store.dequeue_item(key).unwrap(),
+            Some(String::from("not a number"))
+        );
+    }
+
+    #[test]
+    fn dequeue_item_if_should_keep_the_item_in_place() {
+        let store = InMemoryStore::default();
+        let queue = SledQueueKey::from_evm_chain_id(1);
+        for i in 1..=3u32 {
+            store.enqueue_item(queue, i).unwrap();
+        }
+        // the head does not match, so it is left where it was.
+        let dequeued = store.dequeue_item_if(queue, |i: &u32| *i != 1).unwrap();
+        assert!(dequeued.is_none());
+        assert_eq!(store.peek_item(queue).unwrap(), Some(1u32));
+        let dequeued = store.dequeue_item_if(queue, |i: &u32| *i == 1).unwrap();
+        assert_eq!(dequeued.map(|d| d.item), Some(1u32));
+        assert_eq!(store.dequeue_item(queue).unwrap(), Some(2u32));
+        assert_eq!(store.dequeue_item(queue).unwrap(), Some(3u32));
+    }
+
+    #[test]
+    fn queue_priority_and_not_before_should_work() {
+        let store = InMemoryStore::default();
+        let queue = SledQueueKey::from_evm_chain_id(1);
+        store.enqueue_item(queue, 1u32).unwrap();
+        store
+            .enqueue_item_with_options(
+                queue,
+                2u32,
+                EnqueueOptions::with_priority(QueuePriority::Low),
+            )
+            .unwrap();
+        store.enqueue_item(queue, 3u32).unwrap();
+        store
+            .enqueue_item_with_options(
+                queue,
+                4u32,
+                EnqueueOptions::with_priority(QueuePriority::High),
+            )
+            .unwrap();
+        // not ready for an hour.
+        store
+            .enqueue_item_with_options(
+                queue,
+                5u32,
+                EnqueueOptions::with_priority(QueuePriority::High)
+                    .not_before(crate::unix_timestamp() + 3600),
+            )
+            .unwrap();
+        // ready already.
+        store
+            .enqueue_item_with_options(
+                queue,
+                6u32,
+                EnqueueOptions::default().not_before(1),
+            )
+            .unwrap();
+        assert_eq!(store.peek_item(queue).unwrap(), Some(4u32));
+        let dequeued =
+            std::iter::from_fn(|| store.dequeue_item(queue).unwrap())
+                .collect::<Vec<u32>>();
+        assert_eq!(dequeued, vec![4, 1, 3, 6, 2]);
+    }
+
+    #[test]
+    fn dead_letters_should_work() {
+        let store = InMemoryStore::default();
+        let chain_id = 1u32;
+        let queue = SledQueueKey::from_evm_chain_id(chain_id);
+        let make_key =
+            |i: u8| SledQueueKey::from_evm_with_custom_key(chain_id, [i; 64]);
+        store.enqueue_item(make_key(1), 1u32).unwrap();
+        store.enqueue_item(make_key(2), 2u32).unwrap();
+        let item: u32 = store.dequeue_item(queue).unwrap().unwrap();
+        let dead_letter = store
+            .dead_letter_item(make_key(1), item, "dry run failed".into())
+            .unwrap();
+        assert_eq!(dead_letter.attempts, 1);
+        // the same item failing again is only recorded once.
+        let dead_letter = store
+            .dead_letter_item(make_key(1), item, "reverted".into())
+            .unwrap();
+        assert_eq!(dead_letter.attempts, 2);
+        assert_eq!(dead_letter.reason, "reverted");
+        assert!(dead_letter.first_failed_at <= dead_letter.last_failed_at);
+        let dead_letters: Vec<DeadLetter<u32>> =
+            store.get_dead_letters(queue).unwrap();
+        assert_eq!(dead_letters, vec![dead_letter.clone()]);
+
+        // requeue it, behind the item that is still in the queue.
+        assert!(QueueStore::<u32>::requeue_dead_letter(
+            &store,
+            queue,
+            dead_letter.id
+        )
+        .unwrap());
+        assert!(!QueueStore::<u32>::requeue_dead_letter(
+            &store,
+            queue,
+            dead_letter.id
+        )
+        .unwrap());
+        assert!(QueueStore::<u32>::has_item(&store, make_key(1)).unwrap());
+        assert_eq!(store.dequeue_item(queue).unwrap(), Some(2u32));
+        assert_eq!(store.dequeue_item(queue).unwrap(), Some(1u32));
+
+        store
+            .dead_letter_item(make_key(3), 3u32, "dropped".into())
+            .unwrap();
+        assert_eq!(
+            QueueStore::<u32>::purge_dead_letters(&store, queue).unwrap(),
+            1
+        );
+        assert!(QueueStore::<u32>::get_dead_letters(&store, queue)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn requeued_dead_letters_should_keep_their_priority() {
+        let store = InMemoryStore::default();
+        let queue = SledQueueKey::from_evm_chain_id(1);
+        let high = EnqueueOptions::with_priority(QueuePriority::High);
+        store.enqueue_item_with_options(queue, 1u32, high).unwrap();
+        let dequeued: DequeuedItem<u32> =
+            store.dequeue_item_with_options(queue).unwrap().unwrap();
+        let dead_letter = store
+            .dead_letter_item_with_options(
+                queue,
+                dequeued.item,
+                dequeued.options,
+                "reverted".into(),
+            )
+            .unwrap();
+        assert_eq!(dead_letter.priority, QueuePriority::High);
+        store.enqueue_item(queue, 2u32).unwrap();
+        // requeued behind the normal item, but dequeued before it.
+        assert!(QueueStore::<u32>::requeue_dead_letter(
+            &store,
+            queue,
+            dead_letter.id
+        )
+        .unwrap());
+        let requeued: DequeuedItem<u32> =
+            store.dequeue_item_with_options(queue).unwrap().unwrap();
+        assert_eq!(requeued.item, 1u32);
+        assert_eq!(requeued.options, high);
+        assert_eq!(store.dequeue_item(queue).unwrap(), Some(2u32));
+    }
+
+    #[test]
+    fn prune_should_work() {
+        let store = InMemoryStore::default();
+        let policy = RetentionPolicy {
+            event_hashes_max_age: Some(3600),
+            token_prices_max_age: Some(3600),
+            dead_letters_max_age: Some(3600),
+            block_depth: Some(50),
+            finished_queue_entries: true,
+        };
+        // fresh entries.
+        store.store_event(b"new event").unwrap();
+        store.insert_price("ETH", 1800.5f64).unwrap();
+        let queue = SledQueueKey::from_evm_chain_id(1);
+        store
+            .dead_letter_item(queue, 1u32, "failed".into())
+            .unwrap();
+        // entries from a long time ago.
+        store.store_event(b"old event").unwrap();
+        store
+            .event_hashes
+            .write()
+            .insert(ethers::utils::keccak256(b"old event"), 1);
+        store
+            .token_prices_cache
+            .write()
+            .insert("DOT".into(), (1, serde_json::to_vec(&5.5f64).unwrap()));
+        let mut old_dead_letter = store
+            .dead_letter_item(queue, 2u32, "failed".into())
+            .unwrap();
+        old_dead_letter.last_failed_at = 1;
+        store
+            .dead_letters
+            .write()
+            .get_mut(&queue.queue_name())
+            .unwrap()
+            .insert(
+                old_dead_letter.id,
+                serde_json::to_vec(&old_dead_letter).unwrap(),
+            );
+        // one leaf deep enough to be final, one still recent.
+        let key = HistoryStoreKey::from(1u32);
+        for (i, block_number) in [10u64, 100].into_iter().enumerate() {
+            let leaves = vec![(i as u32, types::H256::random().0.to_vec())];
+            store
+                .insert_leaves_and_last_deposit_block_number(
+                    key,
+                    &leaves,
+                    block_number,
+                    types::H256::random(),
+                )
+                .unwrap();
+        }
+
+        let report = store.prune(&policy).unwrap();
+        assert_eq!(report.event_hashes, 1);
+        assert_eq!(report.token_prices, 1);
+        assert_eq!(report.dead_letters, 1);
+        assert_eq!(report.blocks, 1);
+        assert_eq!(report.total(), 4);
+        assert!(report.reclaimed_bytes > 0);
+
+        assert!(store.contains_event(b"new event").unwrap());
+        assert!(!store.contains_event(b"old event").unwrap());
+        assert_eq!(
+            TokenPriceCacheStore::<f64>::get_price(&store, "ETH").unwrap(),
+            Some(1800.5f64)
+        );
+        assert_eq!(
+            TokenPriceCacheStore::<f64>::get_price(&store, "DOT").unwrap(),
+            None
+        );
+        let dead_letters =
+            QueueStore::<u32>::get_dead_letters(&store, queue).unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].item, 1);
+        assert_eq!(store.get_leaf_block(key, 0).unwrap(), None);
+        assert!(store.get_leaf_block(key, 1).unwrap().is_some());
+        // the leaves themselves are kept.
+        assert_eq!(store.get_leaves(key).unwrap().len(), 2);
+        // nothing left to prune.
+        assert_eq!(store.prune(&policy).unwrap(), PruneReport::default());
+    }
+
+    #[test]
+    fn prune_and_rollback_should_not_deadlock() {
+        let store = InMemoryStore::default();
+        let policy = RetentionPolicy {
+            block_depth: Some(1),
+            ..Default::default()
+        };
+        let key = HistoryStoreKey::from(1u32);
+        let block = BlockInfo::new(10, types::H256::random());
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                for _ in 0..1000 {
+                    store.prune(&policy).unwrap();
+                }
+            });
+            s.spawn(|| {
+                for i in 0..1000u32 {
+                    let leaves = vec![(i, types::H256::random().0.to_vec())];
+                    store
+                        .insert_leaves_and_last_deposit_block_number(
+                            key,
+                            &leaves,
+                            block.number + 1,
+                            types::H256::random(),
+                        )
+                        .unwrap();
+                    store.rollback_to_block(key, block).unwrap();
+                }
+            });
+        });
+        assert!(store.get_leaves(key).unwrap().is_empty());
+    }
+
+    #[test]
+    fn events_hash_should_work() {
+        let store = InMemoryStore::default();
+        let event = b"some event".to_vec();
+        assert!(!store.contains_event(&event).unwrap());
+        store.store_event(&event).unwrap();
+        assert!(store.contains_event(&event).unwrap());
+        store.delete_event(&event).unwrap();
+        assert!(!store.contains_event(&event).unwrap());
+    }
+
+    #[test]
+    fn batch_should_work() {
+        let store = InMemoryStore::default();
+        let key = HistoryStoreKey::from(1u32);
+        let queue = SledQueueKey::from_evm_chain_id(1);
+        let leaves = (0..2u32)
+            .map(|i| (i, types::H256::random().to_fixed_bytes().to_vec()))
+            .collect::<Vec<_>>();
+        let event = b"some event".to_vec();
+        store
+            .batch(|batch| {
+                batch
+                    .insert_leaves_and_last_deposit_block_number(
+                        key,
+                        &leaves,
+                        20,
+                        types::H256::random(),
+                    )
+                    .store_event(&event)
+                    .enqueue_item(queue, &1u32)?;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(store.get_leaves(key).unwrap().len(), 2);
+        assert_eq!(store.get_last_deposit_block_number(key).unwrap(), 20);
+        assert!(store.contains_event(&event).unwrap());
+        assert_eq!(store.dequeue_item(queue).unwrap(), Some(1u32));
+    }
 }
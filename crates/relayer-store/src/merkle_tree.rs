@@ -0,0 +1,337 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Incremental Merkle trees, built from the cached leaves.
+//!
+//! The event watchers keep one tree per VAnchor resource up to date with the
+//! leaves cache, so the relayer could serve the current root and the membership
+//! proof of a leaf without clients downloading every leaf.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use webb::evm::ethers::types;
+
+use crate::HistoryStoreKey;
+
+/// The hash function used to compute the inner nodes of a tree.
+pub trait MerkleHasher: Send + Sync {
+    /// Hashes two sibling nodes into their parent node.
+    fn hash(
+        &self,
+        left: &types::H256,
+        right: &types::H256,
+    ) -> crate::Result<types::H256>;
+}
+
+/// The membership proof of a leaf.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MerkleProof {
+    /// The index of the leaf.
+    pub leaf_index: u32,
+    /// The leaf (commitment).
+    pub leaf: types::H256,
+    /// The root of the tree the proof was generated from.
+    pub root: types::H256,
+    /// The sibling nodes on the path from the leaf to the root.
+    pub path_elements: Vec<types::H256>,
+    /// For each level, `0` if the node on the path is the left child, `1` if it is the right one.
+    pub path_indices: Vec<u8>,
+}
+
+/// A Merkle tree of a fixed depth that is filled from left to right.
+///
+/// Only the non-empty nodes are kept in memory, the empty subtrees are
+/// represented by precomputed zero hashes.
+#[derive(Clone)]
+pub struct IncrementalMerkleTree {
+    hasher: Arc<dyn MerkleHasher>,
+    /// `zeros[i]` is the root of an empty subtree of height `i`.
+    zeros: Vec<types::H256>,
+    /// `layers[0]` are the leaves, `layers[depth]` is the root.
+    layers: Vec<Vec<types::H256>>,
+    /// The index of each leaf.
+    indices: HashMap<types::H256, u32>,
+}
+
+impl IncrementalMerkleTree {
+    /// Creates an empty tree of the given depth.
+    pub fn new(
+        depth: usize,
+        empty_leaf: types::H256,
+        hasher: Arc<dyn MerkleHasher>,
+    ) -> crate::Result<Self> {
+        if depth == 0 || depth > 32 {
+            return Err(webb_relayer_utils::Error::Generic(
+                "merkle tree depth must be between 1 and 32",
+            ));
+        }
+        let mut zeros = Vec::with_capacity(depth + 1);
+        let mut zero = empty_leaf;
+        zeros.push(zero);
+        for _ in 0..depth {
+            zero = hasher.hash(&zero, &zero)?;
+            zeros.push(zero);
+        }
+        Ok(Self {
+            hasher,
+            zeros,
+            layers: vec![Vec::new(); depth + 1],
+            indices: HashMap::new(),
+        })
+    }
+
+    /// Creates a tree of the given depth, filled with the given leaves.
+    pub fn from_leaves(
+        depth: usize,
+        empty_leaf: types::H256,
+        hasher: Arc<dyn MerkleHasher>,
+        leaves: &BTreeMap<u32, types::H256>,
+    ) -> crate::Result<Self> {
+        let mut tree = Self::new(depth, empty_leaf, hasher)?;
+        for (index, leaf) in leaves {
+            tree.insert(*index, *leaf)?;
+        }
+        Ok(tree)
+    }
+
+    /// The depth of the tree.
+    pub fn depth(&self) -> usize {
+        self.layers.len() - 1
+    }
+
+    /// The number of leaves, including the empty leaves before the last one.
+    pub fn len(&self) -> u32 {
+        self.layers[0].len() as u32
+    }
+
+    /// Returns `true` if the tree has no leaves.
+    pub fn is_empty(&self) -> bool {
+        self.layers[0].is_empty()
+    }
+
+    /// The current root of the tree.
+    pub fn root(&self) -> types::H256 {
+        self.node(self.depth(), 0)
+    }
+
+    /// Returns the leaf at the given index, if any.
+    pub fn leaf(&self, index: u32) -> Option<types::H256> {
+        self.layers[0].get(index as usize).copied()
+    }
+
+    /// Returns the index of the given leaf (commitment), if any.
+    pub fn index_of(&self, leaf: &types::H256) -> Option<u32> {
+        self.indices.get(leaf).copied()
+    }
+
+    /// Sets the leaf at the given index, and updates its path to the root.
+    pub fn insert(
+        &mut self,
+        index: u32,
+        leaf: types::H256,
+    ) -> crate::Result<()> {
+        let depth = self.depth();
+        if depth < 32 && index >= (1u32 << depth) {
+            return Err(webb_relayer_utils::Error::Generic(
+                "merkle tree is full",
+            ));
+        }
+        if let Some(old) = self.leaf(index) {
+            self.indices.remove(&old);
+        }
+        let mut i = index as usize;
+        self.set_node(0, i, leaf);
+        self.indices.insert(leaf, index);
+        for level in 0..depth {
+            let left = self.node(level, i & !1);
+            let right = self.node(level, i | 1);
+            let parent = self.hasher.hash(&left, &right)?;
+            i >>= 1;
+            self.set_node(level + 1, i, parent);
+        }
+        Ok(())
+    }
+
+    /// Generates the membership proof of the leaf at the given index.
+    ///
+    /// Returns `None` if there is no leaf at this index.
+    pub fn proof(&self, leaf_index: u32) -> Option<MerkleProof> {
+        let leaf = self.leaf(leaf_index)?;
+        let depth = self.depth();
+        let mut path_elements = Vec::with_capacity(depth);
+        let mut path_indices = Vec::with_capacity(depth);
+        let mut i = leaf_index as usize;
+        for level in 0..depth {
+            path_elements.push(self.node(level, i ^ 1));
+            path_indices.push((i & 1) as u8);
+            i >>= 1;
+        }
+        Some(MerkleProof {
+            leaf_index,
+            leaf,
+            root: self.root(),
+            path_elements,
+            path_indices,
+        })
+    }
+
+    fn node(&self, level: usize, index: usize) -> types::H256 {
+        self.layers[level]
+            .get(index)
+            .copied()
+            .unwrap_or(self.zeros[level])
+    }
+
+    fn set_node(&mut self, level: usize, index: usize, node: types::H256) {
+        let layer = &mut self.layers[level];
+        if layer.len() <= index {
+            layer.resize(index + 1, self.zeros[level]);
+        }
+        layer[index] = node;
+    }
+}
+
+/// A tree shared between the event watcher that updates it and the API that reads it.
+pub type SharedMerkleTree = Arc<Mutex<IncrementalMerkleTree>>;
+
+/// The Merkle trees of the VAnchor resources, by their [`HistoryStoreKey`].
+#[derive(Clone, Default)]
+pub struct MerkleTreeCache {
+    trees: Arc<RwLock<HashMap<HistoryStoreKey, SharedMerkleTree>>>,
+}
+
+impl MerkleTreeCache {
+    /// Registers the tree of the given key, replacing any older one.
+    pub fn insert<K>(
+        &self,
+        key: K,
+        tree: IncrementalMerkleTree,
+    ) -> SharedMerkleTree
+    where
+        K: Into<HistoryStoreKey> + Debug,
+    {
+        let tree = Arc::new(Mutex::new(tree));
+        self.trees.write().insert(key.into(), tree.clone());
+        tree
+    }
+
+    /// Returns the tree of the given key, if any.
+    pub fn get<K>(&self, key: K) -> Option<SharedMerkleTree>
+    where
+        K: Into<HistoryStoreKey> + Debug,
+    {
+        self.trees.read().get(&key.into()).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use webb::evm::ethers::utils::keccak256;
+
+    struct Keccak;
+
+    impl MerkleHasher for Keccak {
+        fn hash(
+            &self,
+            left: &types::H256,
+            right: &types::H256,
+        ) -> crate::Result<types::H256> {
+            let mut bytes = left.as_bytes().to_vec();
+            bytes.extend_from_slice(right.as_bytes());
+            Ok(keccak256(bytes).into())
+        }
+    }
+
+    fn compute_root(proof: &MerkleProof) -> types::H256 {
+        proof.path_elements.iter().zip(&proof.path_indices).fold(
+            proof.leaf,
+            |node, (sibling, index)| {
+                if *index == 0 {
+                    Keccak.hash(&node, sibling).unwrap()
+                } else {
+                    Keccak.hash(sibling, &node).unwrap()
+                }
+            },
+        )
+    }
+
+    #[test]
+    fn incremental_root_should_match_full_tree() {
+        let leaves = (0..5u32)
+            .map(|i| (i, types::H256::random()))
+            .collect::<BTreeMap<_, _>>();
+        let tree = IncrementalMerkleTree::from_leaves(
+            3,
+            types::H256::zero(),
+            Arc::new(Keccak),
+            &leaves,
+        )
+        .unwrap();
+        // compute the root of the full tree by hand.
+        let mut level: Vec<_> = (0..8u32)
+            .map(|i| leaves.get(&i).copied().unwrap_or_default())
+            .collect();
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| Keccak.hash(&pair[0], &pair[1]).unwrap())
+                .collect();
+        }
+        assert_eq!(tree.root(), level[0]);
+        assert_eq!(tree.len(), 5);
+        assert_eq!(tree.index_of(&leaves[&3]), Some(3));
+    }
+
+    #[test]
+    fn proofs_should_verify_against_root() {
+        let mut tree = IncrementalMerkleTree::new(
+            20,
+            types::H256::zero(),
+            Arc::new(Keccak),
+        )
+        .unwrap();
+        let empty_root = tree.root();
+        for i in 0..17u32 {
+            tree.insert(i, types::H256::random()).unwrap();
+        }
+        assert_ne!(tree.root(), empty_root);
+        for i in 0..17u32 {
+            let proof = tree.proof(i).unwrap();
+            assert_eq!(proof.path_elements.len(), 20);
+            assert_eq!(proof.root, tree.root());
+            assert_eq!(compute_root(&proof), tree.root());
+        }
+        assert!(tree.proof(17).is_none());
+    }
+
+    #[test]
+    fn full_tree_should_reject_leaves() {
+        let mut tree = IncrementalMerkleTree::new(
+            2,
+            types::H256::zero(),
+            Arc::new(Keccak),
+        )
+        .unwrap();
+        for i in 0..4u32 {
+            tree.insert(i, types::H256::random()).unwrap();
+        }
+        assert!(tree.insert(4, types::H256::random()).is_err());
+    }
+}
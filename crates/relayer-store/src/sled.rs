@@ -14,22 +14,39 @@
 
 use super::HistoryStoreKey;
 use super::{
-    EncryptedOutputCacheStore, EventHashStore, HistoryStore, LeafCacheStore,
-    QueueStore, TokenPriceCacheStore,
+    BlockInfo, DeadLetter, DequeuedItem, EncryptedOutputCacheStore,
+    EventHashStore, HistoryStore, LeafCacheStore, QueueStore, ReorgStore,
+    TokenPriceCacheStore,
+};
+use crate::crypto::{self, StoreCipher};
+/// Re-exported here, since the queue keys were first introduced for the sled backend.
+pub use crate::SledQueueKey;
+use crate::{
+    is_expired, BatchOp, BatchStore, EnqueueOptions, ProposalsQueueStore,
+    PruneReport, QueueKey, QueuePriority, RetentionPolicy, RetentionStore,
+    StoreBatch,
 };
-use crate::{BridgeKey, QueueKey};
-use core::fmt;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use sled::transaction::{ConflictableTransactionError, TransactionalTree};
 use sled::Transactional;
 use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::path::Path;
 use webb::evm::ethers::{self, types};
+
+mod migrations;
+
+/// The tree that holds the key check of an encrypted store.
+const ENCRYPTION_TREE: &str = "encryption";
+/// The key, in the [`ENCRYPTION_TREE`], of the key check.
+const KEY_CHECK_KEY: &str = "key_check";
+
 /// SledStore is a store that stores the history of events in  a [Sled](https://sled.rs)-based database.
 #[derive(Clone)]
 pub struct SledStore {
     db: sled::Db,
+    cipher: Option<StoreCipher>,
 }
 
 impl std::fmt::Debug for SledStore {
@@ -40,14 +57,46 @@ impl std::fmt::Debug for SledStore {
 
 impl SledStore {
     /// Create a new SledStore.
+    ///
+    /// Any pending schema migrations are applied before the store is returned.
     pub fn open<P: AsRef<Path>>(path: P) -> crate::Result<Self> {
+        Self::open_with_cipher(path, None)
+    }
+
+    /// Create a new SledStore, that encrypts the queued items and the dead letters
+    /// with the given cipher.
+    ///
+    /// Opening a store encrypted with another key fails, and so does opening an
+    /// encrypted store without a cipher. Values stored in plaintext before the store
+    /// was encrypted are still readable, until they are re-encrypted by
+    /// [`SledStore::rotate_key`].
+    pub fn open_with_cipher<P: AsRef<Path>>(
+        path: P,
+        cipher: Option<StoreCipher>,
+    ) -> crate::Result<Self> {
         let db = sled::Config::new()
             .path(path)
             .temporary(cfg!(test))
             .mode(sled::Mode::HighThroughput)
             .open()?;
-        Ok(Self { db })
+        Self::from_db(db, cipher)
+    }
+
+    fn from_db(
+        db: sled::Db,
+        cipher: Option<StoreCipher>,
+    ) -> crate::Result<Self> {
+        migrations::run(&db)?;
+        let encryption = db.open_tree(ENCRYPTION_TREE)?;
+        let key_check = encryption.get(KEY_CHECK_KEY)?;
+        crypto::verify_key_check(cipher.as_ref(), key_check.as_deref())?;
+        if let (Some(cipher), None) = (&cipher, key_check) {
+            encryption.insert(KEY_CHECK_KEY, crypto::key_check(cipher)?)?;
+            db.flush()?;
+        }
+        Ok(Self { db, cipher })
     }
+
     /// Creates a temporary SledStore.
     pub fn temporary() -> crate::Result<Self> {
         let dir = tempfile::tempdir()?;
@@ -58,6 +107,76 @@ impl SledStore {
     pub fn get_data_stored_size(&self) -> u64 {
         self.db.size_on_disk().unwrap_or_default()
     }
+
+    /// Returns the schema version of the store.
+    pub fn schema_version(&self) -> crate::Result<u64> {
+        let version = migrations::read_version(&self.db)?;
+        Ok(version.unwrap_or_default())
+    }
+
+    /// Re-encrypts the queued items and the dead letters with the given cipher,
+    /// or decrypts them if there is none, then uses it from now on.
+    ///
+    /// Everything is rewritten in a single transaction, so the store is either
+    /// fully rotated or left untouched. Returns the number of values rewritten.
+    pub fn rotate_key(
+        &mut self,
+        new_cipher: Option<StoreCipher>,
+    ) -> crate::Result<usize> {
+        let trees = self
+            .db
+            .tree_names()
+            .into_iter()
+            .filter(|name| {
+                name.starts_with(b"queue_")
+                    || name.starts_with(b"dead_letters_")
+            })
+            .map(|name| self.db.open_tree(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut updates = Vec::new();
+        for (i, tree) in trees.iter().enumerate() {
+            let is_queue = tree.name().starts_with(b"queue_");
+            for entry in tree.iter() {
+                let (key, value) = entry?;
+                if is_queue && !is_queue_item_key(&key) {
+                    continue;
+                }
+                let plaintext = crypto::unseal(self.cipher.as_ref(), &value)?;
+                let value = crypto::seal(new_cipher.as_ref(), plaintext)?;
+                updates.push((i, key, value));
+            }
+        }
+        let key_check =
+            new_cipher.as_ref().map(crypto::key_check).transpose()?;
+        let mut all_trees = trees;
+        all_trees.push(self.db.open_tree(ENCRYPTION_TREE)?);
+        let encryption = all_trees.len() - 1;
+        all_trees.as_slice().transaction(|trees| {
+            for (i, key, value) in &updates {
+                trees[*i].insert(key.clone(), value.as_slice())?;
+            }
+            match &key_check {
+                Some(key_check) => trees[encryption]
+                    .insert(KEY_CHECK_KEY, key_check.as_slice())?,
+                None => trees[encryption].remove(KEY_CHECK_KEY)?,
+            };
+            Ok::<_, ConflictableTransactionError<std::io::Error>>(())
+        })?;
+        self.db.flush()?;
+        self.cipher = new_cipher;
+        Ok(updates.len())
+    }
+
+    /// Encrypts a queued item or a dead letter, if the store is encrypted.
+    fn seal<V: Serialize>(&self, value: &V) -> crate::Result<Vec<u8>> {
+        crypto::seal(self.cipher.as_ref(), serde_json::to_vec(value)?)
+    }
+
+    /// Decrypts a queued item or a dead letter.
+    fn unseal<V: DeserializeOwned>(&self, value: &[u8]) -> crate::Result<V> {
+        let value = crypto::unseal(self.cipher.as_ref(), value)?;
+        Ok(serde_json::from_slice(&value)?)
+    }
 }
 
 impl HistoryStore for SledStore {
@@ -75,7 +194,7 @@ impl HistoryStore for SledStore {
             Some(v) => {
                 let mut output = [0u8; 8];
                 output.copy_from_slice(&v);
-                Ok(u64::from_be_bytes(output))
+                Ok(u64::from_le_bytes(output))
             }
             None => Ok(block_number),
         }
@@ -114,7 +233,7 @@ impl HistoryStore for SledStore {
             Some(v) => {
                 let mut output = [0u8; 8];
                 output.copy_from_slice(&v);
-                Ok(u64::from_be_bytes(output))
+                Ok(u64::from_le_bytes(output))
             }
             None => Ok(block_number),
         }
@@ -154,6 +273,11 @@ impl LeafCacheStore for SledStore {
             key.chain_id(),
             key.address()
         ))?;
+        self.db.drop_tree(format!(
+            "leaf_blocks/{}/{}",
+            key.chain_id(),
+            key.address()
+        ))?;
         Ok(())
     }
 
@@ -176,7 +300,7 @@ impl LeafCacheStore for SledStore {
                 let leaf_index_bytes = leaf_index_bytes
                     .try_into()
                     .expect("leaf index bytes is u32 bytes");
-                let leaf_index = u32::from_le_bytes(leaf_index_bytes);
+                let leaf_index = u32::from_be_bytes(leaf_index_bytes);
                 let leaf = types::H256::from_slice(&v);
                 (leaf_index, leaf)
             })
@@ -196,8 +320,8 @@ impl LeafCacheStore for SledStore {
             key.chain_id(),
             key.address()
         ))?;
-        let range_start = range.start.to_le_bytes();
-        let range_end = range.end.to_le_bytes();
+        let range_start = range.start.to_be_bytes();
+        let range_end = range.end.to_be_bytes();
         let leaves = tree
             .range(range_start..range_end)
             .flatten()
@@ -206,7 +330,7 @@ impl LeafCacheStore for SledStore {
                 let leaf_index_bytes = leaf_index_bytes
                     .try_into()
                     .expect("leaf index bytes is u32 bytes");
-                let leaf_index = u32::from_le_bytes(leaf_index_bytes);
+                let leaf_index = u32::from_be_bytes(leaf_index_bytes);
                 let leaf = types::H256::from_slice(&v);
                 (leaf_index, leaf)
             })
@@ -240,31 +364,29 @@ impl LeafCacheStore for SledStore {
         key: K,
         leaves: &[(u32, Vec<u8>)],
         block_number: u64,
+        block_hash: types::H256,
     ) -> crate::Result<()> {
-        let key: HistoryStoreKey = key.into();
+        let mut batch = StoreBatch::new();
+        batch.insert_leaves_and_last_deposit_block_number(
+            key,
+            leaves,
+            block_number,
+            block_hash,
+        );
+        self.apply_batch(batch)
+    }
 
-        let leaf_tree = self.db.open_tree(format!(
-            "leaves/{}/{}",
-            key.chain_id(),
-            key.address()
-        ))?;
-        // This is last deposit event block number
-        let set_block_tree1 = self.db.open_tree("last_deposit_block_number")?;
-        // This will be used by event watcher to track the block number has been processed
-        let set_block_tree2 = self.db.open_tree("last_block_numbers")?;
-        let block_number_bytes = block_number.to_le_bytes();
-
-        (&leaf_tree, &set_block_tree1, &set_block_tree2).transaction(
-            |(leaf_tree, set_block_tree1, set_block_tree2)| {
-                for (k, v) in leaves {
-                    leaf_tree.insert(&k.to_le_bytes(), v.as_slice())?;
-                }
-                set_block_tree1.insert(key.to_bytes(), &block_number_bytes)?;
-                set_block_tree2.insert(key.to_bytes(), &block_number_bytes)?;
-                Ok(())
-            },
-        )?;
-        Ok(())
+    #[tracing::instrument(skip(self))]
+    fn get_leaves_cache_keys(&self) -> crate::Result<Vec<HistoryStoreKey>> {
+        let tree = self.db.open_tree("last_deposit_block_number")?;
+        let keys = tree
+            .iter()
+            .keys()
+            .collect::<Result<Vec<_>, _>>()?
+            .iter()
+            .filter_map(|k| HistoryStoreKey::from_bytes(k))
+            .collect();
+        Ok(keys)
     }
 }
 
@@ -299,8 +421,8 @@ impl EncryptedOutputCacheStore for SledStore {
             key.chain_id(),
             key.address()
         ))?;
-        let range_start = range.start.to_le_bytes();
-        let range_end = range.end.to_le_bytes();
+        let range_start = range.start.to_be_bytes();
+        let range_end = range.end.to_be_bytes();
         let encrypted_outputs: Vec<_> = tree
             .range(range_start..range_end)
             .values()
@@ -310,6 +432,31 @@ impl EncryptedOutputCacheStore for SledStore {
         Ok(encrypted_outputs)
     }
 
+    #[tracing::instrument(skip(self))]
+    fn get_encrypted_output_with_indices<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<BTreeMap<u32, Vec<u8>>> {
+        let key: HistoryStoreKey = key.into();
+        let tree = self.db.open_tree(format!(
+            "encrypted_outputs/{}/{}",
+            key.chain_id(),
+            key.address()
+        ))?;
+        let encrypted_outputs = tree
+            .iter()
+            .flatten()
+            .map(|(k, v)| {
+                let index_bytes = k.get(0..4).expect("output index bytes");
+                let index_bytes = index_bytes
+                    .try_into()
+                    .expect("output index bytes is u32 bytes");
+                (u32::from_be_bytes(index_bytes), v.to_vec())
+            })
+            .collect();
+        Ok(encrypted_outputs)
+    }
+
     fn get_last_deposit_block_number_for_encrypted_output<
         K: Into<HistoryStoreKey> + Debug,
     >(
@@ -339,38 +486,248 @@ impl EncryptedOutputCacheStore for SledStore {
         key: K,
         encrypted_output: &[(u32, Vec<u8>)],
         block_number: u64,
+        block_hash: types::H256,
+    ) -> crate::Result<()> {
+        let mut batch = StoreBatch::new();
+        batch.insert_encrypted_output_and_last_deposit_block_number(
+            key,
+            encrypted_output,
+            block_number,
+            block_hash,
+        );
+        self.apply_batch(batch)
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_encrypted_output_cache_keys(
+        &self,
+    ) -> crate::Result<Vec<HistoryStoreKey>> {
+        let tree = self
+            .db
+            .open_tree("encrypted_output_last_deposit_block_number")?;
+        let keys = tree
+            .iter()
+            .keys()
+            .collect::<Result<Vec<_>, _>>()?
+            .iter()
+            .filter_map(|k| HistoryStoreKey::from_bytes(k))
+            .collect();
+        Ok(keys)
+    }
+}
+
+impl ReorgStore for SledStore {
+    #[tracing::instrument(skip(self))]
+    fn set_last_block<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        block: BlockInfo,
     ) -> crate::Result<()> {
         let key: HistoryStoreKey = key.into();
+        let block_number_tree = self.db.open_tree("last_block_numbers")?;
+        let block_hash_tree = self.db.open_tree("last_block_hashes")?;
+        let block_number_bytes = block.number.to_le_bytes();
+        (&block_number_tree, &block_hash_tree).transaction(
+            |(block_number_tree, block_hash_tree)| {
+                block_number_tree
+                    .insert(key.to_bytes(), &block_number_bytes)?;
+                block_hash_tree
+                    .insert(key.to_bytes(), block.hash.as_bytes())?;
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_last_block_hash<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<Option<types::H256>> {
+        let tree = self.db.open_tree("last_block_hashes")?;
+        let key: HistoryStoreKey = key.into();
+        let val = tree.get(key.to_bytes())?;
+        Ok(val.map(|v| types::H256::from_slice(&v)))
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_leaf_block<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        leaf_index: u32,
+    ) -> crate::Result<Option<BlockInfo>> {
+        let key: HistoryStoreKey = key.into();
+        let tree = self.db.open_tree(format!(
+            "leaf_blocks/{}/{}",
+            key.chain_id(),
+            key.address()
+        ))?;
+        let val = tree.get(leaf_index.to_be_bytes())?;
+        Ok(val.and_then(|v| BlockInfo::from_bytes(&v)))
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_encrypted_output_block<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        index: u32,
+    ) -> crate::Result<Option<BlockInfo>> {
+        let key: HistoryStoreKey = key.into();
+        let tree = self.db.open_tree(format!(
+            "encrypted_output_blocks/{}/{}",
+            key.chain_id(),
+            key.address()
+        ))?;
+        let val = tree.get(index.to_be_bytes())?;
+        Ok(val.and_then(|v| BlockInfo::from_bytes(&v)))
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_cached_blocks<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<Vec<BlockInfo>> {
+        let key: HistoryStoreKey = key.into();
+        let leaf_block_tree = self.db.open_tree(format!(
+            "leaf_blocks/{}/{}",
+            key.chain_id(),
+            key.address()
+        ))?;
+        let encrypted_output_block_tree = self.db.open_tree(format!(
+            "encrypted_output_blocks/{}/{}",
+            key.chain_id(),
+            key.address()
+        ))?;
+        let mut blocks = BTreeMap::new();
+        for v in leaf_block_tree
+            .iter()
+            .values()
+            .chain(encrypted_output_block_tree.iter().values())
+        {
+            if let Some(block) = BlockInfo::from_bytes(&v?) {
+                blocks.insert((block.number, block.hash), block);
+            }
+        }
+        Ok(blocks.into_values().rev().collect())
+    }
 
+    #[tracing::instrument(skip(self))]
+    fn rollback_to_block<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        block: BlockInfo,
+    ) -> crate::Result<()> {
+        let key: HistoryStoreKey = key.into();
+        let leaf_tree = self.db.open_tree(format!(
+            "leaves/{}/{}",
+            key.chain_id(),
+            key.address()
+        ))?;
+        let leaf_block_tree = self.db.open_tree(format!(
+            "leaf_blocks/{}/{}",
+            key.chain_id(),
+            key.address()
+        ))?;
         let encrypted_output_tree = self.db.open_tree(format!(
             "encrypted_outputs/{}/{}",
             key.chain_id(),
             key.address()
         ))?;
-        let set_block_tree = self
+        let encrypted_output_block_tree = self.db.open_tree(format!(
+            "encrypted_output_blocks/{}/{}",
+            key.chain_id(),
+            key.address()
+        ))?;
+        let last_deposit_tree =
+            self.db.open_tree("last_deposit_block_number")?;
+        let encrypted_output_last_deposit_tree = self
             .db
             .open_tree("encrypted_output_last_deposit_block_number")?;
-        let block_number_bytes = block_number.to_le_bytes();
-        (&encrypted_output_tree, &set_block_tree).transaction(
-            |(encrypted_output_tree, set_block_tree)| {
-                for (k, v) in encrypted_output {
-                    encrypted_output_tree
-                        .insert(&k.to_le_bytes(), v.as_slice())?;
+        let block_number_tree = self.db.open_tree("last_block_numbers")?;
+        let block_hash_tree = self.db.open_tree("last_block_hashes")?;
+
+        // the indices of everything found after the given block.
+        let orphaned = |tree: &sled::Tree| -> sled::Result<Vec<sled::IVec>> {
+            let mut indices = Vec::new();
+            for entry in tree.iter() {
+                let (k, v) = entry?;
+                match BlockInfo::from_bytes(&v) {
+                    Some(b) if b.number > block.number => indices.push(k),
+                    _ => {}
                 }
-                set_block_tree.insert(key.to_bytes(), &block_number_bytes)?;
-                Ok(())
-            },
-        )?;
+            }
+            Ok(indices)
+        };
+        let orphaned_leaves = orphaned(&leaf_block_tree)?;
+        let orphaned_encrypted_outputs =
+            orphaned(&encrypted_output_block_tree)?;
+        let block_number_bytes = block.number.to_le_bytes();
+        // never move the last deposit block numbers forward.
+        let capped = |tree: &sled::Tree| -> sled::Result<Option<[u8; 8]>> {
+            let val = tree.get(key.to_bytes())?.map(|v| {
+                let mut output = [0u8; 8];
+                output.copy_from_slice(&v);
+                u64::from_le_bytes(output).min(block.number).to_le_bytes()
+            });
+            Ok(val)
+        };
+        let last_deposit = capped(&last_deposit_tree)?;
+        let encrypted_output_last_deposit =
+            capped(&encrypted_output_last_deposit_tree)?;
+
+        (
+            &leaf_tree,
+            &leaf_block_tree,
+            &encrypted_output_tree,
+            &encrypted_output_block_tree,
+            &last_deposit_tree,
+            &encrypted_output_last_deposit_tree,
+            &block_number_tree,
+            &block_hash_tree,
+        )
+            .transaction(
+                |(
+                    leaf_tree,
+                    leaf_block_tree,
+                    encrypted_output_tree,
+                    encrypted_output_block_tree,
+                    last_deposit_tree,
+                    encrypted_output_last_deposit_tree,
+                    block_number_tree,
+                    block_hash_tree,
+                )| {
+                    for k in &orphaned_leaves {
+                        leaf_tree.remove(k)?;
+                        leaf_block_tree.remove(k)?;
+                    }
+                    for k in &orphaned_encrypted_outputs {
+                        encrypted_output_tree.remove(k)?;
+                        encrypted_output_block_tree.remove(k)?;
+                    }
+                    if let Some(v) = last_deposit {
+                        last_deposit_tree.insert(key.to_bytes(), &v)?;
+                    }
+                    if let Some(v) = encrypted_output_last_deposit {
+                        encrypted_output_last_deposit_tree
+                            .insert(key.to_bytes(), &v)?;
+                    }
+                    block_number_tree
+                        .insert(key.to_bytes(), &block_number_bytes)?;
+                    block_hash_tree
+                        .insert(key.to_bytes(), block.hash.as_bytes())?;
+                    Ok(())
+                },
+            )?;
+        self.db.flush()?;
         Ok(())
     }
 }
 
 impl EventHashStore for SledStore {
     fn store_event(&self, event: &[u8]) -> crate::Result<()> {
-        let tree = self.db.open_tree("event_hashes")?;
-        let hash = ethers::utils::keccak256(event);
-        tree.insert(hash, &[])?;
-        Ok(())
+        let mut batch = StoreBatch::new();
+        batch.store_event(event);
+        self.apply_batch(batch)
     }
 
     fn contains_event(&self, event: &[u8]) -> crate::Result<bool> {
@@ -381,199 +738,335 @@ impl EventHashStore for SledStore {
     }
 
     fn delete_event(&self, event: &[u8]) -> crate::Result<()> {
-        let tree = self.db.open_tree("event_hashes")?;
-        let hash = ethers::utils::keccak256(event);
-        tree.remove(hash)?;
-        Ok(())
+        let mut batch = StoreBatch::new();
+        batch.delete_event(event);
+        self.apply_batch(batch)
     }
 }
 
-/// SledQueueKey is a key for a queue in Sled.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum SledQueueKey {
-    /// Queue Key for EVM based Transaction Queue.
-    EvmTx {
-        /// EVM Chain Id.
-        chain_id: u32,
-        /// an optional key for this transaction.
-        optional_key: Option<[u8; 64]>,
-    },
-    /// Queue Key for Substrate based Transaction Queue.
-    SubstrateTx {
-        /// Substrate Chain Id.
-        chain_id: u32,
-        /// an optional key for this transaction.
-        optional_key: Option<[u8; 64]>,
-    },
-    /// Queue Key for Bridge Watcher Command Queue.
-    BridgeCmd {
-        /// Specific Bridge Key.
-        bridge_key: BridgeKey,
-    },
-}
-
-impl SledQueueKey {
-    /// Create a new SledQueueKey from an evm chain id.
-    pub fn from_evm_chain_id(chain_id: u32) -> Self {
-        Self::EvmTx {
-            chain_id,
-            optional_key: None,
+/// The names of the trees the given batch write goes to.
+fn trees_of(op: &BatchOp) -> Vec<String> {
+    match op {
+        BatchOp::SetLastBlockNumber { .. } => {
+            vec![String::from("last_block_numbers")]
         }
-    }
-
-    /// from_evm_with_custom_key returns an EVM specific SledQueueKey.
-    pub fn from_evm_with_custom_key(chain_id: u32, key: [u8; 64]) -> Self {
-        Self::EvmTx {
-            chain_id,
-            optional_key: Some(key),
+        BatchOp::SetTargetBlockNumber { .. } => {
+            vec![String::from("target_block_numbers")]
         }
-    }
-
-    /// Create a new SledQueueKey from an substrate chain id.
-    pub fn from_substrate_chain_id(chain_id: u32) -> Self {
-        Self::SubstrateTx {
-            chain_id,
-            optional_key: None,
+        BatchOp::InsertLeaves { key, .. } => vec![
+            format!("leaves/{}/{}", key.chain_id(), key.address()),
+            // The block where each leaf was found, used to roll back on reorgs.
+            format!("leaf_blocks/{}/{}", key.chain_id(), key.address()),
+            // This is last deposit event block number
+            String::from("last_deposit_block_number"),
+            // This will be used by event watcher to track the block number has been processed
+            String::from("last_block_numbers"),
+            String::from("last_block_hashes"),
+        ],
+        BatchOp::InsertEncryptedOutputs { key, .. } => vec![
+            format!("encrypted_outputs/{}/{}", key.chain_id(), key.address()),
+            // The block where each encrypted output was found, used to roll back on reorgs.
+            format!(
+                "encrypted_output_blocks/{}/{}",
+                key.chain_id(),
+                key.address()
+            ),
+            String::from("encrypted_output_last_deposit_block_number"),
+        ],
+        BatchOp::StoreEvent { .. } | BatchOp::DeleteEvent { .. } => {
+            vec![String::from("event_hashes")]
         }
-    }
-
-    /// from_substrate_with_custom_key returns an Substrate specific SledQueueKey.
-    pub fn from_substrate_with_custom_key(
-        chain_id: u32,
-        key: [u8; 64],
-    ) -> Self {
-        Self::SubstrateTx {
-            chain_id,
-            optional_key: Some(key),
+        BatchOp::EnqueueItem { key, .. } => {
+            vec![format!("queue_{}", key.queue_name())]
         }
     }
-
-    /// from_bridge_key returns a Bridge specific SledQueueKey.
-    pub fn from_bridge_key(bridge_key: BridgeKey) -> Self {
-        Self::BridgeCmd { bridge_key }
-    }
 }
 
-impl fmt::Display for SledQueueKey {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::EvmTx {
-                chain_id,
-                optional_key,
-            } => write!(
-                f,
-                "EvmTx({}, {:?})",
-                chain_id,
-                optional_key.map(hex::encode)
-            ),
-            Self::SubstrateTx {
-                chain_id,
-                optional_key,
-            } => write!(
-                f,
-                "SubstrateTx({}, {:?})",
-                chain_id,
-                optional_key.map(hex::encode)
-            ),
-            Self::BridgeCmd { bridge_key } => {
-                write!(f, "BridgeCmd({bridge_key})")
-            }
+/// Applies a single write of a batch, inside the transaction of the whole batch.
+///
+/// The `trees` are the trees of the transaction, opened in the order of their `names`.
+fn apply_op(
+    trees: &[TransactionalTree],
+    names: &[String],
+    op: &BatchOp,
+    item: Option<&[u8]>,
+    now: u64,
+) -> Result<(), ConflictableTransactionError<std::io::Error>> {
+    let op_trees = trees_of(op)
+        .iter()
+        .map(|name| {
+            let index = names
+                .iter()
+                .position(|n| n == name)
+                .expect("all the trees of the batch are opened");
+            &trees[index]
+        })
+        .collect::<Vec<_>>();
+    match op {
+        BatchOp::SetLastBlockNumber { key, block_number }
+        | BatchOp::SetTargetBlockNumber { key, block_number } => {
+            op_trees[0].insert(key.to_bytes(), &block_number.to_le_bytes())?;
         }
-    }
-}
-
-impl QueueKey for SledQueueKey {
-    fn queue_name(&self) -> String {
-        match self {
-            Self::EvmTx { chain_id, .. } => format!("evm_tx_{chain_id}"),
-            Self::SubstrateTx { chain_id, .. } => {
-                format!("substrate_tx_{chain_id}")
+        BatchOp::InsertLeaves {
+            key,
+            leaves,
+            block_number,
+            block_hash,
+        } => {
+            let block_number_bytes = block_number.to_le_bytes();
+            let block_bytes =
+                BlockInfo::new(*block_number, *block_hash).to_bytes();
+            for (k, v) in leaves {
+                op_trees[0].insert(&k.to_be_bytes(), v.as_slice())?;
+                if !block_hash.is_zero() {
+                    op_trees[1].insert(&k.to_be_bytes(), &block_bytes)?;
+                }
             }
-            Self::BridgeCmd { bridge_key, .. } => {
-                format!("bridge_cmd_{}", bridge_key.chain_id.chain_id())
+            op_trees[2].insert(key.to_bytes(), &block_number_bytes)?;
+            op_trees[3].insert(key.to_bytes(), &block_number_bytes)?;
+            if block_hash.is_zero() {
+                op_trees[4].remove(key.to_bytes())?;
+            } else {
+                op_trees[4].insert(key.to_bytes(), block_hash.as_bytes())?;
             }
         }
-    }
-
-    fn item_key(&self) -> Option<[u8; 64]> {
-        match self {
-            Self::EvmTx { optional_key, .. } => *optional_key,
-            Self::SubstrateTx { optional_key, .. } => *optional_key,
-            Self::BridgeCmd { .. } => None,
+        BatchOp::InsertEncryptedOutputs {
+            key,
+            encrypted_outputs,
+            block_number,
+            block_hash,
+        } => {
+            let block_bytes =
+                BlockInfo::new(*block_number, *block_hash).to_bytes();
+            for (k, v) in encrypted_outputs {
+                op_trees[0].insert(&k.to_be_bytes(), v.as_slice())?;
+                if !block_hash.is_zero() {
+                    op_trees[1].insert(&k.to_be_bytes(), &block_bytes)?;
+                }
+            }
+            op_trees[2].insert(key.to_bytes(), &block_number.to_le_bytes())?;
         }
-    }
-}
-
-impl<T> QueueStore<T> for SledStore
-where
-    T: Serialize + DeserializeOwned + Clone,
-{
-    type Key = SledQueueKey;
-
-    #[tracing::instrument(skip_all, fields(key = %key))]
-    fn enqueue_item(&self, key: Self::Key, item: T) -> crate::Result<()> {
-        let tree = self.db.open_tree(format!("queue_{}", key.queue_name()))?;
-        let item_bytes = serde_json::to_vec(&item)?;
-        // we do everything inside a single transaction
-        // so everything happens atomically and if anything fails
-        // we revert everything back to the old state.
-        tree.transaction::<_, _, std::io::Error>(|db| {
+        BatchOp::StoreEvent { event } => {
+            let hash = ethers::utils::keccak256(event);
+            // the value is the time the event was stored, so it could be pruned.
+            op_trees[0].insert(&hash[..], &now.to_be_bytes())?;
+        }
+        BatchOp::DeleteEvent { event } => {
+            let hash = ethers::utils::keccak256(event);
+            op_trees[0].remove(&hash[..])?;
+        }
+        BatchOp::EnqueueItem { key, options, .. } => {
+            let db = op_trees[0];
+            let item = item.expect("the queue items are encrypted beforehand");
             // get the last id of the queue.
             let last_item_idx = match db.get("last_item_idx")? {
                 Some(v) => {
-                    let mut output = [0u8; 8];
-                    output.copy_from_slice(&v);
+                    // abort the whole batch, instead of panicking on a corrupted queue.
+                    let output = v.as_ref().try_into().map_err(|_| {
+                        ConflictableTransactionError::Abort(
+                            std::io::Error::new(
+                                std::io::ErrorKind::InvalidData,
+                                "invalid last item index of the queue",
+                            ),
+                        )
+                    })?;
                     u64::from_be_bytes(output)
                 }
                 None => 0u64,
             };
             // increment it.
             let next_idx = last_item_idx + 1u64;
-            let idx_bytes = next_idx.to_be_bytes();
             // then save it.
-            db.insert("last_item_idx", &idx_bytes)?;
+            db.insert("last_item_idx", &next_idx.to_be_bytes())?;
             db.insert("key_prefix", "item")?;
-            // we create a item key like so
-            // tx_key = 4 bytes prefix ("item") + 8 bytes of the index.
-            let mut item_key = [0u8; 4 + std::mem::size_of::<u64>()];
-            let prefix =
-                db.get("key_prefix")?.unwrap_or_else(|| b"item".into());
-            item_key[0..4].copy_from_slice(&prefix);
-            item_key[4..].copy_from_slice(&idx_bytes);
+            let item_key = queue_item_key(options.priority, next_idx);
             // then we save it.
-            db.insert(&item_key, item_bytes.as_slice())?;
+            db.insert(&item_key[..], item)?;
+            if let Some(not_before) = options.not_before {
+                db.insert(
+                    not_before_key(&item_key),
+                    &not_before.to_be_bytes(),
+                )?;
+            }
             if let Some(k) = key.item_key() {
-                // also save the key where we can find it by special key.
-                db.insert(&k[..], &item_key)?;
+                // also save the key where we can find it by special key,
+                // and the other way around.
+                db.insert(&k[..], &item_key[..])?;
+                db.insert(custom_key_key(&item_key), &k[..])?;
             }
             tracing::trace!("enqueue item under key = {}", key);
-            Ok(())
+        }
+    }
+    Ok(())
+}
+
+impl BatchStore for SledStore {
+    #[tracing::instrument(skip_all, fields(ops = batch.len()))]
+    fn apply_batch(&self, batch: StoreBatch) -> crate::Result<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        // a sled transaction runs on a fixed set of trees,
+        // so all the trees of the batch are opened first.
+        let mut names: Vec<String> = Vec::new();
+        for name in batch.ops.iter().flat_map(trees_of) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        let trees = names
+            .iter()
+            .map(|name| self.db.open_tree(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        // the queue items are encrypted once, since the transaction could be retried.
+        let items = batch
+            .ops
+            .iter()
+            .map(|op| match op {
+                BatchOp::EnqueueItem { item, .. } => {
+                    crypto::seal(self.cipher.as_ref(), item.as_bytes().to_vec())
+                        .map(Some)
+                }
+                _ => Ok(None),
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+        let now = crate::unix_timestamp();
+        // we do everything inside a single transaction
+        // so everything happens atomically and if anything fails
+        // we revert everything back to the old state.
+        trees.as_slice().transaction(|trees| {
+            for (op, item) in batch.ops.iter().zip(&items) {
+                apply_op(trees, &names, op, item.as_deref(), now)?;
+            }
+            Ok::<_, ConflictableTransactionError<std::io::Error>>(())
         })?;
-        // flush the db to make sure we don't lose anything.
-        self.db.flush()?;
+        if items.iter().any(Option::is_some) {
+            // flush the db to make sure we don't lose any queued item.
+            self.db.flush()?;
+        }
         Ok(())
     }
+}
+
+/// The key of a queue item: the `item` prefix, the inverted priority,
+/// so the highest priority comes first, then the big-endian index of the item.
+fn queue_item_key(priority: QueuePriority, idx: u64) -> [u8; 13] {
+    let mut item_key = [0u8; 13];
+    item_key[0..4].copy_from_slice(b"item");
+    item_key[4] = u8::MAX - priority as u8;
+    item_key[5..].copy_from_slice(&idx.to_be_bytes());
+    item_key
+}
+
+/// The key of the not-before time of a queue item.
+fn not_before_key(item_key: &[u8]) -> Vec<u8> {
+    let mut key = b"not_before/".to_vec();
+    key.extend_from_slice(item_key);
+    key
+}
+
+/// The key of the custom key a queue item was enqueued with.
+fn custom_key_key(item_key: &[u8]) -> Vec<u8> {
+    let mut key = b"custom_key/".to_vec();
+    key.extend_from_slice(item_key);
+    key
+}
+
+/// The not-before time of a queue item, if any.
+fn not_before_of(
+    tree: &sled::Tree,
+    item_key: &[u8],
+) -> crate::Result<Option<u64>> {
+    let not_before = tree.get(not_before_key(item_key))?.map(|v| {
+        let mut output = [0u8; 8];
+        output.copy_from_slice(&v);
+        u64::from_be_bytes(output)
+    });
+    Ok(not_before)
+}
+
+/// Returns `true` if the given key, of a queue tree, is the key of an item.
+///
+/// The custom item keys that happen to start with the same prefix are longer.
+fn is_queue_item_key(key: &[u8]) -> bool {
+    key.len() == 13 && key.starts_with(b"item")
+}
+
+/// Finds the first item of the queue that is ready to be dequeued.
+fn next_ready_item(
+    tree: &sled::Tree,
+) -> crate::Result<Option<(sled::IVec, sled::IVec)>> {
+    let now = crate::unix_timestamp();
+    for entry in tree.scan_prefix(b"item") {
+        let (item_key, value) = entry?;
+        if !is_queue_item_key(&item_key) {
+            continue;
+        }
+        let not_before = not_before_of(tree, &item_key)?;
+        if not_before.map(|t| t <= now).unwrap_or(true) {
+            return Ok(Some((item_key, value)));
+        }
+    }
+    Ok(None)
+}
+
+impl<T> QueueStore<T> for SledStore
+where
+    T: Serialize + DeserializeOwned + Clone,
+{
+    type Key = SledQueueKey;
 
     #[tracing::instrument(skip_all, fields(key = %key))]
-    fn dequeue_item(&self, key: Self::Key) -> crate::Result<Option<T>> {
-        let tree = self.db.open_tree(format!("queue_{}", key.queue_name()))?;
-        // now we create a lazy iterator that will scan
-        // over all saved items in the queue
-        // with the specific key prefix.
-        let prefix = tree.get("key_prefix")?.unwrap_or_else(|| b"item".into());
-        let mut queue = tree.scan_prefix(prefix);
-        let (key, value) = match queue.next() {
-            Some(Ok(v)) => v,
-            _ => {
-                return Ok(None);
-            }
-        };
-        let item = serde_json::from_slice(&value)?;
-        // now it is safe to remove it from the queue.
-        tree.remove(key)?;
-        // flush db
-        self.db.flush()?;
-        Ok(Some(item))
+    fn enqueue_item_with_options(
+        &self,
+        key: Self::Key,
+        item: T,
+        options: EnqueueOptions,
+    ) -> crate::Result<()> {
+        let mut batch = StoreBatch::new();
+        batch.enqueue_item_with_options(key, &item, options)?;
+        self.apply_batch(batch)
+    }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn dequeue_item_if<F>(
+        &self,
+        key: Self::Key,
+        predicate: F,
+    ) -> crate::Result<Option<DequeuedItem<T>>>
+    where
+        F: FnOnce(&T) -> bool,
+    {
+        let tree = self.db.open_tree(format!("queue_{}", key.queue_name()))?;
+        let (item_key, value) = match next_ready_item(&tree)? {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        let item = self.unseal(&value)?;
+        if !predicate(&item) {
+            return Ok(None);
+        }
+        // the priority is inverted in the item key, see `queue_item_key`.
+        let priority =
+            QueuePriority::from_u8(u8::MAX - item_key[4]).unwrap_or_default();
+        let not_before = not_before_of(&tree, &item_key)?;
+        let custom_key = tree
+            .get(custom_key_key(&item_key))?
+            .and_then(|k| k.as_ref().try_into().ok());
+        // now it is safe to remove it from the queue.
+        tree.remove(&item_key)?;
+        tree.remove(not_before_key(&item_key))?;
+        tree.remove(custom_key_key(&item_key))?;
+        // flush db
+        self.db.flush()?;
+        Ok(Some(DequeuedItem {
+            item,
+            item_key: custom_key,
+            options: EnqueueOptions {
+                priority,
+                not_before,
+            },
+        }))
     }
 
     #[tracing::instrument(skip_all, fields(key = %key))]
@@ -581,14 +1074,10 @@ where
         // this method, is similar to dequeue_tx, expect we don't
         // remove anything from the queue.
         let tree = self.db.open_tree(format!("queue_{}", key.queue_name()))?;
-        let prefix = tree.get("key_prefix")?.unwrap_or_else(|| b"item".into());
-        let mut queue = tree.scan_prefix(prefix);
-        let (_, value) = match queue.next() {
-            Some(Ok(v)) => v,
-            _ => return Ok(None),
-        };
-        let item = serde_json::from_slice(&value)?;
-        Ok(Some(item))
+        match next_ready_item(&tree)? {
+            Some((_, value)) => Ok(Some(self.unseal(&value)?)),
+            None => Ok(None),
+        }
     }
 
     #[tracing::instrument(skip_all, fields(key = %key))]
@@ -611,8 +1100,10 @@ where
         match tree.get(&inner_key[..])? {
             Some(k) => {
                 let exists = tree.remove(&k)?;
+                tree.remove(not_before_key(&k))?;
+                tree.remove(custom_key_key(&k))?;
                 tree.remove(inner_key)?;
-                let item = exists.and_then(|v| serde_json::from_slice(&v).ok());
+                let item = exists.and_then(|v| self.unseal(&v).ok());
                 tracing::trace!("removed item from the queue..");
                 self.db.flush()?;
                 Ok(item)
@@ -624,6 +1115,260 @@ where
             }
         }
     }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn dead_letter_item_with_options(
+        &self,
+        key: Self::Key,
+        item: T,
+        options: EnqueueOptions,
+        reason: String,
+    ) -> crate::Result<DeadLetter<T>> {
+        let tree = self
+            .db
+            .open_tree(format!("dead_letters_{}", key.queue_name()))?;
+        let id = DeadLetter::id_of(&item)?;
+        let previous = tree.get(id)?.map(|v| self.unseal(&v)).transpose()?;
+        let dead_letter = DeadLetter::failed(
+            previous,
+            id,
+            key.item_key(),
+            item,
+            options,
+            reason,
+        );
+        tree.insert(id, self.seal(&dead_letter)?)?;
+        tracing::trace!(
+            attempts = dead_letter.attempts,
+            "moved item to the dead-letter queue"
+        );
+        self.db.flush()?;
+        Ok(dead_letter)
+    }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn get_dead_letters(
+        &self,
+        key: Self::Key,
+    ) -> crate::Result<Vec<DeadLetter<T>>> {
+        let tree = self
+            .db
+            .open_tree(format!("dead_letters_{}", key.queue_name()))?;
+        let mut dead_letters = tree
+            .iter()
+            .values()
+            .map(|v| self.unseal::<DeadLetter<T>>(&v?))
+            .collect::<crate::Result<Vec<_>>>()?;
+        dead_letters.sort_by_key(|d| d.first_failed_at);
+        Ok(dead_letters)
+    }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn requeue_dead_letter(
+        &self,
+        key: Self::Key,
+        id: types::H256,
+    ) -> crate::Result<bool> {
+        let tree = self
+            .db
+            .open_tree(format!("dead_letters_{}", key.queue_name()))?;
+        let dead_letter: DeadLetter<T> = match tree.get(id)? {
+            Some(v) => self.unseal(&v)?,
+            None => return Ok(false),
+        };
+        // enqueue it first, so the item is never lost, at worst it is requeued twice.
+        let queue_key = key.with_item_key(dead_letter.queue_item_key());
+        let options = dead_letter.requeue_options();
+        self.enqueue_item_with_options(queue_key, dead_letter.item, options)?;
+        tree.remove(id)?;
+        self.db.flush()?;
+        Ok(true)
+    }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn purge_dead_letters(&self, key: Self::Key) -> crate::Result<usize> {
+        let tree = self
+            .db
+            .open_tree(format!("dead_letters_{}", key.queue_name()))?;
+        let count = tree.len();
+        tree.clear()?;
+        self.db.flush()?;
+        Ok(count)
+    }
+}
+
+/// Reads a time, in seconds since the unix epoch, stored as 8 big-endian bytes.
+fn read_timestamp(bytes: &[u8]) -> Option<u64> {
+    bytes.try_into().ok().map(u64::from_be_bytes)
+}
+
+/// Removes the entry, unless it changed since it was read.
+///
+/// Returns the number of bytes reclaimed, if the entry was removed.
+fn remove_unchanged(
+    tree: &sled::Tree,
+    key: &sled::IVec,
+    value: &sled::IVec,
+) -> crate::Result<Option<u64>> {
+    let removed = tree
+        .compare_and_swap(key, Some(value), None as Option<&[u8]>)?
+        .is_ok();
+    Ok(removed.then_some((key.len() + value.len()) as u64))
+}
+
+impl RetentionStore for SledStore {
+    #[tracing::instrument(skip(self))]
+    fn prune(&self, policy: &RetentionPolicy) -> crate::Result<PruneReport> {
+        let now = crate::unix_timestamp();
+        let mut report = PruneReport::default();
+        let tree_names = self.db.tree_names();
+
+        if let Some(max_age) = policy.event_hashes_max_age {
+            let tree = self.db.open_tree("event_hashes")?;
+            for entry in tree.iter() {
+                let (hash, stored_at) = entry?;
+                let expired = read_timestamp(&stored_at)
+                    .map(|t| is_expired(t, max_age, now))
+                    .unwrap_or(false);
+                if !expired {
+                    continue;
+                }
+                if let Some(bytes) = remove_unchanged(&tree, &hash, &stored_at)?
+                {
+                    report.event_hashes += 1;
+                    report.reclaimed_bytes += bytes;
+                }
+            }
+        }
+
+        if let Some(max_age) = policy.token_prices_max_age {
+            let tree = self.db.open_tree("token_prices")?;
+            let updated_at_tree =
+                self.db.open_tree("token_prices_updated_at")?;
+            for entry in updated_at_tree.iter() {
+                let (token, updated_at) = entry?;
+                let expired = read_timestamp(&updated_at)
+                    .map(|t| is_expired(t, max_age, now))
+                    .unwrap_or(false);
+                if !expired {
+                    continue;
+                }
+                // a price inserted meanwhile updates the time too, and is kept.
+                if let Some(bytes) =
+                    remove_unchanged(&updated_at_tree, &token, &updated_at)?
+                {
+                    let price = tree.remove(&token)?;
+                    report.token_prices += 1;
+                    report.reclaimed_bytes += bytes
+                        + price
+                            .map(|v| (token.len() + v.len()) as u64)
+                            .unwrap_or_default();
+                }
+            }
+        }
+
+        if let Some(max_age) = policy.dead_letters_max_age {
+            let trees = tree_names
+                .iter()
+                .filter(|name| name.starts_with(b"dead_letters_"));
+            for name in trees {
+                let tree = self.db.open_tree(name)?;
+                for entry in tree.iter() {
+                    let (id, value) = entry?;
+                    let expired = self
+                        .unseal::<DeadLetter<serde_json::Value>>(&value)
+                        .map(|d| is_expired(d.last_failed_at, max_age, now))
+                        .unwrap_or(false);
+                    if !expired {
+                        continue;
+                    }
+                    if let Some(bytes) = remove_unchanged(&tree, &id, &value)? {
+                        report.dead_letters += 1;
+                        report.reclaimed_bytes += bytes;
+                    }
+                }
+            }
+        }
+
+        if let Some(depth) = policy.block_depth {
+            let last_block_numbers = self.db.open_tree("last_block_numbers")?;
+            for entry in last_block_numbers.iter() {
+                let (key, block_number) = entry?;
+                let key = match HistoryStoreKey::from_bytes(&key) {
+                    Some(key) => key,
+                    None => continue,
+                };
+                let last_block_number = match block_number.as_ref().try_into() {
+                    Ok(bytes) => u64::from_le_bytes(bytes),
+                    Err(_) => continue,
+                };
+                for prefix in ["leaf_blocks", "encrypted_output_blocks"] {
+                    let name = format!(
+                        "{prefix}/{}/{}",
+                        key.chain_id(),
+                        key.address()
+                    );
+                    // do not create the trees that do not exist.
+                    if !tree_names.iter().any(|n| n.as_ref() == name.as_bytes())
+                    {
+                        continue;
+                    }
+                    let tree = self.db.open_tree(name)?;
+                    for entry in tree.iter() {
+                        let (index, block) = entry?;
+                        let is_final = BlockInfo::from_bytes(&block)
+                            .map(|b| {
+                                b.number.saturating_add(depth)
+                                    < last_block_number
+                            })
+                            .unwrap_or(false);
+                        if !is_final {
+                            continue;
+                        }
+                        if let Some(bytes) =
+                            remove_unchanged(&tree, &index, &block)?
+                        {
+                            report.blocks += 1;
+                            report.reclaimed_bytes += bytes;
+                        }
+                    }
+                }
+            }
+        }
+
+        if policy.finished_queue_entries {
+            let trees =
+                tree_names.iter().filter(|name| name.starts_with(b"queue_"));
+            for name in trees {
+                let tree = self.db.open_tree(name)?;
+                for entry in tree.iter() {
+                    let (key, value) = entry?;
+                    // the custom item keys, and the not-before times, of the items
+                    // that were dequeued.
+                    let item_key = if key.len() == 64 {
+                        value.to_vec()
+                    } else if let Some(item_key) =
+                        key.strip_prefix(b"not_before/".as_slice())
+                    {
+                        item_key.to_vec()
+                    } else {
+                        continue;
+                    };
+                    if tree.contains_key(&item_key)? {
+                        continue;
+                    }
+                    if let Some(bytes) = remove_unchanged(&tree, &key, &value)?
+                    {
+                        report.queue_entries += 1;
+                        report.reclaimed_bytes += bytes;
+                    }
+                }
+            }
+        }
+
+        self.db.flush()?;
+        Ok(report)
+    }
 }
 
 impl<T> TokenPriceCacheStore<T> for SledStore
@@ -641,10 +1386,41 @@ where
     fn insert_price(&self, token: &str, value: T) -> crate::Result<()> {
         let v = serde_json::to_vec(&value)?;
         let tree = self.db.open_tree("token_prices")?;
-        tree.insert(token, v.as_slice())?;
+        let updated_at_tree = self.db.open_tree("token_prices_updated_at")?;
+        let updated_at = crate::unix_timestamp().to_be_bytes();
+        (&tree, &updated_at_tree).transaction(|(tree, updated_at_tree)| {
+            tree.insert(token, v.as_slice())?;
+            updated_at_tree.insert(token, &updated_at)?;
+            Ok(())
+        })?;
         Ok(())
     }
 }
+impl<T> ProposalsQueueStore<T> for SledStore
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn get_proposals_queue(&self, queue_key: &str) -> crate::Result<Option<T>> {
+        let tree = self.db.open_tree("proposals_queues")?;
+        match tree.get(queue_key)? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn save_proposals_queue(
+        &self,
+        queue_key: &str,
+        state: T,
+    ) -> crate::Result<()> {
+        let v = serde_json::to_vec(&state)?;
+        let tree = self.db.open_tree("proposals_queues")?;
+        tree.insert(queue_key, v)?;
+        tree.flush()?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -721,6 +1497,7 @@ mod tests {
                 history_store_key,
                 &generated_leaves,
                 block_number,
+                types::H256::random(),
             )
             .unwrap();
         let leaves = store
@@ -741,6 +1518,283 @@ mod tests {
         );
     }
 
+    #[test]
+    fn set_last_block_number_returns_old_value() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let contract =
+            types::H160::from_slice("11111111111111111111".as_bytes());
+        let history_store_key = (
+            TypedChainId::Evm(1),
+            TargetSystem::new_contract_address(contract.to_fixed_bytes()),
+        );
+        assert_eq!(
+            store.set_last_block_number(history_store_key, 20).unwrap(),
+            20
+        );
+        assert_eq!(
+            store.set_last_block_number(history_store_key, 30).unwrap(),
+            20
+        );
+        assert_eq!(
+            store
+                .set_target_block_number(history_store_key, 40)
+                .unwrap(),
+            40
+        );
+        assert_eq!(
+            store
+                .set_target_block_number(history_store_key, 50)
+                .unwrap(),
+            40
+        );
+    }
+
+    #[test]
+    fn get_leaves_with_range_over_many_leaves_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let contract =
+            types::H160::from_slice("11111111111111111111".as_bytes());
+        let history_store_key = (
+            TypedChainId::Evm(1),
+            TargetSystem::new_contract_address(contract.to_fixed_bytes()),
+        );
+        let generated_leaves = (0..600u32)
+            .map(|i| (i, types::H256::random().to_fixed_bytes().to_vec()))
+            .collect::<Vec<_>>();
+        store
+            .insert_leaves_and_last_deposit_block_number(
+                history_store_key,
+                &generated_leaves,
+                20,
+                types::H256::random(),
+            )
+            .unwrap();
+        let leaves = store
+            .get_leaves_with_range(history_store_key, 250..520)
+            .unwrap();
+        assert_eq!(leaves.len(), 270);
+        assert_eq!(
+            leaves.keys().copied().collect::<Vec<_>>(),
+            (250..520u32).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn rollback_to_block_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let contract =
+            types::H160::from_slice("11111111111111111111".as_bytes());
+        let history_store_key = (
+            TypedChainId::Evm(1),
+            TargetSystem::new_contract_address(contract.to_fixed_bytes()),
+        );
+        // leaves 0..10 at block 10, 10..20 at block 20 and 20..30 at block 30.
+        let blocks =
+            [10u64, 20, 30].map(|n| BlockInfo::new(n, types::H256::random()));
+        for (i, block) in blocks.iter().enumerate() {
+            let start = i as u32 * 10;
+            let leaves = (start..start + 10)
+                .map(|i| (i, types::H256::random().to_fixed_bytes().to_vec()))
+                .collect::<Vec<_>>();
+            store
+                .insert_leaves_and_last_deposit_block_number(
+                    history_store_key,
+                    &leaves,
+                    block.number,
+                    block.hash,
+                )
+                .unwrap();
+            store
+                .insert_encrypted_output_and_last_deposit_block_number(
+                    history_store_key,
+                    &leaves,
+                    block.number,
+                    block.hash,
+                )
+                .unwrap();
+        }
+        assert_eq!(
+            store.get_last_block_hash(history_store_key).unwrap(),
+            Some(blocks[2].hash)
+        );
+        assert_eq!(
+            store.get_leaf_block(history_store_key, 15).unwrap(),
+            Some(blocks[1])
+        );
+        assert_eq!(
+            store.get_cached_blocks(history_store_key).unwrap(),
+            blocks.iter().rev().copied().collect::<Vec<_>>()
+        );
+
+        store
+            .rollback_to_block(history_store_key, blocks[1])
+            .unwrap();
+        assert_eq!(store.get_leaves(history_store_key).unwrap().len(), 20);
+        assert_eq!(
+            store.get_encrypted_output(history_store_key).unwrap().len(),
+            20
+        );
+        assert_eq!(store.get_leaf_block(history_store_key, 25).unwrap(), None);
+        assert_eq!(
+            store.get_last_block_number(history_store_key, 0).unwrap(),
+            20
+        );
+        assert_eq!(
+            store
+                .get_last_deposit_block_number(history_store_key)
+                .unwrap(),
+            20
+        );
+        assert_eq!(
+            store.get_last_block_hash(history_store_key).unwrap(),
+            Some(blocks[1].hash)
+        );
+        assert_eq!(
+            store.get_cached_blocks(history_store_key).unwrap(),
+            vec![blocks[1], blocks[0]]
+        );
+    }
+
+    #[test]
+    fn fresh_store_has_latest_schema_version() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        assert_eq!(store.schema_version().unwrap(), migrations::LATEST_VERSION);
+    }
+
+    #[test]
+    fn fresh_store_should_not_be_migrated() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        migrations::run(&db).unwrap();
+        // the migrations would have opened the trees they migrate.
+        let tree_names = db.tree_names();
+        assert!(tree_names.iter().all(|name| [
+            &b"__sled__default"[..],
+            b"schema"
+        ]
+        .contains(&name.as_ref())));
+        let store = SledStore { db, cipher: None };
+        assert_eq!(store.schema_version().unwrap(), migrations::LATEST_VERSION);
+    }
+
+    #[test]
+    fn legacy_store_should_be_migrated() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let contract =
+            types::H160::from_slice("11111111111111111111".as_bytes());
+        let history_store_key = (
+            TypedChainId::Evm(1),
+            TargetSystem::new_contract_address(contract.to_fixed_bytes()),
+        );
+        let key: HistoryStoreKey = history_store_key.into();
+        // the layout before the schema was versioned,
+        // with the indices stored as little-endian.
+        let leaves_tree = db
+            .open_tree(format!("leaves/{}/{}", key.chain_id(), key.address()))
+            .unwrap();
+        let outputs_tree = db
+            .open_tree(format!(
+                "encrypted_outputs/{}/{}",
+                key.chain_id(),
+                key.address()
+            ))
+            .unwrap();
+        let generated_leaves = (0..300u32)
+            .map(|i| (i, types::H256::random()))
+            .collect::<Vec<_>>();
+        for (i, leaf) in &generated_leaves {
+            leaves_tree
+                .insert(i.to_le_bytes(), leaf.as_bytes())
+                .unwrap();
+            outputs_tree
+                .insert(i.to_le_bytes(), &i.to_le_bytes())
+                .unwrap();
+        }
+
+        migrations::run(&db).unwrap();
+        let store = SledStore { db, cipher: None };
+        assert_eq!(store.schema_version().unwrap(), migrations::LATEST_VERSION);
+        let leaves = store
+            .get_leaves_with_range(history_store_key, 200..300)
+            .unwrap();
+        assert_eq!(
+            leaves.into_iter().collect::<Vec<_>>(),
+            generated_leaves[200..300].to_vec()
+        );
+        let outputs = store
+            .get_encrypted_output_with_range(history_store_key, 254..258)
+            .unwrap();
+        assert_eq!(
+            outputs,
+            (254..258u32)
+                .map(|i| i.to_le_bytes().to_vec())
+                .collect::<Vec<_>>()
+        );
+        // running the migrations again is a no-op.
+        migrations::run(&store.db).unwrap();
+        assert_eq!(store.get_leaves(history_store_key).unwrap().len(), 300);
+    }
+
+    #[test]
+    fn legacy_queue_should_be_migrated() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        db.open_tree("schema")
+            .unwrap()
+            .insert("version", &1u64.to_be_bytes())
+            .unwrap();
+        let queue_key = SledQueueKey::from_evm_chain_id(1);
+        // the layout before the items had a priority.
+        let tree = db
+            .open_tree(format!("queue_{}", queue_key.queue_name()))
+            .unwrap();
+        for idx in 1..=2u64 {
+            let mut item_key = b"item".to_vec();
+            item_key.extend_from_slice(&idx.to_be_bytes());
+            tree.insert(&item_key, serde_json::to_vec(&idx).unwrap())
+                .unwrap();
+            tree.insert([idx as u8; 64], item_key).unwrap();
+        }
+        tree.insert("last_item_idx", &2u64.to_be_bytes()).unwrap();
+
+        migrations::run(&db).unwrap();
+        let store = SledStore { db, cipher: None };
+        assert!(QueueStore::<u64>::has_item(
+            &store,
+            SledQueueKey::from_evm_with_custom_key(1, [2; 64])
+        )
+        .unwrap());
+        assert_eq!(
+            QueueStore::<u64>::remove_item(
+                &store,
+                SledQueueKey::from_evm_with_custom_key(1, [2; 64])
+            )
+            .unwrap(),
+            Some(2u64)
+        );
+        store.enqueue_item(queue_key, 3u64).unwrap();
+        assert_eq!(store.dequeue_item(queue_key).unwrap(), Some(1u64));
+        assert_eq!(store.dequeue_item(queue_key).unwrap(), Some(3u64));
+    }
+
+    #[test]
+    fn newer_schema_version_should_be_rejected() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        store
+            .db
+            .open_tree("schema")
+            .unwrap()
+            .insert("version", &(migrations::LATEST_VERSION + 1).to_be_bytes())
+            .unwrap();
+        assert!(matches!(
+            migrations::run(&store.db),
+            Err(webb_relayer_utils::Error::UnsupportedStoreVersion { .. })
+        ));
+    }
+
     #[test]
     fn tx_queue_should_work() {
         let tmp = tempfile::tempdir().unwrap();
@@ -832,6 +1886,375 @@ mod tests {
         );
     }
 
+    #[test]
+    fn queue_priority_and_not_before_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let queue = SledQueueKey::from_evm_chain_id(1);
+        store.enqueue_item(queue, 1u32).unwrap();
+        store
+            .enqueue_item_with_options(
+                queue,
+                2u32,
+                EnqueueOptions::with_priority(QueuePriority::Low),
+            )
+            .unwrap();
+        store.enqueue_item(queue, 3u32).unwrap();
+        store
+            .enqueue_item_with_options(
+                queue,
+                4u32,
+                EnqueueOptions::with_priority(QueuePriority::High),
+            )
+            .unwrap();
+        // not ready for an hour.
+        store
+            .enqueue_item_with_options(
+                queue,
+                5u32,
+                EnqueueOptions::with_priority(QueuePriority::High)
+                    .not_before(crate::unix_timestamp() + 3600),
+            )
+            .unwrap();
+        // ready already.
+        store
+            .enqueue_item_with_options(
+                queue,
+                6u32,
+                EnqueueOptions::default().not_before(1),
+            )
+            .unwrap();
+        assert_eq!(store.peek_item(queue).unwrap(), Some(4u32));
+        let dequeued =
+            std::iter::from_fn(|| store.dequeue_item(queue).unwrap())
+                .collect::<Vec<u32>>();
+        assert_eq!(dequeued, vec![4, 1, 3, 6, 2]);
+    }
+
+    #[test]
+    fn dequeue_should_return_how_the_item_was_enqueued() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let queue = SledQueueKey::from_evm_chain_id(1);
+        let keyed = queue.with_item_key(Some([1u8; 64]));
+        store
+            .enqueue_item_with_options(
+                keyed,
+                1u32,
+                EnqueueOptions::with_priority(QueuePriority::High)
+                    .not_before(1),
+            )
+            .unwrap();
+        store.enqueue_item(queue, 2u32).unwrap();
+        let first = store.dequeue_item_with_options(queue).unwrap().unwrap();
+        assert_eq!(first.item, 1u32);
+        assert_eq!(first.item_key, Some([1u8; 64]));
+        assert_eq!(
+            first.options,
+            EnqueueOptions::with_priority(QueuePriority::High).not_before(1)
+        );
+        let second = store.dequeue_item_with_options(queue).unwrap().unwrap();
+        assert_eq!(second.item, 2u32);
+        assert_eq!(second.item_key, None);
+        assert_eq!(second.options, EnqueueOptions::default());
+        assert!(QueueStore::<u32>::dequeue_item(&store, queue)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn dequeue_item_if_should_keep_the_item_in_place() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let queue = SledQueueKey::from_evm_chain_id(1);
+        for i in 1..=3u32 {
+            store.enqueue_item(queue, i).unwrap();
+        }
+        // the head does not match, so it is left where it was.
+        let dequeued = store.dequeue_item_if(queue, |i: &u32| *i != 1).unwrap();
+        assert!(dequeued.is_none());
+        assert_eq!(store.peek_item(queue).unwrap(), Some(1u32));
+        let dequeued = store.dequeue_item_if(queue, |i: &u32| *i == 1).unwrap();
+        assert_eq!(dequeued.map(|d| d.item), Some(1u32));
+        assert_eq!(store.dequeue_item(queue).unwrap(), Some(2u32));
+        assert_eq!(store.dequeue_item(queue).unwrap(), Some(3u32));
+    }
+
+    #[test]
+    fn dead_letters_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let chain_id = 1u32;
+        let queue = SledQueueKey::from_evm_chain_id(chain_id);
+        let make_key =
+            |i: u8| SledQueueKey::from_evm_with_custom_key(chain_id, [i; 64]);
+        store.enqueue_item(make_key(1), 1u32).unwrap();
+        store.enqueue_item(make_key(2), 2u32).unwrap();
+        let item: u32 = store.dequeue_item(queue).unwrap().unwrap();
+        let dead_letter = store
+            .dead_letter_item(make_key(1), item, "dry run failed".into())
+            .unwrap();
+        assert_eq!(dead_letter.attempts, 1);
+        // the same item failing again is only recorded once.
+        let dead_letter = store
+            .dead_letter_item(make_key(1), item, "reverted".into())
+            .unwrap();
+        assert_eq!(dead_letter.attempts, 2);
+        assert_eq!(dead_letter.reason, "reverted");
+        assert!(dead_letter.first_failed_at <= dead_letter.last_failed_at);
+        let dead_letters: Vec<DeadLetter<u32>> =
+            store.get_dead_letters(queue).unwrap();
+        assert_eq!(dead_letters, vec![dead_letter.clone()]);
+
+        // requeue it, behind the item that is still in the queue.
+        assert!(QueueStore::<u32>::requeue_dead_letter(
+            &store,
+            queue,
+            dead_letter.id
+        )
+        .unwrap());
+        assert!(!QueueStore::<u32>::requeue_dead_letter(
+            &store,
+            queue,
+            dead_letter.id
+        )
+        .unwrap());
+        assert!(QueueStore::<u32>::has_item(&store, make_key(1)).unwrap());
+        assert_eq!(store.dequeue_item(queue).unwrap(), Some(2u32));
+        assert_eq!(store.dequeue_item(queue).unwrap(), Some(1u32));
+
+        store
+            .dead_letter_item(make_key(3), 3u32, "dropped".into())
+            .unwrap();
+        assert_eq!(
+            QueueStore::<u32>::purge_dead_letters(&store, queue).unwrap(),
+            1
+        );
+        assert!(QueueStore::<u32>::get_dead_letters(&store, queue)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn requeued_dead_letters_should_keep_their_priority() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let queue = SledQueueKey::from_evm_chain_id(1);
+        let high = EnqueueOptions::with_priority(QueuePriority::High);
+        store.enqueue_item_with_options(queue, 1u32, high).unwrap();
+        let dequeued: DequeuedItem<u32> =
+            store.dequeue_item_with_options(queue).unwrap().unwrap();
+        let dead_letter = store
+            .dead_letter_item_with_options(
+                queue,
+                dequeued.item,
+                dequeued.options,
+                "reverted".into(),
+            )
+            .unwrap();
+        assert_eq!(dead_letter.priority, QueuePriority::High);
+        store.enqueue_item(queue, 2u32).unwrap();
+        // requeued behind the normal item, but dequeued before it.
+        assert!(QueueStore::<u32>::requeue_dead_letter(
+            &store,
+            queue,
+            dead_letter.id
+        )
+        .unwrap());
+        let requeued: DequeuedItem<u32> =
+            store.dequeue_item_with_options(queue).unwrap().unwrap();
+        assert_eq!(requeued.item, 1u32);
+        assert_eq!(requeued.options, high);
+        assert_eq!(store.dequeue_item(queue).unwrap(), Some(2u32));
+    }
+
+    #[test]
+    fn encrypted_store_should_work() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let cipher = StoreCipher::new([1u8; 32]);
+        let store =
+            SledStore::from_db(db.clone(), Some(cipher.clone())).unwrap();
+        let queue = SledQueueKey::from_evm_chain_id(1);
+        let secret = String::from("signed transaction");
+        store.enqueue_item(queue, secret.clone()).unwrap();
+        store
+            .dead_letter_item(queue, secret.clone(), "reverted".into())
+            .unwrap();
+        // nothing is stored in plaintext.
+        for prefix in ["queue", "dead_letters"] {
+            let tree = db
+                .open_tree(format!("{prefix}_{}", queue.queue_name()))
+                .unwrap();
+            assert!(tree.iter().values().all(|v| {
+                let v = v.unwrap();
+                !v.windows(secret.len()).any(|w| w == secret.as_bytes())
+            }));
+        }
+        assert_eq!(
+            QueueStore::<String>::peek_item(&store, queue).unwrap(),
+            Some(secret.clone())
+        );
+        let dead_letters: Vec<DeadLetter<String>> =
+            store.get_dead_letters(queue).unwrap();
+        assert_eq!(dead_letters[0].item, secret);
+
+        // the store could not be opened with another key, or without any.
+        assert!(matches!(
+            SledStore::from_db(db.clone(), Some(StoreCipher::new([2u8; 32]))),
+            Err(webb_relayer_utils::Error::WrongStoreKey)
+        ));
+        assert!(matches!(
+            SledStore::from_db(db.clone(), None),
+            Err(webb_relayer_utils::Error::StoreKeyMissing)
+        ));
+        SledStore::from_db(db, Some(cipher)).unwrap();
+    }
+
+    #[test]
+    fn rotate_key_should_work() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let queue = SledQueueKey::from_evm_chain_id(1);
+        // a store that was not encrypted yet.
+        let store = SledStore::from_db(db.clone(), None).unwrap();
+        store.enqueue_item(queue, 1u32).unwrap();
+        store
+            .dead_letter_item(queue, 2u32, "reverted".into())
+            .unwrap();
+        let old_cipher = StoreCipher::new([1u8; 32]);
+        let mut store =
+            SledStore::from_db(db.clone(), Some(old_cipher)).unwrap();
+        // values stored in plaintext are still readable.
+        assert_eq!(
+            QueueStore::<u32>::peek_item(&store, queue).unwrap(),
+            Some(1u32)
+        );
+        store.enqueue_item(queue, 3u32).unwrap();
+
+        let new_cipher = StoreCipher::new([2u8; 32]);
+        assert_eq!(store.rotate_key(Some(new_cipher.clone())).unwrap(), 3);
+        let tree = db
+            .open_tree(format!("queue_{}", queue.queue_name()))
+            .unwrap();
+        assert!(tree
+            .iter()
+            .filter_map(|e| e.ok())
+            .filter(|(k, _)| is_queue_item_key(k))
+            .all(|(_, v)| crypto::is_encrypted(&v)));
+        assert!(matches!(
+            SledStore::from_db(db.clone(), Some(StoreCipher::new([1u8; 32]))),
+            Err(webb_relayer_utils::Error::WrongStoreKey)
+        ));
+        let mut store =
+            SledStore::from_db(db.clone(), Some(new_cipher)).unwrap();
+        assert_eq!(store.dequeue_item(queue).unwrap(), Some(1u32));
+        let dead_letters: Vec<DeadLetter<u32>> =
+            store.get_dead_letters(queue).unwrap();
+        assert_eq!(dead_letters[0].item, 2u32);
+
+        // and back to plaintext.
+        assert_eq!(store.rotate_key(None).unwrap(), 2);
+        let store = SledStore::from_db(db, None).unwrap();
+        assert_eq!(store.dequeue_item(queue).unwrap(), Some(3u32));
+    }
+
+    #[test]
+    fn prune_should_work() {
+        let store = SledStore::temporary().unwrap();
+        let policy = RetentionPolicy {
+            event_hashes_max_age: Some(3600),
+            token_prices_max_age: Some(3600),
+            dead_letters_max_age: Some(3600),
+            block_depth: Some(50),
+            finished_queue_entries: true,
+        };
+        let long_ago = 1u64.to_be_bytes();
+        // fresh entries.
+        store.store_event(b"new event").unwrap();
+        store.insert_price("ETH", 1800.5f64).unwrap();
+        let queue = SledQueueKey::from_evm_chain_id(1);
+        store
+            .dead_letter_item(queue, 1u32, "failed".into())
+            .unwrap();
+        // entries from a long time ago.
+        store.store_event(b"old event").unwrap();
+        store
+            .db
+            .open_tree("event_hashes")
+            .unwrap()
+            .insert(ethers::utils::keccak256(b"old event"), &long_ago)
+            .unwrap();
+        store.insert_price("DOT", 5.5f64).unwrap();
+        store
+            .db
+            .open_tree("token_prices_updated_at")
+            .unwrap()
+            .insert("DOT", &long_ago)
+            .unwrap();
+        let mut old_dead_letter = store
+            .dead_letter_item(queue, 2u32, "failed".into())
+            .unwrap();
+        old_dead_letter.last_failed_at = 1;
+        store
+            .db
+            .open_tree(format!("dead_letters_{}", queue.queue_name()))
+            .unwrap()
+            .insert(
+                old_dead_letter.id,
+                serde_json::to_vec(&old_dead_letter).unwrap(),
+            )
+            .unwrap();
+        // one leaf deep enough to be final, one still recent.
+        let key = HistoryStoreKey::from(1u32);
+        for (i, block_number) in [10u64, 100].into_iter().enumerate() {
+            let leaves = vec![(i as u32, types::H256::random().0.to_vec())];
+            store
+                .insert_leaves_and_last_deposit_block_number(
+                    key,
+                    &leaves,
+                    block_number,
+                    types::H256::random(),
+                )
+                .unwrap();
+        }
+        // a dequeued item, that left its custom key behind, and one still queued.
+        let done = SledQueueKey::from_evm_with_custom_key(1, [1; 64]);
+        let pending = SledQueueKey::from_evm_with_custom_key(1, [2; 64]);
+        store.enqueue_item(done, 1u32).unwrap();
+        store.enqueue_item(pending, 2u32).unwrap();
+        assert_eq!(store.dequeue_item(queue).unwrap(), Some(1u32));
+        assert!(QueueStore::<u32>::has_item(&store, done).unwrap());
+
+        let report = store.prune(&policy).unwrap();
+        assert_eq!(report.event_hashes, 1);
+        assert_eq!(report.token_prices, 1);
+        assert_eq!(report.dead_letters, 1);
+        assert_eq!(report.blocks, 1);
+        assert_eq!(report.queue_entries, 1);
+        assert!(report.reclaimed_bytes > 0);
+
+        assert!(store.contains_event(b"new event").unwrap());
+        assert!(!store.contains_event(b"old event").unwrap());
+        assert_eq!(
+            TokenPriceCacheStore::<f64>::get_price(&store, "ETH").unwrap(),
+            Some(1800.5f64)
+        );
+        assert_eq!(
+            TokenPriceCacheStore::<f64>::get_price(&store, "DOT").unwrap(),
+            None
+        );
+        let dead_letters =
+            QueueStore::<u32>::get_dead_letters(&store, queue).unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].item, 1);
+        assert_eq!(store.get_leaf_block(key, 0).unwrap(), None);
+        assert!(store.get_leaf_block(key, 1).unwrap().is_some());
+        assert_eq!(store.get_leaves(key).unwrap().len(), 2);
+        assert!(!QueueStore::<u32>::has_item(&store, done).unwrap());
+        assert!(QueueStore::<u32>::has_item(&store, pending).unwrap());
+        // nothing left to prune.
+        assert_eq!(store.prune(&policy).unwrap(), PruneReport::default());
+    }
+
     #[test]
     fn events_hash_should_work() {
         let tmp = tempfile::tempdir().unwrap();
@@ -881,6 +2304,7 @@ mod tests {
                 history_store_key,
                 &generated_leaves,
                 block_number,
+                types::H256::random(),
             )
             .unwrap();
         let leaves = store
@@ -910,4 +2334,97 @@ mod tests {
             block_number
         );
     }
+
+    #[test]
+    fn batch_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let key = HistoryStoreKey::from(1u32);
+        let queue = SledQueueKey::from_evm_chain_id(1);
+        let leaves = (0..2u32)
+            .map(|i| (i, types::H256::random().to_fixed_bytes().to_vec()))
+            .collect::<Vec<_>>();
+        let event = b"some event".to_vec();
+        // a failed batch writes nothing.
+        let result = store.batch(|batch| {
+            batch
+                .insert_leaves_and_last_deposit_block_number(
+                    key,
+                    &leaves,
+                    20,
+                    types::H256::random(),
+                )
+                .store_event(&event)
+                .enqueue_item(queue, &1u32)?;
+            Err::<(), _>(webb_relayer_utils::Error::Generic("failed"))
+        });
+        assert!(result.is_err());
+        assert!(store.get_leaves(key).unwrap().is_empty());
+        assert!(!store.contains_event(&event).unwrap());
+        assert_eq!(store.peek_item(queue).unwrap(), Option::<u32>::None);
+
+        store
+            .batch(|batch| {
+                batch
+                    .insert_leaves_and_last_deposit_block_number(
+                        key,
+                        &leaves,
+                        20,
+                        types::H256::random(),
+                    )
+                    .store_event(&event)
+                    .set_target_block_number(key, 30)
+                    .enqueue_item(queue, &1u32)?;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(store.get_leaves(key).unwrap().len(), 2);
+        assert_eq!(store.get_last_deposit_block_number(key).unwrap(), 20);
+        assert_eq!(store.get_last_block_number(key, 1).unwrap(), 20);
+        assert_eq!(store.get_target_block_number(key, 1).unwrap(), 30);
+        assert!(store.contains_event(&event).unwrap());
+        assert_eq!(store.peek_item(queue).unwrap(), Some(1u32));
+        assert_eq!(store.dequeue_item(queue).unwrap(), Some(1u32));
+    }
+
+    #[test]
+    fn batch_should_be_rolled_back_when_an_operation_fails() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SledStore::open(tmp.path()).unwrap();
+        let key = HistoryStoreKey::from(1u32);
+        let queue = SledQueueKey::from_evm_chain_id(1);
+        let leaves = (0..2u32)
+            .map(|i| (i, types::H256::random().to_fixed_bytes().to_vec()))
+            .collect::<Vec<_>>();
+        let event = b"some event".to_vec();
+        // a corrupted queue makes the last operation fail, inside the transaction.
+        store
+            .db
+            .open_tree(format!("queue_{}", queue.queue_name()))
+            .unwrap()
+            .insert("last_item_idx", &[0u8; 3])
+            .unwrap();
+        let result = store.batch(|batch| {
+            batch
+                .insert_leaves_and_last_deposit_block_number(
+                    key,
+                    &leaves,
+                    20,
+                    types::H256::random(),
+                )
+                .store_event(&event)
+                .set_target_block_number(key, 30)
+                .enqueue_item(queue, &1u32)?;
+            Ok(())
+        });
+        assert!(result.is_err());
+        assert!(store.get_leaves(key).unwrap().is_empty());
+        assert_eq!(store.get_last_deposit_block_number(key).unwrap(), 0);
+        assert_eq!(store.get_last_block_number(key, 1).unwrap(), 1);
+        assert_eq!(store.get_target_block_number(key, 1).unwrap(), 1);
+        assert!(!store.contains_event(&event).unwrap());
+        assert!(QueueStore::<u32>::dequeue_item(&store, queue)
+            .unwrap()
+            .is_none());
+    }
 }
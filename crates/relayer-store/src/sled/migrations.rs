@@ -0,0 +1,249 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Versioned schema of the [`SledStore`](super::SledStore).
+//!
+//! The schema version is stored in the `schema` tree. Every time the store is opened,
+//! all the migrations newer than the stored version are applied in order, and the
+//! version is bumped after each one of them.
+//!
+//! To change how the data is laid out on disk, add a new [`Migration`] at the end of
+//! [`MIGRATIONS`]; never edit or reorder the existing ones.
+
+use sled::Transactional;
+
+/// The tree that holds the schema version and the migrations progress.
+const SCHEMA_TREE: &str = "schema";
+/// The key, in the [`SCHEMA_TREE`], of the current schema version.
+const SCHEMA_VERSION_KEY: &str = "version";
+/// The name of the tree sled creates by default.
+const DEFAULT_TREE: &[u8] = b"__sled__default";
+
+/// A single migration step.
+pub(crate) struct Migration {
+    /// The schema version after this migration is applied.
+    pub version: u64,
+    /// A short description of the migration, used for logging.
+    pub description: &'static str,
+    /// Applies the migration to the database.
+    pub migrate: fn(&sled::Db) -> crate::Result<()>,
+}
+
+/// All the migrations, in the order they must be applied.
+pub(crate) const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "store leaf and encrypted output indices as big-endian",
+        migrate: reencode_indices_as_big_endian,
+    },
+    Migration {
+        version: 2,
+        description: "add the priority to the keys of the queue items",
+        migrate: prefix_queue_items_with_priority,
+    },
+    Migration {
+        version: 3,
+        description:
+            "record when the event hashes and token prices were stored",
+        migrate: stamp_event_hashes_and_token_prices,
+    },
+];
+
+/// The latest schema version, that is the version of a freshly created store.
+pub(crate) const LATEST_VERSION: u64 = 3;
+
+/// Reads the schema version of the database, if any.
+pub(crate) fn read_version(db: &sled::Db) -> crate::Result<Option<u64>> {
+    let tree = db.open_tree(SCHEMA_TREE)?;
+    let version = tree.get(SCHEMA_VERSION_KEY)?.map(|v| {
+        let mut output = [0u8; 8];
+        output.copy_from_slice(&v);
+        u64::from_be_bytes(output)
+    });
+    Ok(version)
+}
+
+fn write_version(db: &sled::Db, version: u64) -> crate::Result<()> {
+    let tree = db.open_tree(SCHEMA_TREE)?;
+    tree.insert(SCHEMA_VERSION_KEY, &version.to_be_bytes())?;
+    Ok(())
+}
+
+/// Returns true if nothing was ever written to the database.
+fn is_fresh(db: &sled::Db) -> bool {
+    db.is_empty()
+        && db
+            .tree_names()
+            .iter()
+            .all(|name| name.as_ref() == DEFAULT_TREE)
+}
+
+/// Brings the database schema up to [`LATEST_VERSION`].
+///
+/// A database without a version marker is either fresh, and is marked with
+/// the latest version, or was created before the schema was versioned,
+/// in which case all the migrations are applied.
+pub(crate) fn run(db: &sled::Db) -> crate::Result<()> {
+    debug_assert_eq!(
+        MIGRATIONS.last().map(|m| m.version),
+        Some(LATEST_VERSION)
+    );
+    // checked first, since reading the version creates the schema tree.
+    let fresh = is_fresh(db);
+    let current = match read_version(db)? {
+        Some(version) => version,
+        None if fresh => {
+            write_version(db, LATEST_VERSION)?;
+            db.flush()?;
+            return Ok(());
+        }
+        None => 0,
+    };
+    if current > LATEST_VERSION {
+        return Err(webb_relayer_utils::Error::UnsupportedStoreVersion {
+            found: current,
+            supported: LATEST_VERSION,
+        });
+    }
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        tracing::info!(
+            version = migration.version,
+            "Migrating the store: {}",
+            migration.description
+        );
+        (migration.migrate)(db)?;
+        write_version(db, migration.version)?;
+        db.flush()?;
+    }
+    Ok(())
+}
+
+/// Leaves and encrypted outputs used to be keyed by their little-endian index,
+/// which breaks the ordering of range queries once there are more than 256 of them.
+fn reencode_indices_as_big_endian(db: &sled::Db) -> crate::Result<()> {
+    let schema = db.open_tree(SCHEMA_TREE)?;
+    let tree_names = db.tree_names();
+    let trees = tree_names.iter().filter(|name| {
+        name.starts_with(b"leaves/") || name.starts_with(b"encrypted_outputs/")
+    });
+    for name in trees {
+        // every tree is migrated atomically, and marked as done,
+        // so that the migration could be resumed if interrupted.
+        let mut marker = b"migrations/1/".to_vec();
+        marker.extend_from_slice(name);
+        if schema.contains_key(&marker)? {
+            continue;
+        }
+        let tree = db.open_tree(name)?;
+        let entries = tree.iter().collect::<Result<Vec<_>, _>>()?;
+        (&tree, &schema).transaction(|(tree, schema)| {
+            for (k, _) in &entries {
+                tree.remove(k)?;
+            }
+            for (k, v) in &entries {
+                let index_bytes: [u8; 4] = match k.as_ref().try_into() {
+                    Ok(bytes) => bytes,
+                    // not an index, keep it as is.
+                    Err(_) => {
+                        tree.insert(k, v)?;
+                        continue;
+                    }
+                };
+                let index = u32::from_le_bytes(index_bytes);
+                tree.insert(&index.to_be_bytes(), v)?;
+            }
+            schema.insert(marker.as_slice(), &[])?;
+            Ok(())
+        })?;
+    }
+    // clean up the progress markers.
+    for marker in schema.scan_prefix(b"migrations/1/").keys() {
+        schema.remove(marker?)?;
+    }
+    Ok(())
+}
+
+/// Queue items used to be keyed by `item` followed by their big-endian index.
+/// They are now keyed by `item`, their inverted priority, then their index, so
+/// the existing items get the normal priority.
+fn prefix_queue_items_with_priority(db: &sled::Db) -> crate::Result<()> {
+    // the inverted normal priority, as it was when this migration was written.
+    const NORMAL_PRIORITY: u8 = u8::MAX - 1;
+    let schema = db.open_tree(SCHEMA_TREE)?;
+    let tree_names = db.tree_names();
+    let trees = tree_names.iter().filter(|name| name.starts_with(b"queue_"));
+    for name in trees {
+        let mut marker = b"migrations/2/".to_vec();
+        marker.extend_from_slice(name);
+        if schema.contains_key(&marker)? {
+            continue;
+        }
+        let tree = db.open_tree(name)?;
+        let entries = tree.iter().collect::<Result<Vec<_>, _>>()?;
+        let rekey = |old: &[u8]| -> Option<Vec<u8>> {
+            if old.len() != 12 || !old.starts_with(b"item") {
+                return None;
+            }
+            let mut new = b"item".to_vec();
+            new.push(NORMAL_PRIORITY);
+            new.extend_from_slice(&old[4..]);
+            Some(new)
+        };
+        (&tree, &schema).transaction(|(tree, schema)| {
+            for (k, v) in &entries {
+                if let Some(new_key) = rekey(k.as_ref()) {
+                    // an item.
+                    tree.remove(k)?;
+                    tree.insert(new_key, v)?;
+                } else if let Some(new_value) = rekey(v.as_ref()) {
+                    // a custom key, that points to an item.
+                    tree.insert(k, new_value)?;
+                }
+            }
+            schema.insert(marker.as_slice(), &[])?;
+            Ok(())
+        })?;
+    }
+    // clean up the progress markers.
+    for marker in schema.scan_prefix(b"migrations/2/").keys() {
+        schema.remove(marker?)?;
+    }
+    Ok(())
+}
+
+/// The event hashes used to be stored without a value, and the token prices without
+/// the time they were cached, so they could not be pruned by age.
+/// Both are now considered stored at the time of the migration.
+fn stamp_event_hashes_and_token_prices(db: &sled::Db) -> crate::Result<()> {
+    let now = crate::unix_timestamp().to_be_bytes();
+    // only the entries without a time are updated, so this could be resumed if interrupted.
+    let event_hashes = db.open_tree("event_hashes")?;
+    for hash in event_hashes.iter().keys() {
+        let hash = hash?;
+        let _ = event_hashes.compare_and_swap(
+            &hash,
+            Some(&[] as &[u8]),
+            Some(&now[..]),
+        )?;
+    }
+    let token_prices = db.open_tree("token_prices")?;
+    let updated_at = db.open_tree("token_prices_updated_at")?;
+    for token in token_prices.iter().keys() {
+        let token = token?;
+        if !updated_at.contains_key(&token)? {
+            updated_at.insert(&token, &now[..])?;
+        }
+    }
+    Ok(())
+}
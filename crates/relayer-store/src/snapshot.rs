@@ -0,0 +1,315 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Portable snapshots of the leaves and encrypted outputs caches.
+//!
+//! A snapshot is exported from the store of a synced relayer, and imported into the
+//! store of a new one, so it does not have to replay every event from the deploy block.
+//! After the import, the event watchers continue from the last block of the snapshot.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use webb::evm::ethers::{self, types};
+
+use crate::{
+    EncryptedOutputCacheStore, HistoryStore, HistoryStoreKey, LeafCacheStore,
+};
+
+/// The current version of the snapshot format.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// A snapshot of the leaves and encrypted outputs caches of a store.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snapshot {
+    /// The version of the snapshot format.
+    pub version: u32,
+    /// The keccak256 hash of the JSON encoded entries.
+    pub checksum: types::H256,
+    /// The cached data, one entry per [`HistoryStoreKey`].
+    pub entries: Vec<SnapshotEntry>,
+}
+
+/// The cached data of a single [`HistoryStoreKey`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotEntry {
+    /// The key, as returned by [`HistoryStoreKey::to_bytes`].
+    pub key: types::Bytes,
+    /// The last block number processed by the event watcher.
+    pub last_block_number: u64,
+    /// The block number of the last deposit (leaf insertion).
+    pub last_deposit_block_number: u64,
+    /// The leaves, by their index.
+    pub leaves: BTreeMap<u32, types::H256>,
+    /// The block number of the last encrypted output insertion.
+    pub encrypted_output_last_deposit_block_number: u64,
+    /// The encrypted outputs, by their index.
+    pub encrypted_outputs: BTreeMap<u32, types::Bytes>,
+}
+
+impl Snapshot {
+    /// Exports the caches of every key found in the store.
+    pub fn export<S>(store: &S) -> crate::Result<Self>
+    where
+        S: LeafCacheStore + EncryptedOutputCacheStore,
+    {
+        // sort the keys, so exporting the same store twice gives the same snapshot.
+        let keys: BTreeMap<_, _> = store
+            .get_leaves_cache_keys()?
+            .into_iter()
+            .chain(store.get_encrypted_output_cache_keys()?)
+            .map(|key| (key.to_bytes(), key))
+            .collect();
+        let mut entries = Vec::with_capacity(keys.len());
+        for (bytes, key) in keys {
+            let leaves = store.get_leaves(key)?.into_iter().collect();
+            let encrypted_outputs = store
+                .get_encrypted_output_with_indices(key)?
+                .into_iter()
+                .map(|(i, output)| (i, types::Bytes::from(output)))
+                .collect();
+            entries.push(SnapshotEntry {
+                key: bytes.into(),
+                last_block_number: store.get_last_block_number(key, 0)?,
+                last_deposit_block_number: store
+                    .get_last_deposit_block_number(key)?,
+                leaves,
+                encrypted_output_last_deposit_block_number: store
+                    .get_last_deposit_block_number_for_encrypted_output(key)?,
+                encrypted_outputs,
+            });
+        }
+        let checksum = checksum_of(&entries)?;
+        Ok(Self {
+            version: SNAPSHOT_VERSION,
+            checksum,
+            entries,
+        })
+    }
+
+    /// Checks the version and the checksum of the snapshot.
+    pub fn verify(&self) -> crate::Result<()> {
+        if self.version > SNAPSHOT_VERSION {
+            return Err(webb_relayer_utils::Error::UnsupportedSnapshotVersion(
+                self.version,
+            ));
+        }
+        let checksum = checksum_of(&self.entries)?;
+        if checksum != self.checksum {
+            return Err(webb_relayer_utils::Error::SnapshotChecksumMismatch {
+                expected: format!("{:?}", self.checksum),
+                found: format!("{checksum:?}"),
+            });
+        }
+        Ok(())
+    }
+
+    /// Imports the snapshot into the store.
+    ///
+    /// The store must not have any cached data for the keys of the snapshot.
+    pub fn import<S>(&self, store: &S) -> crate::Result<()>
+    where
+        S: LeafCacheStore + EncryptedOutputCacheStore,
+    {
+        self.verify()?;
+        let entries = self
+            .entries
+            .iter()
+            .map(|entry| {
+                HistoryStoreKey::from_bytes(&entry.key)
+                    .map(|key| (key, entry))
+                    .ok_or(webb_relayer_utils::Error::Generic(
+                        "invalid history store key in the snapshot",
+                    ))
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+        // check everything first, so we do not import half of the snapshot.
+        for (key, _) in &entries {
+            let has_leaves = store.get_last_deposit_block_number(*key)? != 0
+                || store.get_leaves(*key)?.into_iter().next().is_some();
+            let has_encrypted_outputs = store
+                .get_last_deposit_block_number_for_encrypted_output(*key)?
+                != 0
+                || store
+                    .get_encrypted_output(*key)?
+                    .into_iter()
+                    .next()
+                    .is_some();
+            if has_leaves || has_encrypted_outputs {
+                return Err(webb_relayer_utils::Error::StoreNotEmpty(
+                    key.to_string(),
+                ));
+            }
+        }
+        for (key, entry) in entries {
+            if entry.last_deposit_block_number != 0 || !entry.leaves.is_empty()
+            {
+                let leaves: Vec<_> = entry
+                    .leaves
+                    .iter()
+                    .map(|(i, leaf)| (*i, leaf.as_bytes().to_vec()))
+                    .collect();
+                store.insert_leaves_and_last_deposit_block_number(
+                    key,
+                    &leaves,
+                    entry.last_deposit_block_number,
+                    // the blocks of the leaves are unknown.
+                    types::H256::zero(),
+                )?;
+            }
+            if entry.encrypted_output_last_deposit_block_number != 0
+                || !entry.encrypted_outputs.is_empty()
+            {
+                let encrypted_outputs: Vec<_> = entry
+                    .encrypted_outputs
+                    .iter()
+                    .map(|(i, output)| (*i, output.to_vec()))
+                    .collect();
+                store.insert_encrypted_output_and_last_deposit_block_number(
+                    key,
+                    &encrypted_outputs,
+                    entry.encrypted_output_last_deposit_block_number,
+                    types::H256::zero(),
+                )?;
+            }
+            // the event watcher continues from here.
+            store.set_last_block_number(key, entry.last_block_number)?;
+            tracing::debug!(
+                %key,
+                leaves = entry.leaves.len(),
+                encrypted_outputs = entry.encrypted_outputs.len(),
+                last_block_number = entry.last_block_number,
+                "Imported snapshot entry",
+            );
+        }
+        Ok(())
+    }
+
+    /// Writes the snapshot to a file, as JSON.
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> crate::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut writer, self)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads a snapshot from a file, and verifies it.
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> crate::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let snapshot: Self = serde_json::from_reader(reader)?;
+        snapshot.verify()?;
+        Ok(snapshot)
+    }
+}
+
+fn checksum_of(entries: &[SnapshotEntry]) -> crate::Result<types::H256> {
+    let bytes = serde_json::to_vec(entries)?;
+    Ok(types::H256::from(ethers::utils::keccak256(bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryStore;
+    use webb_proposals::{TargetSystem, TypedChainId};
+
+    fn populated_store() -> (InMemoryStore, HistoryStoreKey) {
+        let store = InMemoryStore::default();
+        let contract =
+            types::H160::from_slice("11111111111111111111".as_bytes());
+        let key: HistoryStoreKey = (
+            TypedChainId::Evm(1),
+            TargetSystem::new_contract_address(contract.to_fixed_bytes()),
+        )
+            .into();
+        let leaves = (0..10u32)
+            .map(|i| (i, types::H256::random().to_fixed_bytes().to_vec()))
+            .collect::<Vec<_>>();
+        store
+            .insert_leaves_and_last_deposit_block_number(
+                key,
+                &leaves,
+                20,
+                types::H256::random(),
+            )
+            .unwrap();
+        // a relayer that started from a later block does not have the first outputs.
+        let outputs = (10..20u32)
+            .map(|i| (i, i.to_be_bytes().to_vec()))
+            .collect::<Vec<_>>();
+        store
+            .insert_encrypted_output_and_last_deposit_block_number(
+                key,
+                &outputs,
+                20,
+                types::H256::random(),
+            )
+            .unwrap();
+        store.set_last_block_number(key, 42).unwrap();
+        (store, key)
+    }
+
+    #[test]
+    fn export_and_import_should_work() {
+        let (store, key) = populated_store();
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("snapshot.json");
+        Snapshot::export(&store)
+            .unwrap()
+            .write_to_file(&path)
+            .unwrap();
+
+        let snapshot = Snapshot::read_from_file(&path).unwrap();
+        let fresh = InMemoryStore::default();
+        snapshot.import(&fresh).unwrap();
+        assert_eq!(
+            fresh.get_leaves(key).unwrap(),
+            store.get_leaves(key).unwrap()
+        );
+        assert_eq!(
+            fresh.get_encrypted_output_with_indices(key).unwrap(),
+            store.get_encrypted_output_with_indices(key).unwrap()
+        );
+        assert_eq!(
+            fresh.get_encrypted_output_with_range(key, 10..12).unwrap(),
+            vec![10u32.to_be_bytes().to_vec(), 11u32.to_be_bytes().to_vec()]
+        );
+        assert_eq!(fresh.get_last_deposit_block_number(key).unwrap(), 20);
+        assert_eq!(fresh.get_last_block_number(key, 0).unwrap(), 42);
+        assert_eq!(Snapshot::export(&fresh).unwrap(), snapshot);
+
+        // it cannot be imported twice.
+        assert!(matches!(
+            snapshot.import(&fresh),
+            Err(webb_relayer_utils::Error::StoreNotEmpty(_))
+        ));
+    }
+
+    #[test]
+    fn tampered_snapshot_should_be_rejected() {
+        let (store, _) = populated_store();
+        let mut snapshot = Snapshot::export(&store).unwrap();
+        snapshot.entries[0].last_block_number += 1;
+        assert!(matches!(
+            snapshot.verify(),
+            Err(webb_relayer_utils::Error::SnapshotChecksumMismatch { .. })
+        ));
+        assert!(snapshot.import(&InMemoryStore::default()).is_err());
+    }
+}
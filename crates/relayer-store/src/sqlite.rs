@@ -0,0 +1,2104 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::HistoryStoreKey;
+use super::{
+    BlockInfo, DeadLetter, DequeuedItem, EncryptedOutputCacheStore,
+    EventHashStore, HistoryStore, LeafCacheStore, QueueStore, ReorgStore,
+    TokenPriceCacheStore,
+};
+use crate::crypto::{self, StoreCipher};
+use crate::{
+    is_expired, unix_timestamp, BatchOp, BatchStore, EnqueueOptions,
+    ProposalsQueueStore, PruneReport, QueueKey, QueuePriority, RetentionPolicy,
+    RetentionStore, SledQueueKey, StoreBatch,
+};
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::path::Path;
+use std::sync::Arc;
+use webb::evm::ethers::{self, types};
+
+/// The SQL schema of the store.
+///
+/// Every table here mirrors one of the trees used by the [`SledStore`](crate::SledStore),
+/// but the values are stored in a way that could be queried with plain SQL.
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS last_block_numbers (
+    key BLOB PRIMARY KEY NOT NULL,
+    block_number INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS target_block_numbers (
+    key BLOB PRIMARY KEY NOT NULL,
+    block_number INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS last_deposit_block_number (
+    key BLOB PRIMARY KEY NOT NULL,
+    block_number INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS encrypted_output_last_deposit_block_number (
+    key BLOB PRIMARY KEY NOT NULL,
+    block_number INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS leaves (
+    chain_id INTEGER NOT NULL,
+    address TEXT NOT NULL,
+    leaf_index INTEGER NOT NULL,
+    leaf BLOB NOT NULL,
+    PRIMARY KEY (chain_id, address, leaf_index)
+);
+CREATE TABLE IF NOT EXISTS encrypted_outputs (
+    chain_id INTEGER NOT NULL,
+    address TEXT NOT NULL,
+    output_index INTEGER NOT NULL,
+    encrypted_output BLOB NOT NULL,
+    PRIMARY KEY (chain_id, address, output_index)
+);
+CREATE TABLE IF NOT EXISTS leaf_blocks (
+    chain_id INTEGER NOT NULL,
+    address TEXT NOT NULL,
+    leaf_index INTEGER NOT NULL,
+    block_number INTEGER NOT NULL,
+    block_hash BLOB NOT NULL,
+    PRIMARY KEY (chain_id, address, leaf_index)
+);
+CREATE TABLE IF NOT EXISTS encrypted_output_blocks (
+    chain_id INTEGER NOT NULL,
+    address TEXT NOT NULL,
+    output_index INTEGER NOT NULL,
+    block_number INTEGER NOT NULL,
+    block_hash BLOB NOT NULL,
+    PRIMARY KEY (chain_id, address, output_index)
+);
+CREATE TABLE IF NOT EXISTS last_block_hashes (
+    key BLOB PRIMARY KEY NOT NULL,
+    block_hash BLOB NOT NULL
+);
+CREATE TABLE IF NOT EXISTS event_hashes (
+    hash BLOB PRIMARY KEY NOT NULL,
+    stored_at INTEGER NOT NULL DEFAULT 0
+);
+CREATE TABLE IF NOT EXISTS queue_items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    queue_name TEXT NOT NULL,
+    item_key BLOB,
+    item TEXT NOT NULL,
+    priority INTEGER NOT NULL DEFAULT 1,
+    not_before INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS queue_items_by_item_key
+    ON queue_items (queue_name, item_key);
+CREATE TABLE IF NOT EXISTS dead_letters (
+    queue_name TEXT NOT NULL,
+    id BLOB NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (queue_name, id)
+);
+CREATE TABLE IF NOT EXISTS token_prices (
+    token TEXT PRIMARY KEY NOT NULL,
+    value TEXT NOT NULL,
+    updated_at INTEGER NOT NULL DEFAULT 0
+);
+CREATE TABLE IF NOT EXISTS proposals_queues (
+    queue_key TEXT PRIMARY KEY NOT NULL,
+    state TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS store_meta (
+    key TEXT PRIMARY KEY NOT NULL,
+    value BLOB NOT NULL
+);
+"#;
+
+/// The key, in the `store_meta` table, of the key check of an encrypted store.
+const KEY_CHECK_KEY: &str = "key_check";
+
+/// Returns `true` if the given table has the given column.
+fn has_column(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+) -> crate::Result<bool> {
+    let exists = conn
+        .query_row(
+            "SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2",
+            params![table, column],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some();
+    Ok(exists)
+}
+
+/// Adds the priority and the not-before time to the queue items of a database
+/// created before they were introduced, then indexes them.
+fn upgrade_queue_items(conn: &Connection) -> crate::Result<()> {
+    if !has_column(conn, "queue_items", "priority")? {
+        conn.execute_batch(
+            "ALTER TABLE queue_items ADD COLUMN priority INTEGER NOT NULL DEFAULT 1;",
+        )?;
+    }
+    if !has_column(conn, "queue_items", "not_before")? {
+        conn.execute_batch(
+            "ALTER TABLE queue_items ADD COLUMN not_before INTEGER NOT NULL DEFAULT 0;",
+        )?;
+    }
+    conn.execute_batch(
+        "DROP INDEX IF EXISTS queue_items_by_queue;
+        CREATE INDEX IF NOT EXISTS queue_items_by_priority
+            ON queue_items (queue_name, priority DESC, id);",
+    )?;
+    Ok(())
+}
+
+/// Records when the event hashes and the token prices of a database created before
+/// it was tracked were stored, considering them stored at the time of the upgrade.
+fn upgrade_stored_times(conn: &Connection) -> crate::Result<()> {
+    let now = unix_timestamp() as i64;
+    if !has_column(conn, "event_hashes", "stored_at")? {
+        conn.execute_batch(
+            "ALTER TABLE event_hashes ADD COLUMN stored_at INTEGER NOT NULL DEFAULT 0;",
+        )?;
+        conn.execute("UPDATE event_hashes SET stored_at = ?1", params![now])?;
+    }
+    if !has_column(conn, "token_prices", "updated_at")? {
+        conn.execute_batch(
+            "ALTER TABLE token_prices ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0;",
+        )?;
+        conn.execute("UPDATE token_prices SET updated_at = ?1", params![now])?;
+    }
+    Ok(())
+}
+
+/// Deletes the rows of the given table that match the given condition.
+///
+/// Returns the number of rows deleted, and their size in bytes as computed by the
+/// given SQL expression.
+fn delete_where(
+    conn: &Connection,
+    table: &str,
+    size: &str,
+    condition: &str,
+    params: &[&dyn rusqlite::ToSql],
+) -> crate::Result<(usize, u64)> {
+    let bytes = conn.query_row(
+        &format!(
+            "SELECT COALESCE(SUM({size}), 0) FROM {table} WHERE {condition}"
+        ),
+        params,
+        |row| row.get::<_, i64>(0),
+    )?;
+    let count = conn
+        .execute(&format!("DELETE FROM {table} WHERE {condition}"), params)?;
+    Ok((count, bytes as u64))
+}
+
+/// SqliteStore is a store that stores the history of events in a [SQLite](https://sqlite.org) database.
+///
+/// Unlike the [`SledStore`](crate::SledStore), the on-disk format could be inspected
+/// and queried with any SQLite client.
+#[derive(Clone)]
+pub struct SqliteStore {
+    conn: Arc<Mutex<Connection>>,
+    cipher: Option<StoreCipher>,
+}
+
+impl std::fmt::Debug for SqliteStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SqliteStore").finish()
+    }
+}
+
+impl SqliteStore {
+    /// Opens (or creates) a SqliteStore at the given database file path.
+    pub fn open<P: AsRef<Path>>(path: P) -> crate::Result<Self> {
+        Self::open_with_cipher(path, None)
+    }
+
+    /// Opens (or creates) a SqliteStore, that encrypts the queued items and the
+    /// dead letters with the given cipher.
+    ///
+    /// See [`SledStore::open_with_cipher`](crate::SledStore::open_with_cipher).
+    pub fn open_with_cipher<P: AsRef<Path>>(
+        path: P,
+        cipher: Option<StoreCipher>,
+    ) -> crate::Result<Self> {
+        let conn = Connection::open(path)?;
+        // WAL mode allows readers (e.g. an operator with the sqlite shell)
+        // to query the database while the relayer is writing to it.
+        conn.pragma_update_and_check(None, "journal_mode", &"WAL", |_| Ok(()))?;
+        Self::init(conn, cipher)
+    }
+
+    /// Creates a temporary, in-memory SqliteStore.
+    pub fn temporary() -> crate::Result<Self> {
+        let conn = Connection::open_in_memory()?;
+        Self::init(conn, None)
+    }
+
+    fn init(
+        conn: Connection,
+        cipher: Option<StoreCipher>,
+    ) -> crate::Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        upgrade_queue_items(&conn)?;
+        upgrade_stored_times(&conn)?;
+        let key_check = conn
+            .query_row(
+                "SELECT value FROM store_meta WHERE key = ?1",
+                params![KEY_CHECK_KEY],
+                |row| row.get::<_, Vec<u8>>(0),
+            )
+            .optional()?;
+        crypto::verify_key_check(cipher.as_ref(), key_check.as_deref())?;
+        if let (Some(cipher), None) = (&cipher, key_check) {
+            conn.execute(
+                "INSERT INTO store_meta (key, value) VALUES (?1, ?2)",
+                params![KEY_CHECK_KEY, crypto::key_check(cipher)?],
+            )?;
+        }
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+            cipher,
+        })
+    }
+
+    /// Re-encrypts the queued items and the dead letters with the given cipher,
+    /// or decrypts them if there is none, then uses it from now on.
+    ///
+    /// See [`SledStore::rotate_key`](crate::SledStore::rotate_key).
+    pub fn rotate_key(
+        &mut self,
+        new_cipher: Option<StoreCipher>,
+    ) -> crate::Result<usize> {
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction()?;
+        let mut count = 0;
+        for (table, id, column) in [
+            ("queue_items", "id", "item"),
+            ("dead_letters", "rowid", "value"),
+        ] {
+            let rows = {
+                let mut stmt =
+                    tx.prepare(&format!("SELECT {id}, {column} FROM {table}"))?;
+                let rows = stmt.query_map([], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+                })?;
+                rows.collect::<Result<Vec<_>, _>>()?
+            };
+            for (rowid, value) in rows {
+                let value = crypto::unseal_text(self.cipher.as_ref(), value)?;
+                let value = crypto::seal_text(new_cipher.as_ref(), value)?;
+                tx.execute(
+                    &format!(
+                        "UPDATE {table} SET {column} = ?1 WHERE {id} = ?2"
+                    ),
+                    params![value, rowid],
+                )?;
+                count += 1;
+            }
+        }
+        match &new_cipher {
+            Some(cipher) => tx.execute(
+                "INSERT OR REPLACE INTO store_meta (key, value) VALUES (?1, ?2)",
+                params![KEY_CHECK_KEY, crypto::key_check(cipher)?],
+            )?,
+            None => tx.execute(
+                "DELETE FROM store_meta WHERE key = ?1",
+                params![KEY_CHECK_KEY],
+            )?,
+        };
+        tx.commit()?;
+        self.cipher = new_cipher;
+        Ok(count)
+    }
+
+    /// Encrypts a queued item or a dead letter, if the store is encrypted.
+    fn seal<V: Serialize>(&self, value: &V) -> crate::Result<String> {
+        crypto::seal_text(self.cipher.as_ref(), serde_json::to_string(value)?)
+    }
+
+    /// Decrypts a queued item or a dead letter.
+    fn unseal<V: DeserializeOwned>(&self, value: String) -> crate::Result<V> {
+        let value = crypto::unseal_text(self.cipher.as_ref(), value)?;
+        Ok(serde_json::from_str(&value)?)
+    }
+
+    /// Gets the total amount of data stored on disk
+    pub fn get_data_stored_size(&self) -> u64 {
+        let conn = self.conn.lock();
+        conn.query_row(
+            "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+            [],
+            |row| row.get::<_, i64>(0),
+        )
+        .map(|v| v as u64)
+        .unwrap_or_default()
+    }
+
+    /// Reads a block number from one of the block number tables.
+    fn get_block_number(
+        &self,
+        table: &'static str,
+        key: &HistoryStoreKey,
+    ) -> crate::Result<Option<u64>> {
+        let conn = self.conn.lock();
+        let val = conn
+            .query_row(
+                &format!("SELECT block_number FROM {table} WHERE key = ?1"),
+                params![key.to_bytes()],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()?;
+        Ok(val.map(|v| v as u64))
+    }
+
+    /// Sets a block number in one of the block number tables, and returns the old one.
+    fn set_block_number(
+        &self,
+        table: &'static str,
+        key: &HistoryStoreKey,
+        block_number: u64,
+    ) -> crate::Result<Option<u64>> {
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction()?;
+        let old = tx
+            .query_row(
+                &format!("SELECT block_number FROM {table} WHERE key = ?1"),
+                params![key.to_bytes()],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()?;
+        upsert_block_number(&tx, table, key, block_number)?;
+        tx.commit()?;
+        Ok(old.map(|v| v as u64))
+    }
+
+    /// Lists all the keys of one of the block number tables.
+    fn get_block_number_keys(
+        &self,
+        table: &'static str,
+    ) -> crate::Result<Vec<HistoryStoreKey>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(&format!("SELECT key FROM {table}"))?;
+        let keys = stmt
+            .query_map([], |row| row.get::<_, Vec<u8>>(0))?
+            .collect::<Result<Vec<_>, _>>()?
+            .iter()
+            .filter_map(|bytes| HistoryStoreKey::from_bytes(bytes))
+            .collect();
+        Ok(keys)
+    }
+}
+
+/// Inserts or replaces the block number of the given key in the given table.
+fn upsert_block_number(
+    conn: &Connection,
+    table: &'static str,
+    key: &HistoryStoreKey,
+    block_number: u64,
+) -> crate::Result<()> {
+    conn.execute(
+        &format!(
+            "INSERT INTO {table} (key, block_number) VALUES (?1, ?2) \
+             ON CONFLICT(key) DO UPDATE SET block_number = excluded.block_number"
+        ),
+        params![key.to_bytes(), block_number as i64],
+    )?;
+    Ok(())
+}
+
+/// Inserts or replaces the hash of the last block processed for the given key.
+fn upsert_block_hash(
+    conn: &Connection,
+    key: &HistoryStoreKey,
+    block_hash: types::H256,
+) -> crate::Result<()> {
+    conn.execute(
+        "INSERT INTO last_block_hashes (key, block_hash) VALUES (?1, ?2) \
+         ON CONFLICT(key) DO UPDATE SET block_hash = excluded.block_hash",
+        params![key.to_bytes(), block_hash.as_bytes()],
+    )?;
+    Ok(())
+}
+
+/// Reads a block from a row of the `leaf_blocks` or `encrypted_output_blocks` tables.
+fn block_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<BlockInfo> {
+    let number = row.get::<_, i64>(0)?;
+    let hash = row.get::<_, Vec<u8>>(1)?;
+    Ok(BlockInfo::new(
+        number as u64,
+        types::H256::from_slice(&hash),
+    ))
+}
+
+/// The address of the key, as a hex string, used in the leaves and encrypted outputs tables.
+fn address_of(key: &HistoryStoreKey) -> String {
+    format!("{:?}", key.address())
+}
+
+/// Applies a single write of a batch, inside the transaction of the whole batch.
+fn apply_op(
+    conn: &Connection,
+    op: &BatchOp,
+    cipher: Option<&StoreCipher>,
+) -> crate::Result<()> {
+    match op {
+        BatchOp::SetLastBlockNumber { key, block_number } => {
+            upsert_block_number(
+                conn,
+                "last_block_numbers",
+                key,
+                *block_number,
+            )?;
+        }
+        BatchOp::SetTargetBlockNumber { key, block_number } => {
+            upsert_block_number(
+                conn,
+                "target_block_numbers",
+                key,
+                *block_number,
+            )?;
+        }
+        BatchOp::InsertLeaves {
+            key,
+            leaves,
+            block_number,
+            block_hash,
+        } => {
+            let address = address_of(key);
+            let mut stmt = conn.prepare_cached(
+                "INSERT OR REPLACE INTO leaves (chain_id, address, leaf_index, leaf) \
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for (index, leaf) in leaves {
+                stmt.execute(params![key.chain_id(), address, index, leaf])?;
+            }
+            if block_hash.is_zero() {
+                conn.execute(
+                    "DELETE FROM last_block_hashes WHERE key = ?1",
+                    params![key.to_bytes()],
+                )?;
+            } else {
+                let mut stmt = conn.prepare_cached(
+                    "INSERT OR REPLACE INTO leaf_blocks \
+                     (chain_id, address, leaf_index, block_number, block_hash) \
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                )?;
+                for (index, _) in leaves {
+                    stmt.execute(params![
+                        key.chain_id(),
+                        address,
+                        index,
+                        *block_number as i64,
+                        block_hash.as_bytes()
+                    ])?;
+                }
+                upsert_block_hash(conn, key, *block_hash)?;
+            }
+            // This is last deposit event block number
+            upsert_block_number(
+                conn,
+                "last_deposit_block_number",
+                key,
+                *block_number,
+            )?;
+            // This will be used by event watcher to track the block number has been processed
+            upsert_block_number(
+                conn,
+                "last_block_numbers",
+                key,
+                *block_number,
+            )?;
+        }
+        BatchOp::InsertEncryptedOutputs {
+            key,
+            encrypted_outputs,
+            block_number,
+            block_hash,
+        } => {
+            let address = address_of(key);
+            let mut stmt = conn.prepare_cached(
+                "INSERT OR REPLACE INTO encrypted_outputs \
+                 (chain_id, address, output_index, encrypted_output) \
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for (index, output) in encrypted_outputs {
+                stmt.execute(params![key.chain_id(), address, index, output])?;
+            }
+            if !block_hash.is_zero() {
+                let mut stmt = conn.prepare_cached(
+                    "INSERT OR REPLACE INTO encrypted_output_blocks \
+                     (chain_id, address, output_index, block_number, block_hash) \
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                )?;
+                for (index, _) in encrypted_outputs {
+                    stmt.execute(params![
+                        key.chain_id(),
+                        address,
+                        index,
+                        *block_number as i64,
+                        block_hash.as_bytes()
+                    ])?;
+                }
+            }
+            upsert_block_number(
+                conn,
+                "encrypted_output_last_deposit_block_number",
+                key,
+                *block_number,
+            )?;
+        }
+        BatchOp::StoreEvent { event } => {
+            let hash = ethers::utils::keccak256(event);
+            conn.execute(
+                "INSERT INTO event_hashes (hash, stored_at) VALUES (?1, ?2) \
+                 ON CONFLICT(hash) DO UPDATE SET stored_at = excluded.stored_at",
+                params![&hash[..], unix_timestamp() as i64],
+            )?;
+        }
+        BatchOp::DeleteEvent { event } => {
+            let hash = ethers::utils::keccak256(event);
+            conn.execute(
+                "DELETE FROM event_hashes WHERE hash = ?1",
+                params![&hash[..]],
+            )?;
+        }
+        BatchOp::EnqueueItem { key, item, options } => {
+            let item = crypto::seal_text(cipher, item.clone())?;
+            let item_key = key.item_key().map(|k| k.to_vec());
+            conn.execute(
+                "INSERT INTO queue_items (queue_name, item_key, item, priority, not_before) \
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    key.queue_name(),
+                    item_key,
+                    item,
+                    options.priority as u8,
+                    options.not_before.unwrap_or_default() as i64
+                ],
+            )?;
+            tracing::trace!("enqueue item under key = {}", key);
+        }
+    }
+    Ok(())
+}
+
+impl BatchStore for SqliteStore {
+    #[tracing::instrument(skip_all, fields(ops = batch.len()))]
+    fn apply_batch(&self, batch: StoreBatch) -> crate::Result<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction()?;
+        for op in &batch.ops {
+            apply_op(&tx, op, self.cipher.as_ref())?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+impl HistoryStore for SqliteStore {
+    #[tracing::instrument(skip(self))]
+    fn set_last_block_number<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        block_number: u64,
+    ) -> crate::Result<u64> {
+        let key: HistoryStoreKey = key.into();
+        let old =
+            self.set_block_number("last_block_numbers", &key, block_number)?;
+        Ok(old.unwrap_or(block_number))
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_last_block_number<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        default_block_number: u64,
+    ) -> crate::Result<u64> {
+        let key: HistoryStoreKey = key.into();
+        let val = self.get_block_number("last_block_numbers", &key)?;
+        Ok(val.unwrap_or(default_block_number))
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn set_target_block_number<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        block_number: u64,
+    ) -> crate::Result<u64> {
+        let key: HistoryStoreKey = key.into();
+        let old =
+            self.set_block_number("target_block_numbers", &key, block_number)?;
+        Ok(old.unwrap_or(block_number))
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_target_block_number<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        default_block_number: u64,
+    ) -> crate::Result<u64> {
+        let key: HistoryStoreKey = key.into();
+        let val = self.get_block_number("target_block_numbers", &key)?;
+        Ok(val.unwrap_or(default_block_number))
+    }
+}
+
+impl LeafCacheStore for SqliteStore {
+    type Output = BTreeMap<u32, types::H256>;
+
+    #[tracing::instrument(skip(self))]
+    fn clear_leaves_cache<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<()> {
+        let key: HistoryStoreKey = key.into();
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM leaves WHERE chain_id = ?1 AND address = ?2",
+            params![key.chain_id(), address_of(&key)],
+        )?;
+        tx.execute(
+            "DELETE FROM leaf_blocks WHERE chain_id = ?1 AND address = ?2",
+            params![key.chain_id(), address_of(&key)],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_leaves<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<Self::Output> {
+        self.get_leaves_with_range(key, 0..u32::MAX)
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_leaves_with_range<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        range: core::ops::Range<u32>,
+    ) -> crate::Result<Self::Output> {
+        let key: HistoryStoreKey = key.into();
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            "SELECT leaf_index, leaf FROM leaves \
+             WHERE chain_id = ?1 AND address = ?2 \
+             AND leaf_index >= ?3 AND leaf_index < ?4 \
+             ORDER BY leaf_index",
+        )?;
+        let leaves = stmt
+            .query_map(
+                params![
+                    key.chain_id(),
+                    address_of(&key),
+                    range.start,
+                    range.end
+                ],
+                |row| {
+                    let leaf_index: u32 = row.get(0)?;
+                    let leaf: Vec<u8> = row.get(1)?;
+                    Ok((leaf_index, types::H256::from_slice(&leaf)))
+                },
+            )?
+            .collect::<Result<_, _>>()?;
+        Ok(leaves)
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_last_deposit_block_number<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<u64> {
+        let key: HistoryStoreKey = key.into();
+        let val = self.get_block_number("last_deposit_block_number", &key)?;
+        Ok(val.unwrap_or(0u64))
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn insert_leaves_and_last_deposit_block_number<
+        K: Into<HistoryStoreKey> + Debug,
+    >(
+        &self,
+        key: K,
+        leaves: &[(u32, Vec<u8>)],
+        block_number: u64,
+        block_hash: types::H256,
+    ) -> crate::Result<()> {
+        let mut batch = StoreBatch::new();
+        batch.insert_leaves_and_last_deposit_block_number(
+            key,
+            leaves,
+            block_number,
+            block_hash,
+        );
+        self.apply_batch(batch)
+    }
+
+    fn get_leaves_cache_keys(&self) -> crate::Result<Vec<HistoryStoreKey>> {
+        self.get_block_number_keys("last_deposit_block_number")
+    }
+}
+
+impl EncryptedOutputCacheStore for SqliteStore {
+    type Output = Vec<Vec<u8>>;
+
+    #[tracing::instrument(skip(self))]
+    fn get_encrypted_output<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<Self::Output> {
+        self.get_encrypted_output_with_range(key, 0..u32::MAX)
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_encrypted_output_with_range<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        range: core::ops::Range<u32>,
+    ) -> crate::Result<Self::Output> {
+        let key: HistoryStoreKey = key.into();
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            "SELECT encrypted_output FROM encrypted_outputs \
+             WHERE chain_id = ?1 AND address = ?2 \
+             AND output_index >= ?3 AND output_index < ?4 \
+             ORDER BY output_index",
+        )?;
+        let encrypted_outputs = stmt
+            .query_map(
+                params![
+                    key.chain_id(),
+                    address_of(&key),
+                    range.start,
+                    range.end
+                ],
+                |row| row.get::<_, Vec<u8>>(0),
+            )?
+            .collect::<Result<_, _>>()?;
+        Ok(encrypted_outputs)
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_encrypted_output_with_indices<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<BTreeMap<u32, Vec<u8>>> {
+        let key: HistoryStoreKey = key.into();
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            "SELECT output_index, encrypted_output FROM encrypted_outputs \
+             WHERE chain_id = ?1 AND address = ?2",
+        )?;
+        let encrypted_outputs = stmt
+            .query_map(params![key.chain_id(), address_of(&key)], |row| {
+                Ok((row.get::<_, u32>(0)?, row.get::<_, Vec<u8>>(1)?))
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(encrypted_outputs)
+    }
+
+    fn get_last_deposit_block_number_for_encrypted_output<
+        K: Into<HistoryStoreKey> + Debug,
+    >(
+        &self,
+        key: K,
+    ) -> crate::Result<u64> {
+        let key: HistoryStoreKey = key.into();
+        let val = self.get_block_number(
+            "encrypted_output_last_deposit_block_number",
+            &key,
+        )?;
+        Ok(val.unwrap_or(0u64))
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn insert_encrypted_output_and_last_deposit_block_number<
+        K: Into<HistoryStoreKey> + Debug + Clone,
+    >(
+        &self,
+        key: K,
+        encrypted_output: &[(u32, Vec<u8>)],
+        block_number: u64,
+        block_hash: types::H256,
+    ) -> crate::Result<()> {
+        let mut batch = StoreBatch::new();
+        batch.insert_encrypted_output_and_last_deposit_block_number(
+            key,
+            encrypted_output,
+            block_number,
+            block_hash,
+        );
+        self.apply_batch(batch)
+    }
+
+    fn get_encrypted_output_cache_keys(
+        &self,
+    ) -> crate::Result<Vec<HistoryStoreKey>> {
+        self.get_block_number_keys("encrypted_output_last_deposit_block_number")
+    }
+}
+
+impl ReorgStore for SqliteStore {
+    #[tracing::instrument(skip(self))]
+    fn set_last_block<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        block: BlockInfo,
+    ) -> crate::Result<()> {
+        let key: HistoryStoreKey = key.into();
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction()?;
+        upsert_block_number(&tx, "last_block_numbers", &key, block.number)?;
+        upsert_block_hash(&tx, &key, block.hash)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_last_block_hash<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<Option<types::H256>> {
+        let key: HistoryStoreKey = key.into();
+        let conn = self.conn.lock();
+        let val = conn
+            .query_row(
+                "SELECT block_hash FROM last_block_hashes WHERE key = ?1",
+                params![key.to_bytes()],
+                |row| row.get::<_, Vec<u8>>(0),
+            )
+            .optional()?;
+        Ok(val.map(|v| types::H256::from_slice(&v)))
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_leaf_block<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        leaf_index: u32,
+    ) -> crate::Result<Option<BlockInfo>> {
+        let key: HistoryStoreKey = key.into();
+        let conn = self.conn.lock();
+        let val = conn
+            .query_row(
+                "SELECT block_number, block_hash FROM leaf_blocks \
+                 WHERE chain_id = ?1 AND address = ?2 AND leaf_index = ?3",
+                params![key.chain_id(), address_of(&key), leaf_index],
+                block_from_row,
+            )
+            .optional()?;
+        Ok(val)
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_encrypted_output_block<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        index: u32,
+    ) -> crate::Result<Option<BlockInfo>> {
+        let key: HistoryStoreKey = key.into();
+        let conn = self.conn.lock();
+        let val = conn
+            .query_row(
+                "SELECT block_number, block_hash FROM encrypted_output_blocks \
+                 WHERE chain_id = ?1 AND address = ?2 AND output_index = ?3",
+                params![key.chain_id(), address_of(&key), index],
+                block_from_row,
+            )
+            .optional()?;
+        Ok(val)
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_cached_blocks<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+    ) -> crate::Result<Vec<BlockInfo>> {
+        let key: HistoryStoreKey = key.into();
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            "SELECT block_number, block_hash FROM leaf_blocks \
+             WHERE chain_id = ?1 AND address = ?2 \
+             UNION \
+             SELECT block_number, block_hash FROM encrypted_output_blocks \
+             WHERE chain_id = ?1 AND address = ?2 \
+             ORDER BY block_number DESC, block_hash DESC",
+        )?;
+        let blocks = stmt
+            .query_map(
+                params![key.chain_id(), address_of(&key)],
+                block_from_row,
+            )?
+            .collect::<Result<_, _>>()?;
+        Ok(blocks)
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn rollback_to_block<K: Into<HistoryStoreKey> + Debug>(
+        &self,
+        key: K,
+        block: BlockInfo,
+    ) -> crate::Result<()> {
+        let key: HistoryStoreKey = key.into();
+        let address = address_of(&key);
+        let block_number = block.number as i64;
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM leaves WHERE chain_id = ?1 AND address = ?2 \
+             AND leaf_index IN (SELECT leaf_index FROM leaf_blocks \
+             WHERE chain_id = ?1 AND address = ?2 AND block_number > ?3)",
+            params![key.chain_id(), address, block_number],
+        )?;
+        tx.execute(
+            "DELETE FROM leaf_blocks \
+             WHERE chain_id = ?1 AND address = ?2 AND block_number > ?3",
+            params![key.chain_id(), address, block_number],
+        )?;
+        tx.execute(
+            "DELETE FROM encrypted_outputs WHERE chain_id = ?1 AND address = ?2 \
+             AND output_index IN (SELECT output_index FROM encrypted_output_blocks \
+             WHERE chain_id = ?1 AND address = ?2 AND block_number > ?3)",
+            params![key.chain_id(), address, block_number],
+        )?;
+        tx.execute(
+            "DELETE FROM encrypted_output_blocks \
+             WHERE chain_id = ?1 AND address = ?2 AND block_number > ?3",
+            params![key.chain_id(), address, block_number],
+        )?;
+        // never move the last deposit block numbers forward.
+        for table in [
+            "last_deposit_block_number",
+            "encrypted_output_last_deposit_block_number",
+        ] {
+            tx.execute(
+                &format!(
+                    "UPDATE {table} SET block_number = MIN(block_number, ?2) \
+                     WHERE key = ?1"
+                ),
+                params![key.to_bytes(), block_number],
+            )?;
+        }
+        upsert_block_number(&tx, "last_block_numbers", &key, block.number)?;
+        upsert_block_hash(&tx, &key, block.hash)?;
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+impl EventHashStore for SqliteStore {
+    fn store_event(&self, event: &[u8]) -> crate::Result<()> {
+        let mut batch = StoreBatch::new();
+        batch.store_event(event);
+        self.apply_batch(batch)
+    }
+
+    fn contains_event(&self, event: &[u8]) -> crate::Result<bool> {
+        let hash = ethers::utils::keccak256(event);
+        let conn = self.conn.lock();
+        let exists = conn
+            .query_row(
+                "SELECT 1 FROM event_hashes WHERE hash = ?1",
+                params![&hash[..]],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        Ok(exists)
+    }
+
+    fn delete_event(&self, event: &[u8]) -> crate::Result<()> {
+        let mut batch = StoreBatch::new();
+        batch.delete_event(event);
+        self.apply_batch(batch)
+    }
+}
+
+impl<T> QueueStore<T> for SqliteStore
+where
+    T: Serialize + DeserializeOwned + Clone,
+{
+    type Key = SledQueueKey;
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn enqueue_item_with_options(
+        &self,
+        key: Self::Key,
+        item: T,
+        options: EnqueueOptions,
+    ) -> crate::Result<()> {
+        let mut batch = StoreBatch::new();
+        batch.enqueue_item_with_options(key, &item, options)?;
+        self.apply_batch(batch)
+    }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn dequeue_item_if<F>(
+        &self,
+        key: Self::Key,
+        predicate: F,
+    ) -> crate::Result<Option<DequeuedItem<T>>>
+    where
+        F: FnOnce(&T) -> bool,
+    {
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction()?;
+        let head = tx
+            .query_row(
+                "SELECT id, item, item_key, priority, not_before FROM queue_items \
+                 WHERE queue_name = ?1 AND not_before <= ?2 \
+                 ORDER BY priority DESC, id LIMIT 1",
+                params![key.queue_name(), unix_timestamp() as i64],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, Option<Vec<u8>>>(2)?,
+                        row.get::<_, u8>(3)?,
+                        row.get::<_, i64>(4)?,
+                    ))
+                },
+            )
+            .optional()?;
+        let (id, value, item_key, priority, not_before) = match head {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        let item = self.unseal(value)?;
+        if !predicate(&item) {
+            return Ok(None);
+        }
+        // now it is safe to remove it from the queue.
+        tx.execute("DELETE FROM queue_items WHERE id = ?1", params![id])?;
+        tx.commit()?;
+        Ok(Some(DequeuedItem {
+            item,
+            item_key: item_key.and_then(|k| k.as_slice().try_into().ok()),
+            options: EnqueueOptions {
+                priority: QueuePriority::from_u8(priority).unwrap_or_default(),
+                not_before: (not_before > 0).then_some(not_before as u64),
+            },
+        }))
+    }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn peek_item(&self, key: Self::Key) -> crate::Result<Option<T>> {
+        let conn = self.conn.lock();
+        let value = conn
+            .query_row(
+                "SELECT item FROM queue_items WHERE queue_name = ?1 \
+                 AND not_before <= ?2 ORDER BY priority DESC, id LIMIT 1",
+                params![key.queue_name(), unix_timestamp() as i64],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+        match value {
+            Some(v) => Ok(Some(self.unseal(v)?)),
+            None => Ok(None),
+        }
+    }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn has_item(&self, key: Self::Key) -> crate::Result<bool> {
+        let item_key = match key.item_key() {
+            Some(k) => k,
+            None => return Ok(false),
+        };
+        let conn = self.conn.lock();
+        let exists = conn
+            .query_row(
+                "SELECT 1 FROM queue_items WHERE queue_name = ?1 AND item_key = ?2",
+                params![key.queue_name(), &item_key[..]],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        Ok(exists)
+    }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn remove_item(&self, key: Self::Key) -> crate::Result<Option<T>> {
+        let item_key = match key.item_key() {
+            Some(k) => k,
+            None => return Ok(None),
+        };
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction()?;
+        let value = tx
+            .query_row(
+                "SELECT item FROM queue_items WHERE queue_name = ?1 AND item_key = ?2 \
+                 ORDER BY id DESC LIMIT 1",
+                params![key.queue_name(), &item_key[..]],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+        match value {
+            Some(v) => {
+                tx.execute(
+                    "DELETE FROM queue_items WHERE queue_name = ?1 AND item_key = ?2",
+                    params![key.queue_name(), &item_key[..]],
+                )?;
+                tx.commit()?;
+                let item = self.unseal(v).ok();
+                tracing::trace!("removed item from the queue..");
+                Ok(item)
+            }
+            None => {
+                // not found!
+                tracing::trace!("item with key {} not found in queue", key);
+                Ok(None)
+            }
+        }
+    }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn dead_letter_item_with_options(
+        &self,
+        key: Self::Key,
+        item: T,
+        options: EnqueueOptions,
+        reason: String,
+    ) -> crate::Result<DeadLetter<T>> {
+        let id = DeadLetter::id_of(&item)?;
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction()?;
+        let previous = tx
+            .query_row(
+                "SELECT value FROM dead_letters WHERE queue_name = ?1 AND id = ?2",
+                params![key.queue_name(), id.as_bytes()],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?
+            .map(|v| self.unseal(v))
+            .transpose()?;
+        let dead_letter = DeadLetter::failed(
+            previous,
+            id,
+            key.item_key(),
+            item,
+            options,
+            reason,
+        );
+        tx.execute(
+            "INSERT INTO dead_letters (queue_name, id, value) VALUES (?1, ?2, ?3) \
+             ON CONFLICT (queue_name, id) DO UPDATE SET value = excluded.value",
+            params![
+                key.queue_name(),
+                id.as_bytes(),
+                self.seal(&dead_letter)?
+            ],
+        )?;
+        tx.commit()?;
+        tracing::trace!(
+            attempts = dead_letter.attempts,
+            "moved item to the dead-letter queue"
+        );
+        Ok(dead_letter)
+    }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn get_dead_letters(
+        &self,
+        key: Self::Key,
+    ) -> crate::Result<Vec<DeadLetter<T>>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn
+            .prepare("SELECT value FROM dead_letters WHERE queue_name = ?1")?;
+        let values = stmt
+            .query_map(params![key.queue_name()], |row| {
+                row.get::<_, String>(0)
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut dead_letters = values
+            .into_iter()
+            .map(|v| self.unseal::<DeadLetter<T>>(v))
+            .collect::<crate::Result<Vec<_>>>()?;
+        dead_letters.sort_by_key(|d| d.first_failed_at);
+        Ok(dead_letters)
+    }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn requeue_dead_letter(
+        &self,
+        key: Self::Key,
+        id: types::H256,
+    ) -> crate::Result<bool> {
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction()?;
+        let value = tx
+            .query_row(
+                "SELECT value FROM dead_letters WHERE queue_name = ?1 AND id = ?2",
+                params![key.queue_name(), id.as_bytes()],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+        let dead_letter: DeadLetter<T> = match value {
+            Some(v) => self.unseal(v)?,
+            None => return Ok(false),
+        };
+        // enqueued in the same transaction, so it is either requeued or kept.
+        let queue_key = key.with_item_key(dead_letter.queue_item_key());
+        let mut batch = StoreBatch::new();
+        batch.enqueue_item_with_options(
+            queue_key,
+            &dead_letter.item,
+            dead_letter.requeue_options(),
+        )?;
+        for op in &batch.ops {
+            apply_op(&tx, op, self.cipher.as_ref())?;
+        }
+        tx.execute(
+            "DELETE FROM dead_letters WHERE queue_name = ?1 AND id = ?2",
+            params![key.queue_name(), id.as_bytes()],
+        )?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    #[tracing::instrument(skip_all, fields(key = %key))]
+    fn purge_dead_letters(&self, key: Self::Key) -> crate::Result<usize> {
+        let conn = self.conn.lock();
+        let count = conn.execute(
+            "DELETE FROM dead_letters WHERE queue_name = ?1",
+            params![key.queue_name()],
+        )?;
+        Ok(count)
+    }
+}
+
+impl RetentionStore for SqliteStore {
+    #[tracing::instrument(skip(self))]
+    fn prune(&self, policy: &RetentionPolicy) -> crate::Result<PruneReport> {
+        let now = unix_timestamp();
+        // everything stored before this time has expired.
+        let cutoff = |max_age: u64| now.saturating_sub(max_age) as i64;
+        let mut report = PruneReport::default();
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction()?;
+
+        if let Some(max_age) = policy.event_hashes_max_age {
+            let (count, bytes) = delete_where(
+                &tx,
+                "event_hashes",
+                "length(hash) + 8",
+                "stored_at < ?1",
+                &[&cutoff(max_age)],
+            )?;
+            report.event_hashes += count;
+            report.reclaimed_bytes += bytes;
+        }
+
+        if let Some(max_age) = policy.token_prices_max_age {
+            let (count, bytes) = delete_where(
+                &tx,
+                "token_prices",
+                "length(CAST(token AS BLOB)) + length(CAST(value AS BLOB)) + 8",
+                "updated_at < ?1",
+                &[&cutoff(max_age)],
+            )?;
+            report.token_prices += count;
+            report.reclaimed_bytes += bytes;
+        }
+
+        if let Some(max_age) = policy.dead_letters_max_age {
+            let expired = {
+                let mut stmt = tx.prepare(
+                    "SELECT queue_name, id, value FROM dead_letters",
+                )?;
+                let rows = stmt
+                    .query_map([], |row| {
+                        Ok((
+                            row.get::<_, String>(0)?,
+                            row.get::<_, Vec<u8>>(1)?,
+                            row.get::<_, String>(2)?,
+                        ))
+                    })?
+                    .collect::<Result<Vec<_>, _>>()?;
+                rows.into_iter()
+                    .filter(|(_, _, value)| {
+                        self.unseal::<DeadLetter<serde_json::Value>>(
+                            value.clone(),
+                        )
+                        .map(|d| is_expired(d.last_failed_at, max_age, now))
+                        .unwrap_or(false)
+                    })
+                    .collect::<Vec<_>>()
+            };
+            for (queue_name, id, value) in expired {
+                tx.execute(
+                    "DELETE FROM dead_letters WHERE queue_name = ?1 AND id = ?2",
+                    params![queue_name, id],
+                )?;
+                report.dead_letters += 1;
+                report.reclaimed_bytes +=
+                    (queue_name.len() + id.len() + value.len()) as u64;
+            }
+        }
+
+        if let Some(depth) = policy.block_depth {
+            let last_block_numbers = {
+                let mut stmt = tx.prepare(
+                    "SELECT key, block_number FROM last_block_numbers",
+                )?;
+                let rows = stmt
+                    .query_map([], |row| {
+                        Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, i64>(1)?))
+                    })?
+                    .collect::<Result<Vec<_>, _>>()?;
+                rows
+            };
+            for (key, last_block_number) in last_block_numbers {
+                let key = match HistoryStoreKey::from_bytes(&key) {
+                    Some(key) => key,
+                    None => continue,
+                };
+                for table in ["leaf_blocks", "encrypted_output_blocks"] {
+                    // the index and the encoded block.
+                    let (count, bytes) = delete_where(
+                        &tx,
+                        table,
+                        "4 + 40",
+                        "chain_id = ?1 AND address = ?2 AND block_number + ?3 < ?4",
+                        &[
+                            &key.chain_id(),
+                            &address_of(&key),
+                            &(depth as i64),
+                            &last_block_number,
+                        ],
+                    )?;
+                    report.blocks += count;
+                    report.reclaimed_bytes += bytes;
+                }
+            }
+        }
+        // the queue items are deleted once they are dequeued, nothing is left of them.
+        tx.commit()?;
+        Ok(report)
+    }
+}
+
+impl<T> TokenPriceCacheStore<T> for SqliteStore
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn get_price(&self, token: &str) -> crate::Result<Option<T>> {
+        let conn = self.conn.lock();
+        let value = conn
+            .query_row(
+                "SELECT value FROM token_prices WHERE token = ?1",
+                params![token],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+        match value {
+            Some(v) => Ok(Some(serde_json::from_str(&v)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn insert_price(&self, token: &str, value: T) -> crate::Result<()> {
+        let v = serde_json::to_string(&value)?;
+        let conn = self.conn.lock();
+        conn.execute(
+            "INSERT OR REPLACE INTO token_prices (token, value, updated_at) \
+             VALUES (?1, ?2, ?3)",
+            params![token, v, unix_timestamp() as i64],
+        )?;
+        Ok(())
+    }
+}
+
+impl<T> ProposalsQueueStore<T> for SqliteStore
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn get_proposals_queue(&self, queue_key: &str) -> crate::Result<Option<T>> {
+        let conn = self.conn.lock();
+        let state = conn
+            .query_row(
+                "SELECT state FROM proposals_queues WHERE queue_key = ?1",
+                params![queue_key],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+        match state {
+            Some(v) => Ok(Some(serde_json::from_str(&v)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn save_proposals_queue(
+        &self,
+        queue_key: &str,
+        state: T,
+    ) -> crate::Result<()> {
+        let v = serde_json::to_string(&state)?;
+        let conn = self.conn.lock();
+        conn.execute(
+            "INSERT OR REPLACE INTO proposals_queues (queue_key, state) \
+             VALUES (?1, ?2)",
+            params![queue_key, v],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::QueuePriority;
+    use webb::evm::contract::protocol_solidity::v_anchor_contract::NewNullifierFilter;
+    use webb::evm::ethers::core::types::transaction::eip2718::TypedTransaction;
+    use webb::evm::ethers::types;
+    use webb::evm::ethers::types::transaction::request::TransactionRequest;
+    use webb_proposals::{TargetSystem, TypedChainId};
+
+    fn open_store(tmp: &tempfile::TempDir) -> SqliteStore {
+        SqliteStore::open(tmp.path().join("store.db")).unwrap()
+    }
+
+    #[test]
+    fn get_last_block_number_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = open_store(&tmp);
+        let chain_id = 1u32;
+        let contract =
+            types::H160::from_slice("11111111111111111111".as_bytes());
+        let history_store_key = (
+            TypedChainId::Evm(chain_id),
+            TargetSystem::new_contract_address(contract.to_fixed_bytes()),
+        );
+        assert_eq!(
+            store
+                .get_last_block_number(history_store_key, 1u64)
+                .unwrap(),
+            1u64
+        );
+        store
+            .set_last_block_number(history_store_key, 20u64)
+            .unwrap();
+        let old = store
+            .set_last_block_number(history_store_key, 30u64)
+            .unwrap();
+        assert_eq!(old, 20u64);
+        assert_eq!(
+            store
+                .get_last_block_number(history_store_key, 1u64)
+                .unwrap(),
+            30u64
+        );
+    }
+
+    #[test]
+    fn insert_leaves_and_last_deposit_block_number_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = open_store(&tmp);
+        let chain_id = 1u32;
+        let contract =
+            types::H160::from_slice("11111111111111111111".as_bytes());
+        let history_store_key = (
+            TypedChainId::Evm(chain_id),
+            TargetSystem::new_contract_address(contract.to_fixed_bytes()),
+        );
+        let generated_leaves = (0..300u32)
+            .map(|i| (i, types::H256::random().to_fixed_bytes().to_vec()))
+            .collect::<Vec<_>>();
+        let block_number = 20u64;
+        store
+            .insert_leaves_and_last_deposit_block_number(
+                history_store_key,
+                &generated_leaves,
+                block_number,
+                types::H256::random(),
+            )
+            .unwrap();
+        let leaves = store
+            .get_leaves_with_range(history_store_key, 250..260)
+            .unwrap();
+        assert_eq!(leaves.len(), 10);
+        assert_eq!(
+            leaves
+                .values()
+                .map(|v| v.to_fixed_bytes().to_vec())
+                .collect::<Vec<_>>(),
+            generated_leaves
+                .iter()
+                .skip(250)
+                .take(10)
+                .map(|(_, v)| v.clone())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(store.get_leaves(history_store_key).unwrap().len(), 300);
+        assert_eq!(
+            store
+                .get_last_deposit_block_number(history_store_key)
+                .unwrap(),
+            block_number
+        );
+        assert_eq!(
+            store
+                .get_last_block_number(history_store_key, 1u64)
+                .unwrap(),
+            block_number
+        );
+        store.clear_leaves_cache(history_store_key).unwrap();
+        assert!(store.get_leaves(history_store_key).unwrap().is_empty());
+    }
+
+    #[test]
+    fn tx_queue_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = open_store(&tmp);
+        let chain_id = 1u32;
+        let make_key = |tx: &TypedTransaction| {
+            let mut key = [0u8; 64];
+            key[..32].copy_from_slice(tx.sighash().as_fixed_bytes());
+            SledQueueKey::from_evm_with_custom_key(chain_id, key)
+        };
+        // it is now empty
+        assert_eq!(
+            store
+                .dequeue_item(SledQueueKey::from_evm_chain_id(chain_id))
+                .unwrap(),
+            Option::<TypedTransaction>::None
+        );
+
+        let txs: Vec<TypedTransaction> = (0..3)
+            .map(|_| {
+                TransactionRequest::pay(
+                    types::Address::random(),
+                    types::U256::one(),
+                )
+                .from(types::Address::random())
+                .into()
+            })
+            .collect();
+        for tx in &txs {
+            store.enqueue_item(make_key(tx), tx.clone()).unwrap();
+        }
+
+        // items are dequeued in the same order they were enqueued.
+        assert_eq!(
+            store
+                .dequeue_item(SledQueueKey::from_evm_chain_id(chain_id))
+                .unwrap(),
+            Some(txs[0].clone())
+        );
+        assert_eq!(
+            store
+                .peek_item(SledQueueKey::from_evm_chain_id(chain_id))
+                .unwrap(),
+            Some(txs[1].clone())
+        );
+        assert!(QueueStore::<TypedTransaction>::has_item(
+            &store,
+            make_key(&txs[2])
+        )
+        .unwrap());
+        let removed: Option<TypedTransaction> =
+            store.remove_item(make_key(&txs[2])).unwrap();
+        assert_eq!(removed, Some(txs[2].clone()));
+        assert!(!QueueStore::<TypedTransaction>::has_item(
+            &store,
+            make_key(&txs[2])
+        )
+        .unwrap());
+        assert_eq!(
+            store
+                .dequeue_item(SledQueueKey::from_evm_chain_id(chain_id))
+                .unwrap(),
+            Some(txs[1].clone())
+        );
+        assert_eq!(
+            store
+                .dequeue_item(SledQueueKey::from_evm_chain_id(chain_id))
+                .unwrap(),
+            Option::<TypedTransaction>::None
+        );
+    }
+
+    #[test]
+    fn queue_priority_and_not_before_should_work() {
+        let store = SqliteStore::temporary().unwrap();
+        let queue = SledQueueKey::from_evm_chain_id(1);
+        store.enqueue_item(queue, 1u32).unwrap();
+        store
+            .enqueue_item_with_options(
+                queue,
+                2u32,
+                EnqueueOptions::with_priority(QueuePriority::Low),
+            )
+            .unwrap();
+        store.enqueue_item(queue, 3u32).unwrap();
+        store
+            .enqueue_item_with_options(
+                queue,
+                4u32,
+                EnqueueOptions::with_priority(QueuePriority::High),
+            )
+            .unwrap();
+        // not ready for an hour.
+        store
+            .enqueue_item_with_options(
+                queue,
+                5u32,
+                EnqueueOptions::with_priority(QueuePriority::High)
+                    .not_before(unix_timestamp() + 3600),
+            )
+            .unwrap();
+        // ready already.
+        store
+            .enqueue_item_with_options(
+                queue,
+                6u32,
+                EnqueueOptions::default().not_before(1),
+            )
+            .unwrap();
+        assert_eq!(store.peek_item(queue).unwrap(), Some(4u32));
+        let dequeued =
+            std::iter::from_fn(|| store.dequeue_item(queue).unwrap())
+                .collect::<Vec<u32>>();
+        assert_eq!(dequeued, vec![4, 1, 3, 6, 2]);
+    }
+
+    #[test]
+    fn dequeue_should_return_how_the_item_was_enqueued() {
+        let store = SqliteStore::temporary().unwrap();
+        let queue = SledQueueKey::from_evm_chain_id(1);
+        let keyed = queue.with_item_key(Some([1u8; 64]));
+        store
+            .enqueue_item_with_options(
+                keyed,
+                1u32,
+                EnqueueOptions::with_priority(QueuePriority::High)
+                    .not_before(1),
+            )
+            .unwrap();
+        store.enqueue_item(queue, 2u32).unwrap();
+        let first = store.dequeue_item_with_options(queue).unwrap().unwrap();
+        assert_eq!(first.item, 1u32);
+        assert_eq!(first.item_key, Some([1u8; 64]));
+        assert_eq!(
+            first.options,
+            EnqueueOptions::with_priority(QueuePriority::High).not_before(1)
+        );
+        let second = store.dequeue_item_with_options(queue).unwrap().unwrap();
+        assert_eq!(second.item, 2u32);
+        assert_eq!(second.item_key, None);
+        assert_eq!(second.options, EnqueueOptions::default());
+        assert!(QueueStore::<u32>::dequeue_item(&store, queue)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn dequeue_item_if_should_keep_the_item_in_place() {
+        let store = SqliteStore::temporary().unwrap();
+        let queue = SledQueueKey::from_evm_chain_id(1);
+        for i in 1..=3u32 {
+            store.enqueue_item(queue, i).unwrap();
+        }
+        // the head does not match, so it is left where it was.
+        let dequeued = store.dequeue_item_if(queue, |i: &u32| *i != 1).unwrap();
+        assert!(dequeued.is_none());
+        assert_eq!(store.peek_item(queue).unwrap(), Some(1u32));
+        let dequeued = store.dequeue_item_if(queue, |i: &u32| *i == 1).unwrap();
+        assert_eq!(dequeued.map(|d| d.item), Some(1u32));
+        assert_eq!(store.dequeue_item(queue).unwrap(), Some(2u32));
+        assert_eq!(store.dequeue_item(queue).unwrap(), Some(3u32));
+    }
+
+    #[test]
+    fn requeued_dead_letters_should_keep_their_priority() {
+        let store = SqliteStore::temporary().unwrap();
+        let queue = SledQueueKey::from_evm_chain_id(1);
+        let high = EnqueueOptions::with_priority(QueuePriority::High);
+        store.enqueue_item_with_options(queue, 1u32, high).unwrap();
+        let dequeued: DequeuedItem<u32> =
+            store.dequeue_item_with_options(queue).unwrap().unwrap();
+        let dead_letter = store
+            .dead_letter_item_with_options(
+                queue,
+                dequeued.item,
+                dequeued.options,
+                "reverted".into(),
+            )
+            .unwrap();
+        assert_eq!(dead_letter.priority, QueuePriority::High);
+        store.enqueue_item(queue, 2u32).unwrap();
+        // requeued behind the normal item, but dequeued before it.
+        assert!(QueueStore::<u32>::requeue_dead_letter(
+            &store,
+            queue,
+            dead_letter.id
+        )
+        .unwrap());
+        let requeued: DequeuedItem<u32> =
+            store.dequeue_item_with_options(queue).unwrap().unwrap();
+        assert_eq!(requeued.item, 1u32);
+        assert_eq!(requeued.options, high);
+        assert_eq!(store.dequeue_item(queue).unwrap(), Some(2u32));
+    }
+
+    #[test]
+    fn encrypted_store_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("store.db");
+        let cipher = StoreCipher::new([1u8; 32]);
+        let store =
+            SqliteStore::open_with_cipher(&path, Some(cipher.clone())).unwrap();
+        let queue = SledQueueKey::from_evm_chain_id(1);
+        let secret = String::from("signed transaction");
+        store.enqueue_item(queue, secret.clone()).unwrap();
+        store
+            .dead_letter_item(queue, secret.clone(), "reverted".into())
+            .unwrap();
+        {
+            let conn = store.conn.lock();
+            let mut stmt = conn
+                .prepare(
+                    "SELECT item FROM queue_items UNION ALL SELECT value FROM dead_letters",
+                )
+                .unwrap();
+            let stored = stmt
+                .query_map([], |row| row.get::<_, String>(0))
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            assert_eq!(stored.len(), 2);
+            // nothing is stored in plaintext.
+            assert!(stored.iter().all(|v| !v.contains(&secret)));
+        }
+        assert_eq!(
+            QueueStore::<String>::peek_item(&store, queue).unwrap(),
+            Some(secret.clone())
+        );
+        let dead_letters: Vec<DeadLetter<String>> =
+            store.get_dead_letters(queue).unwrap();
+        assert_eq!(dead_letters[0].item, secret);
+        drop(store);
+
+        assert!(matches!(
+            SqliteStore::open_with_cipher(
+                &path,
+                Some(StoreCipher::new([2u8; 32]))
+            ),
+            Err(webb_relayer_utils::Error::WrongStoreKey)
+        ));
+        assert!(matches!(
+            SqliteStore::open(&path),
+            Err(webb_relayer_utils::Error::StoreKeyMissing)
+        ));
+        SqliteStore::open_with_cipher(&path, Some(cipher)).unwrap();
+    }
+
+    #[test]
+    fn rotate_key_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("store.db");
+        let queue = SledQueueKey::from_evm_chain_id(1);
+        // a store that was not encrypted yet.
+        let store = SqliteStore::open(&path).unwrap();
+        store.enqueue_item(queue, 1u32).unwrap();
+        store
+            .dead_letter_item(queue, 2u32, "reverted".into())
+            .unwrap();
+        drop(store);
+        let old_cipher = StoreCipher::new([1u8; 32]);
+        let mut store =
+            SqliteStore::open_with_cipher(&path, Some(old_cipher)).unwrap();
+        // values stored in plaintext are still readable.
+        assert_eq!(
+            QueueStore::<u32>::peek_item(&store, queue).unwrap(),
+            Some(1u32)
+        );
+        store.enqueue_item(queue, 3u32).unwrap();
+
+        let new_cipher = StoreCipher::new([2u8; 32]);
+        assert_eq!(store.rotate_key(Some(new_cipher.clone())).unwrap(), 3);
+        drop(store);
+        assert!(matches!(
+            SqliteStore::open_with_cipher(
+                &path,
+                Some(StoreCipher::new([1u8; 32]))
+            ),
+            Err(webb_relayer_utils::Error::WrongStoreKey)
+        ));
+        let mut store =
+            SqliteStore::open_with_cipher(&path, Some(new_cipher)).unwrap();
+        assert_eq!(store.dequeue_item(queue).unwrap(), Some(1u32));
+        let dead_letters: Vec<DeadLetter<u32>> =
+            store.get_dead_letters(queue).unwrap();
+        assert_eq!(dead_letters[0].item, 2u32);
+
+        // and back to plaintext.
+        assert_eq!(store.rotate_key(None).unwrap(), 2);
+        drop(store);
+        let store = SqliteStore::open(&path).unwrap();
+        assert_eq!(store.dequeue_item(queue).unwrap(), Some(3u32));
+    }
+
+    #[test]
+    fn prune_should_work() {
+        let store = SqliteStore::temporary().unwrap();
+        let policy = RetentionPolicy {
+            event_hashes_max_age: Some(3600),
+            token_prices_max_age: Some(3600),
+            dead_letters_max_age: Some(3600),
+            block_depth: Some(50),
+            finished_queue_entries: true,
+        };
+        // fresh entries.
+        store.store_event(b"new event").unwrap();
+        store.insert_price("ETH", 1800.5f64).unwrap();
+        let queue = SledQueueKey::from_evm_chain_id(1);
+        store
+            .dead_letter_item(queue, 1u32, "failed".into())
+            .unwrap();
+        // entries from a long time ago.
+        store.store_event(b"old event").unwrap();
+        store.insert_price("DOT", 5.5f64).unwrap();
+        let mut old_dead_letter = store
+            .dead_letter_item(queue, 2u32, "failed".into())
+            .unwrap();
+        old_dead_letter.last_failed_at = 1;
+        {
+            let conn = store.conn.lock();
+            conn.execute(
+                "UPDATE event_hashes SET stored_at = 1 WHERE hash = ?1",
+                params![&ethers::utils::keccak256(b"old event")[..]],
+            )
+            .unwrap();
+            conn.execute(
+                "UPDATE token_prices SET updated_at = 1 WHERE token = 'DOT'",
+                [],
+            )
+            .unwrap();
+            conn.execute(
+                "UPDATE dead_letters SET value = ?1 WHERE id = ?2",
+                params![
+                    serde_json::to_string(&old_dead_letter).unwrap(),
+                    old_dead_letter.id.as_bytes()
+                ],
+            )
+            .unwrap();
+        }
+        // one leaf deep enough to be final, one still recent.
+        let key = HistoryStoreKey::from(1u32);
+        for (i, block_number) in [10u64, 100].into_iter().enumerate() {
+            let leaves = vec![(i as u32, types::H256::random().0.to_vec())];
+            store
+                .insert_leaves_and_last_deposit_block_number(
+                    key,
+                    &leaves,
+                    block_number,
+                    types::H256::random(),
+                )
+                .unwrap();
+        }
+
+        let report = store.prune(&policy).unwrap();
+        assert_eq!(report.event_hashes, 1);
+        assert_eq!(report.token_prices, 1);
+        assert_eq!(report.dead_letters, 1);
+        assert_eq!(report.blocks, 1);
+        assert!(report.reclaimed_bytes > 0);
+
+        assert!(store.contains_event(b"new event").unwrap());
+        assert!(!store.contains_event(b"old event").unwrap());
+        assert_eq!(
+            TokenPriceCacheStore::<f64>::get_price(&store, "ETH").unwrap(),
+            Some(1800.5f64)
+        );
+        assert_eq!(
+            TokenPriceCacheStore::<f64>::get_price(&store, "DOT").unwrap(),
+            None
+        );
+        let dead_letters =
+            QueueStore::<u32>::get_dead_letters(&store, queue).unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].item, 1);
+        assert_eq!(store.get_leaf_block(key, 0).unwrap(), None);
+        assert!(store.get_leaf_block(key, 1).unwrap().is_some());
+        assert_eq!(store.get_leaves(key).unwrap().len(), 2);
+        // nothing left to prune.
+        assert_eq!(store.prune(&policy).unwrap(), PruneReport::default());
+    }
+
+    #[test]
+    fn events_hash_should_work() {
+        let store = SqliteStore::temporary().unwrap();
+
+        let events = (0..20)
+            .map(|_| NewNullifierFilter {
+                nullifier: types::H256::random().to_fixed_bytes().into(),
+            })
+            .collect::<Vec<_>>();
+
+        for event in &events {
+            let event_bytes = serde_json::to_vec(&event).unwrap();
+            assert!(!store.contains_event(&event_bytes).unwrap());
+            store.store_event(&event_bytes).unwrap();
+            // storing the same event twice is a no-op.
+            store.store_event(&event_bytes).unwrap();
+        }
+
+        for event in &events {
+            let event_bytes = serde_json::to_vec(&event).unwrap();
+            assert!(store.contains_event(&event_bytes).unwrap());
+            store.delete_event(&event_bytes).unwrap();
+            assert!(!store.contains_event(&event_bytes).unwrap());
+        }
+    }
+
+    #[test]
+    fn token_prices_should_work() {
+        let store = SqliteStore::temporary().unwrap();
+        assert_eq!(
+            TokenPriceCacheStore::<f64>::get_price(&store, "ETH").unwrap(),
+            None
+        );
+        store.insert_price("ETH", 1800.5f64).unwrap();
+        store.insert_price("ETH", 1900.5f64).unwrap();
+        assert_eq!(
+            TokenPriceCacheStore::<f64>::get_price(&store, "ETH").unwrap(),
+            Some(1900.5f64)
+        );
+    }
+
+    #[test]
+    fn proposals_queues_should_survive_reopening() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = open_store(&tmp);
+        assert_eq!(
+            ProposalsQueueStore::<Vec<u64>>::get_proposals_queue(&store, "q")
+                .unwrap(),
+            None
+        );
+        store.save_proposals_queue("q", vec![1u64, 2]).unwrap();
+        store.save_proposals_queue("q", vec![3u64]).unwrap();
+        drop(store);
+        let store = open_store(&tmp);
+        assert_eq!(
+            ProposalsQueueStore::<Vec<u64>>::get_proposals_queue(&store, "q")
+                .unwrap(),
+            Some(vec![3u64])
+        );
+    }
+
+    #[test]
+    fn batch_should_work() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = open_store(&tmp);
+        let key = HistoryStoreKey::from(1u32);
+        let queue = SledQueueKey::from_evm_chain_id(1);
+        let leaves = (0..2u32)
+            .map(|i| (i, types::H256::random().to_fixed_bytes().to_vec()))
+            .collect::<Vec<_>>();
+        let event = b"some event".to_vec();
+        // a failed batch writes nothing.
+        let result = store.batch(|batch| {
+            batch
+                .insert_leaves_and_last_deposit_block_number(
+                    key,
+                    &leaves,
+                    20,
+                    types::H256::random(),
+                )
+                .store_event(&event)
+                .enqueue_item(queue, &1u32)?;
+            Err::<(), _>(webb_relayer_utils::Error::Generic("failed"))
+        });
+        assert!(result.is_err());
+        assert!(store.get_leaves(key).unwrap().is_empty());
+        assert!(!store.contains_event(&event).unwrap());
+        assert_eq!(store.peek_item(queue).unwrap(), Option::<u32>::None);
+
+        store
+            .batch(|batch| {
+                batch
+                    .insert_leaves_and_last_deposit_block_number(
+                        key,
+                        &leaves,
+                        20,
+                        types::H256::random(),
+                    )
+                    .store_event(&event)
+                    .set_target_block_number(key, 30)
+                    .enqueue_item(queue, &1u32)?;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(store.get_leaves(key).unwrap().len(), 2);
+        assert_eq!(store.get_last_deposit_block_number(key).unwrap(), 20);
+        assert_eq!(store.get_last_block_number(key, 1).unwrap(), 20);
+        assert_eq!(store.get_target_block_number(key, 1).unwrap(), 30);
+        assert!(store.contains_event(&event).unwrap());
+        assert_eq!(store.peek_item(queue).unwrap(), Some(1u32));
+        assert_eq!(store.dequeue_item(queue).unwrap(), Some(1u32));
+    }
+
+    #[test]
+    fn batch_should_be_rolled_back_when_an_operation_fails() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = open_store(&tmp);
+        let key = HistoryStoreKey::from(1u32);
+        let queue = SledQueueKey::from_evm_chain_id(1);
+        let leaves = (0..2u32)
+            .map(|i| (i, types::H256::random().to_fixed_bytes().to_vec()))
+            .collect::<Vec<_>>();
+        let event = b"some event".to_vec();
+        // the last operation fails, inside the transaction.
+        store
+            .conn
+            .lock()
+            .execute_batch(
+                "CREATE TEMP TRIGGER fail_enqueue BEFORE INSERT ON queue_items \
+                 BEGIN SELECT RAISE(ABORT, 'failed'); END;",
+            )
+            .unwrap();
+        let result = store.batch(|batch| {
+            batch
+                .insert_leaves_and_last_deposit_block_number(
+                    key,
+                    &leaves,
+                    20,
+                    types::H256::random(),
+                )
+                .store_event(&event)
+                .set_target_block_number(key, 30)
+                .enqueue_item(queue, &1u32)?;
+            Ok(())
+        });
+        assert!(result.is_err());
+        assert!(store.get_leaves(key).unwrap().is_empty());
+        assert_eq!(store.get_last_deposit_block_number(key).unwrap(), 0);
+        assert_eq!(store.get_last_block_number(key, 1).unwrap(), 1);
+        assert_eq!(store.get_target_block_number(key, 1).unwrap(), 1);
+        assert!(!store.contains_event(&event).unwrap());
+        assert!(QueueStore::<u32>::dequeue_item(&store, queue)
+            .unwrap()
+            .is_none());
+    }
+}
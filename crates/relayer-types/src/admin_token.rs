@@ -0,0 +1,85 @@
+use serde::Deserialize;
+
+use crate::secret_source;
+
+/// AdminToken is the bearer token the operators authenticate with, to use the
+/// admin actions of the relayer API.
+///
+/// Like the other secrets, it could be given as:
+/// * the token itself.
+/// * an env var containing the token in it, `$ADMIN_TOKEN`.
+/// * a file containing the token in it, `file:/path/to/token`.
+/// * a command that prints the token, `> pass show relayer/admin-token`.
+#[derive(Clone)]
+pub struct AdminToken(String);
+
+impl AdminToken {
+    /// Checks the given token against this one, in constant time.
+    pub fn verify(&self, token: &str) -> bool {
+        let (expected, given) = (self.0.as_bytes(), token.as_bytes());
+        expected.len() == given.len()
+            && expected
+                .iter()
+                .zip(given)
+                .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+                == 0
+    }
+}
+
+/// Two tokens are equal if they are the same token, compared in constant time.
+impl PartialEq for AdminToken {
+    fn eq(&self, other: &Self) -> bool {
+        self.verify(&other.0)
+    }
+}
+
+impl Eq for AdminToken {}
+
+impl std::fmt::Debug for AdminToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("AdminToken").finish()
+    }
+}
+
+impl std::str::FromStr for AdminToken {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let token = secret_source::read_secret(value)?
+            .unwrap_or_else(|| value.to_string())
+            .trim()
+            .to_string();
+        if token.is_empty() {
+            return Err(String::from("the admin token cannot be empty"));
+        }
+        Ok(AdminToken(token))
+    }
+}
+
+impl<'de> Deserialize<'de> for AdminToken {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn admin_token_should_only_verify_the_same_token() {
+        let token: AdminToken = "s3cr3t\n".parse().unwrap();
+        assert!(token.verify("s3cr3t"));
+        assert!(!token.verify("s3cr3"));
+        assert!(!token.verify("s3cr3t!"));
+        assert!(!token.verify(""));
+        assert_eq!(token, "s3cr3t".parse().unwrap());
+        assert_ne!(token, "s3cr3".parse().unwrap());
+        assert_eq!(format!("{token:?}"), "AdminToken");
+        assert!("  ".parse::<AdminToken>().is_err());
+    }
+}
@@ -0,0 +1,61 @@
+use std::str::FromStr;
+
+use ethereum_types::H256;
+
+use crate::secret_source;
+
+/// EncryptionKey represents a 256-bit key, used to encrypt data at rest.
+///
+/// Like the [`PrivateKey`](crate::private_key::PrivateKey), it could be given as:
+/// * a hex string, starting with `0x`.
+/// * an env var containing a hex string in it, `$STORE_KEY`.
+/// * a file containing a hex string in it, `file:/path/to/key`.
+/// * a command that prints a hex string, `> pass show relayer/store-key`.
+#[derive(Clone)]
+pub struct EncryptionKey([u8; 32]);
+
+impl EncryptionKey {
+    /// Returns the raw bytes of the key.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+}
+
+impl std::fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("EncryptionKey").finish()
+    }
+}
+
+impl From<[u8; 32]> for EncryptionKey {
+    fn from(key: [u8; 32]) -> Self {
+        EncryptionKey(key)
+    }
+}
+
+/// Parses a `0x` prefixed, 32 bytes, hex string.
+fn parse_hex(value: &str) -> Result<EncryptionKey, String> {
+    let value = value.trim();
+    if !value.starts_with("0x") {
+        return Err(String::from("expected a hex string, starting with 0x"));
+    }
+    H256::from_str(value)
+        .map(|key| EncryptionKey(key.to_fixed_bytes()))
+        .map_err(|e| {
+            format!(
+                "{e}\n expected a 66 chars string (including the 0x prefix) but found {} char",
+                value.len()
+            )
+        })
+}
+
+impl FromStr for EncryptionKey {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match secret_source::read_secret(value)? {
+            Some(val) => parse_hex(&val),
+            None => parse_hex(value),
+        }
+    }
+}
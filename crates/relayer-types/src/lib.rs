@@ -1,5 +1,8 @@
+pub mod admin_token;
+pub mod encryption_key;
 pub mod etherscan_api;
 pub mod mnemonic;
 pub mod private_key;
 pub mod rpc_url;
+pub mod secret_source;
 pub mod suri;
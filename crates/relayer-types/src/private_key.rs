@@ -4,6 +4,8 @@ use ethereum_types::Secret;
 use serde::Deserialize;
 use webb::evm::ethers::signers::{coins_bip39::English, MnemonicBuilder};
 
+use crate::secret_source;
+
 /// PrivateKey represents a private key.
 #[derive(Clone)]
 pub struct PrivateKey(Secret);
@@ -56,52 +58,32 @@ impl<'de> Deserialize<'de> for PrivateKey {
                     match maybe_hex {
                         Ok(val) => Ok(val),
                         Err(e) => Err(serde::de::Error::custom(format!("{e}\n got {} but expected a 66 string (including the 0x prefix)", value.len()))),                    }
-                } else if value.starts_with('$') {
-                    // env
-                    let var = value.strip_prefix('$').unwrap_or(value);
-                    tracing::trace!("Reading {} from env", var);
-                    let val = std::env::var(var).map_err(|e| {
-                        serde::de::Error::custom(format!(
-                            "error while loading this env {var}: {e}",
-                        ))
-                    })?;
-                    let maybe_hex = Secret::from_str(&val);
-                    match maybe_hex {
-                        Ok(val) => Ok(val),
-                        Err(e) => Err(serde::de::Error::custom(format!("{e}\n expected a 66 chars string (including the 0x prefix) but found {} char",  val.len())))
-                    }
-                } else if value.starts_with("file:") {
-                    // Read secrets from the file path
-                    let file_path =
-                        value.strip_prefix("file:").unwrap_or(value);
-                    let val =
-                        std::fs::read_to_string(file_path).map_err(|e| {
+                } else if let Some(source) =
+                    value.strip_prefix(secret_source::KEYSTORE_PREFIX)
+                {
+                    // Decrypt the key of an encrypted keystore
+                    let key = secret_source::read_keystore(source)
+                        .map_err(serde::de::Error::custom)?;
+                    let private_key: [u8; 32] =
+                        key.as_slice().try_into().map_err(|_| {
                             serde::de::Error::custom(format!(
-                                "error while reading file path {file_path} : {e}"
+                                "expected a 32 bytes key in the keystore but found {} bytes",
+                                key.len()
                             ))
                         })?;
-                    if val.starts_with("0x") {
-                        let maybe_hex = Secret::from_str(&val);
-                        match maybe_hex {
-                            Ok(val) => Ok(val),
-                            Err(e) => Err(serde::de::Error::custom(format!("{e}\n expected a 66 chars string (including the 0x prefix) but found {} char",  val.len())))
-                        }
-                    } else {
-                        // if secret file does not start with '0x' and has 12 or 24 words in it
-                        let wallet = MnemonicBuilder::<English>::default()
-                            .phrase(val.as_str())
-                            .build()
-                            .map_err(|e| {
-                                serde::de::Error::custom(format!(
-                                    "{e}\n expected valid mnemonic word list",
-                                ))
-                            })?;
-                        let private_key: [u8; 32] =
-                            wallet.signer().to_bytes().into();
-                        Ok(Secret::from(&private_key))
-                    }
-                } else if value.starts_with('>') {
-                    todo!("Implement command execution to extract the private key")
+                    Ok(Secret::from(&private_key))
+                } else if let Some(source) =
+                    value.strip_prefix(secret_source::VAULT_PREFIX)
+                {
+                    // Fetch the secret from the vault
+                    let val = secret_source::fetch_from_vault(source)
+                        .map_err(serde::de::Error::custom)?;
+                    from_hex_or_mnemonic(&val).map_err(serde::de::Error::custom)
+                } else if let Some(val) = secret_source::read_secret(value)
+                    .map_err(serde::de::Error::custom)?
+                {
+                    // Read the key from an env var, a file or a command
+                    from_hex_or_mnemonic(&val).map_err(serde::de::Error::custom)
                 } else {
                     // if it doesn't contains special characters and has 12 or 24 words in it
                     let wallet = MnemonicBuilder::<English>::default()
@@ -123,3 +105,21 @@ impl<'de> Deserialize<'de> for PrivateKey {
         Ok(Self(secret))
     }
 }
+
+/// Reads a secret that is either a hex string or a mnemonic word list.
+fn from_hex_or_mnemonic(val: &str) -> Result<Secret, String> {
+    let val = val.trim();
+    if val.starts_with("0x") {
+        Secret::from_str(val).map_err(|e| {
+            format!("{e}\n expected a 66 chars string (including the 0x prefix) but found {} char", val.len())
+        })
+    } else {
+        // if the secret does not start with '0x' and has 12 or 24 words in it
+        let wallet = MnemonicBuilder::<English>::default()
+            .phrase(val)
+            .build()
+            .map_err(|e| format!("{e}\n expected valid mnemonic word list"))?;
+        let private_key: [u8; 32] = wallet.signer().to_bytes().into();
+        Ok(Secret::from(&private_key))
+    }
+}
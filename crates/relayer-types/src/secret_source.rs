@@ -0,0 +1,196 @@
+//! Secrets that are not written in the config files, but read from somewhere else
+//! when the config is loaded.
+//!
+//! * `$ENV_VAR` reads the secret from an env var.
+//! * `file:<path>` reads the secret from a file.
+//! * `> <command>` runs the command with `sh`, and reads the secret from its output.
+//! * `keystore:<path>#<password>` reads a [Web3 Secret Storage] (v3 JSON
+//!   keystore) file, and decrypts it with the password, that is given either as
+//!   `$ENV_VAR` or as `file:<path>`.
+//! * `vault:<url>#<field>` fetches the field of a secret from a Vault KV
+//!   backend, both v1 and v2 are supported. The token is read from the
+//!   `VAULT_TOKEN` env var.
+//!
+//! [Web3 Secret Storage]: https://ethereum.org/en/developers/docs/data-structures-and-encoding/web3-secret-storage/
+
+/// The env var the Vault token is read from.
+pub const VAULT_TOKEN_ENV: &str = "VAULT_TOKEN";
+
+/// The prefix of a secret read from an encrypted keystore.
+pub const KEYSTORE_PREFIX: &str = "keystore:";
+
+/// The prefix of a secret fetched from a Vault KV backend.
+pub const VAULT_PREFIX: &str = "vault:";
+
+/// Reads the secret given as `$ENV_VAR`, `file:<path>` or `> <command>`.
+///
+/// Returns `None` if the value is none of them, that is, the secret itself.
+pub fn read_secret(value: &str) -> Result<Option<String>, String> {
+    if let Some(var) = value.strip_prefix('$') {
+        tracing::trace!("Reading {} from env", var);
+        std::env::var(var)
+            .map(Some)
+            .map_err(|e| format!("error while loading this env {var}: {e}"))
+    } else if let Some(path) = value.strip_prefix("file:") {
+        std::fs::read_to_string(path)
+            .map(Some)
+            .map_err(|e| format!("error while reading file path {path} : {e}"))
+    } else if let Some(command) = value.strip_prefix('>') {
+        run_command(command.trim()).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Runs the command with `sh`, and returns what it printed.
+fn run_command(command: &str) -> Result<String, String> {
+    tracing::trace!("Reading the secret from `{}`", command);
+    let output = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .output()
+        .map_err(|e| {
+            format!("error while running the command {command}: {e}")
+        })?;
+    if !output.status.success() {
+        return Err(format!(
+            "the command {command} failed with {}",
+            output.status
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|e| {
+        format!("the command {command} printed an invalid string: {e}")
+    })
+}
+
+/// Reads and decrypts the key of the keystore given as `<path>#<password>`.
+pub fn read_keystore(source: &str) -> Result<Vec<u8>, String> {
+    let (path, password) = source.rsplit_once('#').ok_or_else(|| {
+        format!("expected `<path>#<password>` for the keystore, got {source}")
+    })?;
+    let password = read_password(password)?;
+    tracing::trace!("Decrypting the keystore {}", path);
+    eth_keystore::decrypt_key(path, password)
+        .map_err(|e| format!("error while decrypting the keystore {path}: {e}"))
+}
+
+/// Fetches the secret given as `<url>#<field>` from a Vault KV backend.
+///
+/// The request is blocking, it is made from its own thread so that the config
+/// could also be loaded from within an async runtime.
+pub fn fetch_from_vault(source: &str) -> Result<String, String> {
+    let (url, field) = source.rsplit_once('#').ok_or_else(|| {
+        format!("expected `<url>#<field>` for the vault secret, got {source}")
+    })?;
+    let token = std::env::var(VAULT_TOKEN_ENV).map_err(|e| {
+        format!("error while loading this env {VAULT_TOKEN_ENV}: {e}")
+    })?;
+    tracing::trace!("Fetching the secret {} from {}", field, url);
+    let request = {
+        let url = url.to_owned();
+        move || -> reqwest::Result<serde_json::Value> {
+            reqwest::blocking::Client::new()
+                .get(url)
+                .header("X-Vault-Token", token)
+                .send()?
+                .error_for_status()?
+                .json()
+        }
+    };
+    let response = std::thread::spawn(request)
+        .join()
+        .map_err(|_| format!("the request to {url} panicked"))?
+        .map_err(|e| format!("error while fetching the secret {url}: {e}"))?;
+    secret_field(&response, field)
+        .map(ToOwned::to_owned)
+        .ok_or_else(|| format!("the secret {url} has no field {field}"))
+}
+
+/// Reads the password of a keystore, from an env var or a file.
+fn read_password(password: &str) -> Result<String, String> {
+    if let Some(var) = password.strip_prefix('$') {
+        std::env::var(var)
+            .map_err(|e| format!("error while loading this env {var}: {e}"))
+    } else if let Some(path) = password.strip_prefix("file:") {
+        std::fs::read_to_string(path)
+            .map(|password| password.trim_end().to_owned())
+            .map_err(|e| format!("error while reading file path {path}: {e}"))
+    } else {
+        Err("the keystore password must be given as `$ENV_VAR` or `file:<path>`"
+            .to_string())
+    }
+}
+
+/// Returns the field of the secret in a Vault response, the secret is under
+/// `data.data` in the KV v2 backend and under `data` in the KV v1 backend.
+fn secret_field<'a>(
+    response: &'a serde_json::Value,
+    field: &str,
+) -> Option<&'a str> {
+    let data = &response["data"];
+    data["data"][field]
+        .as_str()
+        .or_else(|| data[field].as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_decrypt_a_keystore() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = [7u8; 32];
+        let name = eth_keystore::encrypt_key(
+            dir.path(),
+            &mut rand::rngs::OsRng,
+            key,
+            "secret",
+            None,
+        )
+        .unwrap();
+        std::env::set_var("WEBB_RELAYER_KEYSTORE_TEST_PASSWORD", "secret");
+        let path = dir.path().join(name);
+        let source =
+            format!("{}#$WEBB_RELAYER_KEYSTORE_TEST_PASSWORD", path.display());
+        assert_eq!(read_keystore(&source).unwrap(), key);
+
+        let source = format!("{}#$SOME_UNSET_PASSWORD_VAR", path.display());
+        assert!(read_keystore(&source).is_err());
+        assert!(read_keystore(&path.display().to_string()).is_err());
+    }
+
+    #[test]
+    fn should_read_secrets_from_env_files_and_commands() {
+        std::env::set_var("WEBB_RELAYER_SECRET_SOURCE_TEST", "from env");
+        assert_eq!(
+            read_secret("$WEBB_RELAYER_SECRET_SOURCE_TEST").unwrap(),
+            Some(String::from("from env"))
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secret");
+        std::fs::write(&path, "from file").unwrap();
+        assert_eq!(
+            read_secret(&format!("file:{}", path.display())).unwrap(),
+            Some(String::from("from file"))
+        );
+        assert_eq!(
+            read_secret("> echo from command").unwrap(),
+            Some(String::from("from command\n"))
+        );
+        assert!(read_secret("> exit 1").is_err());
+        assert!(read_secret("$SOME_UNSET_SECRET_VAR").is_err());
+        assert_eq!(read_secret("0x01").unwrap(), None);
+    }
+
+    #[test]
+    fn should_read_the_secret_field_of_both_kv_versions() {
+        let v1 = serde_json::json!({ "data": { "key": "0x01" } });
+        let v2 = serde_json::json!({
+            "data": { "data": { "key": "0x02" }, "metadata": {} }
+        });
+        assert_eq!(secret_field(&v1, "key"), Some("0x01"));
+        assert_eq!(secret_field(&v2, "key"), Some("0x02"));
+        assert_eq!(secret_field(&v2, "other"), None);
+    }
+}
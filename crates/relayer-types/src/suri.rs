@@ -2,6 +2,8 @@ use serde::Deserialize;
 use sp_core::sr25519::Pair as Sr25519Pair;
 use sp_core::Pair;
 
+use crate::secret_source;
+
 /// [`Substrate Uri`](https://polkadot.js.org/docs/keyring/start/suri/)
 #[derive(Clone)]
 pub struct Suri(pub Sr25519Pair);
@@ -65,6 +67,27 @@ impl<'de> Deserialize<'de> for Suri {
                             Err(serde::de::Error::custom(format!("{e:?}")))
                         }
                     }
+                } else if let Some(source) =
+                    value.strip_prefix(secret_source::KEYSTORE_PREFIX)
+                {
+                    // the keystore holds the seed of the pair
+                    let seed = secret_source::read_keystore(source)
+                        .map_err(serde::de::Error::custom)?;
+                    Sr25519Pair::from_seed_slice(&seed)
+                        .map_err(|e| serde::de::Error::custom(format!("{e:?}")))
+                } else if let Some(source) =
+                    value.strip_prefix(secret_source::VAULT_PREFIX)
+                {
+                    let val = secret_source::fetch_from_vault(source)
+                        .map_err(serde::de::Error::custom)?;
+                    let maybe_pair =
+                        Sr25519Pair::from_string_with_seed(val.trim(), None);
+                    match maybe_pair {
+                        Ok((pair, _)) => Ok(pair),
+                        Err(e) => {
+                            Err(serde::de::Error::custom(format!("{e:?}")))
+                        }
+                    }
                 } else if value.starts_with('>') {
                     todo!("Implement command execution to extract the private key")
                 } else {
@@ -0,0 +1,403 @@
+use std::sync::Arc;
+
+use webb::evm::ethers::providers::{Http, HttpClientError, JsonRpcClient};
+use webb::evm::ethers::signers::{LocalWallet, Signer, WalletError};
+use webb::evm::ethers::types::transaction::eip2718::{
+    TypedTransaction, TypedTransactionError,
+};
+use webb::evm::ethers::types::transaction::eip712::Eip712;
+use webb::evm::ethers::types::{
+    Address, Bytes, RecoveryMessage, Signature, SignatureError, H256,
+};
+use webb::evm::ethers::utils::rlp;
+
+/// The JSON-RPC method used to sign a 32 bytes hash as is, without the
+/// [EIP-191](https://eips.ethereum.org/EIPS/eip-191) prefix of `eth_sign`.
+///
+/// It is not a standard method, neither web3signer nor Clef implement it, so
+/// the signer service must implement it to sign proposals. It takes the
+/// address of the account and the hash, and returns the 65 bytes signature.
+pub const SIGN_HASH_METHOD: &str = "eth_signHash";
+
+/// The JSON-RPC error code of a method the server does not implement.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// An error while signing with an [`EvmSigner`].
+#[derive(Debug, thiserror::Error)]
+pub enum EvmSignerError {
+    /// Error from the local wallet.
+    #[error(transparent)]
+    Wallet(#[from] WalletError),
+    /// Error while calling the remote signer.
+    #[error(transparent)]
+    Rpc(#[from] HttpClientError),
+    /// The remote signer returned an invalid signature.
+    #[error(transparent)]
+    Signature(#[from] SignatureError),
+    /// The remote signer returned an invalid signed transaction.
+    #[error(transparent)]
+    Transaction(#[from] TypedTransactionError),
+    /// The remote signer signed with another account.
+    #[error("The remote signer signed with {found:?} instead of {expected:?}")]
+    WrongSigner {
+        /// The account the remote signer should sign with.
+        expected: Address,
+        /// The account that made the signature.
+        found: Address,
+    },
+    /// The remote signer does not support this kind of signature.
+    #[error("{} is not supported by the remote signer", _0)]
+    Unsupported(&'static str),
+}
+
+/// RemoteSigner is a signer that delegates the signing to an external signer
+/// service over HTTP JSON-RPC, so the key of the account never lives inside
+/// the relayer.
+///
+/// The transactions are signed with `eth_signTransaction`, which web3signer
+/// and Clef implement, but the proposals are signed with [`SIGN_HASH_METHOD`],
+/// which the signer service must implement itself.
+///
+/// Every signature it returns is checked to be made by its account.
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    client: Arc<Http>,
+    address: Address,
+    chain_id: u64,
+}
+
+impl RemoteSigner {
+    /// Creates a signer for the given account of the signer service at `url`.
+    pub fn new(url: url::Url, address: Address) -> Self {
+        Self {
+            client: Arc::new(Http::new(url)),
+            address,
+            chain_id: 1,
+        }
+    }
+
+    /// Signs the given hash as is, without any prefix.
+    pub async fn sign_hash(
+        &self,
+        hash: H256,
+    ) -> Result<Signature, EvmSignerError> {
+        let signature: Bytes = self
+            .client
+            .request(SIGN_HASH_METHOD, (self.address, hash))
+            .await
+            .map_err(|e| match e {
+                HttpClientError::JsonRpcError(ref err)
+                    if err.code == METHOD_NOT_FOUND =>
+                {
+                    EvmSignerError::Unsupported(
+                        "signing a proposal hash with `eth_signHash`",
+                    )
+                }
+                e => e.into(),
+            })?;
+        let signature = Signature::try_from(signature.as_ref())?;
+        self.checked(signature, RecoveryMessage::Hash(hash))
+    }
+
+    /// Checks that the signature of the message is made by the account.
+    fn checked(
+        &self,
+        signature: Signature,
+        message: RecoveryMessage,
+    ) -> Result<Signature, EvmSignerError> {
+        let found = signature.recover(message)?;
+        if found != self.address {
+            return Err(EvmSignerError::WrongSigner {
+                expected: self.address,
+                found,
+            });
+        }
+        Ok(signature)
+    }
+}
+
+/// The response of `eth_signTransaction`, which is either the raw signed
+/// transaction (web3signer) or an object that includes it (Clef).
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum SignedTransaction {
+    Raw(Bytes),
+    Object { raw: Bytes },
+}
+
+#[async_trait::async_trait]
+impl Signer for RemoteSigner {
+    type Error = EvmSignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: S,
+    ) -> Result<Signature, Self::Error> {
+        let message = message.as_ref();
+        let data = Bytes::from(message.to_vec());
+        let signature: Bytes = self
+            .client
+            .request("eth_sign", (self.address, data))
+            .await?;
+        let signature = Signature::try_from(signature.as_ref())?;
+        self.checked(signature, RecoveryMessage::Data(message.to_vec()))
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &TypedTransaction,
+    ) -> Result<Signature, Self::Error> {
+        let mut tx = tx.clone();
+        tx.set_from(self.address);
+        if tx.chain_id().is_none() {
+            tx.set_chain_id(self.chain_id);
+        }
+        let signed: SignedTransaction =
+            self.client.request("eth_signTransaction", [&tx]).await?;
+        let raw = match signed {
+            SignedTransaction::Raw(raw) => raw,
+            SignedTransaction::Object { raw } => raw,
+        };
+        let (_, signature) =
+            TypedTransaction::decode_signed(&rlp::Rlp::new(raw.as_ref()))?;
+        // the signature must be of this very transaction, since it is sent
+        // along with it and not with the one the signer decoded.
+        self.checked(signature, RecoveryMessage::Hash(tx.sighash()))
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        _payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        Err(EvmSignerError::Unsupported("signing typed data"))
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        Self {
+            chain_id: chain_id.into(),
+            ..self
+        }
+    }
+}
+
+/// EvmSigner signs the transactions and proposals of an EVM account, either
+/// with its private key, or with a remote signer that holds it.
+#[derive(Debug, Clone)]
+pub enum EvmSigner {
+    /// A wallet with the private key in memory.
+    Local(LocalWallet),
+    /// An external signer service.
+    Remote(RemoteSigner),
+}
+
+impl EvmSigner {
+    /// Signs the given hash as is, without any prefix.
+    pub async fn sign_hash(
+        &self,
+        hash: H256,
+    ) -> Result<Signature, EvmSignerError> {
+        match self {
+            Self::Local(wallet) => Ok(wallet.sign_hash(hash)?),
+            Self::Remote(signer) => signer.sign_hash(hash).await,
+        }
+    }
+}
+
+impl From<LocalWallet> for EvmSigner {
+    fn from(wallet: LocalWallet) -> Self {
+        Self::Local(wallet)
+    }
+}
+
+impl From<RemoteSigner> for EvmSigner {
+    fn from(signer: RemoteSigner) -> Self {
+        Self::Remote(signer)
+    }
+}
+
+#[async_trait::async_trait]
+impl Signer for EvmSigner {
+    type Error = EvmSignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: S,
+    ) -> Result<Signature, Self::Error> {
+        match self {
+            Self::Local(wallet) => Ok(wallet.sign_message(message).await?),
+            Self::Remote(signer) => signer.sign_message(message).await,
+        }
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &TypedTransaction,
+    ) -> Result<Signature, Self::Error> {
+        match self {
+            Self::Local(wallet) => Ok(wallet.sign_transaction(tx).await?),
+            Self::Remote(signer) => signer.sign_transaction(tx).await,
+        }
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        match self {
+            Self::Local(wallet) => Ok(wallet.sign_typed_data(payload).await?),
+            Self::Remote(signer) => signer.sign_typed_data(payload).await,
+        }
+    }
+
+    fn address(&self) -> Address {
+        match self {
+            Self::Local(wallet) => wallet.address(),
+            Self::Remote(signer) => signer.address(),
+        }
+    }
+
+    fn chain_id(&self) -> u64 {
+        match self {
+            Self::Local(wallet) => wallet.chain_id(),
+            Self::Remote(signer) => signer.chain_id(),
+        }
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        match self {
+            Self::Local(wallet) => Self::Local(wallet.with_chain_id(chain_id)),
+            Self::Remote(signer) => {
+                Self::Remote(signer.with_chain_id(chain_id))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::State;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde_json::{json, Value};
+    use webb::evm::ethers::core::rand::thread_rng;
+    use webb::evm::ethers::types::TransactionRequest;
+
+    /// A signer service that signs with the given wallet.
+    async fn mock_signer(wallet: LocalWallet) -> url::Url {
+        async fn handle(
+            State(wallet): State<LocalWallet>,
+            Json(request): Json<Value>,
+        ) -> Json<Value> {
+            let params = &request["params"];
+            let result = match request["method"].as_str().unwrap() {
+                "eth_signTransaction" => {
+                    let tx: TypedTransaction =
+                        serde_json::from_value(params[0].clone()).unwrap();
+                    let signature = wallet.sign_transaction(&tx).await.unwrap();
+                    json!(tx.rlp_signed(&signature))
+                }
+                "eth_sign" => {
+                    let data: Bytes =
+                        serde_json::from_value(params[1].clone()).unwrap();
+                    let signature = wallet.sign_message(data).await.unwrap();
+                    json!(Bytes::from(signature.to_vec()))
+                }
+                SIGN_HASH_METHOD => {
+                    let hash: H256 =
+                        serde_json::from_value(params[1].clone()).unwrap();
+                    let signature = wallet.sign_hash(hash).unwrap();
+                    json!(Bytes::from(signature.to_vec()))
+                }
+                method => panic!("unexpected method {method}"),
+            };
+            Json(json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "result": result,
+            }))
+        }
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route("/", post(handle)).with_state(wallet);
+        let server = axum::Server::from_tcp(listener).unwrap();
+        tokio::spawn(server.serve(app.into_make_service()));
+        format!("http://{addr}").parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn remote_signer_should_sign_like_the_local_wallet() {
+        let wallet = LocalWallet::new(&mut thread_rng());
+        let url = mock_signer(wallet.clone()).await;
+        let signer = EvmSigner::from(
+            RemoteSigner::new(url, wallet.address()).with_chain_id(1337u64),
+        );
+        let local = EvmSigner::from(wallet.with_chain_id(1337u64));
+
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(Address::random())
+            .value(42)
+            .nonce(7)
+            .gas(21_000)
+            .gas_price(1)
+            .into();
+        assert_eq!(
+            signer.sign_transaction(&tx).await.unwrap(),
+            local.sign_transaction(&tx).await.unwrap()
+        );
+        assert_eq!(
+            signer.sign_message("proposal").await.unwrap(),
+            local.sign_message("proposal").await.unwrap()
+        );
+        let hash = H256::random();
+        assert_eq!(
+            signer.sign_hash(hash).await.unwrap(),
+            local.sign_hash(hash).await.unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn remote_signer_should_reject_another_account() {
+        let wallet = LocalWallet::new(&mut thread_rng());
+        let url = mock_signer(wallet).await;
+        let signer = RemoteSigner::new(url, Address::random());
+        assert!(matches!(
+            signer.sign_hash(H256::random()).await,
+            Err(EvmSignerError::WrongSigner { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn remote_signer_should_report_a_missing_sign_hash_method() {
+        // a signer service like web3signer or Clef, without `eth_signHash`.
+        async fn handle(Json(request): Json<Value>) -> Json<Value> {
+            Json(json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "error": {
+                    "code": METHOD_NOT_FOUND,
+                    "message": "Method not found",
+                },
+            }))
+        }
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route("/", post(handle));
+        let server = axum::Server::from_tcp(listener).unwrap();
+        tokio::spawn(server.serve(app.into_make_service()));
+        let url = format!("http://{addr}").parse().unwrap();
+        let signer = RemoteSigner::new(url, Address::random());
+        assert!(matches!(
+            signer.sign_hash(H256::random()).await,
+            Err(EvmSignerError::Unsupported(_))
+        ));
+    }
+}
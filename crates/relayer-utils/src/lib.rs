@@ -22,6 +22,9 @@ use webb_proposals::ResourceId;
 
 pub mod clickable_link;
 
+/// Signers of the EVM accounts, local or remote.
+pub mod evm_signer;
+
 /// Metrics functionality
 pub mod metric;
 /// Multi provider for ethers.
@@ -32,6 +35,10 @@ pub mod probe;
 pub mod retry;
 /// type-erased StaticTxPayload for Substrate Transaction queue.
 pub mod static_tx_payload;
+/// Failover between the endpoints of a Substrate node.
+pub mod substrate_endpoints;
+/// Pools of EVM accounts, with their nonces managed locally.
+pub mod wallet_pool;
 
 type RetryClientProvider = ethers::providers::Provider<
     ethers::providers::RetryClient<MultiProvider<ethers::providers::Http>>,
@@ -153,6 +160,10 @@ pub enum Error {
     SledTransaction(
         #[from] sled::transaction::TransactionError<std::io::Error>,
     ),
+    /// SQLite database error.
+    #[cfg(feature = "sqlite")]
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
     /// Reqwest error
     #[error(transparent)]
     Reqwest(#[from] reqwest::Error),
@@ -164,6 +175,9 @@ pub enum Error {
     /// Ether wallet errors.
     #[error(transparent)]
     EtherWalletError(#[from] ethers::signers::WalletError),
+    /// EVM signer errors, local or remote.
+    #[error(transparent)]
+    EvmSigner(#[from] evm_signer::EvmSignerError),
     /// Ethers currency conversion error
     #[error(transparent)]
     Conversion(#[from] ethers::utils::ConversionError),
@@ -196,6 +210,9 @@ pub enum Error {
         /// The chain id of the node.
         chain_id: String,
     },
+    /// The config has problems, found while validating it.
+    #[error("Invalid config:\n{}", _0.join("\n"))]
+    InvalidConfig(Vec<String>),
     /// Missing Secrets in the config, either Private key, SURI, ...etc.
     #[error("Missing required private-key or SURI in the config")]
     MissingSecrets,
@@ -239,6 +256,69 @@ pub enum Error {
     /// Provider not found error.
     #[error("Provider not found for index {0}")]
     ProviderNotFound(usize),
+    /// The store was created by a newer version of the relayer.
+    #[error(
+        "Unsupported store schema version {found}, the latest supported is {supported}"
+    )]
+    UnsupportedStoreVersion {
+        /// The schema version found in the store.
+        found: u64,
+        /// The latest schema version supported by this relayer.
+        supported: u64,
+    },
+    /// The checksum of a store snapshot does not match its content.
+    #[error("Snapshot checksum mismatch, expected {expected} but got {found}")]
+    SnapshotChecksumMismatch {
+        /// The checksum recorded in the snapshot.
+        expected: String,
+        /// The checksum of the snapshot content.
+        found: String,
+    },
+    /// The store snapshot was created by a newer version of the relayer.
+    #[error("Unsupported snapshot version {0}")]
+    UnsupportedSnapshotVersion(u32),
+    /// A snapshot could only be imported into a store without cached data.
+    #[error("Cannot import the snapshot, the store already has data for {0}")]
+    StoreNotEmpty(String),
+    /// The store is encrypted, but it was opened without an encryption key.
+    #[error("The store is encrypted, but no encryption key was given")]
+    StoreKeyMissing,
+    /// The store could not be decrypted with the given encryption key.
+    #[error("Wrong store encryption key")]
+    WrongStoreKey,
+    /// Fewer signers than the threshold of the signing backend signed a proposal.
+    #[error(
+        "Only {signed} signers signed the proposal, {threshold} are required"
+    )]
+    ThresholdNotReached {
+        /// How many signers signed the proposal.
+        signed: usize,
+        /// How many signers are required.
+        threshold: usize,
+    },
+    /// The threshold of the signing backend could never be met by its signers.
+    #[error(
+        "The threshold {threshold} must be between 1 and the {signers} signers"
+    )]
+    InvalidThreshold {
+        /// How many signers are required.
+        threshold: usize,
+        /// How many signers are configured.
+        signers: usize,
+    },
+    /// The signature bridge of a chain could not verify the concatenated
+    /// signatures of the threshold signing backend.
+    #[error(
+        "The signature bridge on {:?} cannot verify threshold signatures: {}",
+        typed_chain_id,
+        reason
+    )]
+    ThresholdSignaturesNotSupported {
+        /// The chain id of the signature bridge.
+        typed_chain_id: webb_proposals::TypedChainId,
+        /// Why the signatures could not be verified.
+        reason: String,
+    },
 }
 
 /// A type alias for the result for webb relayer, that uses the `Error` enum.
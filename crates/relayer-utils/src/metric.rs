@@ -58,6 +58,8 @@ pub struct Metrics {
     pub gas_spent: GenericCounter<AtomicF64>,
     /// Total amount of data stored metric
     pub total_amount_of_data_stored: GenericGauge<AtomicF64>,
+    /// Total amount of data reclaimed by pruning the store metric
+    pub total_amount_of_data_reclaimed: GenericCounter<AtomicF64>,
     /// Resource metric
     resource_metric_map: HashMap<ResourceId, ResourceMetric>,
     /// Metric for account balance (in gwei) on specific chain
@@ -131,6 +133,11 @@ impl Metrics {
             "The Total number of data stored",
         )?;
 
+        let total_amount_of_data_reclaimed = register_counter!(
+            "total_amount_of_data_reclaimed",
+            "The total number of bytes reclaimed by pruning the store",
+        )?;
+
         Ok(Self {
             bridge_watcher_back_off,
             total_transaction_made,
@@ -145,6 +152,7 @@ impl Metrics {
             total_fee_earned,
             gas_spent,
             total_amount_of_data_stored,
+            total_amount_of_data_reclaimed,
             resource_metric_map: Default::default(),
             account_balance: Default::default(),
         })
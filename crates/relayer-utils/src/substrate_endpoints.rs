@@ -0,0 +1,118 @@
+use crate::Error as WebbRelayerError;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use std::time::Duration;
+use webb::substrate::subxt::{self, OnlineClient};
+
+/// How long connecting to an endpoint and checking its health could take.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// SubstrateEndpoints are the websocket endpoints of a Substrate node, that are
+/// connected to in turn until a healthy one is found.
+///
+/// The search starts from the endpoint that was healthy last, so the clients keep
+/// using the same endpoint until it is lost, and then fail over to the next one.
+#[derive(Debug, Clone)]
+pub struct SubstrateEndpoints {
+    urls: Arc<Vec<String>>,
+    last_healthy: Arc<AtomicUsize>,
+}
+
+impl SubstrateEndpoints {
+    /// Creates the endpoints from their urls, the first one is tried first.
+    pub fn new(urls: Vec<String>) -> Self {
+        Self {
+            urls: Arc::new(urls),
+            last_healthy: Default::default(),
+        }
+    }
+
+    /// Connects to the first healthy endpoint.
+    ///
+    /// An endpoint is healthy if its node answers the health check and is not
+    /// syncing. A syncing node is only used if no endpoint is healthy.
+    pub async fn connect<C: subxt::Config>(
+        &self,
+    ) -> crate::Result<OnlineClient<C>> {
+        let count = self.urls.len();
+        let start = self.last_healthy.load(Ordering::SeqCst);
+        let mut syncing = None;
+        let mut last_error = WebbRelayerError::ProviderNotFound(start);
+        for i in (0..count).map(|i| (start + i) % count) {
+            let url = &self.urls[i];
+            match connect_to::<C>(url).await {
+                Ok((client, false)) => {
+                    if i != start {
+                        tracing::warn!(
+                            "Failed over to the substrate endpoint {}",
+                            url
+                        );
+                    }
+                    self.last_healthy.store(i, Ordering::SeqCst);
+                    return Ok(client);
+                }
+                Ok((client, true)) => {
+                    tracing::warn!("The substrate node at {} is syncing", url);
+                    syncing.get_or_insert((i, client));
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "The substrate endpoint {} is unhealthy: {}",
+                        url,
+                        e
+                    );
+                    last_error = e;
+                }
+            }
+        }
+        match syncing {
+            Some((i, client)) => {
+                self.last_healthy.store(i, Ordering::SeqCst);
+                Ok(client)
+            }
+            None => Err(last_error),
+        }
+    }
+}
+
+/// Connects to the given endpoint, and returns the client with whether its node
+/// is syncing.
+async fn connect_to<C: subxt::Config>(
+    url: &str,
+) -> crate::Result<(OnlineClient<C>, bool)> {
+    let connect = async {
+        let client = OnlineClient::<C>::from_url(url).await?;
+        let health = client.rpc().system_health().await?;
+        crate::Result::Ok((client, health.is_syncing))
+    };
+    tokio::time::timeout(CONNECT_TIMEOUT, connect)
+        .await
+        .map_err(|_| {
+            WebbRelayerError::Generic("timed out connecting to the node")
+        })?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use webb::substrate::subxt::PolkadotConfig;
+
+    #[tokio::test]
+    async fn should_fail_without_a_healthy_endpoint() {
+        let endpoints = SubstrateEndpoints::new(vec![
+            "ws://127.0.0.1:1".to_string(),
+            "ws://127.0.0.1:2".to_string(),
+        ]);
+        let result = endpoints.connect::<PolkadotConfig>().await;
+        assert!(result.is_err());
+        assert_eq!(endpoints.last_healthy.load(Ordering::SeqCst), 0);
+
+        let endpoints = SubstrateEndpoints::new(vec![]);
+        assert!(matches!(
+            endpoints.connect::<PolkadotConfig>().await,
+            Err(WebbRelayerError::ProviderNotFound(0))
+        ));
+    }
+}
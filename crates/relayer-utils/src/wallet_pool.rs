@@ -0,0 +1,276 @@
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use webb::evm::ethers::providers::Middleware;
+use webb::evm::ethers::signers::Signer;
+use webb::evm::ethers::types::{Address, BlockNumber, U256};
+
+use crate::evm_signer::EvmSigner;
+
+/// NonceManager hands out the nonces of an account locally, so the concurrent
+/// transactions of the account never share a nonce.
+///
+/// It is synced with the chain on first use, from the pending transactions of
+/// the account, so the transactions still in the mempool are not replaced. A
+/// nonce that was reserved but never sent, or whose transaction was dropped, is
+/// handed out again before any new one, so the account does not stall on a gap.
+#[derive(Debug, Clone)]
+pub struct NonceManager {
+    address: Address,
+    state: Arc<Mutex<NonceState>>,
+}
+
+#[derive(Debug, Default)]
+struct NonceState {
+    /// The next nonce that was never handed out, unknown until synced.
+    next: Option<U256>,
+    /// The nonces that were handed out but not used.
+    released: BTreeSet<U256>,
+}
+
+impl NonceManager {
+    /// Creates the nonce manager of the given account, synced on first use.
+    pub fn new(address: Address) -> Self {
+        Self {
+            address,
+            state: Default::default(),
+        }
+    }
+
+    /// Reserves the next nonce of the account.
+    pub async fn reserve<M>(&self, client: &M) -> crate::Result<U256>
+    where
+        M: Middleware,
+        crate::Error: From<M::Error>,
+    {
+        let synced = self.state.lock().unwrap().next.is_some();
+        let pending = if synced {
+            None
+        } else {
+            Some(self.transaction_count(client, BlockNumber::Pending).await?)
+        };
+        // the nonces below the mined ones are used, even if not by us.
+        let mined = self.transaction_count(client, BlockNumber::Latest).await?;
+        let mut state = self.state.lock().unwrap();
+        let next = state.next.get_or_insert(pending.unwrap_or(mined));
+        if *next < mined {
+            *next = mined;
+        }
+        let next = *next;
+        state.released = state.released.split_off(&mined);
+        let nonce = match state.released.pop_first() {
+            Some(nonce) => nonce,
+            None => {
+                state.next = Some(next + 1);
+                next
+            }
+        };
+        Ok(nonce)
+    }
+
+    /// Gives back a nonce that was not used, to be reserved again.
+    pub fn release(&self, nonce: U256) {
+        tracing::trace!(address = ?self.address, %nonce, "Nonce released");
+        self.state.lock().unwrap().released.insert(nonce);
+    }
+
+    async fn transaction_count<M>(
+        &self,
+        client: &M,
+        block: BlockNumber,
+    ) -> crate::Result<U256>
+    where
+        M: Middleware,
+        crate::Error: From<M::Error>,
+    {
+        let count = client
+            .get_transaction_count(self.address, Some(block.into()))
+            .await?;
+        Ok(count)
+    }
+}
+
+/// A nonce reserved for a transaction of an account of a [`WalletPool`].
+///
+/// The nonce is released when this is dropped, unless the transaction was sent.
+#[derive(Debug)]
+pub struct NonceReservation {
+    signer: EvmSigner,
+    nonces: NonceManager,
+    nonce: U256,
+    sent: bool,
+}
+
+impl NonceReservation {
+    /// The signer of the account to send the transaction from.
+    pub fn signer(&self) -> &EvmSigner {
+        &self.signer
+    }
+
+    /// The nonce to send the transaction with.
+    pub fn nonce(&self) -> U256 {
+        self.nonce
+    }
+
+    /// Marks the transaction as sent, which keeps the nonce used.
+    pub fn mark_sent(&mut self) {
+        self.sent = true;
+    }
+
+    /// Releases the nonce, even if its transaction was sent, because it was
+    /// dropped from the mempool.
+    pub fn release(mut self) {
+        self.sent = false;
+    }
+}
+
+impl Drop for NonceReservation {
+    fn drop(&mut self) {
+        if !self.sent {
+            self.nonces.release(self.nonce);
+        }
+    }
+}
+
+/// WalletPool is the pool of the accounts of the relayer on an EVM chain, that
+/// the transactions are spread across, round-robin.
+///
+/// The first account is the primary one, that receives the relaying fees.
+#[derive(Debug, Clone)]
+pub struct WalletPool {
+    accounts: Arc<Vec<(EvmSigner, NonceManager)>>,
+    last_used: Arc<AtomicUsize>,
+}
+
+impl WalletPool {
+    /// Creates a pool of the given accounts, which must not be empty.
+    pub fn new(signers: Vec<EvmSigner>) -> Self {
+        assert!(!signers.is_empty(), "a wallet pool needs an account");
+        let accounts = signers
+            .into_iter()
+            .map(|signer| {
+                let nonces = NonceManager::new(signer.address());
+                (signer, nonces)
+            })
+            .collect();
+        Self {
+            accounts: Arc::new(accounts),
+            last_used: Default::default(),
+        }
+    }
+
+    /// Shares the nonces of the accounts that are also in the `previous` pool,
+    /// so both pools could be used at once, like while the config is reloaded.
+    pub fn sharing_nonces_with(self, previous: &WalletPool) -> Self {
+        let accounts = self
+            .accounts
+            .iter()
+            .map(|(signer, nonces)| {
+                let shared = previous
+                    .accounts
+                    .iter()
+                    .find(|(other, _)| other.address() == signer.address())
+                    .map(|(_, nonces)| nonces);
+                (signer.clone(), shared.unwrap_or(nonces).clone())
+            })
+            .collect();
+        Self {
+            accounts: Arc::new(accounts),
+            ..self
+        }
+    }
+
+    /// The number of accounts in the pool.
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    /// Returns `true` if the pool has no accounts, which never happens.
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// The primary account of the pool.
+    pub fn primary(&self) -> &EvmSigner {
+        &self.accounts[0].0
+    }
+
+    /// Reserves a nonce of the next account of the pool.
+    pub async fn reserve<M>(
+        &self,
+        client: &M,
+    ) -> crate::Result<NonceReservation>
+    where
+        M: Middleware,
+        crate::Error: From<M::Error>,
+    {
+        let i = self
+            .last_used
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last_used| {
+                Some(last_used.saturating_add(1) % self.accounts.len())
+            })
+            .unwrap_or_default();
+        let (signer, nonces) = &self.accounts[i];
+        let nonce = nonces.reserve(client).await?;
+        Ok(NonceReservation {
+            signer: signer.clone(),
+            nonces: nonces.clone(),
+            nonce,
+            sent: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use webb::evm::ethers::core::rand::thread_rng;
+    use webb::evm::ethers::providers::Provider;
+    use webb::evm::ethers::signers::LocalWallet;
+
+    #[tokio::test]
+    async fn nonces_should_be_reserved_locally_and_reused() {
+        let (provider, mock) = Provider::mocked();
+        let nonces = NonceManager::new(Address::random());
+        // the responses are popped in reverse: pending, then latest.
+        let push = |count: u64| mock.push(U256::from(count)).unwrap();
+        push(5);
+        push(7);
+        assert_eq!(nonces.reserve(&provider).await.unwrap(), 7.into());
+        push(5);
+        assert_eq!(nonces.reserve(&provider).await.unwrap(), 8.into());
+        // a released nonce is handed out again first.
+        nonces.release(7.into());
+        push(5);
+        assert_eq!(nonces.reserve(&provider).await.unwrap(), 7.into());
+        // unless it was mined meanwhile.
+        nonces.release(8.into());
+        push(10);
+        assert_eq!(nonces.reserve(&provider).await.unwrap(), 10.into());
+    }
+
+    #[tokio::test]
+    async fn pool_should_spread_the_transactions() {
+        let (provider, mock) = Provider::mocked();
+        let pool = WalletPool::new(vec![
+            LocalWallet::new(&mut thread_rng()).into(),
+            LocalWallet::new(&mut thread_rng()).into(),
+        ]);
+        let mut used = Vec::new();
+        for i in 0..4 {
+            mock.push(U256::zero()).unwrap();
+            // each account is synced from its pending txs on first use only.
+            if i < 2 {
+                mock.push(U256::zero()).unwrap();
+            }
+            let mut reservation = pool.reserve(&provider).await.unwrap();
+            reservation.mark_sent();
+            used.push((reservation.signer().address(), reservation.nonce()));
+        }
+        assert_ne!(used[0].0, used[1].0);
+        assert_eq!(used[0].0, used[2].0);
+        assert_eq!(used[2].1, used[0].1 + 1);
+        assert_eq!(used[3].1, used[1].1 + 1);
+    }
+}
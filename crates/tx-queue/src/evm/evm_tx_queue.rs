@@ -23,11 +23,14 @@ use webb::evm::ethers::middleware::SignerMiddleware;
 use webb::evm::ethers::prelude::TimeLag;
 use webb::evm::ethers::providers::Middleware;
 
+use webb::evm::ethers::signers::Signer;
 use webb::evm::ethers::types;
+use webb_relayer_config::evm::EvmChainConfig;
 use webb_relayer_context::RelayerContext;
 use webb_relayer_store::sled::SledQueueKey;
-use webb_relayer_store::QueueStore;
+use webb_relayer_store::{DequeuedItem, EnqueueOptions, QueueStore};
 use webb_relayer_utils::clickable_link::ClickableLink;
+use webb_relayer_utils::wallet_pool::WalletPool;
 
 /// The TxQueue stores transaction requests so the relayer can process them later.
 /// This prevents issues such as creating transactions with the same nonce.
@@ -35,7 +38,7 @@ use webb_relayer_utils::clickable_link::ClickableLink;
 /// the same transaction.
 #[derive(Clone)]
 pub struct TxQueue<S: QueueStore<TypedTransaction>> {
-    ctx: RelayerContext,
+    ctx: RelayerContext<S>,
     chain_id: types::U256,
     store: Arc<S>,
 }
@@ -54,7 +57,7 @@ where
     /// * `chain_id` - The chainId that this queue is for
     /// * `store` - [Sled](https://sled.rs)-based database store
     pub fn new(
-        ctx: RelayerContext,
+        ctx: RelayerContext<S>,
         chain_id: types::U256,
         store: Arc<S>,
     ) -> Self {
@@ -70,8 +73,7 @@ where
     #[tracing::instrument(skip_all, fields(chain = %self.chain_id))]
     pub async fn run(self) -> webb_relayer_utils::Result<()> {
         let provider = self.ctx.evm_provider(&self.chain_id).await?;
-        let wallet = self.ctx.evm_wallet(self.chain_id).await?;
-        let signer_client = SignerMiddleware::new(provider, wallet);
+        let pool = self.ctx.evm_wallet_pool(self.chain_id)?;
 
         let chain_config = self
             .ctx
@@ -82,10 +84,7 @@ where
                 chain_id: self.chain_id.to_string(),
             })?;
 
-        // TimeLag client
-        let client =
-            TimeLag::new(signer_client, chain_config.block_confirmations);
-        let chain_id = client
+        let chain_id = provider
             .get_chainid()
             .map_err(|_| {
                 webb_relayer_utils::Error::Generic(
@@ -95,7 +94,6 @@ where
             .await?
             .as_u32();
 
-        let store = self.store;
         let backoff = backoff::ExponentialBackoff {
             max_elapsed_time: None,
             ..Default::default()
@@ -107,220 +105,39 @@ where
             ty = "EVM",
             chain_id = %chain_id,
             starting = true,
+            accounts = pool.len(),
         );
-        let metrics_clone = self.ctx.metrics.clone();
         let task = || async {
             loop {
-                let maybe_tx = store
-                    .dequeue_item(SledQueueKey::from_evm_chain_id(chain_id))?;
-                let maybe_explorer = &chain_config.explorer;
-                let mut tx_hash: H256;
-                if let Some(mut raw_tx) = maybe_tx {
-                    let raw_tx =
-                        raw_tx.set_chain_id(U64::from(chain_id)).clone();
-                    let my_tx_hash = raw_tx.sighash();
-                    tx_hash = my_tx_hash;
-                    tracing::debug!(?tx_hash, tx = ?raw_tx, "Found tx in queue");
-                    // dry run test
-                    let dry_run_outcome =
-                        client.call(&raw_tx.clone(), None).await;
-                    match dry_run_outcome {
-                        Ok(_) => {
-                            tracing::event!(
-                                target: webb_relayer_utils::probe::TARGET,
-                                tracing::Level::DEBUG,
-                                kind = %webb_relayer_utils::probe::Kind::TxQueue,
-                                ty = "EVM",
-                                chain_id = %chain_id,
-                                dry_run = "passed",
-                                %tx_hash,
-                            );
-                        }
-                        Err(err) => {
-                            tracing::event!(
-                                target: webb_relayer_utils::probe::TARGET,
-                                tracing::Level::DEBUG,
-                                kind = %webb_relayer_utils::probe::Kind::TxQueue,
-                                ty = "EVM",
-                                chain_id = %chain_id,
-                                errored = true,
-                                error = %err,
-                                dry_run = "failed",
-                                %tx_hash,
-                            );
-                            continue; // keep going.
-                        }
-                    }
-
-                    let pending_tx =
-                        client.send_transaction(raw_tx.clone(), None);
-                    let tx = match pending_tx.await {
-                        Ok(pending) => {
-                            tx_hash = *pending;
-                            tracing::event!(
-                                target: webb_relayer_utils::probe::TARGET,
-                                tracing::Level::DEBUG,
-                                kind = %webb_relayer_utils::probe::Kind::TxQueue,
-                                ty = "EVM",
-                                chain_id = %chain_id,
-                                pending = true,
-                                %tx_hash,
-                            );
-
-                            let tx_hash_string = format!("0x{tx_hash:x}");
-                            if let Some(mut url) = maybe_explorer.clone() {
-                                url.set_path(&format!("tx/{tx_hash_string}"));
-                                let clickable_link = ClickableLink::new(
-                                    &tx_hash_string,
-                                    url.as_str(),
-                                );
-                                tracing::info!(
-                                    "Tx {} is submitted and pending!",
-                                    clickable_link,
-                                );
-                            } else {
-                                tracing::info!(
-                                    "Tx {} is submitted and pending!",
-                                    tx_hash_string,
-                                );
-                            }
-                            pending.interval(Duration::from_millis(1000)).await
-                        }
-                        Err(e) => {
-                            let tx_hash_string = format!("0x{tx_hash:x}");
-                            if let Some(mut url) = maybe_explorer.clone() {
-                                url.set_path(&format!("tx/{tx_hash_string}"));
-                                let clickable_link = ClickableLink::new(
-                                    &tx_hash_string,
-                                    url.as_str(),
-                                );
-                                tracing::error!(
-                                    "Error while sending tx {}, {}",
-                                    clickable_link,
-                                    e,
-                                );
-                            } else {
-                                tracing::error!(
-                                    "Error while sending tx {}, {}",
-                                    tx_hash_string,
-                                    e
-                                );
-                            }
-                            tracing::event!(
-                                target: webb_relayer_utils::probe::TARGET,
-                                tracing::Level::DEBUG,
-                                kind = %webb_relayer_utils::probe::Kind::TxQueue,
-                                ty = "EVM",
-                                chain_id = %chain_id,
-                                errored = true,
-                                %tx_hash,
-                                error = %e,
-                            );
-
-                            continue; // keep going.
-                        }
-                    };
-                    match tx {
-                        Ok(Some(receipt)) => {
-                            let tx_hash_string =
-                                format!("0x{:x}", receipt.transaction_hash);
-                            match receipt.status {
-                                Some(v) if v.is_zero() => {
-                                    tracing::info!(
-                                        "Tx {} Failed",
-                                        tx_hash_string,
-                                    );
-                                    continue;
-                                }
-                                _ => {}
-                            }
-
-                            if let Some(mut url) = maybe_explorer.clone() {
-                                url.set_path(&format!("tx/{tx_hash_string}"));
-                                let clickable_link = ClickableLink::new(
-                                    &tx_hash_string,
-                                    url.as_str(),
-                                );
-                                tracing::info!(
-                                    "Tx {} Finalized",
-                                    clickable_link
-                                );
-                            } else {
-                                tracing::info!(
-                                    "Tx {} Finalized",
-                                    tx_hash_string,
-                                );
-                            }
-                            let gas_price =
-                                receipt.gas_used.unwrap_or_default();
-                            // metrics for  transaction processed by evm tx queue
-                            let metrics = metrics_clone.lock().await;
-                            metrics.proposals_processed_tx_queue.inc();
-                            metrics.proposals_processed_evm_tx_queue.inc();
-                            // gas spent metric
-                            metrics.gas_spent.inc_by(gas_price.as_u64() as f64);
-                            tracing::event!(
-                                target: webb_relayer_utils::probe::TARGET,
-                                tracing::Level::DEBUG,
-                                kind = %webb_relayer_utils::probe::Kind::TxQueue,
-                                ty = "EVM",
-                                chain_id = %chain_id,
-                                finalized = true,
-                                %tx_hash,
-                            );
-                        }
-                        Ok(None) => {
-                            // this should never happen
-                            // as we already know that is a bug in ethers
-                            // about timeing, so we already wait a bit
-                            // and increased the time interval for checking for
-                            // transaction status.
-                            let tx_hash_string = format!("0x{tx_hash:x}");
-                            tracing::warn!(
-                                "Tx {} Dropped from Mempool!!",
-                                tx_hash_string
-                            );
-                            // enquing the tx again
-                            store.enqueue_item(
-                                SledQueueKey::from_evm_chain_id(chain_id),
-                                raw_tx,
-                            )?;
-                        }
-                        Err(e) => {
-                            let reason = e.to_string();
-                            let tx_hash_string = format!("0x{tx_hash:x}");
-                            if let Some(mut url) = maybe_explorer.clone() {
-                                url.set_path(&format!("tx/{tx_hash_string}"));
-                                let clickable_link = ClickableLink::new(
-                                    &tx_hash_string,
-                                    url.as_str(),
-                                );
-                                tracing::error!(
-                                    "Tx {} Errored: {}",
-                                    clickable_link,
-                                    reason,
-                                );
-                            } else {
-                                tracing::error!(
-                                    "Tx {} Errored: {}",
-                                    tx_hash_string,
-                                    reason,
-                                );
-                            }
-
-                            tracing::event!(
-                                target: webb_relayer_utils::probe::TARGET,
-                                tracing::Level::DEBUG,
-                                kind = %webb_relayer_utils::probe::Kind::TxQueue,
-                                ty = "EVM",
-                                chain_id = %chain_id,
-                                errored = true,
-                                %tx_hash,
-                                error = %e,
-                            );
-                        }
+                // send up to one tx per account of the pool at once.
+                //
+                // the txs to the same contract may depend on each other, like the
+                // proposals of a bridge, which must be executed in the order of
+                // their nonces. So the batch stops at the first tx whose target
+                // is already in it, that tx is sent in the next round.
+                let key = SledQueueKey::from_evm_chain_id(chain_id);
+                let mut batch = Vec::new();
+                let mut targets = Vec::new();
+                while batch.len() < pool.len() {
+                    // the tx is only dequeued if its target is not in the batch,
+                    // otherwise it stays at the head of the queue, in its place.
+                    let dequeued = self.store.dequeue_item_if(key, |tx| {
+                        !targets.contains(&tx.to().cloned())
+                    })?;
+                    let Some(dequeued) = dequeued else {
+                        break;
                     };
+                    targets.push(dequeued.item.to().cloned());
+                    batch.push(dequeued);
                 }
+                let sends = batch.into_iter().map(|dequeued| {
+                    self.send_tx(dequeued, chain_id, chain_config, &pool)
+                });
+                // wait for all of them before failing, so none of them is lost.
+                futures::future::join_all(sends)
+                    .await
+                    .into_iter()
+                    .collect::<webb_relayer_utils::Result<Vec<_>>>()?;
                 // sleep for a random amount of time.
                 let max_sleep_interval =
                     chain_config.tx_queue.max_sleep_interval;
@@ -338,4 +155,262 @@ where
         backoff::future::retry::<(), _, _, _, _>(backoff, task).await?;
         Ok(())
     }
+
+    /// Sends a tx of the queue from the next account of the pool, with a nonce
+    /// reserved for it, and waits for it to be finalized.
+    ///
+    /// If it has to be enqueued again or dead-lettered, it keeps the key and the
+    /// priority it was enqueued with.
+    async fn send_tx(
+        &self,
+        dequeued: DequeuedItem<TypedTransaction>,
+        chain_id: u32,
+        chain_config: &EvmChainConfig,
+        pool: &WalletPool,
+    ) -> webb_relayer_utils::Result<()> {
+        let store = &self.store;
+        let DequeuedItem {
+            item: dequeued_tx,
+            item_key,
+            options,
+        } = dequeued;
+        let queue_key =
+            SledQueueKey::from_evm_chain_id(chain_id).with_item_key(item_key);
+        let requeue_options = EnqueueOptions::with_priority(options.priority);
+        let provider = self.ctx.evm_provider(&self.chain_id).await?;
+        let mut reservation = match pool.reserve(&provider).await {
+            Ok(reservation) => reservation,
+            Err(e) => {
+                // put it back, to send it once the chain is reachable again.
+                store.enqueue_item_with_options(
+                    queue_key,
+                    dequeued_tx,
+                    requeue_options,
+                )?;
+                return Err(e);
+            }
+        };
+        let signer = reservation.signer().clone();
+        // the tx is dead-lettered or enqueued again as it was dequeued, without
+        // the account and nonce of this attempt, so it keeps the same id.
+        let mut raw_tx = dequeued_tx.clone();
+        raw_tx.set_from(signer.address());
+        raw_tx.set_nonce(reservation.nonce());
+        // TimeLag client
+        let client = TimeLag::new(
+            SignerMiddleware::new(provider, signer),
+            chain_config.block_confirmations,
+        );
+        let metrics_clone = self.ctx.metrics.clone();
+        let maybe_explorer = &chain_config.explorer;
+        let mut tx_hash: H256;
+        let raw_tx = raw_tx.set_chain_id(U64::from(chain_id)).clone();
+        let my_tx_hash = raw_tx.sighash();
+        tx_hash = my_tx_hash;
+        tracing::debug!(?tx_hash, tx = ?raw_tx, "Found tx in queue");
+        // dry run test
+        let dry_run_outcome = client.call(&raw_tx.clone(), None).await;
+        match dry_run_outcome {
+            Ok(_) => {
+                tracing::event!(
+                    target: webb_relayer_utils::probe::TARGET,
+                    tracing::Level::DEBUG,
+                    kind = %webb_relayer_utils::probe::Kind::TxQueue,
+                    ty = "EVM",
+                    chain_id = %chain_id,
+                    dry_run = "passed",
+                    %tx_hash,
+                );
+            }
+            Err(err) => {
+                tracing::event!(
+                    target: webb_relayer_utils::probe::TARGET,
+                    tracing::Level::DEBUG,
+                    kind = %webb_relayer_utils::probe::Kind::TxQueue,
+                    ty = "EVM",
+                    chain_id = %chain_id,
+                    errored = true,
+                    error = %err,
+                    dry_run = "failed",
+                    %tx_hash,
+                );
+                // keep it aside, so it could be requeued later.
+                store.dead_letter_item_with_options(
+                    queue_key,
+                    dequeued_tx,
+                    requeue_options,
+                    format!("dry run failed: {err}"),
+                )?;
+                return Ok(()); // keep going.
+            }
+        }
+
+        let pending_tx = client.send_transaction(raw_tx.clone(), None);
+        let tx = match pending_tx.await {
+            Ok(pending) => {
+                reservation.mark_sent();
+                tx_hash = *pending;
+                tracing::event!(
+                    target: webb_relayer_utils::probe::TARGET,
+                    tracing::Level::DEBUG,
+                    kind = %webb_relayer_utils::probe::Kind::TxQueue,
+                    ty = "EVM",
+                    chain_id = %chain_id,
+                    pending = true,
+                    %tx_hash,
+                );
+
+                let tx_hash_string = format!("0x{tx_hash:x}");
+                if let Some(mut url) = maybe_explorer.clone() {
+                    url.set_path(&format!("tx/{tx_hash_string}"));
+                    let clickable_link =
+                        ClickableLink::new(&tx_hash_string, url.as_str());
+                    tracing::info!(
+                        "Tx {} is submitted and pending!",
+                        clickable_link,
+                    );
+                } else {
+                    tracing::info!(
+                        "Tx {} is submitted and pending!",
+                        tx_hash_string,
+                    );
+                }
+                pending.interval(Duration::from_millis(1000)).await
+            }
+            Err(e) => {
+                let tx_hash_string = format!("0x{tx_hash:x}");
+                if let Some(mut url) = maybe_explorer.clone() {
+                    url.set_path(&format!("tx/{tx_hash_string}"));
+                    let clickable_link =
+                        ClickableLink::new(&tx_hash_string, url.as_str());
+                    tracing::error!(
+                        "Error while sending tx {}, {}",
+                        clickable_link,
+                        e,
+                    );
+                } else {
+                    tracing::error!(
+                        "Error while sending tx {}, {}",
+                        tx_hash_string,
+                        e
+                    );
+                }
+                tracing::event!(
+                    target: webb_relayer_utils::probe::TARGET,
+                    tracing::Level::DEBUG,
+                    kind = %webb_relayer_utils::probe::Kind::TxQueue,
+                    ty = "EVM",
+                    chain_id = %chain_id,
+                    errored = true,
+                    %tx_hash,
+                    error = %e,
+                );
+                store.dead_letter_item_with_options(
+                    queue_key,
+                    dequeued_tx,
+                    requeue_options,
+                    format!("failed to send: {e}"),
+                )?;
+                return Ok(()); // keep going.
+            }
+        };
+        match tx {
+            Ok(Some(receipt)) => {
+                let tx_hash_string =
+                    format!("0x{:x}", receipt.transaction_hash);
+                match receipt.status {
+                    Some(v) if v.is_zero() => {
+                        tracing::info!("Tx {} Failed", tx_hash_string,);
+                        store.dead_letter_item_with_options(
+                            queue_key,
+                            dequeued_tx,
+                            requeue_options,
+                            format!("reverted in {tx_hash_string}"),
+                        )?;
+                        return Ok(());
+                    }
+                    _ => {}
+                }
+
+                if let Some(mut url) = maybe_explorer.clone() {
+                    url.set_path(&format!("tx/{tx_hash_string}"));
+                    let clickable_link =
+                        ClickableLink::new(&tx_hash_string, url.as_str());
+                    tracing::info!("Tx {} Finalized", clickable_link);
+                } else {
+                    tracing::info!("Tx {} Finalized", tx_hash_string,);
+                }
+                let gas_price = receipt.gas_used.unwrap_or_default();
+                // metrics for  transaction processed by evm tx queue
+                let metrics = metrics_clone.lock().await;
+                metrics.proposals_processed_tx_queue.inc();
+                metrics.proposals_processed_evm_tx_queue.inc();
+                // gas spent metric
+                metrics.gas_spent.inc_by(gas_price.as_u64() as f64);
+                tracing::event!(
+                    target: webb_relayer_utils::probe::TARGET,
+                    tracing::Level::DEBUG,
+                    kind = %webb_relayer_utils::probe::Kind::TxQueue,
+                    ty = "EVM",
+                    chain_id = %chain_id,
+                    finalized = true,
+                    %tx_hash,
+                );
+            }
+            Ok(None) => {
+                // this should never happen
+                // as we already know that is a bug in ethers
+                // about timeing, so we already wait a bit
+                // and increased the time interval for checking for
+                // transaction status.
+                let tx_hash_string = format!("0x{tx_hash:x}");
+                tracing::warn!("Tx {} Dropped from Mempool!!", tx_hash_string);
+                // its nonce is free again, and the tx is enqueued again.
+                reservation.release();
+                store.enqueue_item_with_options(
+                    queue_key,
+                    dequeued_tx,
+                    requeue_options,
+                )?;
+            }
+            Err(e) => {
+                let reason = e.to_string();
+                let tx_hash_string = format!("0x{tx_hash:x}");
+                if let Some(mut url) = maybe_explorer.clone() {
+                    url.set_path(&format!("tx/{tx_hash_string}"));
+                    let clickable_link =
+                        ClickableLink::new(&tx_hash_string, url.as_str());
+                    tracing::error!(
+                        "Tx {} Errored: {}",
+                        clickable_link,
+                        reason,
+                    );
+                } else {
+                    tracing::error!(
+                        "Tx {} Errored: {}",
+                        tx_hash_string,
+                        reason,
+                    );
+                }
+
+                tracing::event!(
+                    target: webb_relayer_utils::probe::TARGET,
+                    tracing::Level::DEBUG,
+                    kind = %webb_relayer_utils::probe::Kind::TxQueue,
+                    ty = "EVM",
+                    chain_id = %chain_id,
+                    errored = true,
+                    %tx_hash,
+                    error = %e,
+                );
+                store.dead_letter_item_with_options(
+                    queue_key,
+                    dequeued_tx,
+                    requeue_options,
+                    reason,
+                )?;
+            }
+        };
+        Ok(())
+    }
 }
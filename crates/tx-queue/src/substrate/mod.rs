@@ -76,6 +76,7 @@ mod tests {
                     beneficiary: None,
                     pallets: Default::default(),
                     tx_queue: Default::default(),
+                    fee_policy: Default::default(),
                 },
             )]),
             ..Default::default()
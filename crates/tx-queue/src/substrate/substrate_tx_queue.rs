@@ -20,7 +20,7 @@ use webb::substrate::subxt::config::ExtrinsicParams;
 use webb::substrate::subxt::PolkadotConfig;
 use webb_relayer_context::RelayerContext;
 use webb_relayer_store::sled::SledQueueKey;
-use webb_relayer_store::QueueStore;
+use webb_relayer_store::{DequeuedItem, EnqueueOptions, QueueStore};
 use webb_relayer_utils::static_tx_payload::TypeErasedStaticTxPayload;
 
 use std::sync::Arc;
@@ -38,7 +38,7 @@ pub struct SubstrateTxQueue<S>
 where
     S: QueueStore<TypeErasedStaticTxPayload, Key = SledQueueKey>,
 {
-    ctx: RelayerContext,
+    ctx: RelayerContext<S>,
     chain_id: u32,
     store: Arc<S>,
 }
@@ -56,7 +56,7 @@ where
     /// * `ctx` - RelayContext reference that holds the configuration
     /// * `chain_name` - The name of the chain that this queue is for
     /// * `store` - [Sled](https://sled.rs)-based database store
-    pub fn new(ctx: RelayerContext, chain_id: u32, store: Arc<S>) -> Self {
+    pub fn new(ctx: RelayerContext<S>, chain_id: u32, store: Arc<S>) -> Self {
         Self {
             ctx,
             chain_id,
@@ -120,19 +120,52 @@ where
             let signer = subxt::tx::PairSigner::<PolkadotConfig, _>::new(pair);
             loop {
                 // dequeue signed transaction
-                let tx_call_data = store.dequeue_item(
+                let tx_call_data = store.dequeue_item_with_options(
                     SledQueueKey::from_substrate_chain_id(chain_id),
                 )?;
-                if let Some(payload) = tx_call_data {
-                    let signed_extrinsic = client
+                if let Some(DequeuedItem {
+                    item: payload,
+                    item_key,
+                    options,
+                }) = tx_call_data
+                {
+                    // it keeps its key and priority if it is enqueued again or
+                    // dead-lettered.
+                    let queue_key =
+                        SledQueueKey::from_substrate_chain_id(chain_id)
+                            .with_item_key(item_key);
+                    let requeue_options =
+                        EnqueueOptions::with_priority(options.priority);
+                    let signed_extrinsic = match client
                         .tx()
                         .create_signed(&payload, &signer, Default::default())
-                        .map_err(Into::into)
-                        .map_err(backoff::Error::transient)
-                        .await?;
+                        .await
+                    {
+                        Ok(signed_extrinsic) => signed_extrinsic,
+                        Err(e) => {
+                            // keep it aside, so it could be requeued later.
+                            store.dead_letter_item_with_options(
+                                queue_key,
+                                payload,
+                                requeue_options,
+                                format!("failed to sign: {e}"),
+                            )?;
+                            return Err(backoff::Error::transient(e.into()));
+                        }
+                    };
                     // dry run test
                     let dry_run_outcome = signed_extrinsic.dry_run(None).await;
                     match dry_run_outcome {
+                        Err(err) if is_connection_error(&err) => {
+                            // the node is lost, so put the transaction back, and
+                            // connect again.
+                            store.enqueue_item_with_options(
+                                queue_key,
+                                payload,
+                                requeue_options,
+                            )?;
+                            return Err(backoff::Error::transient(err.into()));
+                        }
                         Ok(_) => {
                             tracing::event!(
                                 target: webb_relayer_utils::probe::TARGET,
@@ -156,11 +189,17 @@ where
                                 error = %err,
                                 dry_run = "failed"
                             );
+                            store.dead_letter_item_with_options(
+                                queue_key,
+                                payload,
+                                requeue_options,
+                                format!("dry run failed: {err}"),
+                            )?;
                             continue; // keep going.
                         }
                     }
                     // watch_extrinsic submits and returns transaction subscription
-                    let mut progress = signed_extrinsic
+                    let maybe_progress = signed_extrinsic
                         .submit_and_watch()
                         .inspect_err(|e| {
                             tracing::event!(
@@ -175,9 +214,27 @@ where
                                 progress = "failed",
                             );
                         })
-                        .map_err(Into::into)
-                        .map_err(backoff::Error::transient)
-                        .await?;
+                        .await;
+                    let mut progress = match maybe_progress {
+                        Ok(progress) => progress,
+                        Err(e) if is_connection_error(&e) => {
+                            store.enqueue_item_with_options(
+                                queue_key,
+                                payload,
+                                requeue_options,
+                            )?;
+                            return Err(backoff::Error::transient(e.into()));
+                        }
+                        Err(e) => {
+                            store.dead_letter_item_with_options(
+                                queue_key,
+                                payload,
+                                requeue_options,
+                                format!("failed to submit: {e}"),
+                            )?;
+                            return Err(backoff::Error::transient(e.into()));
+                        }
+                    };
 
                     while let Some(event) = progress.next().await {
                         let e = match event {
@@ -294,6 +351,13 @@ where
                                     chain_id = %chain_id,
                                     status = "Usurped",
                                 );
+                                store.dead_letter_item_with_options(
+                                    queue_key,
+                                    payload.clone(),
+                                    requeue_options,
+                                    "usurped".to_string(),
+                                )?;
+                                break;
                             }
                             TransactionStatus::Dropped => {
                                 tracing::event!(
@@ -305,6 +369,13 @@ where
                                     chain_id = %chain_id,
                                     status = "Dropped",
                                 );
+                                store.dead_letter_item_with_options(
+                                    queue_key,
+                                    payload.clone(),
+                                    requeue_options,
+                                    "dropped".to_string(),
+                                )?;
+                                break;
                             }
                             TransactionStatus::Invalid => {
                                 tracing::event!(
@@ -316,6 +387,13 @@ where
                                     chain_id = %chain_id,
                                     status = "Invalid",
                                 );
+                                store.dead_letter_item_with_options(
+                                    queue_key,
+                                    payload.clone(),
+                                    requeue_options,
+                                    "invalid".to_string(),
+                                )?;
+                                break;
                             }
                         }
                     }
@@ -338,3 +416,26 @@ where
         Ok(())
     }
 }
+
+/// Returns `true` if the error is a failure to reach the node, rather than a
+/// problem with the transaction itself.
+///
+/// The transactions rejected by the node, like `1010: Invalid Transaction`, are
+/// rpc errors too, but they are not retried.
+fn is_connection_error(err: &subxt::Error) -> bool {
+    use jsonrpsee_core::Error as JsonRpcError;
+    use subxt::error::RpcError;
+    match err {
+        subxt::Error::Io(_) => true,
+        subxt::Error::Rpc(RpcError::SubscriptionDropped) => true,
+        subxt::Error::Rpc(RpcError::ClientError(e)) => matches!(
+            e.downcast_ref::<JsonRpcError>(),
+            Some(
+                JsonRpcError::Transport(_)
+                    | JsonRpcError::RestartNeeded(_)
+                    | JsonRpcError::RequestTimeout
+            )
+        ),
+        _ => false,
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{MAX_REFUND_USD, TRANSACTION_PROFIT_USD};
+use crate::fee_policy_of;
 use chrono::DateTime;
 use chrono::Duration;
 use chrono::Utc;
@@ -20,6 +20,7 @@ use webb::evm::ethers::utils::{format_units, parse_units};
 use webb_chains_info::chain_info_by_chain_id;
 use webb_price_oracle_backends::PriceBackend;
 use webb_proposals::TypedChainId;
+use webb_relayer_config::FeePolicyConfig;
 use webb_relayer_context::RelayerContext;
 use webb_relayer_utils::Result;
 
@@ -61,16 +62,45 @@ pub struct EvmFeeInfo {
     wrapped_token_decimals: u32,
 }
 
+impl EvmFeeInfo {
+    /// Returns the maximum refund of a relay transaction sent from `account`,
+    /// since it is paid from the balance of that account.
+    ///
+    /// The `max_refund` of the fee info is bounded by the balance of the primary
+    /// account of the relayer, while the transactions are spread across all the
+    /// accounts of its wallet pool.
+    pub async fn max_refund_of<S>(
+        &self,
+        chain_id: TypedChainId,
+        account: Address,
+        ctx: &RelayerContext<S>,
+    ) -> Result<U256> {
+        let fee_policy = fee_policy_of(chain_id, ctx)?;
+        max_refund(
+            chain_id,
+            account,
+            self.native_token_price,
+            self.native_token_decimals,
+            &fee_policy,
+            ctx,
+        )
+        .await
+    }
+}
+
 /// Get the current fee info.
 ///
 /// If fee info was recently requested, the cached value is used. Otherwise it is regenerated
 /// based on the current exchange rate and estimated gas price.
-pub async fn get_evm_fee_info(
+///
+/// The fees and refunds follow the fee policy of the chain.
+pub async fn get_evm_fee_info<S>(
     chain_id: TypedChainId,
     vanchor: Address,
     gas_amount: U256,
-    ctx: &RelayerContext,
+    ctx: &RelayerContext<S>,
 ) -> Result<EvmFeeInfo> {
+    let fee_policy = fee_policy_of(chain_id, ctx)?;
     // Retrieve cached fee info item
     let fee_info_cached = {
         let mut lock =
@@ -92,19 +122,24 @@ pub async fn get_evm_fee_info(
             fee_info.native_token_price,
             fee_info.wrapped_token_price,
             fee_info.wrapped_token_decimals,
+            &fee_policy,
         )?;
         // Recalculate max refund in case relayer balance changed.
+        let wallet = ctx.evm_wallet(chain_id.underlying_chain_id()).await?;
         fee_info.max_refund = max_refund(
             chain_id,
+            wallet.address(),
             fee_info.native_token_price,
             fee_info.native_token_decimals,
+            &fee_policy,
             ctx,
         )
         .await?;
         Ok(fee_info)
     } else {
         let fee_info =
-            generate_fee_info(chain_id, vanchor, gas_amount, ctx).await?;
+            generate_fee_info(chain_id, vanchor, gas_amount, &fee_policy, ctx)
+                .await?;
 
         // Insert newly generated fee info into cache.
         FEE_INFO_CACHED
@@ -116,11 +151,12 @@ pub async fn get_evm_fee_info(
 }
 
 /// Generate new fee info by fetching relevant data from remote APIs and doing calculations.
-async fn generate_fee_info(
+async fn generate_fee_info<S>(
     chain_id: TypedChainId,
     vanchor: Address,
     gas_amount: U256,
-    ctx: &RelayerContext,
+    fee_policy: &FeePolicyConfig,
+    ctx: &RelayerContext<S>,
 ) -> Result<EvmFeeInfo> {
     // Get token names
     let (native_token, native_token_decimals) =
@@ -165,6 +201,7 @@ async fn generate_fee_info(
         native_token_price,
         wrapped_token_price,
         wrapped_token_decimals,
+        fee_policy,
     )?;
 
     // Calculate the exchange rate from wrapped token to native token which is used for the refund.
@@ -174,14 +211,17 @@ async fn generate_fee_info(
     )?
     .into();
 
+    let wallet = ctx.evm_wallet(chain_id.underlying_chain_id()).await?;
     Ok(EvmFeeInfo {
         estimated_fee,
         gas_price,
         refund_exchange_rate,
         max_refund: max_refund(
             chain_id,
+            wallet.address(),
             native_token_price,
             native_token_decimals,
+            fee_policy,
             ctx,
         )
         .await?,
@@ -193,19 +233,21 @@ async fn generate_fee_info(
     })
 }
 
-async fn max_refund(
+/// Calculates the maximum refund of a relay transaction sent from `account`.
+async fn max_refund<S>(
     chain_id: TypedChainId,
+    account: Address,
     native_token_price: f64,
     native_token_decimals: u8,
-    ctx: &RelayerContext,
+    fee_policy: &FeePolicyConfig,
+    ctx: &RelayerContext<S>,
 ) -> Result<U256> {
-    let wallet = ctx.evm_wallet(chain_id.underlying_chain_id()).await?;
     let provider = ctx.evm_provider(chain_id.underlying_chain_id()).await?;
-    let relayer_balance = provider.get_balance(wallet.address(), None).await?;
+    let relayer_balance = provider.get_balance(account, None).await?;
     // Calculate the maximum refund amount per relay transaction in `nativeToken`.
     // Ensuring that refund <= relayer balance
     let max_refund = parse_units(
-        MAX_REFUND_USD / native_token_price,
+        fee_policy.max_refund_usd / native_token_price,
         u32::from(native_token_decimals),
     )?
     .into();
@@ -213,7 +255,8 @@ async fn max_refund(
 }
 
 /// Pull USD prices of base token from coingecko.com, and use this to calculate the transaction
-/// fee in `wrappedToken` wei. This fee includes a profit for the relay of `TRANSACTION_PROFIT_USD`.
+/// fee in `wrappedToken` wei. This fee includes the profit of the relay, and is at least the
+/// minimum fee, as set by the fee policy of the chain.
 ///
 /// The algorithm is explained at https://www.notion.so/hicommonwealth/Private-Tx-Relay-Support-v1-f5522b04d6a349aab1bbdb0dd83a7fb4#6bb2b4920e3f42d69988688c6fa54e6e
 fn calculate_transaction_fee(
@@ -222,6 +265,7 @@ fn calculate_transaction_fee(
     native_token_price: f64,
     wrapped_token_price: f64,
     wrapped_token_decimals: u32,
+    fee_policy: &FeePolicyConfig,
 ) -> Result<U256> {
     // Step 1: Calculate the tx fee in native token (in wei)
     let tx_fee_native_token_wei = gas_price * gas_amount;
@@ -233,7 +277,7 @@ fn calculate_transaction_fee(
     let tx_fee_usd = tx_fee_tokens * native_token_price;
     // Step 3: Calculate the profit that the relayer should make, and add it to the tx fee in USD.
     // This is the total amount of USD that the relayer should receive.
-    let total_fee_with_profit_in_usd = tx_fee_usd + TRANSACTION_PROFIT_USD;
+    let total_fee_with_profit_in_usd = fee_policy.fee_of(tx_fee_usd);
     // Step 4: Convert the total fee to `wrappedToken` using the exchange rate for the underlying
     // wrapped token.
     // This is the total amount of `wrappedToken` that the relayer should receive.
@@ -247,10 +291,10 @@ fn calculate_transaction_fee(
 
 /// Returns the name and decimals of the wrapped token for the given chain.
 /// then converts it to the underlying token token name to be used in the price oracle.
-async fn get_wrapped_token_name_and_decimals(
+async fn get_wrapped_token_name_and_decimals<S>(
     chain_id: TypedChainId,
     vanchor: Address,
-    ctx: &RelayerContext,
+    ctx: &RelayerContext<S>,
 ) -> Result<(String, u32)> {
     let provider = ctx.evm_provider(chain_id.underlying_chain_id()).await?;
     let client = Arc::new(provider);
@@ -12,6 +12,7 @@ use webb_relayer_handler_utils::{
     into_withdraw_error, CommandResponse, CommandStream, WithdrawStatus,
 };
 use webb_relayer_utils::metric::{self};
+use webb_relayer_utils::wallet_pool::NonceReservation;
 
 pub mod fees;
 /// Variable Anchor transaction relayer.
@@ -22,8 +23,12 @@ pub mod vanchor;
 /// This is meant to be reused amongst all kinds of EVM transactions that the relayer sends.
 /// The intention is that a dry-run call is made first to ensure that the transaction is valid
 /// and then the actual transaction is submitted and its progress is monitored.
+///
+/// The nonce of the `reservation` is kept used only if the transaction is sent
+/// and not dropped from the mempool.
 pub async fn handle_evm_tx<M, D>(
     call: ContractCall<M, D>,
+    mut reservation: NonceReservation,
     stream: CommandStream,
     chain_id: u64,
     metrics: Arc<Mutex<metric::Metrics>>,
@@ -56,6 +61,7 @@ where
         Withdraw(into_withdraw_error(e))
     })?;
 
+    reservation.mark_sent();
     let _ = stream.send(Withdraw(WithdrawStatus::Sent)).await;
     let tx_hash = *pending;
     tracing::event!(
@@ -86,7 +92,10 @@ where
             );
             Withdraw(WithdrawStatus::Errored { reason, code: 4 })
         })?
-        .ok_or(Withdraw(WithdrawStatus::DroppedFromMemPool))?;
+        .ok_or_else(|| {
+            reservation.release();
+            Withdraw(WithdrawStatus::DroppedFromMemPool)
+        })?;
 
     tracing::event!(
         target: webb_relayer_utils::probe::TARGET,
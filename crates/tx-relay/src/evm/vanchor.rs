@@ -1,4 +1,5 @@
 use super::*;
+use crate::accepted_fee;
 use crate::evm::fees::{get_evm_fee_info, EvmFeeInfo};
 use crate::evm::handle_evm_tx;
 use ethereum_types::U256;
@@ -24,8 +25,8 @@ use webb_relayer_handler_utils::{CommandStream, NetworkStatus};
 /// * `ctx` - RelayContext reference that holds the configuration
 /// * `cmd` - The command to execute
 /// * `stream` - The stream to write the response to
-pub async fn handle_vanchor_relay_tx<'a>(
-    ctx: RelayerContext,
+pub async fn handle_vanchor_relay_tx<'a, S>(
+    ctx: RelayerContext<S>,
     cmd: EvmVanchorCommand,
     stream: CommandStream,
 ) -> Result<(), CommandResponse> {
@@ -82,7 +83,17 @@ pub async fn handle_vanchor_relay_tx<'a>(
     })?;
     let _ = stream.send(Network(NetworkStatus::Connected)).await;
 
-    let client = Arc::new(SignerMiddleware::new(provider, wallet));
+    // the tx is sent from the next account of the pool, with its own nonce.
+    let pool = ctx.evm_wallet_pool(cmd.chain_id).map_err(|e| {
+        Error(format!("Misconfigured Network: {:?}, {e}", cmd.chain_id))
+    })?;
+    let reservation = pool.reserve(&provider).await.map_err(|e| {
+        Network(NetworkStatus::Failed {
+            reason: e.to_string(),
+        })
+    })?;
+    let signer = reservation.signer().clone();
+    let client = Arc::new(SignerMiddleware::new(provider, signer));
     let contract = VAnchorContract::new(cmd.id, client.clone());
 
     let common_ext_data = CommonExtData {
@@ -134,6 +145,7 @@ pub async fn handle_vanchor_relay_tx<'a>(
     if !cmd.ext_data.refund.is_zero() {
         call = call.value(cmd.ext_data.refund);
     }
+    call.tx.set_nonce(reservation.nonce());
 
     let gas_amount =
         client.estimate_gas(&call.tx, None).await.map_err(|e| {
@@ -155,18 +167,25 @@ pub async fn handle_vanchor_relay_tx<'a>(
         })
     })?;
 
-    // validate refund amount
-    if cmd.ext_data.refund > fee_info.max_refund {
+    // validate refund amount, against the balance of the account sending it.
+    let max_refund = fee_info
+        .max_refund_of(typed_chain_id, client.signer().address(), &ctx)
+        .await
+        .map_err(|e| {
+            Network(NetworkStatus::Failed {
+                reason: e.to_string(),
+            })
+        })?;
+    if cmd.ext_data.refund > max_refund {
         let msg = format!(
-            "User requested a refund which is higher than the maximum of {}",
-            fee_info.max_refund
+            "User requested a refund which is higher than the maximum of {max_refund}"
         );
         return Err(Error(msg));
     }
 
-    // check the fee
+    // check the fee, with the tolerance of the fee policy
     // TODO: This adjustment could potentially be exploited
-    let adjusted_fee = fee_info.estimated_fee / 100 * 96;
+    let adjusted_fee = accepted_fee(fee_info.estimated_fee, &chain.fee_policy);
     let wrapped_amount =
         calculate_wrapped_refund_amount(cmd.ext_data.refund, &fee_info)
             .map_err(|e| {
@@ -187,8 +206,15 @@ pub async fn handle_vanchor_relay_tx<'a>(
     let resource_id = ResourceId::new(target_system, typed_chain_id);
 
     tracing::trace!("About to send Tx to {:?} Chain", cmd.chain_id);
-    handle_evm_tx(call, stream, cmd.chain_id, ctx.metrics.clone(), resource_id)
-        .await?;
+    handle_evm_tx(
+        call,
+        reservation,
+        stream,
+        cmd.chain_id,
+        ctx.metrics.clone(),
+        resource_id,
+    )
+    .await?;
 
     // update metric
     let metrics_clone = ctx.metrics.clone();
@@ -1,3 +1,8 @@
+use ethereum_types::U256;
+use webb_proposals::TypedChainId;
+use webb_relayer_config::FeePolicyConfig;
+use webb_relayer_context::RelayerContext;
+
 /// EVM Transactional Relayer.
 #[cfg(feature = "evm")]
 pub mod evm;
@@ -5,7 +10,67 @@ pub mod evm;
 #[cfg(feature = "substrate")]
 pub mod substrate;
 
-/// Maximum refund amount per relay transaction in USD.
-const MAX_REFUND_USD: f64 = 5.;
-/// Amount of profit that the relay should make with each transaction (in USD).
-const TRANSACTION_PROFIT_USD: f64 = 5.;
+/// Returns the fee policy of the EVM chain or Substrate node with the given
+/// chain id.
+fn fee_policy_of<S>(
+    chain_id: TypedChainId,
+    ctx: &RelayerContext<S>,
+) -> webb_relayer_utils::Result<FeePolicyConfig> {
+    let key = chain_id.underlying_chain_id().to_string();
+    let fee_policy = match chain_id {
+        TypedChainId::Evm(_) => ctx.config.evm.get(&key).map(|c| c.fee_policy),
+        TypedChainId::Substrate(_) => {
+            ctx.config.substrate.get(&key).map(|c| c.fee_policy)
+        }
+        _ => None,
+    };
+    fee_policy.ok_or_else(|| webb_relayer_utils::Error::ChainNotFound {
+        chain_id: chain_id.chain_id().to_string(),
+    })
+}
+
+/// Returns the lowest fee accepted from a user, when the estimated fee is
+/// `estimated_fee`, allowing for the tolerance of the fee policy.
+fn accepted_fee(estimated_fee: U256, fee_policy: &FeePolicyConfig) -> U256 {
+    estimated_fee / 10_000 * fee_policy.accepted_fee_bps()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepted_fee_should_allow_for_the_tolerance() {
+        let default = FeePolicyConfig::default();
+        let estimated_fee = U256::from(1_000_000u64);
+        // 4% lower by default.
+        assert_eq!(
+            accepted_fee(estimated_fee, &default),
+            U256::from(960_000u64)
+        );
+
+        let policy = FeePolicyConfig {
+            tolerance_percent: 10.,
+            ..Default::default()
+        };
+        assert_eq!(
+            accepted_fee(estimated_fee, &policy),
+            U256::from(900_000u64)
+        );
+
+        let exact = FeePolicyConfig {
+            tolerance_percent: 0.,
+            ..Default::default()
+        };
+        assert_eq!(accepted_fee(estimated_fee, &exact), estimated_fee);
+        assert_eq!(accepted_fee(U256::zero(), &default), U256::zero());
+        // a whole number of basis points of the fee is accepted, never more.
+        let fee = U256::from(1_234_567u64);
+        assert_eq!(accepted_fee(fee, &exact), U256::from(1_230_000u64));
+        // it does not overflow with the largest fees.
+        assert_eq!(
+            accepted_fee(U256::MAX, &exact),
+            U256::MAX / 10_000 * 10_000
+        );
+    }
+}
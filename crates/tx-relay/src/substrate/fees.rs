@@ -1,12 +1,13 @@
+use crate::fee_policy_of;
 use crate::substrate::balance;
-use crate::{MAX_REFUND_USD, TRANSACTION_PROFIT_USD};
 use chrono::{DateTime, Utc};
 use serde::Serialize;
 use sp_core::U256;
-use std::cmp::min;
+use std::cmp::{max, min};
 use webb::evm::ethers::utils::__serde_json::Value;
 use webb::substrate::subxt::tx::PairSigner;
 use webb::substrate::subxt::PolkadotConfig;
+use webb_proposals::TypedChainId;
 use webb_relayer_context::RelayerContext;
 use webb_relayer_utils::Error;
 
@@ -26,11 +27,16 @@ pub struct SubstrateFeeInfo {
     timestamp: DateTime<Utc>,
 }
 
-pub async fn get_substrate_fee_info(
+/// Get the current fee info, for a transaction with the given network fees.
+///
+/// The fees and refunds follow the fee policy of the node.
+pub async fn get_substrate_fee_info<S>(
     chain_id: u64,
     estimated_tx_fees: U256,
-    ctx: &RelayerContext,
+    ctx: &RelayerContext<S>,
 ) -> webb_relayer_utils::Result<SubstrateFeeInfo> {
+    let fee_policy =
+        fee_policy_of(TypedChainId::Substrate(chain_id as u32), ctx)?;
     let client = ctx
         .substrate_provider::<PolkadotConfig, _>(chain_id)
         .await?;
@@ -42,14 +48,23 @@ pub async fn get_substrate_fee_info(
         .and_then(Value::as_i64)
         .ok_or(Error::ReadSubstrateStorageError)?
         as i32;
+    let tx_fee_usd =
+        unit_to_native_token(estimated_tx_fees, decimals) * TOKEN_PRICE_USD;
     let estimated_fee = estimated_tx_fees
         + native_token_to_unit(
-            TRANSACTION_PROFIT_USD / TOKEN_PRICE_USD,
+            fee_policy.profit_of(tx_fee_usd) / TOKEN_PRICE_USD,
             decimals,
         );
+    let min_fee = native_token_to_unit(
+        fee_policy.min_fee_usd / TOKEN_PRICE_USD,
+        decimals,
+    );
+    let estimated_fee = max(estimated_fee, min_fee);
     let refund_exchange_rate = native_token_to_unit(1., decimals);
-    let max_refund =
-        native_token_to_unit(MAX_REFUND_USD / TOKEN_PRICE_USD, decimals);
+    let max_refund = native_token_to_unit(
+        fee_policy.max_refund_usd / TOKEN_PRICE_USD,
+        decimals,
+    );
     let pair = ctx.substrate_wallet(chain_id).await?;
     let signer = PairSigner::new(pair.clone());
 
@@ -69,3 +84,8 @@ pub async fn get_substrate_fee_info(
 fn native_token_to_unit(matic: f64, token_decimals: i32) -> U256 {
     U256::from((matic * 10_f64.powi(token_decimals)) as u128)
 }
+
+/// Convert from smallest unit amount to full wrapped token amount.
+fn unit_to_native_token(amount: U256, token_decimals: i32) -> f64 {
+    amount.low_u128() as f64 / 10_f64.powi(token_decimals)
+}
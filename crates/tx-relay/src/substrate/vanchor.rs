@@ -1,5 +1,6 @@
 use super::*;
 use crate::substrate::fees::get_substrate_fee_info;
+use crate::{accepted_fee, fee_policy_of};
 use crate::substrate::handle_substrate_tx;
 use webb::substrate::tangle_runtime::api as RuntimeApi;
 use webb::substrate::subxt::utils::AccountId32;
@@ -24,8 +25,8 @@ use webb_relayer_handler_utils::SubstrateVAchorCommand;
 /// * `ctx` - RelayContext reference that holds the configuration
 /// * `cmd` - The command to execute
 /// * `stream` - The stream to write the response to
-pub async fn handle_substrate_vanchor_relay_tx<'a>(
-    ctx: RelayerContext,
+pub async fn handle_substrate_vanchor_relay_tx<'a, S>(
+    ctx: RelayerContext<S>,
     cmd: SubstrateVAchorCommand,
     stream: CommandStream,
 ) -> Result<(), CommandResponse> {
@@ -127,16 +128,19 @@ pub async fn handle_substrate_vanchor_relay_tx<'a>(
         return Err(Error(msg));
     }
 
-    // Check that transaction fee is enough to cover network fee and relayer fee
+    // Check that transaction fee is enough to cover network fee and relayer fee,
+    // with the tolerance of the fee policy
     // TODO: refund needs to be converted from wrapped token to native token once there
     //       is an exchange rate
-    if U256::from(cmd.ext_data.fee)
-        < fee_info.estimated_fee + cmd.ext_data.refund
-    {
+    let typed_chain_id = TypedChainId::Substrate(cmd.chain_id as u32);
+    let fee_policy = fee_policy_of(typed_chain_id, &ctx)
+        .map_err(|e| Error(format!("Get substrate fee policy failed: {e}")))?;
+    let adjusted_fee = accepted_fee(fee_info.estimated_fee, &fee_policy);
+    if U256::from(cmd.ext_data.fee) < adjusted_fee + cmd.ext_data.refund {
         let msg = format!(
             "User sent a fee that is too low ({}) but expected {}",
             cmd.ext_data.fee,
-            fee_info.estimated_fee + cmd.ext_data.refund
+            adjusted_fee + cmd.ext_data.refund
         );
         return Err(Error(msg));
     }
@@ -160,7 +164,6 @@ pub async fn handle_substrate_vanchor_relay_tx<'a>(
         .map_err(|e| Error(format!("Vanchor handler pallet not found: {e}")))?;
 
     let target_system = TargetSystem::Substrate(target);
-    let typed_chain_id = TypedChainId::Substrate(cmd.chain_id as u32);
     let resource_id = ResourceId::new(target_system, typed_chain_id);
 
     // update metric
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::marker::PhantomData;
+
 /// A module for listening on proposal events.
 mod proposal_signed_handler;
 #[doc(hidden)]
@@ -27,29 +29,48 @@ use webb::substrate::tangle_runtime::api::{
     dkg_proposal_handler::events::ProposalSigned,
 };
 use webb_event_watcher_traits::SubstrateEventWatcher;
+use webb_relayer_store::HistoryStore;
 
 /// The DKGMetadataWatcher watches for the events from Dkg Pallet.
-#[derive(Copy, Clone, Debug, Default)]
-pub struct DKGMetadataWatcher;
+#[derive(Clone, Debug)]
+pub struct DKGMetadataWatcher<S>(PhantomData<S>);
+
+impl<S> Default for DKGMetadataWatcher<S> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
 
 #[async_trait::async_trait]
-impl SubstrateEventWatcher<PolkadotConfig> for DKGMetadataWatcher {
+impl<S> SubstrateEventWatcher<PolkadotConfig> for DKGMetadataWatcher<S>
+where
+    S: HistoryStore,
+{
     const TAG: &'static str = "DKG Pallet Event Watcher";
 
     const PALLET_NAME: &'static str = PublicKeySignatureChanged::PALLET;
 
-    type Store = webb_relayer_store::SledStore;
+    type Store = S;
 }
 
 /// The DKGProposalHandlerWatcher watches for the events from Dkg Proposal Handler Pallet.
-#[derive(Copy, Clone, Debug, Default)]
-pub struct DKGProposalHandlerWatcher;
+#[derive(Clone, Debug)]
+pub struct DKGProposalHandlerWatcher<S>(PhantomData<S>);
+
+impl<S> Default for DKGProposalHandlerWatcher<S> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
 
 #[async_trait::async_trait]
-impl SubstrateEventWatcher<PolkadotConfig> for DKGProposalHandlerWatcher {
+impl<S> SubstrateEventWatcher<PolkadotConfig> for DKGProposalHandlerWatcher<S>
+where
+    S: HistoryStore,
+{
     const TAG: &'static str = "DKG Proposal Handler Pallet Event Watcher";
 
     const PALLET_NAME: &'static str = ProposalSigned::PALLET;
 
-    type Store = webb_relayer_store::SledStore;
+    type Store = S;
 }
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::marker::PhantomData;
 use std::sync::Arc;
 
 use tokio::sync::Mutex;
@@ -20,21 +21,30 @@ use webb::substrate::tangle_runtime::api::dkg_proposal_handler;
 use webb::substrate::tangle_runtime::api::runtime_types::webb_proposals::header::TypedChainId;
 use webb::substrate::subxt::{self, OnlineClient, PolkadotConfig};
 
-use webb_relayer_store::sled::{SledQueueKey, SledStore};
-use webb_relayer_store::{BridgeCommand, BridgeKey, QueueStore};
+use webb_relayer_store::sled::SledQueueKey;
+use webb_relayer_store::{BridgeCommand, BridgeKey, HistoryStore, QueueStore};
 use webb_relayer_utils::metric;
 
 use webb_event_watcher_traits::substrate::EventHandler;
 
 /// A ProposalSignedHandler handles the `ProposalSigned` event and signals signature bridge to execute them.
-#[derive(Copy, Clone, Debug, Default)]
-pub struct ProposalSignedHandler;
+#[derive(Clone, Debug)]
+pub struct ProposalSignedHandler<S>(PhantomData<S>);
+
+impl<S> Default for ProposalSignedHandler<S> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
 
 #[async_trait::async_trait]
-impl EventHandler<PolkadotConfig> for ProposalSignedHandler {
+impl<S> EventHandler<PolkadotConfig> for ProposalSignedHandler<S>
+where
+    S: HistoryStore + QueueStore<BridgeCommand, Key = SledQueueKey>,
+{
     type Client = OnlineClient<PolkadotConfig>;
 
-    type Store = SledStore;
+    type Store = S;
 
     async fn can_handle_events(
         &self,
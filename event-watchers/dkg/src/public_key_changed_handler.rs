@@ -12,13 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::marker::PhantomData;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use webb::substrate::subxt::{self, OnlineClient, PolkadotConfig};
 use webb::substrate::tangle_runtime::api::dkg;
 
-use webb_relayer_store::sled::{SledQueueKey, SledStore};
-use webb_relayer_store::{BridgeCommand, BridgeKey, QueueStore};
+use webb_relayer_store::sled::SledQueueKey;
+use webb_relayer_store::{
+    BridgeCommand, BridgeKey, EnqueueOptions, HistoryStore, QueuePriority,
+    QueueStore,
+};
 use webb_relayer_utils::metric;
 
 use webb_event_watcher_traits::substrate::EventHandler;
@@ -26,21 +30,28 @@ use webb_event_watcher_traits::substrate::EventHandler;
 /// DKGPublicKeyChanged handler handles the `PublicKeySignatureChanged` event and then signals
 /// signature bridge watcher to update governor.
 #[derive(Clone, Debug)]
-pub struct DKGPublicKeyChangedHandler {
+pub struct DKGPublicKeyChangedHandler<S> {
     webb_config: webb_relayer_config::WebbRelayerConfig,
+    _store: PhantomData<S>,
 }
 
-impl DKGPublicKeyChangedHandler {
+impl<S> DKGPublicKeyChangedHandler<S> {
     pub fn new(webb_config: webb_relayer_config::WebbRelayerConfig) -> Self {
-        Self { webb_config }
+        Self {
+            webb_config,
+            _store: PhantomData,
+        }
     }
 }
 
 #[async_trait::async_trait]
-impl EventHandler<PolkadotConfig> for DKGPublicKeyChangedHandler {
+impl<S> EventHandler<PolkadotConfig> for DKGPublicKeyChangedHandler<S>
+where
+    S: HistoryStore + QueueStore<BridgeCommand, Key = SledQueueKey>,
+{
     type Client = OnlineClient<PolkadotConfig>;
 
-    type Store = SledStore;
+    type Store = S;
 
     async fn can_handle_events(
         &self,
@@ -107,13 +118,15 @@ impl EventHandler<PolkadotConfig> for DKGPublicKeyChangedHandler {
                     nonce = %nonce,
                     signature = %hex::encode(&event.pub_key_sig),
                 );
-                store.enqueue_item(
+                // ownership transfers go ahead of the routine bridge commands.
+                store.enqueue_item_with_options(
                     SledQueueKey::from_bridge_key(bridge_key),
                     BridgeCommand::TransferOwnershipWithSignature {
                         public_key: public_key_uncompressed.clone(),
                         nonce,
                         signature: event.pub_key_sig.clone(),
                     },
+                    EnqueueOptions::with_priority(QueuePriority::High),
                 )?;
             }
         }
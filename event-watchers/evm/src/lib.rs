@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::marker::PhantomData;
 use std::ops;
 use std::sync::Arc;
 use std::time::Duration;
@@ -33,7 +34,7 @@ mod tests;
 
 use webb_event_watcher_traits::evm::{EventWatcher, WatchableContract};
 use webb_event_watcher_traits::EthersTimeLagClient;
-use webb_relayer_store::SledStore;
+use webb_relayer_store::{EventHashStore, HistoryStore, ReorgStore};
 
 // VAnchorContractWrapper contains VAnchorContract contract along with configurations for Anchor contract, and Relayer.
 #[derive(Clone, Debug)]
@@ -104,17 +105,26 @@ where
 pub struct AnchorContractWatcher;
 
 /// An VAnchor Contract Watcher that watches for the Anchor contract events and calls the event
-/// handlers.
-#[derive(Copy, Clone, Debug, Default)]
-pub struct VAnchorContractWatcher;
+/// handlers, using the store `S`.
+#[derive(Clone, Debug)]
+pub struct VAnchorContractWatcher<S>(PhantomData<S>);
+
+impl<S> Default for VAnchorContractWatcher<S> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
 
 #[async_trait::async_trait]
-impl EventWatcher for VAnchorContractWatcher {
+impl<S> EventWatcher for VAnchorContractWatcher<S>
+where
+    S: HistoryStore + EventHashStore + ReorgStore,
+{
     const TAG: &'static str = "VAnchor Contract Watcher";
 
     type Contract = VAnchorContractWrapper<EthersTimeLagClient>;
 
     type Events = VAnchorContractEvents;
 
-    type Store = SledStore;
+    type Store = S;
 }
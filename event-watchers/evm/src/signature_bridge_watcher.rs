@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::marker::PhantomData;
 use std::ops;
 use std::sync::Arc;
 use std::time::Duration;
@@ -20,6 +21,7 @@ use tokio::sync::Mutex;
 use webb::evm::contract::protocol_solidity::{
     SignatureBridgeContract, SignatureBridgeContractEvents,
 };
+use webb::evm::ethers::abi;
 use webb::evm::ethers::contract::Contract;
 use webb::evm::ethers::core::types::transaction::eip2718::TypedTransaction;
 use webb::evm::ethers::prelude::*;
@@ -30,8 +32,11 @@ use webb_event_watcher_traits::evm::{
     BridgeWatcher, EventHandler, EventWatcher, WatchableContract,
 };
 use webb_event_watcher_traits::EthersTimeLagClient;
-use webb_relayer_store::sled::{SledQueueKey, SledStore};
-use webb_relayer_store::{BridgeCommand, QueueStore};
+use webb_relayer_store::sled::SledQueueKey;
+use webb_relayer_store::{
+    BridgeCommand, EnqueueOptions, EventHashStore, HistoryStore, QueuePriority,
+    QueueStore, ReorgStore,
+};
 use webb_relayer_utils::metric;
 
 /// A Wrapper around the `SignatureBridgeContract` contract.
@@ -93,31 +98,54 @@ impl<M: Middleware> WatchableContract for SignatureBridgeContractWrapper<M> {
     }
 }
 
-/// A SignatureBridge contract events & commands watcher.
-#[derive(Copy, Clone, Debug, Default)]
-pub struct SignatureBridgeContractWatcher;
+/// A SignatureBridge contract events & commands watcher, using the store `S`.
+#[derive(Clone, Debug)]
+pub struct SignatureBridgeContractWatcher<S>(PhantomData<S>);
+
+impl<S> Default for SignatureBridgeContractWatcher<S> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
 
-#[derive(Copy, Clone, Debug, Default)]
-pub struct SignatureBridgeGovernanceOwnershipTransferredHandler;
+#[derive(Clone, Debug)]
+pub struct SignatureBridgeGovernanceOwnershipTransferredHandler<S>(
+    PhantomData<S>,
+);
+
+impl<S> Default for SignatureBridgeGovernanceOwnershipTransferredHandler<S> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
 
 #[async_trait::async_trait]
-impl EventWatcher for SignatureBridgeContractWatcher {
+impl<S> EventWatcher for SignatureBridgeContractWatcher<S>
+where
+    S: HistoryStore + EventHashStore + ReorgStore,
+{
     const TAG: &'static str = "Signature Bridge Watcher";
 
     type Contract = SignatureBridgeContractWrapper<EthersTimeLagClient>;
 
     type Events = SignatureBridgeContractEvents;
 
-    type Store = SledStore;
+    type Store = S;
 }
 
 #[async_trait::async_trait]
-impl EventHandler for SignatureBridgeGovernanceOwnershipTransferredHandler {
+impl<S> EventHandler for SignatureBridgeGovernanceOwnershipTransferredHandler<S>
+where
+    S: HistoryStore
+        + EventHashStore
+        + ReorgStore
+        + QueueStore<TypedTransaction, Key = SledQueueKey>,
+{
     type Contract = SignatureBridgeContractWrapper<EthersTimeLagClient>;
 
     type Events = SignatureBridgeContractEvents;
 
-    type Store = SledStore;
+    type Store = S;
 
     async fn can_handle_events(
         &self,
@@ -168,7 +196,14 @@ impl EventHandler for SignatureBridgeGovernanceOwnershipTransferredHandler {
 }
 
 #[async_trait::async_trait]
-impl BridgeWatcher for SignatureBridgeContractWatcher {
+impl<S> BridgeWatcher for SignatureBridgeContractWatcher<S>
+where
+    S: HistoryStore
+        + EventHashStore
+        + ReorgStore
+        + QueueStore<TypedTransaction, Key = SledQueueKey>
+        + QueueStore<BridgeCommand, Key = SledQueueKey>,
+{
     #[tracing::instrument(skip_all)]
     async fn handle_cmd(
         &self,
@@ -204,14 +239,14 @@ impl BridgeWatcher for SignatureBridgeContractWatcher {
     }
 }
 
-impl SignatureBridgeContractWatcher
+impl<S> SignatureBridgeContractWatcher<S>
 where
-    Self: BridgeWatcher,
+    S: QueueStore<TypedTransaction, Key = SledQueueKey>,
 {
     #[tracing::instrument(skip_all)]
     async fn execute_proposal_with_signature(
         &self,
-        store: Arc<<Self as EventWatcher>::Store>,
+        store: Arc<S>,
         contract: &SignatureBridgeContract<EthersTimeLagClient>,
         (proposal_data, signature): (Vec<u8>, Vec<u8>),
     ) -> webb_relayer_utils::Result<()> {
@@ -245,21 +280,32 @@ where
         }
 
         // 3. Verify proposal signature. Proposal should be signed by active maintainer/dkg-key
-        let (proposal_data_clone, signature_clone) =
-            (proposal_data.clone(), signature.clone());
-        let is_signature_valid = contract
-            .is_signature_from_governor(
-                proposal_data_clone.into(),
-                signature_clone.into(),
-            )
-            .call()
-            .await?;
-
         let governor = contract.governor().call().await?;
         tracing::debug!(
             governor = ?hex::encode(governor),
             "GOVERNOR",
         );
+        let is_signature_valid = if signature.len() > SIGNATURE_LENGTH {
+            // several signatures, of the `Threshold` signing backend, which
+            // only a governor contract could verify.
+            is_valid_governor_contract_signature(
+                contract,
+                governor,
+                proposal_data_hash,
+                &signature,
+            )
+            .await
+        } else {
+            let (proposal_data_clone, signature_clone) =
+                (proposal_data.clone(), signature.clone());
+            contract
+                .is_signature_from_governor(
+                    proposal_data_clone.into(),
+                    signature_clone.into(),
+                )
+                .call()
+                .await?
+        };
         let signature_hex = hex::encode(&signature);
         if !is_signature_valid {
             tracing::warn!(
@@ -297,7 +343,7 @@ where
     #[tracing::instrument(skip_all)]
     async fn transfer_ownership_with_signature(
         &self,
-        store: Arc<<Self as EventWatcher>::Store>,
+        store: Arc<S>,
         contract: &SignatureBridgeContract<EthersTimeLagClient>,
         (public_key, nonce, signature): (Vec<u8>, u32, Vec<u8>),
     ) -> webb_relayer_utils::Result<()> {
@@ -392,7 +438,13 @@ where
             )
             .gas(estimate_gas.saturating_mul(U256::from(2)));
 
-        QueueStore::<TypedTransaction>::enqueue_item(&store, tx_key, call.tx)?;
+        // ownership transfers go ahead of the routine transactions.
+        QueueStore::<TypedTransaction>::enqueue_item_with_options(
+            &store,
+            tx_key,
+            call.tx,
+            EnqueueOptions::with_priority(QueuePriority::High),
+        )?;
         tracing::debug!(
             chain_id = %chain_id.as_u64(),
             "Enqueued the ownership transfer for execution in the tx queue",
@@ -401,6 +453,48 @@ where
     }
 }
 
+/// The length of a single ECDSA signature, `r || s || v`.
+const SIGNATURE_LENGTH: usize = 65;
+
+/// The selector of `isValidSignature(bytes32,bytes)` of [EIP-1271], which is also
+/// the value returned for a valid signature.
+///
+/// [EIP-1271]: https://eips.ethereum.org/EIPS/eip-1271
+const EIP1271_MAGIC_VALUE: [u8; 4] = [0x16, 0x26, 0xba, 0x7e];
+
+/// Checks the concatenated signatures of the `Threshold` signing backend with the
+/// governor of the signature bridge, which must be a contract verifying them
+/// through [EIP-1271], like a multisig checking `n-of-m` signatures.
+///
+/// Returns `false` if the governor rejects them, or is not such a contract.
+///
+/// [EIP-1271]: https://eips.ethereum.org/EIPS/eip-1271
+async fn is_valid_governor_contract_signature(
+    contract: &SignatureBridgeContract<EthersTimeLagClient>,
+    governor: Address,
+    proposal_data_hash: [u8; 32],
+    signature: &[u8],
+) -> bool {
+    let args = abi::encode(&[
+        abi::Token::FixedBytes(proposal_data_hash.to_vec()),
+        abi::Token::Bytes(signature.to_vec()),
+    ]);
+    let data = [&EIP1271_MAGIC_VALUE[..], &args].concat();
+    let call: TypedTransaction =
+        TransactionRequest::new().to(governor).data(data).into();
+    match contract.client().call(&call, None).await {
+        Ok(output) => output.get(..4) == Some(&EIP1271_MAGIC_VALUE[..]),
+        Err(e) => {
+            tracing::debug!(
+                governor = ?hex::encode(governor),
+                "The governor did not verify the signatures: {}",
+                e
+            );
+            false
+        }
+    }
+}
+
 fn make_execute_proposal_key(data_hash: [u8; 32]) -> [u8; 64] {
     let mut result = [0u8; 64];
     let prefix = b"execute_proposal_with_signature_";
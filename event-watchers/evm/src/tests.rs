@@ -38,3 +38,43 @@ fn test_merkle_root() {
         "304341db4305ca71db912b3ea85acb4ab8f687435aa51a9a65220bfc558eb8d1";
     assert_eq!(hex_root, expected_root);
 }
+
+// the incremental merkle tree should match the sparse merkle tree.
+#[test]
+fn test_incremental_merkle_root() {
+    use crate::vanchor::PoseidonHasher;
+    use std::sync::Arc;
+    use webb::evm::ethers::types::H256;
+    use webb_relayer_store::merkle_tree::IncrementalMerkleTree;
+
+    let relayer_leaves = vec![
+        "0x017dc570cb5c6807dbaa475c9d4e445ac95a73400692541c367786c009c844cf",
+        "0x04568790fcfc67d855dfb60de6844f6d82f4b8dc6dd0115f9f04ece21ebffb8d",
+    ];
+    let leaves: BTreeMap<u32, H256> = parse_vec(relayer_leaves)
+        .unwrap()
+        .iter()
+        .enumerate()
+        .map(|(i, l)| (i as u32, H256::from_slice(l)))
+        .collect();
+    let default_leaf_hex = vec![
+        "0x2fe54c60d3acabf3343a35b6eba15db4821b340f76e741e2249685ed4899af6c",
+    ];
+    let default_leaf_scalar: Vec<Bn254Fr> =
+        bytes_vec_to_f(&parse_vec(default_leaf_hex).unwrap());
+    let default_leaf =
+        H256::from_slice(&default_leaf_scalar[0].into_repr().to_bytes_be());
+    let tree = IncrementalMerkleTree::from_leaves(
+        30,
+        default_leaf,
+        Arc::new(PoseidonHasher::new()),
+        &leaves,
+    )
+    .unwrap();
+    let expected_root =
+        "304341db4305ca71db912b3ea85acb4ab8f687435aa51a9a65220bfc558eb8d1";
+    assert_eq!(hex::encode(tree.root()), expected_root);
+    let proof = tree.proof(1).unwrap();
+    assert_eq!(proof.path_indices[0], 1);
+    assert_eq!(proof.path_elements[0], leaves[&0]);
+}
@@ -28,34 +28,34 @@ use webb_proposal_signing_backends::queue::{
     ProposalsQueue, QueuedAnchorUpdateProposal,
 };
 use webb_relayer_config::anchor::LinkedAnchorConfig;
-use webb_relayer_store::SledStore;
-use webb_relayer_store::{EventHashStore, HistoryStore};
+use webb_relayer_store::{EventHashStore, HistoryStore, ReorgStore};
 use webb_relayer_utils::metric;
 
 /// Represents an VAnchor Contract Watcher which will use a configured signing backend for signing proposals.
 #[derive(typed_builder::TypedBuilder)]
-pub struct VAnchorDepositHandler<Q, P, C> {
+pub struct VAnchorDepositHandler<Q, P, C, S> {
     #[builder(setter(into))]
     chain_id: types::U256,
     #[builder(setter(into))]
-    store: Arc<SledStore>,
+    store: Arc<S>,
     proposals_queue: Q,
     policy: P,
     bridge_registry_backend: C,
 }
 
 #[async_trait::async_trait]
-impl<Q, P, C> EventHandler for VAnchorDepositHandler<Q, P, C>
+impl<Q, P, C, S> EventHandler for VAnchorDepositHandler<Q, P, C, S>
 where
     Q: ProposalsQueue<Proposal = QueuedAnchorUpdateProposal> + Send + Sync,
     P: ProposalPolicy + Send + Sync + Clone,
     C: BridgeRegistryBackend + Send + Sync,
+    S: HistoryStore + EventHashStore + ReorgStore,
 {
     type Contract = VAnchorContractWrapper<EthersTimeLagClient>;
 
     type Events = VAnchorContractEvents;
 
-    type Store = SledStore;
+    type Store = S;
 
     async fn can_handle_events(
         &self,
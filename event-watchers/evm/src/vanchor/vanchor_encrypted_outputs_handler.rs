@@ -14,6 +14,7 @@
 
 use super::VAnchorContractWrapper;
 use ethereum_types::H256;
+use std::marker::PhantomData;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use webb::evm::contract::protocol_solidity::VAnchorContractEvents;
@@ -22,30 +23,38 @@ use webb::evm::ethers::types;
 use webb_event_watcher_traits::evm::EventHandler;
 use webb_event_watcher_traits::EthersTimeLagClient;
 use webb_proposals::{ResourceId, TargetSystem, TypedChainId};
-use webb_relayer_store::SledStore;
-use webb_relayer_store::{EncryptedOutputCacheStore, EventHashStore};
+use webb_relayer_store::{
+    BatchStore, EventHashStore, HistoryStore, ReorgStore,
+};
 use webb_relayer_utils::metric;
 
 /// An Encrypted Output Handler that handles `NewCommitment` events and saves the encrypted_output to the store.
 /// It serves as a cache for encrypted_output that could be used by dApp for proof generation.
-#[derive(Copy, Clone, Debug)]
-pub struct VAnchorEncryptedOutputHandler {
+#[derive(Clone, Debug)]
+pub struct VAnchorEncryptedOutputHandler<S> {
     chain_id: types::U256,
+    _store: PhantomData<S>,
 }
 
-impl VAnchorEncryptedOutputHandler {
+impl<S> VAnchorEncryptedOutputHandler<S> {
     pub fn new(chain_id: types::U256) -> Self {
-        Self { chain_id }
+        Self {
+            chain_id,
+            _store: PhantomData,
+        }
     }
 }
 
 #[async_trait::async_trait]
-impl EventHandler for VAnchorEncryptedOutputHandler {
+impl<S> EventHandler for VAnchorEncryptedOutputHandler<S>
+where
+    S: HistoryStore + EventHashStore + ReorgStore + BatchStore,
+{
     type Contract = VAnchorContractWrapper<EthersTimeLagClient>;
 
     type Events = VAnchorContractEvents;
 
-    type Store = SledStore;
+    type Store = S;
 
     async fn can_handle_events(
         &self,
@@ -79,13 +88,18 @@ impl EventHandler for VAnchorEncryptedOutputHandler {
                 let history_store_key =
                     ResourceId::new(target_system, typed_chain_id);
 
-                store.insert_encrypted_output_and_last_deposit_block_number(
-                    history_store_key,
-                    &[value.clone()],
-                    log.block_number.as_u64(),
-                )?;
                 let events_bytes = serde_json::to_vec(&deposit)?;
-                store.store_event(&events_bytes)?;
+                store.batch(|batch| {
+                    batch
+                        .insert_encrypted_output_and_last_deposit_block_number(
+                            history_store_key,
+                            &[value.clone()],
+                            log.block_number.as_u64(),
+                            log.block_hash,
+                        )
+                        .store_event(&events_bytes);
+                    Ok(())
+                })?;
                 tracing::trace!(
                     %log.block_number,
                     "detected block number",
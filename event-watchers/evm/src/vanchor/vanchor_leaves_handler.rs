@@ -15,97 +15,237 @@
 use super::VAnchorContractWrapper;
 use ark_bn254::Fr as Bn254Fr;
 use ark_ff::{BigInteger, PrimeField};
-use arkworks_native_gadgets::merkle_tree::SparseMerkleTree;
-use arkworks_native_gadgets::poseidon::Poseidon;
+use arkworks_native_gadgets::poseidon::{FieldHasher, Poseidon};
 use arkworks_setups::common::setup_params;
 use arkworks_setups::Curve;
 use arkworks_utils::bytes_vec_to_f;
 use ethereum_types::{H256, U256};
 use std::collections::BTreeMap;
+use std::marker::PhantomData;
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use webb::evm::contract::protocol_solidity::v_anchor_contract::NewCommitmentFilter;
 use webb::evm::contract::protocol_solidity::VAnchorContractEvents;
 use webb::evm::ethers::prelude::LogMeta;
 use webb::evm::ethers::types;
 use webb_event_watcher_traits::evm::EventHandler;
 use webb_event_watcher_traits::EthersTimeLagClient;
 use webb_proposals::{ResourceId, TargetSystem, TypedChainId};
-use webb_relayer_store::SledStore;
-use webb_relayer_store::{EventHashStore, LeafCacheStore};
+use webb_relayer_store::merkle_tree::{
+    IncrementalMerkleTree, MerkleHasher, MerkleTreeCache, SharedMerkleTree,
+};
+use webb_relayer_store::{
+    BatchStore, EventHashStore, HistoryStore, LeafCacheStore, ReorgStore,
+};
 use webb_relayer_utils::metric;
 use webb_relayer_utils::Error;
 
-/// An VAnchor Leaves Handler that handles `NewCommitment` events and saves the leaves to the store.
-/// It serves as a cache for leaves that could be used by dApp for proof generation.
+/// The depth of the VAnchor merkle trees.
+const TREE_DEPTH: usize = 30;
 
-type MerkleTree = SparseMerkleTree<Bn254Fr, Poseidon<Bn254Fr>, 30>;
+/// A [`MerkleHasher`] using the Poseidon hash function over the Bn254 scalar field.
+pub struct PoseidonHasher(Poseidon<Bn254Fr>);
+
+impl PoseidonHasher {
+    /// Creates the Poseidon hasher used by the VAnchor contracts.
+    pub fn new() -> Self {
+        let params = setup_params::<Bn254Fr>(Curve::Bn254, 5, 3);
+        Self(Poseidon::<Bn254Fr>::new(params))
+    }
+}
 
-pub struct VAnchorLeavesHandler {
-    mt: Arc<Mutex<MerkleTree>>,
-    hasher: Poseidon<Bn254Fr>,
+impl Default for PoseidonHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MerkleHasher for PoseidonHasher {
+    fn hash(
+        &self,
+        left: &types::H256,
+        right: &types::H256,
+    ) -> webb_relayer_utils::Result<types::H256> {
+        let left = Bn254Fr::from_be_bytes_mod_order(left.as_bytes());
+        let right = Bn254Fr::from_be_bytes_mod_order(right.as_bytes());
+        let parent = self
+            .0
+            .hash_two(&left, &right)
+            .map_err(|e| Error::ArkworksError(e.to_string()))?;
+        Ok(types::H256::from_slice(&parent.into_repr().to_bytes_be()))
+    }
+}
+
+/// An VAnchor Leaves Handler that handles `NewCommitment` events and saves the leaves to the store.
+/// It serves as a cache for leaves that could be used by dApp for proof generation.
+///
+/// It also keeps the merkle tree of the VAnchor in the [`MerkleTreeCache`], so the relayer
+/// could serve its root and the membership proofs of its leaves.
+pub struct VAnchorLeavesHandler<S> {
+    mt: SharedMerkleTree,
     chain_id: types::U256,
+    empty_leaf: types::H256,
+    _store: PhantomData<S>,
 }
 
-impl VAnchorLeavesHandler {
+impl<S> VAnchorLeavesHandler<S>
+where
+    S: LeafCacheStore<Output = BTreeMap<u32, types::H256>> + BatchStore,
+{
     /// Creates a new Leaves Handler for the given contract address.
     /// on the given chain id.
     ///
     /// Using the storage, it will try to load any old leaves and
-    /// construct the merkle tree in memory.
+    /// construct the merkle tree in memory, then registers it in the given cache.
     pub fn new(
         chain_id: types::U256,
         contract_address: types::Address,
-        storage: Arc<SledStore>,
+        storage: Arc<S>,
         empty_leaf: Vec<u8>,
+        merkle_trees: MerkleTreeCache,
     ) -> webb_relayer_utils::Result<Self> {
-        let params = setup_params::<Bn254Fr>(Curve::Bn254, 5, 3);
-        let poseidon = Poseidon::<Bn254Fr>::new(params);
         let empty_leaf_scalar: Vec<Bn254Fr> = bytes_vec_to_f(&vec![empty_leaf]);
-        let empty_leaf_vec = empty_leaf_scalar
+        let empty_leaf = empty_leaf_scalar
             .get(0)
-            .map(|d| d.into_repr().to_bytes_be())
+            .map(|d| types::H256::from_slice(&d.into_repr().to_bytes_be()))
             .ok_or(webb_relayer_utils::Error::ConvertLeafScalarError)?;
+        let history_store_key =
+            Self::history_store_key(chain_id, contract_address);
+        let mt =
+            Self::load_merkle_tree(history_store_key, &storage, empty_leaf)?;
 
+        Ok(Self {
+            chain_id,
+            mt: merkle_trees.insert(history_store_key, mt),
+            empty_leaf,
+            _store: PhantomData,
+        })
+    }
+
+    fn history_store_key(
+        chain_id: types::U256,
+        contract_address: types::Address,
+    ) -> ResourceId {
         let target_system = TargetSystem::new_contract_address(
             contract_address.to_fixed_bytes(),
         );
         let typed_chain_id = TypedChainId::Evm(chain_id.as_u32());
-        let history_store_key = ResourceId::new(target_system, typed_chain_id);
+        ResourceId::new(target_system, typed_chain_id)
+    }
+
+    /// Constructs the merkle tree from the leaves in the store.
+    fn load_merkle_tree(
+        history_store_key: ResourceId,
+        storage: &S,
+        empty_leaf: types::H256,
+    ) -> webb_relayer_utils::Result<IncrementalMerkleTree> {
         // Load all the old leaves
         let leaves = storage.get_leaves(history_store_key)?;
-        let mut batch: BTreeMap<u32, Bn254Fr> = BTreeMap::new();
-        for (i, leaf) in leaves.into_iter() {
+        for (i, leaf) in leaves.iter() {
             tracing::trace!(
                 leaf_index = i,
                 leaf = hex::encode(leaf.as_bytes()),
                 "Inserting leaf into merkle tree",
             );
-
-            let leaf: Bn254Fr =
-                Bn254Fr::from_be_bytes_mod_order(leaf.as_bytes());
-            batch.insert(i as _, leaf);
         }
-        let mt = MerkleTree::new(&batch, &poseidon, &empty_leaf_vec)?;
+        let mt = IncrementalMerkleTree::from_leaves(
+            TREE_DEPTH,
+            empty_leaf,
+            Arc::new(PoseidonHasher::new()),
+            &leaves,
+        )?;
         tracing::debug!(
-            root = hex::encode(mt.root().into_repr().to_bytes_be()),
+            root = hex::encode(mt.root()),
             "Loaded merkle tree from store",
         );
+        Ok(mt)
+    }
 
-        Ok(Self {
-            chain_id,
-            mt: Arc::new(Mutex::new(mt)),
-            hasher: poseidon,
-        })
+    /// Validates the leaf of the `NewCommitment` event, then inserts it into the
+    /// merkle tree and the store.
+    ///
+    /// The leaf is inserted into the shared tree in place, before it is stored.
+    /// If this fails, the tree is left with the leaf, and must be rebuilt from the store.
+    async fn insert_leaf(
+        &self,
+        store: &S,
+        wrapper: &VAnchorContractWrapper<EthersTimeLagClient>,
+        history_store_key: ResourceId,
+        event_data: &NewCommitmentFilter,
+        log: &LogMeta,
+    ) -> webb_relayer_utils::Result<()> {
+        let commitment: [u8; 32] = event_data.commitment.into();
+        let leaf_index = event_data.leaf_index.as_u32();
+        // 1. We will validate leaf before inserting it into store.
+        let root_bytes = {
+            let mut mt = self.mt.lock();
+            mt.insert(leaf_index, types::H256::from(commitment))?;
+            mt.root()
+        };
+        // If leaf index is even number then we don't need to verify commitment
+        if leaf_index % 2 == 0 {
+            tracing::debug!(
+                leaf_index = leaf_index,
+                commitment = hex::encode(commitment.as_slice()),
+                "Verified commitment",
+            );
+        } else {
+            // We will verify commitment
+            let root = U256::from_big_endian(root_bytes.as_bytes());
+            let is_known_root = wrapper
+                .contract
+                .is_known_root(root)
+                .block(log.block_number)
+                .call()
+                .await?;
+
+            tracing::debug!(
+                leaf_index = leaf_index,
+                root = hex::encode(root_bytes.as_bytes()),
+                is_known_root,
+                "New commitment need to be verified",
+            );
+
+            if !is_known_root {
+                tracing::warn!(
+                    expected_root = ?root,
+                    "Invalid merkle root. Maybe invalid leaf or commitment"
+                );
+                return Err(Error::InvalidMerkleRootError(leaf_index));
+            }
+        }
+        // 2. We will insert leaf and last deposit block number into store,
+        // along with the event, so that a crash could not leave one without the other.
+        let events_bytes = serde_json::to_vec(event_data)?;
+        store.batch(|batch| {
+            batch
+                .insert_leaves_and_last_deposit_block_number(
+                    history_store_key,
+                    &[(leaf_index, commitment.to_vec())],
+                    log.block_number.as_u64(),
+                    log.block_hash,
+                )
+                .store_event(&events_bytes);
+            Ok(())
+        })?;
+        Ok(())
     }
 }
 
 #[async_trait::async_trait]
-impl EventHandler for VAnchorLeavesHandler {
+impl<S> EventHandler for VAnchorLeavesHandler<S>
+where
+    S: HistoryStore
+        + EventHashStore
+        + ReorgStore
+        + LeafCacheStore<Output = BTreeMap<u32, types::H256>>
+        + BatchStore,
+{
     type Contract = VAnchorContractWrapper<EthersTimeLagClient>;
 
     type Events = VAnchorContractEvents;
 
-    type Store = SledStore;
+    type Store = S;
 
     async fn can_handle_events(
         &self,
@@ -117,6 +257,28 @@ impl EventHandler for VAnchorLeavesHandler {
         Ok(has_event)
     }
 
+    #[tracing::instrument(skip_all)]
+    async fn handle_rollback(
+        &self,
+        store: Arc<Self::Store>,
+        wrapper: &Self::Contract,
+        block_number: u64,
+    ) -> webb_relayer_utils::Result<()> {
+        // the orphaned leaves are gone from the store, rebuild the tree without them.
+        let history_store_key =
+            Self::history_store_key(self.chain_id, wrapper.contract.address());
+        let rebuilt =
+            Self::load_merkle_tree(history_store_key, &store, self.empty_leaf)?;
+        let mut mt = self.mt.lock();
+        *mt = rebuilt;
+        tracing::debug!(
+            block_number,
+            root = hex::encode(mt.root()),
+            "Rebuilt merkle tree after rollback",
+        );
+        Ok(())
+    }
+
     #[tracing::instrument(skip_all)]
     async fn handle_event(
         &self,
@@ -126,71 +288,36 @@ impl EventHandler for VAnchorLeavesHandler {
         _metrics: Arc<Mutex<metric::Metrics>>,
     ) -> webb_relayer_utils::Result<()> {
         use VAnchorContractEvents::*;
-        let mut batch: BTreeMap<u32, Bn254Fr> = BTreeMap::new();
-        let mut mt = self.mt.lock().await;
-        // We will clone the tree to compare it with the new one.
-        let mt_snapshot = mt.tree.clone();
-
         match event {
             NewCommitmentFilter(event_data) => {
                 let commitment: [u8; 32] = event_data.commitment.into();
                 let leaf_index = event_data.leaf_index.as_u32();
                 let value = (leaf_index, commitment.to_vec());
-                let target_system = TargetSystem::new_contract_address(
-                    wrapper.contract.address().to_fixed_bytes(),
+                let history_store_key = Self::history_store_key(
+                    self.chain_id,
+                    wrapper.contract.address(),
                 );
-                let typed_chain_id = TypedChainId::Evm(self.chain_id.as_u32());
-                let history_store_key =
-                    ResourceId::new(target_system, typed_chain_id);
-
-                // 1. We will validate leaf before inserting it into store
-                let leaf: Bn254Fr =
-                    Bn254Fr::from_be_bytes_mod_order(commitment.as_slice());
-                batch.insert(leaf_index, leaf);
-                mt.insert_batch(&batch, &self.hasher)?;
-                // If leaf index is even number then we don't need to verify commitment
-                if event_data.leaf_index.as_u32() % 2 == 0 {
-                    tracing::debug!(
-                        leaf_index = leaf_index,
-                        commitment = hex::encode(commitment.as_slice()),
-                        "Verified commitment",
-                    );
-                } else {
-                    // We will verify commitment
-                    let root_bytes = mt.root().into_repr().to_bytes_be();
-                    let root = U256::from_big_endian(root_bytes.as_slice());
-                    let is_known_root = wrapper
-                        .contract
-                        .is_known_root(root)
-                        .block(log.block_number)
-                        .call()
-                        .await?;
-
-                    tracing::debug!(
-                        leaf_index = leaf_index,
-                        root = hex::encode(root_bytes.as_slice()),
-                        is_known_root,
-                        "New commitment need to be verified",
-                    );
-
-                    if !is_known_root {
-                        tracing::warn!(
-                            expected_root = ?root,
-                            "Invalid merkle root. Maybe invalid leaf or commitment"
-                        );
-                        // Restore previous state of the tree.
-                        mt.tree = mt_snapshot;
-                        return Err(Error::InvalidMerkleRootError(leaf_index));
-                    }
+
+                let result = self
+                    .insert_leaf(
+                        &store,
+                        wrapper,
+                        history_store_key,
+                        &event_data,
+                        &log,
+                    )
+                    .await;
+                if let Err(e) = result {
+                    // the store batch is atomic, so the store has none of the leaf:
+                    // rebuild the tree from it to drop the leaf from the tree too.
+                    let rebuilt = Self::load_merkle_tree(
+                        history_store_key,
+                        &store,
+                        self.empty_leaf,
+                    )?;
+                    *self.mt.lock() = rebuilt;
+                    return Err(e);
                 }
-                // 2. We will insert leaf and last deposit block number into store
-                store.insert_leaves_and_last_deposit_block_number(
-                    history_store_key,
-                    &[value.clone()],
-                    log.block_number.as_u64(),
-                )?;
-                let events_bytes = serde_json::to_vec(&event_data)?;
-                store.store_event(&events_bytes)?;
                 tracing::trace!(
                     %log.block_number,
                     "detected block number",
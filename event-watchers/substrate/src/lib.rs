@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::marker::PhantomData;
+
 mod signature_bridge_watcher;
 mod vanchor_deposit_handler;
 mod vanchor_encrypted_output_handler;
@@ -31,16 +33,26 @@ use webb::substrate::{
     tangle_runtime::api::v_anchor_bn254::events::Transaction,
 };
 use webb_event_watcher_traits::SubstrateEventWatcher;
-use webb_relayer_store::SledStore;
+use webb_relayer_store::HistoryStore;
+
+#[derive(Clone, Debug)]
+pub struct SubstrateVAnchorEventWatcher<S>(PhantomData<S>);
 
-#[derive(Copy, Clone, Debug, Default)]
-pub struct SubstrateVAnchorEventWatcher;
+impl<S> Default for SubstrateVAnchorEventWatcher<S> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
 
 #[async_trait::async_trait]
-impl SubstrateEventWatcher<PolkadotConfig> for SubstrateVAnchorEventWatcher {
+impl<S> SubstrateEventWatcher<PolkadotConfig>
+    for SubstrateVAnchorEventWatcher<S>
+where
+    S: HistoryStore,
+{
     const TAG: &'static str = "Substrate VAnchor Event Watcher";
 
     const PALLET_NAME: &'static str = Transaction::PALLET;
 
-    type Store = SledStore;
+    type Store = S;
 }
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::marker::PhantomData;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -25,8 +26,10 @@ use webb_event_watcher_traits::substrate::{
     EventHandler, SubstrateBridgeWatcher,
 };
 use webb_event_watcher_traits::SubstrateEventWatcher;
-use webb_relayer_store::sled::{SledQueueKey, SledStore};
-use webb_relayer_store::{BridgeCommand, QueueStore};
+use webb_relayer_store::sled::SledQueueKey;
+use webb_relayer_store::{
+    BridgeCommand, EnqueueOptions, HistoryStore, QueuePriority, QueueStore,
+};
 
 use webb::evm::ethers::utils;
 use webb::substrate::tangle_runtime::api as RuntimeApi;
@@ -40,14 +43,23 @@ use webb::substrate::tangle_runtime::api::runtime_types::bounded_collections::bo
 
 /// A MaintainerSetEvent handler handles `MaintainerSet` events and signals signature bridge watcher
 /// to remove pending tx trying to do governor transfer.
-#[derive(Copy, Clone, Debug, Default)]
-pub struct MaintainerSetEventHandler;
+#[derive(Clone, Debug)]
+pub struct MaintainerSetEventHandler<S>(PhantomData<S>);
+
+impl<S> Default for MaintainerSetEventHandler<S> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
 
 #[async_trait::async_trait]
-impl EventHandler<PolkadotConfig> for MaintainerSetEventHandler {
+impl<S> EventHandler<PolkadotConfig> for MaintainerSetEventHandler<S>
+where
+    S: HistoryStore,
+{
     type Client = OnlineClient<PolkadotConfig>;
 
-    type Store = SledStore;
+    type Store = S;
 
     async fn can_handle_events(
         &self,
@@ -91,18 +103,34 @@ impl EventHandler<PolkadotConfig> for MaintainerSetEventHandler {
     }
 }
 
-/// A SignatureBridge watcher watches for signature bridge events and bridge commands.
-#[derive(Copy, Clone, Debug, Default)]
-pub struct SubstrateBridgeEventWatcher;
+/// A SignatureBridge watcher watches for signature bridge events and bridge commands,
+/// using the store `S`.
+#[derive(Clone, Debug)]
+pub struct SubstrateBridgeEventWatcher<S>(PhantomData<S>);
 
-impl SubstrateEventWatcher<PolkadotConfig> for SubstrateBridgeEventWatcher {
+impl<S> Default for SubstrateBridgeEventWatcher<S> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<S> SubstrateEventWatcher<PolkadotConfig> for SubstrateBridgeEventWatcher<S>
+where
+    S: HistoryStore,
+{
     const TAG: &'static str = "Substrate bridge pallet Watcher";
     const PALLET_NAME: &'static str = MaintainerSet::PALLET;
-    type Store = SledStore;
+    type Store = S;
 }
 
 #[async_trait::async_trait]
-impl SubstrateBridgeWatcher<PolkadotConfig> for SubstrateBridgeEventWatcher {
+impl<S> SubstrateBridgeWatcher<PolkadotConfig>
+    for SubstrateBridgeEventWatcher<S>
+where
+    S: HistoryStore
+        + QueueStore<BridgeCommand, Key = SledQueueKey>
+        + QueueStore<TypeErasedStaticTxPayload, Key = SledQueueKey>,
+{
     #[tracing::instrument(skip_all)]
     async fn handle_cmd(
         &self,
@@ -142,15 +170,15 @@ impl SubstrateBridgeWatcher<PolkadotConfig> for SubstrateBridgeEventWatcher {
     }
 }
 
-impl SubstrateBridgeEventWatcher
+impl<S> SubstrateBridgeEventWatcher<S>
 where
-    Self: SubstrateBridgeWatcher<PolkadotConfig>,
+    S: QueueStore<TypeErasedStaticTxPayload, Key = SledQueueKey>,
 {
     #[tracing::instrument(skip_all)]
     async fn execute_proposal_with_signature(
         &self,
         chain_id: u32,
-        store: Arc<<Self as SubstrateEventWatcher<PolkadotConfig>>::Store>,
+        store: Arc<S>,
         api: Arc<OnlineClient<PolkadotConfig>>,
         (proposal_data, signature): (Vec<u8>, Vec<u8>),
     ) -> webb_relayer_utils::Result<()> {
@@ -237,7 +265,7 @@ where
     async fn transfer_ownership_with_signature(
         &self,
         chain_id: u32,
-        store: Arc<<Self as SubstrateEventWatcher<PolkadotConfig>>::Store>,
+        store: Arc<S>,
         api: Arc<OnlineClient<PolkadotConfig>>,
         (public_key, nonce, signature): (Vec<u8>, u32, Vec<u8>),
     ) -> webb_relayer_utils::Result<()> {
@@ -317,8 +345,14 @@ where
         );
 
         let tx = TypeErasedStaticTxPayload::try_from(set_maintainer_tx)?;
-        // Enqueue transaction in protocol-substrate transaction queue
-        QueueStore::enqueue_item(&store, tx_key, tx)?;
+        // Enqueue transaction in protocol-substrate transaction queue,
+        // ahead of the routine transactions.
+        QueueStore::enqueue_item_with_options(
+            &store,
+            tx_key,
+            tx,
+            EnqueueOptions::with_priority(QueuePriority::High),
+        )?;
         tracing::debug!(
             data_hash = ?hex::encode(data_hash),
             "Enqueued set-maintainer tx for execution through protocol-substrate tx queue",
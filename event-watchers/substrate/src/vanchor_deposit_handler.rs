@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::marker::PhantomData;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -26,17 +27,17 @@ use webb_proposal_signing_backends::{
     proposal_handler, ProposalSigningBackend,
 };
 use webb_relayer_config::anchor::LinkedAnchorConfig;
-use webb_relayer_store::EventHashStore;
-use webb_relayer_store::SledStore;
+use webb_relayer_store::{EventHashStore, HistoryStore};
 use webb_relayer_utils::metric;
 /// SubstrateVAnchorDeposit handler handles `Transaction` event and creates `AnchorUpdate` proposals for linked anchors.
-pub struct SubstrateVAnchorDepositHandler<B, C> {
+pub struct SubstrateVAnchorDepositHandler<B, C, S> {
     proposal_signing_backend: B,
     bridge_registry_backend: C,
     linked_anchors: Option<Vec<LinkedAnchorConfig>>,
+    _store: PhantomData<S>,
 }
 
-impl<B, C> SubstrateVAnchorDepositHandler<B, C>
+impl<B, C, S> SubstrateVAnchorDepositHandler<B, C, S>
 where
     B: ProposalSigningBackend,
     C: BridgeRegistryBackend,
@@ -50,19 +51,22 @@ where
             proposal_signing_backend,
             bridge_registry_backend,
             linked_anchors,
+            _store: PhantomData,
         }
     }
 }
 
 #[async_trait::async_trait]
-impl<B, C> EventHandler<PolkadotConfig> for SubstrateVAnchorDepositHandler<B, C>
+impl<B, C, S> EventHandler<PolkadotConfig>
+    for SubstrateVAnchorDepositHandler<B, C, S>
 where
     B: ProposalSigningBackend + Send + Sync,
     C: BridgeRegistryBackend + Send + Sync,
+    S: HistoryStore + EventHashStore,
 {
     type Client = OnlineClient<PolkadotConfig>;
 
-    type Store = SledStore;
+    type Store = S;
 
     async fn can_handle_events(
         &self,
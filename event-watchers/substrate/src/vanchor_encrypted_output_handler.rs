@@ -12,8 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::marker::PhantomData;
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use webb::evm::ethers::types::H256;
 use webb::substrate::subxt::{self, OnlineClient, PolkadotConfig};
 use webb::substrate::tangle_runtime::api as RuntimeApi;
 use webb::substrate::tangle_runtime::api::v_anchor_bn254;
@@ -23,19 +25,28 @@ use webb_proposals::{
     ResourceId, SubstrateTargetSystem, TargetSystem, TypedChainId,
 };
 
-use webb_relayer_store::sled::SledStore;
-use webb_relayer_store::EncryptedOutputCacheStore;
+use webb_relayer_store::{BatchStore, HistoryStore, StoreBatch};
 use webb_relayer_utils::{metric, Error};
 // An Substrate VAnchor encrypted output Watcher that watches for Deposit events and save the encrypted output to the store.
 /// It serves as a cache for encrypted outputs that could be used by dApp.
-#[derive(Clone, Debug, Default)]
-pub struct SubstrateVAnchorEncryptedOutputHandler;
+#[derive(Clone, Debug)]
+pub struct SubstrateVAnchorEncryptedOutputHandler<S>(PhantomData<S>);
+
+impl<S> Default for SubstrateVAnchorEncryptedOutputHandler<S> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
 
 #[async_trait::async_trait]
-impl EventHandler<PolkadotConfig> for SubstrateVAnchorEncryptedOutputHandler {
+impl<S> EventHandler<PolkadotConfig>
+    for SubstrateVAnchorEncryptedOutputHandler<S>
+where
+    S: HistoryStore + BatchStore,
+{
     type Client = OnlineClient<PolkadotConfig>;
 
-    type Store = SledStore;
+    type Store = S;
 
     async fn can_handle_events(
         &self,
@@ -95,6 +106,8 @@ impl EventHandler<PolkadotConfig> for SubstrateVAnchorEncryptedOutputHandler {
             let history_store_key =
                 ResourceId::new(src_target_system, src_chain_id);
 
+            // all the writes of the event are stored at once.
+            let mut batch = StoreBatch::new();
             let mut index = next_leaf_index.saturating_sub(leaf_count as u32);
             let mut output_store = Vec::with_capacity(leaf_count);
             for encrypted_output in
@@ -111,14 +124,16 @@ impl EventHandler<PolkadotConfig> for SubstrateVAnchorEncryptedOutputHandler {
                     tree_id = %tree_id,
                     block_number = %block_number
                 );
-                store.insert_encrypted_output_and_last_deposit_block_number(
+                batch.insert_encrypted_output_and_last_deposit_block_number(
                     history_store_key,
                     &[value],
                     block_number,
-                )?;
+                    H256::from(at_hash.0),
+                );
                 output_store.push(encrypted_output);
                 index += 1;
             }
+            store.apply_batch(batch)?;
         }
         Ok(())
     }
@@ -12,9 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::marker::PhantomData;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use webb::evm::ethers::types::H256;
 use webb::substrate::subxt::{self, OnlineClient, PolkadotConfig};
 use webb::substrate::tangle_runtime::api as RuntimeApi;
 use webb::substrate::tangle_runtime::api::v_anchor_bn254;
@@ -23,18 +25,26 @@ use webb_event_watcher_traits::substrate::EventHandler;
 use webb_proposals::{
     ResourceId, SubstrateTargetSystem, TargetSystem, TypedChainId,
 };
-use webb_relayer_store::sled::SledStore;
-use webb_relayer_store::LeafCacheStore;
+use webb_relayer_store::{BatchStore, HistoryStore, StoreBatch};
 use webb_relayer_utils::{metric, Error};
 // An Substrate VAnchor Leaves Watcher that watches for Deposit events and save the leaves to the store.
 /// It serves as a cache for leaves that could be used by dApp for proof generation.
-#[derive(Clone, Debug, Default)]
-pub struct SubstrateVAnchorLeavesHandler;
+#[derive(Clone, Debug)]
+pub struct SubstrateVAnchorLeavesHandler<S>(PhantomData<S>);
+
+impl<S> Default for SubstrateVAnchorLeavesHandler<S> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
 
 #[async_trait::async_trait]
-impl EventHandler<PolkadotConfig> for SubstrateVAnchorLeavesHandler {
+impl<S> EventHandler<PolkadotConfig> for SubstrateVAnchorLeavesHandler<S>
+where
+    S: HistoryStore + BatchStore,
+{
     type Client = OnlineClient<PolkadotConfig>;
-    type Store = SledStore;
+    type Store = S;
     async fn can_handle_events(
         &self,
         events: subxt::events::Events<PolkadotConfig>,
@@ -89,6 +99,8 @@ impl EventHandler<PolkadotConfig> for SubstrateVAnchorLeavesHandler {
             let src_target_system = TargetSystem::Substrate(target);
             let history_store_key =
                 ResourceId::new(src_target_system, src_chain_id);
+            // all the writes of the event are stored at once.
+            let mut batch = StoreBatch::new();
             let mut leaf_index =
                 next_leaf_index.saturating_sub(leaf_count as u32);
             let mut leaf_store = Vec::with_capacity(leaf_count);
@@ -104,14 +116,16 @@ impl EventHandler<PolkadotConfig> for SubstrateVAnchorLeavesHandler {
                     tree_id = %tree_id,
                     block_number = %block_number
                 );
-                store.insert_leaves_and_last_deposit_block_number(
+                batch.insert_leaves_and_last_deposit_block_number(
                     history_store_key,
                     &[value],
                     block_number,
-                )?;
+                    H256::from(at_hash.0),
+                );
                 leaf_store.push(leaf.0);
                 leaf_index += 1;
             }
+            store.apply_batch(batch)?;
         }
         Ok(())
     }
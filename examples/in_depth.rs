@@ -32,6 +32,7 @@ use webb_relayer_config::{
     FeaturesConfig, WebbRelayerConfig,
 };
 use webb_relayer_context::RelayerContext;
+use webb_relayer_store::AnyStore;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -49,6 +50,7 @@ async fn main() -> anyhow::Result<()> {
             data_query: true,
             private_tx_relay: true,
             governance_relay: true,
+            proposals_admin: false,
         },
         evm: HashMap::from([(
             String::from("polygon"),
@@ -64,6 +66,8 @@ async fn main() -> anyhow::Result<()> {
                 explorer: Some("https://polygonscan.com".parse()?),
                 chain_id: 137,
                 private_key: Some(ethereum_types::Secret::random().into()),
+                remote_signer: None,
+                accounts: Vec::new(),
                 beneficiary: Some(ethereum_types::Address::random()), // Do not ever hardcode a private key in production!
                 contracts: vec![
                     Contract::VAnchor(VAnchorContractConfig {
@@ -82,12 +86,15 @@ async fn main() -> anyhow::Result<()> {
                         proposal_signing_backend: Some(
                             ProposalSigningBackendConfig::Mocked(
                                 MockedProposalSigningBackendConfig {
-                                    private_key:
+                                    private_key: Some(
                                         ethereum_types::Secret::random().into(),
+                                    ),
+                                    remote_signer: None,
                                 },
                             ),
                         ),
                         linked_anchors: None,
+                        governed_contracts: Vec::new(),
                         smart_anchor_updates: Default::default(),
                     }),
                     Contract::SignatureBridge(SignatureBridgeContractConfig {
@@ -108,6 +115,7 @@ async fn main() -> anyhow::Result<()> {
                 block_poller: None,
                 block_confirmations: 0,
                 tx_queue: Default::default(),
+                fee_policy: Default::default(),
             },
         )]),
         ..Default::default()
@@ -122,12 +130,15 @@ async fn main() -> anyhow::Result<()> {
     let config = webb_relayer_config::utils::parse_from_files(&config_files)?;
 
     // next is to build the store, or the storage backend:
-    let store = webb_relayer_store::sled::SledStore::open("path/to/store")?;
+    let store: AnyStore =
+        webb_relayer_store::sled::SledStore::open("path/to/store")?.into();
+    // or a temporary store, with `SledStore::temporary()`,
+    // or an in-memory store, which is useful for tests, with
+    // `AnyStore::from(webb_relayer_store::InMemoryStore::default())`,
+    // or your own store, as long as it implements `RelayerStore`.
 
     // finally, after loading the config files, we can build the relayer context.
     let ctx = RelayerContext::new(config, store.clone())?;
-    // or temporary store:
-    let store = webb_relayer_store::sled::SledStore::temporary()?;
 
     // it is now up to you to start the web interface/server for the relayer and the background
     // services.
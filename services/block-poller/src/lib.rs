@@ -1,33 +1,46 @@
+use std::marker::PhantomData;
 use std::sync::Arc;
 
 use crate::block_poller::{BlockPoller, BlockPollingHandler};
 use ethereum_types::U256;
 use webb::evm::ethers::types::{Block, TxHash};
-use webb_relayer::service::{evm::Client, Store};
+use webb_relayer::service::evm::Client;
 use webb_relayer_config::block_poller::BlockPollerConfig;
 use webb_relayer_context::RelayerContext;
-use webb_relayer_store::SledStore;
+use webb_relayer_store::HistoryStore;
 use webb_relayer_utils::{Error, Result};
 
 mod block_poller;
 
 /// A struct for listening to blocks / block headers that implements
-/// the [`BlockPoller`] trait.
-#[derive(Copy, Clone, Debug, Default)]
-pub struct BlockWatcher;
+/// the [`BlockPoller`] trait, using the store `S`.
+#[derive(Clone, Debug)]
+pub struct BlockWatcher<S>(PhantomData<S>);
+
+impl<S> Default for BlockWatcher<S> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
 
 #[async_trait::async_trait]
-impl BlockPoller for BlockWatcher {
+impl<S> BlockPoller for BlockWatcher<S>
+where
+    S: HistoryStore,
+{
     const TAG: &'static str = "Block Watcher";
-    type Store = SledStore;
+    type Store = S;
 }
 
 #[derive(Clone, Debug)]
-struct BlockListener;
+struct BlockListener<S>(PhantomData<S>);
 
 #[async_trait::async_trait]
-impl BlockPollingHandler for BlockListener {
-    type Store = SledStore;
+impl<S> BlockPollingHandler for BlockListener<S>
+where
+    S: HistoryStore,
+{
+    type Store = S;
 
     async fn handle_block(
         &self,
@@ -40,13 +53,16 @@ impl BlockPollingHandler for BlockListener {
 }
 
 /// Start the block poller service which polls ETH blocks
-pub fn start_block_poller_service(
-    ctx: &RelayerContext,
+pub fn start_block_poller_service<S>(
+    ctx: &RelayerContext<S>,
     chain_id: U256,
     client: Arc<Client>,
-    store: Arc<Store>,
+    store: Arc<S>,
     poller_config: BlockPollerConfig,
-) -> Result<()> {
+) -> Result<()>
+where
+    S: HistoryStore + 'static,
+{
     let mut shutdown_signal = ctx.shutdown_signal();
     let _my_ctx = ctx.clone();
     tracing::info!("Starting block relay service");
@@ -56,8 +72,8 @@ pub fn start_block_poller_service(
             chain_id,
         );
 
-        let block_watcher = BlockWatcher::default();
-        let block_finality_handler = BlockListener;
+        let block_watcher = BlockWatcher::<S>::default();
+        let block_finality_handler = BlockListener::<S>(PhantomData);
         let block_watcher_task = block_watcher.run(
             client,
             store,
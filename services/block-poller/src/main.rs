@@ -7,9 +7,10 @@ use ethereum_types::U256;
 use std::sync::Arc;
 use tokio::signal::unix;
 use webb_block_poller::start_block_poller_service;
-use webb_relayer::service::{build_web_services, Store};
+use webb_relayer::service::build_web_services;
 use webb_relayer_config::cli::{create_store, load_config, setup_logger, Opts};
 use webb_relayer_context::RelayerContext;
+use webb_relayer_store::AnyStore;
 use webb_relayer_utils::Result;
 
 /// Starts all background services for all chains configured in the config file.
@@ -19,10 +20,10 @@ use webb_relayer_utils::Result;
 /// # Arguments
 ///
 /// * `ctx` - RelayContext reference that holds the configuration
-/// * `store` -[Sled](https://sled.rs)-based database store
+/// * `store` - The database store
 pub async fn ignite(
-    ctx: &RelayerContext,
-    store: Arc<Store>,
+    ctx: &RelayerContext<AnyStore>,
+    store: Arc<AnyStore>,
 ) -> crate::Result<()> {
     tracing::debug!(
         "Relayer configuration: {}",
@@ -1,28 +1,42 @@
+use std::marker::PhantomData;
+
 use crate::light_client::LightClientPoller;
 
 use eth2_to_substrate_relay::config::Config;
 use webb_relayer_context::RelayerContext;
-use webb_relayer_store::SledStore;
+use webb_relayer_store::HistoryStore;
 use webb_relayer_utils::Result;
 
 mod light_client;
 
 /// A struct for listening to blocks / block headers that implements
-/// the [`LightClientPoller`] trait.
-#[derive(Copy, Clone, Debug, Default)]
-pub struct LightClientWatcher;
+/// the [`LightClientPoller`] trait, using the store `S`.
+#[derive(Clone, Debug)]
+pub struct LightClientWatcher<S>(PhantomData<S>);
+
+impl<S> Default for LightClientWatcher<S> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
 
 #[async_trait::async_trait]
-impl LightClientPoller for LightClientWatcher {
+impl<S> LightClientPoller for LightClientWatcher<S>
+where
+    S: HistoryStore,
+{
     const TAG: &'static str = "Block Watcher";
-    type Store = SledStore;
+    type Store = S;
 }
 
 /// Start the block poller service which polls ETH blocks
-pub fn start_light_client_service(
-    ctx: &RelayerContext,
+pub fn start_light_client_service<S>(
+    ctx: &RelayerContext<S>,
     config: Config,
-) -> Result<()> {
+) -> Result<()>
+where
+    S: HistoryStore + 'static,
+{
     let mut shutdown_signal = ctx.shutdown_signal();
     let _my_ctx = ctx.clone();
     let chain_id = config.chain_id;
@@ -33,7 +47,7 @@ pub fn start_light_client_service(
             chain_id,
         );
 
-        let light_client_watcher = LightClientWatcher::default();
+        let light_client_watcher = LightClientWatcher::<S>::default();
         let light_client_watcher_task = light_client_watcher.run(config);
         tokio::select! {
             res = light_client_watcher_task => {
@@ -11,6 +11,7 @@ use webb_relayer_config::{
     cli::{create_store, load_config, setup_logger, Opts},
 };
 use webb_relayer_context::RelayerContext;
+use webb_relayer_store::AnyStore;
 
 /// Starts all background services for all chains configured in the config file.
 ///
@@ -19,8 +20,8 @@ use webb_relayer_context::RelayerContext;
 /// # Arguments
 ///
 /// * `ctx` - RelayContext reference that holds the configuration
-/// * `store` -[Sled](https://sled.rs)-based database store
-pub async fn ignite(ctx: &RelayerContext) -> anyhow::Result<()> {
+/// * `store` - The database store
+pub async fn ignite(ctx: &RelayerContext<AnyStore>) -> anyhow::Result<()> {
     tracing::debug!(
         "Relayer configuration: {}",
         serde_json::to_string_pretty(&ctx.config)?
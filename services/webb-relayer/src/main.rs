@@ -16,15 +16,23 @@
 #![deny(unsafe_code)]
 #![warn(missing_docs)]
 
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::signal::unix;
+use tokio::sync::mpsc::Receiver;
 use tokio::time;
-use webb_relayer::service::build_web_services;
+use webb_relayer::service::reload::{watch_config_dir, Services};
 
-use webb_relayer_config::cli::{create_store, load_config, setup_logger, Opts};
+use webb_relayer_config::cli::{
+    config_dir_of, create_store, load_config, run_command, setup_logger,
+    validate_config, write_config_schema, Command, Opts,
+};
 use webb_relayer_context::RelayerContext;
 
+/// How often the config files are checked for changes, when they are watched.
+const CONFIG_WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
 /// The main entry point for the relayer.
 ///
 /// # Arguments
@@ -43,11 +51,27 @@ async fn main(args: Opts) -> anyhow::Result<()> {
         }
     }
 
-    // The configuration is validated and configured from the given directory
-    let config = load_config(args.config_dir.clone())?;
+    // run the configuration commands without opening the store.
+    match &args.cmd {
+        Some(Command::ValidateConfig { online }) => {
+            return validate_config(args.config_dir.clone(), *online).await;
+        }
+        Some(Command::ConfigSchema { output }) => {
+            return write_config_schema(output.as_deref());
+        }
+        _ => {}
+    }
 
     // persistent storage for the relayer
-    let store = create_store(&args).await?;
+    let mut store = create_store(&args).await?;
+
+    // run the maintenance command, if any, instead of starting the relayer.
+    if let Some(cmd) = &args.cmd {
+        return run_command(cmd, &mut store);
+    }
+
+    // The configuration is validated and configured from the given directory
+    let config = load_config(args.config_dir.clone())?;
     let cloned_store = store.clone();
 
     // The RelayerContext takes a configuration, and populates objects that are needed
@@ -71,12 +95,22 @@ async fn main(args: Opts) -> anyhow::Result<()> {
         }
     });
 
-    // the build_web_relayer command sets up routing (endpoint queries / requests mapped to handled code)
-    // so clients can interact with the relayer
-    let server_handle = tokio::spawn(build_web_services(ctx.clone()));
-    // start all background services.
+    // start the web server, which sets up routing (endpoint queries / requests mapped
+    // to handled code) so clients can interact with the relayer, and all background services.
     // this does not block, will fire the services on background tasks.
-    webb_relayer::service::ignite(ctx.clone(), Arc::new(store)).await?;
+    let mut services = Services::ignite(ctx.clone(), Arc::new(store)).await?;
+    // reload the configuration when the config files change, if enabled.
+    let mut config_changes = if args.watch_config {
+        let config_dir = config_dir_of(args.config_dir.clone())?;
+        Some(watch_config_dir(&ctx, config_dir, CONFIG_WATCH_INTERVAL))
+    } else {
+        None
+    };
+    // reload the configuration periodically to refresh the secrets, if enabled.
+    let mut secrets_refresh = args.secrets_refresh_interval.map(|secs| {
+        let period = Duration::from_secs(secs.get());
+        time::interval_at(time::Instant::now() + period, period)
+    });
 
     tracing::event!(
         target: webb_relayer_utils::probe::TARGET,
@@ -88,37 +122,105 @@ async fn main(args: Opts) -> anyhow::Result<()> {
     let mut ctrlc_signal = unix::signal(unix::SignalKind::interrupt())?;
     let mut termination_signal = unix::signal(unix::SignalKind::terminate())?;
     let mut quit_signal = unix::signal(unix::SignalKind::quit())?;
-    let shutdown = || {
-        tracing::event!(
-            target: webb_relayer_utils::probe::TARGET,
-            tracing::Level::DEBUG,
-            kind = %webb_relayer_utils::probe::Kind::Lifecycle,
-            shutdown = true
-        );
-        tracing::warn!("Shutting down...");
-        // shut down storage fetching
-        // send shutdown signal to all of the application.
-        ctx.shutdown();
-        // also abort the server task
-        server_handle.abort();
-        // abort get sled storage data task
-        sled_metric_task_handle.abort();
-        std::thread::sleep(std::time::Duration::from_millis(300));
-        tracing::info!("Clean Exit ..");
-    };
-    tokio::select! {
-        _ = ctrlc_signal.recv() => {
-            tracing::warn!("Interrupted (Ctrl+C) ...");
-            shutdown();
-        },
-        _ = termination_signal.recv() => {
-            tracing::warn!("Got Terminate signal ...");
-            shutdown();
-        },
-        _ = quit_signal.recv() => {
-            tracing::warn!("Quitting ...");
-            shutdown();
-        },
+    let mut hangup_signal = unix::signal(unix::SignalKind::hangup())?;
+    loop {
+        tokio::select! {
+            _ = ctrlc_signal.recv() => {
+                tracing::warn!("Interrupted (Ctrl+C) ...");
+                break;
+            },
+            _ = termination_signal.recv() => {
+                tracing::warn!("Got Terminate signal ...");
+                break;
+            },
+            _ = quit_signal.recv() => {
+                tracing::warn!("Quitting ...");
+                break;
+            },
+            _ = hangup_signal.recv() => {
+                tracing::warn!("Got Hangup signal, reloading the configuration ...");
+            },
+            Some(()) = recv_change(&mut config_changes) => {
+                tracing::info!("Config files changed, reloading the configuration ...");
+            },
+            _ = tick(&mut secrets_refresh) => {
+                tracing::debug!("Refreshing the secrets, reloading the configuration ...");
+            },
+        }
+        reload_config(&mut services, args.config_dir.clone()).await;
     }
+    tracing::event!(
+        target: webb_relayer_utils::probe::TARGET,
+        tracing::Level::DEBUG,
+        kind = %webb_relayer_utils::probe::Kind::Lifecycle,
+        shutdown = true
+    );
+    tracing::warn!("Shutting down...");
+    // send shutdown signal to all of the application, and abort the server task.
+    services.shutdown();
+    // abort get sled storage data task
+    sled_metric_task_handle.abort();
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    tracing::info!("Clean Exit ..");
     Ok(())
 }
+
+/// Reloads the configuration from the config directory, and restarts the services
+/// that it changed. The current configuration is kept if the new one is invalid.
+async fn reload_config(services: &mut Services, config_dir: Option<PathBuf>) {
+    // loading the secrets blocks, while decrypting the keystores or fetching
+    // them from the Vault, so keep it off the runtime threads.
+    let loaded =
+        tokio::task::spawn_blocking(move || load_config(config_dir)).await;
+    let config = match loaded {
+        Ok(Ok(config)) => config,
+        Ok(Err(e)) => {
+            tracing::error!(
+                "Failed to load the configuration, keeping the current one: {}",
+                e
+            );
+            return;
+        }
+        Err(e) => {
+            tracing::error!(
+                "Failed to load the configuration, keeping the current one: {}",
+                e
+            );
+            return;
+        }
+    };
+    match services.reload(config).await {
+        Ok(diff) if diff.is_empty() => {
+            tracing::info!("The configuration did not change");
+        }
+        Ok(diff) => {
+            tracing::info!(
+                evm = ?diff.evm,
+                substrate = ?diff.substrate,
+                retention = diff.retention,
+                "Reloaded the configuration",
+            );
+        }
+        Err(e) => {
+            tracing::error!("Failed to reload the configuration: {}", e);
+        }
+    }
+}
+
+/// Waits for the next change of the config files, if they are watched.
+async fn recv_change(changes: &mut Option<Receiver<()>>) -> Option<()> {
+    match changes {
+        Some(changes) => changes.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Waits for the next tick of the interval, if any.
+async fn tick(interval: &mut Option<time::Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
@@ -0,0 +1,84 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use webb_relayer_context::RelayerContext;
+use webb_relayer_store::RetentionStore;
+
+/// Starts the background task that prunes the store, following the configured
+/// retention policy.
+///
+/// # Arguments
+///
+/// * `ctx` - RelayContext reference that holds the configuration
+/// * `store` - The database store
+pub fn ignite<S>(ctx: &RelayerContext<S>, store: Arc<S>)
+where
+    S: RetentionStore + 'static,
+{
+    let config = ctx.config.retention;
+    if !config.enabled {
+        tracing::debug!("Store compaction is disabled");
+        return;
+    }
+    let policy = config.policy();
+    let metrics = ctx.metrics.clone();
+    let mut shutdown_signal = ctx.shutdown_signal();
+    let task = async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(config.interval.max(1)));
+        loop {
+            interval.tick().await;
+            let store = store.clone();
+            // pruning walks the whole store, keep it off the async workers.
+            let result =
+                tokio::task::spawn_blocking(move || store.prune(&policy)).await;
+            match result {
+                Ok(Ok(report)) => {
+                    tracing::info!(
+                        event_hashes = report.event_hashes,
+                        token_prices = report.token_prices,
+                        dead_letters = report.dead_letters,
+                        blocks = report.blocks,
+                        queue_entries = report.queue_entries,
+                        reclaimed_bytes = report.reclaimed_bytes,
+                        "Compacted the store, pruned {} entries",
+                        report.total(),
+                    );
+                    metrics
+                        .lock()
+                        .await
+                        .total_amount_of_data_reclaimed
+                        .inc_by(report.reclaimed_bytes as f64);
+                }
+                Ok(Err(e)) => {
+                    tracing::error!("Failed to compact the store: {}", e);
+                }
+                Err(e) => {
+                    tracing::error!("Store compaction task panicked: {}", e);
+                }
+            }
+        }
+    };
+    tokio::task::spawn(async move {
+        tokio::select! {
+            _ = task => {},
+            _ = shutdown_signal.recv() => {
+                tracing::trace!("Stopping the store compaction");
+            },
+        }
+    });
+}
@@ -1,7 +1,8 @@
 use std::sync::Arc;
 
-use axum::routing::get;
+use axum::routing::{delete, get, post};
 use axum::Router;
+use webb::evm::ethers::middleware::gas_oracle::GasOracle;
 use webb::evm::ethers::prelude::TimeLag;
 use webb_bridge_registry_backends::dkg::DkgBridgeRegistryBackend;
 use webb_bridge_registry_backends::mocked::MockedBridgeRegistryBackend;
@@ -17,15 +18,19 @@ use webb_ew_evm::vanchor::{
     VAnchorDepositHandler, VAnchorEncryptedOutputHandler, VAnchorLeavesHandler,
 };
 use webb_ew_evm::{VAnchorContractWatcher, VAnchorContractWrapper};
+use webb_proposal_signing_backends::queue::control::ProposalsQueueController;
 use webb_proposal_signing_backends::queue::{self, policy};
-use webb_proposals::TypedChainId;
+use webb_proposal_signing_backends::ProposalSigningBackend;
+use webb_proposals::{ResourceId, TargetSystem, TypedChainId};
 use webb_relayer_config::evm::{
-    Contract, SignatureBridgeContractConfig, SmartAnchorUpdatesConfig,
-    VAnchorContractConfig,
+    Contract, EvmChainConfig, SignatureBridgeContractConfig,
+    SmartAnchorUpdatesConfig, VAnchorContractConfig,
 };
-use webb_relayer_context::RelayerContext;
+use webb_relayer_context::{RelayerContext, RelayerStore};
 use webb_relayer_handlers::handle_evm_fee_info;
-use webb_relayer_handlers::routes::{encrypted_outputs, leaves, metric};
+use webb_relayer_handlers::routes::{
+    encrypted_outputs, leaves, metric, proposals,
+};
 use webb_relayer_tx_queue::evm::TxQueue;
 
 use super::make_proposal_signing_backend;
@@ -37,25 +42,56 @@ pub type Client = EthersClient;
 pub type TimeLagClient = EthersTimeLagClient;
 
 /// Setup and build all the EVM web services and handlers.
-pub fn build_web_services() -> Router<Arc<RelayerContext>> {
+pub fn build_web_services<S>() -> Router<Arc<RelayerContext<S>>>
+where
+    S: RelayerStore,
+{
     Router::new()
         .route(
             "/leaves/evm/:chain_id/:contract",
-            get(leaves::handle_leaves_cache_evm),
+            get(leaves::handle_leaves_cache_evm::<S>),
+        )
+        .route(
+            "/leaves/evm/:chain_id/:contract/root",
+            get(leaves::handle_merkle_root_evm::<S>),
+        )
+        .route(
+            "/leaves/evm/:chain_id/:contract/proof",
+            get(leaves::handle_merkle_proof_evm::<S>),
         )
         .route(
             "/encrypted_outputs/evm/:chain_id/:contract_address",
-            get(encrypted_outputs::handle_encrypted_outputs_cache_evm),
+            get(encrypted_outputs::handle_encrypted_outputs_cache_evm::<S>),
         )
         .route(
             "/metrics/evm/:chain_id/:contract",
-            get(metric::handle_evm_metric_info),
+            get(metric::handle_evm_metric_info::<S>),
+        )
+        .route(
+            "/proposals/evm/:chain_id/:contract",
+            get(proposals::handle_proposals_queue_evm::<S>),
+        )
+        .route(
+            "/proposals/evm/:chain_id/:contract/governance",
+            post(proposals::handle_governance_proposal_evm::<S>),
+        )
+        .route(
+            "/proposals/evm/:chain_id/:contract/:hash",
+            delete(proposals::handle_drop_proposal_evm::<S>),
+        )
+        .route(
+            "/proposals/evm/:chain_id/:contract/:hash/expedite",
+            post(proposals::handle_expedite_proposal_evm::<S>),
+        )
+        .route(
+            "/proposals/evm/:chain_id/:contract/:hash/resign",
+            post(proposals::handle_resign_proposal_evm::<S>),
         )
         // for backward compatibility
         .route("/metrics", get(metric::handle_metric_info))
         .route(
             "/fee_info/evm/:chain_id/:vanchor/:gas_amount",
-            get(handle_evm_fee_info),
+            get(handle_evm_fee_info::<S>),
         )
 }
 
@@ -66,56 +102,131 @@ pub fn build_web_services() -> Router<Arc<RelayerContext>> {
 /// # Arguments
 ///
 /// * `ctx` - RelayContext reference that holds the configuration
-/// * `store` -[Sled](https://sled.rs)-based database store
-pub async fn ignite(
-    ctx: &RelayerContext,
-    store: Arc<super::Store>,
-) -> crate::Result<()> {
+/// * `store` - The database store
+pub async fn ignite<S>(
+    ctx: &RelayerContext<S>,
+    store: Arc<S>,
+) -> crate::Result<()>
+where
+    S: RelayerStore,
+{
     for chain_config in ctx.config.evm.values() {
-        if !chain_config.enabled {
-            continue;
-        }
-        let chain_name = &chain_config.name;
-        let chain_id = chain_config.chain_id;
-        let client = ctx.evm_provider(chain_id).await?;
-        // Time lag offset tip.
-        let block_confirmations = chain_config.block_confirmations;
-        let timelag_client =
-            Arc::new(TimeLag::new(client.clone(), block_confirmations));
-        tracing::debug!(
-            "Starting Background Services for ({}) chain.",
-            chain_name
-        );
+        ignite_chain(ctx, chain_config, store.clone()).await?;
+    }
+    Ok(())
+}
 
-        for contract in &chain_config.contracts {
-            match contract {
-                Contract::VAnchor(config) => {
-                    start_vanchor_events_watcher(
-                        ctx,
-                        config,
-                        chain_id,
-                        timelag_client.clone(),
-                        store.clone(),
-                    )
-                    .await?;
-                }
-                Contract::SignatureBridge(config) => {
-                    start_signature_bridge_events_watcher(
-                        ctx,
-                        config,
-                        timelag_client.clone(),
-                        store.clone(),
-                    )
-                    .await?;
-                }
+/// Fires up all background services for the given EVM chain, that is the event
+/// watchers of its contracts and its transaction queue.
+///
+/// Returns a future that resolves when all services are started successfully.
+///
+/// # Arguments
+///
+/// * `ctx` - RelayContext reference that holds the configuration
+/// * `chain_config` - The configuration of the chain
+/// * `store` - The database store
+pub async fn ignite_chain<S>(
+    ctx: &RelayerContext<S>,
+    chain_config: &EvmChainConfig,
+    store: Arc<S>,
+) -> crate::Result<()>
+where
+    S: RelayerStore,
+{
+    if !chain_config.enabled {
+        return Ok(());
+    }
+    let chain_name = &chain_config.name;
+    let chain_id = chain_config.chain_id;
+    let client = ctx.evm_provider(chain_id).await?;
+    // Time lag offset tip.
+    let block_confirmations = chain_config.block_confirmations;
+    let timelag_client =
+        Arc::new(TimeLag::new(client.clone(), block_confirmations));
+    tracing::debug!("Starting Background Services for ({}) chain.", chain_name);
+
+    for contract in &chain_config.contracts {
+        match contract {
+            Contract::VAnchor(config) => {
+                start_vanchor_events_watcher(
+                    ctx,
+                    config,
+                    chain_id,
+                    timelag_client.clone(),
+                    store.clone(),
+                )
+                .await?;
+            }
+            Contract::SignatureBridge(config) => {
+                start_signature_bridge_events_watcher(
+                    ctx,
+                    config,
+                    timelag_client.clone(),
+                    store.clone(),
+                )
+                .await?;
             }
         }
-        // start the transaction queue after starting other tasks.
-        start_tx_queue(ctx.clone(), chain_config.chain_id, store.clone())?;
     }
+    // start the transaction queue after starting other tasks.
+    start_tx_queue(ctx.clone(), chain_config.chain_id, store)?;
     Ok(())
 }
 
+/// Runs the proposals queue, along with the refresh of the gas prices checked by
+/// its gas price policy, if any.
+///
+/// The queue is registered in the context under the resource id of its anchor,
+/// so it could be inspected and controlled from the API while it runs, and
+/// unregistered once it stops.
+async fn run_proposals_queue<S, Queue, Policy, PSB>(
+    ctx: &RelayerContext<S>,
+    resource_id: ResourceId,
+    proposals_queue: Queue,
+    time_delay_policy: Option<policy::TimeDelayPolicy>,
+    dequeue_policy: Policy,
+    gas_price_policy: Option<policy::GasPricePolicy>,
+    proposal_signing_backend: PSB,
+) where
+    Queue: queue::ProposalsQueue + Clone + Send + Sync + 'static,
+    Policy: policy::ProposalPolicy + Clone,
+    PSB: ProposalSigningBackend + Send + Sync + 'static,
+{
+    let metrics = ctx.metrics.clone();
+    let proposal_signing_backend = Arc::new(proposal_signing_backend);
+    let controller = ProposalsQueueController::builder()
+        .queue(proposals_queue.clone())
+        .time_delay_policy(time_delay_policy)
+        .proposal_signing_backend(proposal_signing_backend.clone())
+        .metrics(metrics.clone())
+        .build();
+    // dropped along with this task, which unregisters the queue.
+    let _registration = ctx.proposals_queues().insert(resource_id, controller);
+    let gas_prices_task = async {
+        match gas_price_policy {
+            Some(gas_price_policy) => {
+                gas_price_policy
+                    .run(|chain_id| async move {
+                        let gas_price =
+                            ctx.gas_oracle(chain_id).await?.fetch().await?;
+                        Ok(gas_price)
+                    })
+                    .await
+            }
+            // nothing to refresh.
+            None => std::future::pending().await,
+        }
+    };
+    let proposals_queue_task = queue::run(
+        proposals_queue,
+        dequeue_policy,
+        proposal_signing_backend,
+        metrics,
+    );
+    tokio::join!(proposals_queue_task, gas_prices_task);
+}
+
 /// Starts the event watcher for EVM VAnchor events.
 ///
 /// Returns Ok(()) if successful, or an error if not.
@@ -125,14 +236,17 @@ pub async fn ignite(
 /// * `ctx` - RelayContext reference that holds the configuration
 /// * `config` - VAnchor contract configuration
 /// * `client` - EVM Chain api client
-/// * `store` -[Sled](https://sled.rs)-based database store
-async fn start_vanchor_events_watcher(
-    ctx: &RelayerContext,
+/// * `store` - The database store
+async fn start_vanchor_events_watcher<S>(
+    ctx: &RelayerContext<S>,
     config: &VAnchorContractConfig,
     chain_id: u32,
     client: Arc<TimeLagClient>,
-    store: Arc<super::Store>,
-) -> crate::Result<()> {
+    store: Arc<S>,
+) -> crate::Result<()>
+where
+    S: RelayerStore,
+{
     if !config.events_watcher.enabled {
         tracing::warn!(
             "VAnchor events watcher is disabled for ({}).",
@@ -154,12 +268,13 @@ async fn start_vanchor_events_watcher(
             "VAnchor events watcher for ({}) Started.",
             contract_address,
         );
-        let contract_watcher = VAnchorContractWatcher::default();
+        let contract_watcher = VAnchorContractWatcher::<S>::default();
         let proposal_signing_backend = make_proposal_signing_backend(
             &my_ctx,
             store.clone(),
             TypedChainId::Evm(chain_id),
             my_config.linked_anchors,
+            my_config.governed_contracts,
             my_config.proposal_signing_backend,
         )
         .await?;
@@ -178,7 +293,6 @@ async fn start_vanchor_events_watcher(
         let mut zero_hash_bytes = [0u8; 32];
         zero_hash.to_big_endian(&mut zero_hash_bytes);
 
-        let proposals_queue = queue::mem::InMemoryProposalsQueue::new();
         let time_delay_policy = {
             let defaults = SmartAnchorUpdatesConfig::default();
             let v = &my_config.smart_anchor_updates;
@@ -206,6 +320,28 @@ async fn start_vanchor_events_watcher(
                 .window_size(window_size)
                 .build()
         };
+        // the proposals queue is saved in the store, so the queued proposals
+        // (and the delay before sending them) survive a restart. The substrate
+        // anchors do not queue their anchor updates, so only the evm ones need it.
+        let proposals_queue = queue::store::StoreProposalsQueue::new(
+            store.clone(),
+            format!("vanchor_{chain_id}_{contract_address:?}"),
+        )?
+        .with_time_delay_policy(time_delay_policy.clone());
+        let gas_price_policy = {
+            let defaults = SmartAnchorUpdatesConfig::default();
+            let v = &my_config.smart_anchor_updates;
+            let max_staleness = v
+                .gas_price_max_staleness
+                .or(defaults.gas_price_max_staleness)
+                .expect("gas price max staleness is set by default");
+            v.max_gas_price.map(|max_gas_price| {
+                policy::GasPricePolicy::builder()
+                    .max_gas_price(max_gas_price)
+                    .max_staleness(max_staleness)
+                    .build()
+            })
+        };
 
         if my_config.smart_anchor_updates.enabled {
             tracing::info!(
@@ -224,12 +360,24 @@ async fn start_vanchor_events_watcher(
         let enqueue_policy = my_config.smart_anchor_updates.enabled.then_some(
             (policy::AlwaysHigherNoncePolicy, time_delay_policy.clone()),
         );
+        // the delay of the queue is only reported if the policy is used.
+        let queue_time_delay_policy = my_config
+            .smart_anchor_updates
+            .enabled
+            .then(|| time_delay_policy.clone());
         let dequeue_policy = my_config
             .smart_anchor_updates
             .enabled
-            .then_some(time_delay_policy);
+            .then_some((time_delay_policy, gas_price_policy.clone()));
+        let gas_price_policy =
+            gas_price_policy.filter(|_| my_config.smart_anchor_updates.enabled);
 
-        let metrics = my_ctx.metrics.clone();
+        let resource_id = ResourceId::new(
+            TargetSystem::new_contract_address(
+                contract_address.to_fixed_bytes(),
+            ),
+            TypedChainId::Evm(chain_id),
+        );
         match proposal_signing_backend {
             ProposalSigningBackendSelector::Dkg(backend) => {
                 let bridge_registry =
@@ -246,9 +394,10 @@ async fn start_vanchor_events_watcher(
                     contract_address,
                     store.clone(),
                     zero_hash_bytes.to_vec(),
+                    my_ctx.merkle_trees().clone(),
                 )?;
                 let encrypted_output_handler =
-                    VAnchorEncryptedOutputHandler::new(chain_id.into());
+                    VAnchorEncryptedOutputHandler::<S>::new(chain_id.into());
                 let vanchor_watcher_task = contract_watcher.run(
                     client,
                     store,
@@ -261,11 +410,14 @@ async fn start_vanchor_events_watcher(
                     &my_ctx,
                 );
 
-                let proposals_queue_task = queue::run(
+                let proposals_queue_task = run_proposals_queue(
+                    &my_ctx,
+                    resource_id,
                     proposals_queue,
+                    queue_time_delay_policy,
                     dequeue_policy,
+                    gas_price_policy,
                     backend,
-                    metrics,
                 );
 
                 tokio::select! {
@@ -304,9 +456,10 @@ async fn start_vanchor_events_watcher(
                     contract_address,
                     store.clone(),
                     zero_hash_bytes.to_vec(),
+                    my_ctx.merkle_trees().clone(),
                 )?;
                 let encrypted_output_handler =
-                    VAnchorEncryptedOutputHandler::new(chain_id.into());
+                    VAnchorEncryptedOutputHandler::<S>::new(chain_id.into());
                 let vanchor_watcher_task = contract_watcher.run(
                     client,
                     store,
@@ -319,11 +472,14 @@ async fn start_vanchor_events_watcher(
                     &my_ctx,
                 );
 
-                let proposals_queue_task = queue::run(
+                let proposals_queue_task = run_proposals_queue(
+                    &my_ctx,
+                    resource_id,
                     proposals_queue,
+                    queue_time_delay_policy,
                     dequeue_policy,
+                    gas_price_policy,
                     backend,
-                    metrics,
                 );
 
                 tokio::select! {
@@ -347,15 +503,147 @@ async fn start_vanchor_events_watcher(
                     },
                 }
             }
+            ProposalSigningBackendSelector::Threshold(backend) => {
+                let bridge_registry =
+                    MockedBridgeRegistryBackend::builder().build();
+                let deposit_handler = VAnchorDepositHandler::builder()
+                    .chain_id(chain_id)
+                    .store(store.clone())
+                    .bridge_registry_backend(bridge_registry)
+                    .proposals_queue(proposals_queue.clone())
+                    .policy(enqueue_policy)
+                    .build();
+                let leaves_handler = VAnchorLeavesHandler::new(
+                    chain_id.into(),
+                    contract_address,
+                    store.clone(),
+                    zero_hash_bytes.to_vec(),
+                    my_ctx.merkle_trees().clone(),
+                )?;
+                let encrypted_output_handler =
+                    VAnchorEncryptedOutputHandler::<S>::new(chain_id.into());
+                let vanchor_watcher_task = contract_watcher.run(
+                    client,
+                    store,
+                    wrapper,
+                    vec![
+                        Box::new(deposit_handler),
+                        Box::new(leaves_handler),
+                        Box::new(encrypted_output_handler),
+                    ],
+                    &my_ctx,
+                );
+
+                let proposals_queue_task = run_proposals_queue(
+                    &my_ctx,
+                    resource_id,
+                    proposals_queue,
+                    queue_time_delay_policy,
+                    dequeue_policy,
+                    gas_price_policy,
+                    backend,
+                );
+
+                tokio::select! {
+                    _ = proposals_queue_task => {
+                        tracing::warn!(
+                            "Proposals queue task stopped for ({})",
+                            contract_address,
+                        );
+                    },
+                    _ = vanchor_watcher_task => {
+                        tracing::warn!(
+                            "VAnchor watcher task stopped for ({})",
+                            contract_address,
+                        );
+                    },
+                    _ = shutdown_signal.recv() => {
+                        tracing::trace!(
+                            "Stopping VAnchor watcher for ({})",
+                            contract_address,
+                        );
+                    },
+                }
+            }
+            ProposalSigningBackendSelector::AirGapped(backend) => {
+                let inbox_task = backend.inbox_watcher().run();
+                let bridge_registry =
+                    MockedBridgeRegistryBackend::builder().build();
+                let deposit_handler = VAnchorDepositHandler::builder()
+                    .chain_id(chain_id)
+                    .store(store.clone())
+                    .bridge_registry_backend(bridge_registry)
+                    .proposals_queue(proposals_queue.clone())
+                    .policy(enqueue_policy)
+                    .build();
+                let leaves_handler = VAnchorLeavesHandler::new(
+                    chain_id.into(),
+                    contract_address,
+                    store.clone(),
+                    zero_hash_bytes.to_vec(),
+                    my_ctx.merkle_trees().clone(),
+                )?;
+                let encrypted_output_handler =
+                    VAnchorEncryptedOutputHandler::<S>::new(chain_id.into());
+                let vanchor_watcher_task = contract_watcher.run(
+                    client,
+                    store,
+                    wrapper,
+                    vec![
+                        Box::new(deposit_handler),
+                        Box::new(leaves_handler),
+                        Box::new(encrypted_output_handler),
+                    ],
+                    &my_ctx,
+                );
+
+                let proposals_queue_task = run_proposals_queue(
+                    &my_ctx,
+                    resource_id,
+                    proposals_queue,
+                    queue_time_delay_policy,
+                    dequeue_policy,
+                    gas_price_policy,
+                    backend,
+                );
+
+                tokio::select! {
+                    _ = inbox_task => {
+                        tracing::warn!(
+                            "Inbox watcher (AirGapped Backend) task stopped for ({})",
+                            contract_address,
+                        );
+                    },
+                    _ = proposals_queue_task => {
+                        tracing::warn!(
+                            "Proposals queue task stopped for ({})",
+                            contract_address,
+                        );
+                    },
+                    _ = vanchor_watcher_task => {
+                        tracing::warn!(
+                            "VAnchor watcher task stopped for ({})",
+                            contract_address,
+                        );
+                    },
+                    _ = shutdown_signal.recv() => {
+                        tracing::trace!(
+                            "Stopping VAnchor watcher for ({})",
+                            contract_address,
+                        );
+                    },
+                }
+            }
             ProposalSigningBackendSelector::None => {
                 let leaves_handler = VAnchorLeavesHandler::new(
                     chain_id.into(),
                     contract_address,
                     store.clone(),
                     zero_hash_bytes.to_vec(),
+                    my_ctx.merkle_trees().clone(),
                 )?;
                 let encrypted_output_handler =
-                    VAnchorEncryptedOutputHandler::new(chain_id.into());
+                    VAnchorEncryptedOutputHandler::<S>::new(chain_id.into());
                 let vanchor_watcher_task = contract_watcher.run(
                     client,
                     store,
@@ -391,12 +679,15 @@ async fn start_vanchor_events_watcher(
 }
 
 /// Starts the event watcher for Signature Bridge contract.
-pub async fn start_signature_bridge_events_watcher(
-    ctx: &RelayerContext,
+pub async fn start_signature_bridge_events_watcher<S>(
+    ctx: &RelayerContext<S>,
     config: &SignatureBridgeContractConfig,
     client: Arc<TimeLagClient>,
-    store: Arc<super::Store>,
-) -> crate::Result<()> {
+    store: Arc<S>,
+) -> crate::Result<()>
+where
+    S: RelayerStore,
+{
     if !config.events_watcher.enabled {
         tracing::warn!(
             "Signature Bridge events watcher is disabled for ({}).",
@@ -416,9 +707,11 @@ pub async fn start_signature_bridge_events_watcher(
             "Signature Bridge watcher for ({}) Started.",
             contract_address
         );
-        let bridge_contract_watcher = SignatureBridgeContractWatcher::default();
+        let bridge_contract_watcher =
+            SignatureBridgeContractWatcher::<S>::default();
         let governance_transfer_handler =
-            SignatureBridgeGovernanceOwnershipTransferredHandler::default();
+            SignatureBridgeGovernanceOwnershipTransferredHandler::<S>::default(
+            );
         let events_watcher_task = EventWatcher::run(
             &bridge_contract_watcher,
             client.clone(),
@@ -468,12 +761,15 @@ pub async fn start_signature_bridge_events_watcher(
 ///
 /// * `ctx` - RelayContext reference that holds the configuration
 /// * `chain_name` - Name of the chain
-/// * `store` -[Sled](https://sled.rs)-based database store
-pub fn start_tx_queue(
-    ctx: RelayerContext,
+/// * `store` - The database store
+pub fn start_tx_queue<S>(
+    ctx: RelayerContext<S>,
     chain_id: u32,
-    store: Arc<super::Store>,
-) -> crate::Result<()> {
+    store: Arc<S>,
+) -> crate::Result<()>
+where
+    S: RelayerStore,
+{
     // Start tx_queue only when governance relaying feature is enabled for relayer.
     if !ctx.config.features.governance_relay {
         tracing::warn!("Tx Queue disabled for ({})", chain_id,);
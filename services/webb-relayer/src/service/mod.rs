@@ -24,49 +24,59 @@
 use std::collections::HashSet;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum::routing::get;
 use axum::Router;
 use tower_http::cors::Any;
 use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
-use webb::substrate::subxt;
+use webb::evm::contract::protocol_solidity::SignatureBridgeContract;
+use webb::evm::ethers::providers::Middleware;
+use webb::evm::ethers::types::Bytes;
 use webb_proposal_signing_backends::{
-    DkgProposalSigningBackend, MockedProposalSigningBackend,
+    AirGappedProposalSigningBackend, DkgProposalSigningBackend,
+    GovernedContracts, MockedProposalSigningBackend, Outbox,
+    ThresholdProposalSigningBackend,
 };
-use webb_relayer_config::anchor::LinkedAnchorConfig;
+use webb_proposals::TypedChainId;
+use webb_relayer_config::anchor::{GovernedContractConfig, LinkedAnchorConfig};
+use webb_relayer_config::evm::Contract;
 
 use webb_relayer_config::signing_backend::ProposalSigningBackendConfig;
-use webb_relayer_context::RelayerContext;
+use webb_relayer_context::{RelayerContext, RelayerStore};
 use webb_relayer_handlers::routes::info::handle_relayer_info;
 use webb_relayer_handlers::{handle_socket_info, websocket_handler};
-use webb_relayer_store::SledStore;
 
+/// Store Compaction Service
+pub mod compaction;
 /// EVM Specific Services
 pub mod evm;
+/// Configuration Reload Service
+pub mod reload;
 /// Substrate Specific Services
 pub mod substrate;
 
-/// Type alias for [Sled](https://sled.rs)-based database store
-pub type Store = SledStore;
-
 /// Sets up the web socket server for the relayer, routing (endpoint queries / requests mapped to
 /// handled code) and instantiates the database store. Allows clients to interact with the relayer.
 ///
 /// # Arguments
 ///
 /// * `ctx` - RelayContext reference that holds the configuration and database
-pub async fn build_web_services(ctx: RelayerContext) -> crate::Result<()> {
+pub async fn build_web_services<S>(ctx: RelayerContext<S>) -> crate::Result<()>
+where
+    S: RelayerStore,
+{
     let socket_addr = SocketAddr::new([0, 0, 0, 0].into(), ctx.config.port);
     let api = Router::new()
         .route("/ip", get(handle_socket_info))
-        .route("/info", get(handle_relayer_info))
+        .route("/info", get(handle_relayer_info::<S>))
         .merge(evm::build_web_services())
         .merge(substrate::build_web_services());
 
     let app = Router::new()
         .nest("/api/v1", api)
-        .route("/ws", get(websocket_handler))
+        .route("/ws", get(websocket_handler::<S>))
         .layer(CorsLayer::new().allow_origin(Any))
         .layer(TraceLayer::new_for_http())
         .with_state(Arc::new(ctx))
@@ -84,38 +94,57 @@ pub async fn build_web_services(ctx: RelayerContext) -> crate::Result<()> {
 /// # Arguments
 ///
 /// * `ctx` - RelayContext reference that holds the configuration
-/// * `store` -[Sled](https://sled.rs)-based database store
-pub async fn ignite(
-    ctx: RelayerContext,
-    store: Arc<Store>,
-) -> crate::Result<()> {
+/// * `store` - The database store
+pub async fn ignite<S>(
+    ctx: RelayerContext<S>,
+    store: Arc<S>,
+) -> crate::Result<()>
+where
+    S: RelayerStore,
+{
     tracing::trace!(
         "Relayer configuration: {}",
         serde_json::to_string_pretty(&ctx.config)?
     );
     evm::ignite(&ctx, store.clone()).await?;
     substrate::ignite(ctx.clone(), store.clone()).await?;
+    compaction::ignite(&ctx, store);
     Ok(())
 }
 
+/// The length of a single ECDSA signature, `r || s || v`.
+const SIGNATURE_LENGTH: usize = 65;
+
 /// Proposal signing backend config
 #[allow(clippy::large_enum_variant)]
-pub enum ProposalSigningBackendSelector {
+pub enum ProposalSigningBackendSelector<S>
+where
+    S: RelayerStore,
+{
     /// None
     None,
     /// Mocked
-    Mocked(MockedProposalSigningBackend<SledStore>),
+    Mocked(MockedProposalSigningBackend<S>),
     /// Dkg
-    Dkg(DkgProposalSigningBackend),
+    Dkg(DkgProposalSigningBackend<S>),
+    /// Threshold
+    Threshold(ThresholdProposalSigningBackend<S>),
+    /// AirGapped
+    AirGapped(AirGappedProposalSigningBackend<S>),
 }
 /// utility to configure proposal signing backend
-pub async fn make_proposal_signing_backend(
-    ctx: &RelayerContext,
-    store: Arc<Store>,
+pub async fn make_proposal_signing_backend<S>(
+    ctx: &RelayerContext<S>,
+    store: Arc<S>,
     typed_chain_id: webb_proposals::TypedChainId,
     linked_anchors: Option<Vec<LinkedAnchorConfig>>,
+    governed_contracts: Vec<GovernedContractConfig>,
     proposal_signing_backend: Option<ProposalSigningBackendConfig>,
-) -> crate::Result<ProposalSigningBackendSelector> {
+) -> crate::Result<ProposalSigningBackendSelector<S>>
+where
+    S: RelayerStore,
+{
+    let governed_contracts = governed_contracts_of(governed_contracts);
     // Check if contract is configured with governance support for the relayer.
     if !ctx.config.features.governance_relay {
         tracing::warn!("Governance relaying is not enabled for relayer");
@@ -127,11 +156,9 @@ pub async fn make_proposal_signing_backend(
         Some(ProposalSigningBackendConfig::DkgNode(c)) => {
             // if it is the dkg backend, we will need to connect to that node first,
             // and then use the DkgProposalSigningBackend to sign the proposal.
-            let dkg_client = ctx
-                .substrate_provider::<subxt::PolkadotConfig, _>(c.chain_id)
-                .await?;
+            let dkg_endpoints = ctx.substrate_endpoints(c.chain_id)?;
             let backend = DkgProposalSigningBackend::builder()
-                .client(dkg_client)
+                .endpoints(dkg_endpoints)
                 .src_chain_id(typed_chain_id)
                 .store(store.clone())
                 .build();
@@ -141,45 +168,216 @@ pub async fn make_proposal_signing_backend(
             // if it is the mocked backend, we will use the MockedProposalSigningBackend to sign the proposal.
             // which is a bit simpler than the DkgProposalSigningBackend.
             // get only the linked chains to that anchor.
-            let mut signature_bridges: HashSet<webb_proposals::ResourceId> =
-                HashSet::new();
-
-            // Check if linked anchors are provided.
-            let linked_anchors = match linked_anchors {
-                Some(anchors) => {
-                    if anchors.is_empty() {
-                        tracing::warn!("Misconfigured Network: Linked anchors cannot be empty for governance relaying");
-                        return Ok(ProposalSigningBackendSelector::None);
-                    } else {
-                        anchors
-                    }
-                }
-                None => {
-                    tracing::warn!("Misconfigured Network: Linked anchors must be configured for governance relaying");
-                    return Ok(ProposalSigningBackendSelector::None);
-                }
+            let Some(signature_bridges) = signature_bridges_of(linked_anchors)
+            else {
+                return Ok(ProposalSigningBackendSelector::None);
             };
-            linked_anchors.iter().for_each(|anchor| {
-                // using chain_id to ensure that we have only one signature bridge
-                let resource_id = match anchor {
-                    LinkedAnchorConfig::Raw(target) => {
-                        let bytes: [u8; 32] = target.resource_id.into();
-                        webb_proposals::ResourceId::from(bytes)
-                    }
-                    _ => unreachable!("unsupported"),
-                };
-                signature_bridges.insert(resource_id);
-            });
+            let signer = webb_relayer_context::evm_signer(
+                mocked.private_key.as_deref(),
+                mocked.remote_signer.as_ref(),
+            )?;
             let backend = MockedProposalSigningBackend::builder()
                 .store(store.clone())
-                .private_key(mocked.private_key)
+                .signer(signer)
                 .signature_bridges(signature_bridges)
+                .governed_contracts(governed_contracts)
                 .build();
             Ok(ProposalSigningBackendSelector::Mocked(backend))
         }
+        Some(ProposalSigningBackendConfig::Threshold(threshold)) => {
+            // same as the mocked backend, but the proposals are signed by
+            // several signers, and only sent once enough of them signed.
+            let Some(signature_bridges) = signature_bridges_of(linked_anchors)
+            else {
+                return Ok(ProposalSigningBackendSelector::None);
+            };
+            if threshold.threshold == 0
+                || threshold.threshold > threshold.signers.len()
+            {
+                return Err(crate::Error::InvalidThreshold {
+                    threshold: threshold.threshold,
+                    signers: threshold.signers.len(),
+                });
+            }
+            ensure_threshold_signatures_supported(
+                ctx,
+                &signature_bridges,
+                threshold.threshold,
+            )
+            .await?;
+            let signers = threshold
+                .signers
+                .iter()
+                .map(|signer| {
+                    webb_relayer_context::evm_signer(
+                        signer.private_key.as_deref(),
+                        signer.remote_signer.as_ref(),
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let backend = ThresholdProposalSigningBackend::builder()
+                .store(store.clone())
+                .signers(signers)
+                .threshold(threshold.threshold)
+                .signature_bridges(signature_bridges)
+                .governed_contracts(governed_contracts)
+                .build();
+            Ok(ProposalSigningBackendSelector::Threshold(backend))
+        }
+        Some(ProposalSigningBackendConfig::AirGapped(air_gapped)) => {
+            // the proposals are exported to be signed offline, and imported
+            // back from the inbox once signed.
+            let Some(signature_bridges) = signature_bridges_of(linked_anchors)
+            else {
+                return Ok(ProposalSigningBackendSelector::None);
+            };
+            let outbox = match (air_gapped.outbox, air_gapped.outbox_url) {
+                (Some(dir), _) => Outbox::Dir(dir),
+                (None, Some(url)) => Outbox::Http(url.into()),
+                (None, None) => {
+                    tracing::warn!("Misconfigured Network: An outbox must be configured for the AirGapped backend");
+                    return Ok(ProposalSigningBackendSelector::None);
+                }
+            };
+            let backend = AirGappedProposalSigningBackend::builder()
+                .store(store.clone())
+                .outbox(outbox)
+                .inbox(air_gapped.inbox)
+                .governor(air_gapped.governor)
+                .inbox_polling_interval(Duration::from_millis(
+                    air_gapped.inbox_polling_interval,
+                ))
+                .signature_bridges(signature_bridges)
+                .governed_contracts(governed_contracts)
+                .build();
+            Ok(ProposalSigningBackendSelector::AirGapped(backend))
+        }
         None => {
             tracing::warn!("Misconfigured Network: Proposal signing backend must be configured for governance relaying");
             Ok(ProposalSigningBackendSelector::None)
         }
     }
 }
+
+/// Checks that the signature bridge of every linked chain could verify the
+/// `threshold` concatenated signatures of the threshold signing backend.
+///
+/// The stock `SignatureBridge` recovers a single ECDSA signature of its governor,
+/// so it reverts on the concatenated signatures, and every proposal would fail.
+/// Only a bridge that verifies them with its governor contract, through
+/// [EIP-1271](https://eips.ethereum.org/EIPS/eip-1271), is supported.
+async fn ensure_threshold_signatures_supported<S>(
+    ctx: &RelayerContext<S>,
+    signature_bridges: &HashSet<webb_proposals::ResourceId>,
+    threshold: usize,
+) -> crate::Result<()> {
+    for resource_id in signature_bridges {
+        let typed_chain_id = resource_id.typed_chain_id();
+        let not_supported =
+            |reason: String| crate::Error::ThresholdSignaturesNotSupported {
+                typed_chain_id,
+                reason,
+            };
+        let TypedChainId::Evm(chain_id) = typed_chain_id else {
+            return Err(not_supported(
+                "only EVM signature bridges are supported".into(),
+            ));
+        };
+        let bridge_address = ctx
+            .config
+            .evm
+            .values()
+            .filter(|chain| chain.chain_id == chain_id)
+            .flat_map(|chain| &chain.contracts)
+            .find_map(|contract| match contract {
+                Contract::SignatureBridge(c) => Some(c.common.address),
+                _ => None,
+            })
+            .ok_or_else(|| {
+                not_supported("no SignatureBridge is configured".into())
+            })?;
+        let provider = ctx.evm_provider(chain_id).await?;
+        let bridge =
+            SignatureBridgeContract::new(bridge_address, provider.clone());
+        let governor = bridge.governor().call().await?;
+        if provider.get_code(governor, None).await?.is_empty() {
+            return Err(not_supported(format!(
+                "its governor {governor:?} is not a contract"
+            )));
+        }
+        // the stock bridge reverts on a signature that is not 65 bytes long,
+        // while a bridge that asks its governor only rejects it.
+        let signature = vec![0u8; SIGNATURE_LENGTH * threshold];
+        if let Err(e) = bridge
+            .is_signature_from_governor(Bytes::default(), signature.into())
+            .call()
+            .await
+        {
+            return Err(not_supported(format!(
+                "it does not verify the signatures through its governor: {e}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the signature bridges of the linked anchors, that the proposals of
+/// an anchor are sent to, or `None` if no anchors are linked.
+fn signature_bridges_of(
+    linked_anchors: Option<Vec<LinkedAnchorConfig>>,
+) -> Option<HashSet<webb_proposals::ResourceId>> {
+    let mut signature_bridges: HashSet<webb_proposals::ResourceId> =
+        HashSet::new();
+
+    // Check if linked anchors are provided.
+    let linked_anchors = match linked_anchors {
+        Some(anchors) => {
+            if anchors.is_empty() {
+                tracing::warn!("Misconfigured Network: Linked anchors cannot be empty for governance relaying");
+                return None;
+            } else {
+                anchors
+            }
+        }
+        None => {
+            tracing::warn!("Misconfigured Network: Linked anchors must be configured for governance relaying");
+            return None;
+        }
+    };
+    linked_anchors.iter().for_each(|anchor| {
+        // using chain_id to ensure that we have only one signature bridge
+        let resource_id = match anchor {
+            LinkedAnchorConfig::Raw(target) => {
+                let bytes: [u8; 32] = target.resource_id.into();
+                webb_proposals::ResourceId::from(bytes)
+            }
+            _ => unreachable!("unsupported"),
+        };
+        signature_bridges.insert(resource_id);
+    });
+    Some(signature_bridges)
+}
+
+/// Returns the contracts, other than the anchors, that the governance proposals
+/// could target, by their kind.
+fn governed_contracts_of(
+    governed_contracts: Vec<GovernedContractConfig>,
+) -> GovernedContracts {
+    let mut contracts = GovernedContracts::default();
+    for contract in governed_contracts {
+        let (set, raw) = match contract {
+            GovernedContractConfig::TokenWrapper(raw) => {
+                (&mut contracts.token_wrappers, raw)
+            }
+            GovernedContractConfig::Treasury(raw) => {
+                (&mut contracts.treasuries, raw)
+            }
+            GovernedContractConfig::SignatureBridge(raw) => {
+                (&mut contracts.bridges, raw)
+            }
+        };
+        let bytes: [u8; 32] = raw.resource_id.into();
+        set.insert(webb_proposals::ResourceId::from(bytes));
+    }
+    contracts
+}
@@ -0,0 +1,241 @@
+// Copyright 2022 Webb Technologies Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use webb_relayer_config::diff::ConfigDiff;
+use webb_relayer_config::WebbRelayerConfig;
+use webb_relayer_context::{RelayerContext, RelayerStore};
+
+use super::{build_web_services, compaction, evm, substrate};
+
+/// The background services of a running relayer, and its HTTP server.
+///
+/// The services of every chain are started with their own shutdown signal, so
+/// when the configuration is reloaded, only the services of the chains that
+/// changed are restarted.
+pub struct Services<S> {
+    /// The context of the current configuration.
+    ctx: RelayerContext<S>,
+    store: Arc<S>,
+    server: JoinHandle<crate::Result<()>>,
+    /// The contexts the services of every EVM chain were started with.
+    evm: HashMap<String, RelayerContext<S>>,
+    /// The contexts the services of every Substrate chain were started with.
+    substrate: HashMap<String, RelayerContext<S>>,
+    /// The context the store compaction was started with.
+    compaction: RelayerContext<S>,
+}
+
+impl<S> Services<S>
+where
+    S: RelayerStore,
+{
+    /// Starts the HTTP server, and all background services for all chains
+    /// configured in the config file.
+    ///
+    /// Returns once all services are started successfully.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - RelayContext reference that holds the configuration
+    /// * `store` - The database store
+    pub async fn ignite(
+        ctx: RelayerContext<S>,
+        store: Arc<S>,
+    ) -> crate::Result<Self> {
+        tracing::trace!(
+            "Relayer configuration: {}",
+            serde_json::to_string_pretty(&ctx.config)?
+        );
+        let server = tokio::spawn(build_web_services(ctx.clone()));
+        let mut services = Self {
+            compaction: ctx.scoped(),
+            ctx,
+            store,
+            server,
+            evm: HashMap::new(),
+            substrate: HashMap::new(),
+        };
+        let evm_chains = services.ctx.config.evm.keys().cloned();
+        for chain in evm_chains.collect::<Vec<_>>() {
+            services.start_evm_chain(&chain).await?;
+        }
+        let substrate_chains = services.ctx.config.substrate.keys().cloned();
+        for chain in substrate_chains.collect::<Vec<_>>() {
+            services.start_substrate_chain(&chain).await?;
+        }
+        compaction::ignite(&services.compaction, services.store.clone());
+        Ok(services)
+    }
+
+    /// Returns the context of the current configuration.
+    pub fn context(&self) -> &RelayerContext<S> {
+        &self.ctx
+    }
+
+    /// Switches to the given configuration, restarting only the services it
+    /// changed, and returns what changed.
+    ///
+    /// The HTTP server is restarted on any change, to serve the new configuration.
+    /// A chain whose services fail to start is logged and skipped, so that the
+    /// other chains keep running.
+    pub async fn reload(
+        &mut self,
+        config: WebbRelayerConfig,
+    ) -> crate::Result<ConfigDiff> {
+        let diff = ConfigDiff::between(&self.ctx.config, &config);
+        if diff.is_empty() {
+            return Ok(diff);
+        }
+        let ctx = self.ctx.with_config(config)?;
+        // stop the services of the changed chains first.
+        for chain in &diff.evm {
+            if let Some(chain_ctx) = self.evm.remove(chain) {
+                tracing::debug!("Stopping the services of EVM chain {}", chain);
+                chain_ctx.shutdown();
+            }
+        }
+        for chain in &diff.substrate {
+            if let Some(chain_ctx) = self.substrate.remove(chain) {
+                tracing::debug!(
+                    "Stopping the services of Substrate chain {}",
+                    chain
+                );
+                chain_ctx.shutdown();
+            }
+        }
+        self.ctx = ctx;
+        // then start them again, unless they were removed.
+        for chain in &diff.evm {
+            if let Err(e) = self.start_evm_chain(chain).await {
+                tracing::error!(
+                    "Failed to start the services of EVM chain {}: {}",
+                    chain,
+                    e
+                );
+            }
+        }
+        for chain in &diff.substrate {
+            if let Err(e) = self.start_substrate_chain(chain).await {
+                tracing::error!(
+                    "Failed to start the services of Substrate chain {}: {}",
+                    chain,
+                    e
+                );
+            }
+        }
+        if diff.retention {
+            self.compaction.shutdown();
+            self.compaction = self.ctx.scoped();
+            compaction::ignite(&self.compaction, self.store.clone());
+        }
+        // wait for the old server to release its port, before starting the new one.
+        self.server.abort();
+        let _ = (&mut self.server).await;
+        self.server = tokio::spawn(build_web_services(self.ctx.clone()));
+        Ok(diff)
+    }
+
+    /// Sends the shutdown signal to all the services, and stops the HTTP server.
+    pub fn shutdown(&self) {
+        self.ctx.shutdown();
+        self.server.abort();
+    }
+
+    async fn start_evm_chain(&mut self, chain: &str) -> crate::Result<()> {
+        let Some(chain_config) = self.ctx.config.evm.get(chain) else {
+            return Ok(());
+        };
+        let chain_ctx = self.ctx.scoped();
+        let result =
+            evm::ignite_chain(&chain_ctx, chain_config, self.store.clone())
+                .await;
+        // keep the context even if some services failed to start, to stop the
+        // others along with it.
+        self.evm.insert(chain.to_owned(), chain_ctx);
+        result
+    }
+
+    async fn start_substrate_chain(
+        &mut self,
+        chain: &str,
+    ) -> crate::Result<()> {
+        let Some(node_config) = self.ctx.config.substrate.get(chain) else {
+            return Ok(());
+        };
+        let chain_ctx = self.ctx.scoped();
+        let result = substrate::ignite_chain(
+            chain_ctx.clone(),
+            self.store.clone(),
+            node_config,
+        )
+        .await;
+        self.substrate.insert(chain.to_owned(), chain_ctx);
+        result
+    }
+}
+
+/// Watches the config files in the given directory, by polling them at the given
+/// interval, and sends a notification whenever any of them is added, removed or
+/// modified.
+///
+/// Stops with the shutdown signal of the given context.
+pub fn watch_config_dir<S>(
+    ctx: &RelayerContext<S>,
+    config_dir: PathBuf,
+    interval: Duration,
+) -> mpsc::Receiver<()> {
+    let (tx, rx) = mpsc::channel(1);
+    let mut shutdown_signal = ctx.shutdown_signal();
+    let task = async move {
+        let state_of = |dir: &PathBuf| {
+            webb_relayer_config::utils::config_files_state(dir)
+                .map_err(|e| {
+                    tracing::warn!("Failed to read the config files: {}", e)
+                })
+                .ok()
+        };
+        let mut last_state = state_of(&config_dir);
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            let state = state_of(&config_dir);
+            if state.is_none() || state == last_state {
+                continue;
+            }
+            last_state = state;
+            tracing::debug!(
+                "The config files in {} changed",
+                config_dir.display()
+            );
+            // a pending notification already covers this change.
+            let _ = tx.try_send(());
+        }
+    };
+    tokio::spawn(async move {
+        tokio::select! {
+            _ = task => {},
+            _ = shutdown_signal.recv() => {
+                tracing::trace!("Stopping the config watcher");
+            },
+        }
+    });
+    rx
+}
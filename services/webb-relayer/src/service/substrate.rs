@@ -23,7 +23,7 @@ use webb_relayer_config::substrate::{
     DKGPalletConfig, DKGProposalHandlerPalletConfig, Pallet,
     SignatureBridgePalletConfig, SubstrateConfig, VAnchorBn254PalletConfig,
 };
-use webb_relayer_context::RelayerContext;
+use webb_relayer_context::{RelayerContext, RelayerStore};
 use webb_relayer_handlers::handle_substrate_fee_info;
 use webb_relayer_handlers::routes::{leaves, metric};
 use webb_relayer_tx_queue::substrate::SubstrateTxQueue;
@@ -34,19 +34,22 @@ use super::ProposalSigningBackendSelector;
 pub type TangleClient = subxt::OnlineClient<PolkadotConfig>;
 
 /// Setup and build all the Substrate web services and handlers.
-pub fn build_web_services() -> Router<Arc<RelayerContext>> {
+pub fn build_web_services<S>() -> Router<Arc<RelayerContext<S>>>
+where
+    S: RelayerStore,
+{
     Router::new()
         .route(
             "/leaves/substrate/:chain_id/:tree_id/:pallet_id",
-            get(leaves::handle_leaves_cache_substrate),
+            get(leaves::handle_leaves_cache_substrate::<S>),
         )
         .route(
             "/metrics/substrate/:chain_id/:tree_id/:pallet_id",
-            get(metric::handle_substrate_metric_info),
+            get(metric::handle_substrate_metric_info::<S>),
         )
         .route(
             "/fee_info/substrate/:chain_id/:estimated_tx_fees",
-            get(handle_substrate_fee_info),
+            get(handle_substrate_fee_info::<S>),
         )
 }
 
@@ -57,25 +60,41 @@ pub fn build_web_services() -> Router<Arc<RelayerContext>> {
 /// # Arguments
 ///
 /// * `ctx` - RelayContext reference that holds the configuration
-/// * `store` -[Sled](https://sled.rs)-based database store
-pub async fn ignite(
-    ctx: RelayerContext,
-    store: Arc<super::Store>,
-) -> crate::Result<()> {
+/// * `store` - The database store
+pub async fn ignite<S>(
+    ctx: RelayerContext<S>,
+    store: Arc<S>,
+) -> crate::Result<()>
+where
+    S: RelayerStore,
+{
     for (_, node_config) in ctx.clone().config.substrate {
-        if !node_config.enabled {
-            continue;
-        }
-        ignite_tangle_runtime(ctx.clone(), store.clone(), &node_config).await?;
+        ignite_chain(ctx.clone(), store.clone(), &node_config).await?;
     }
     Ok(())
 }
 
-async fn ignite_tangle_runtime(
-    ctx: RelayerContext,
-    store: Arc<super::Store>,
+/// Fires up all background services for the given Substrate chain, that is the
+/// event watchers of its pallets and its transaction queue.
+///
+/// Returns a future that resolves when all services are started successfully.
+///
+/// # Arguments
+///
+/// * `ctx` - RelayContext reference that holds the configuration
+/// * `store` - The database store
+/// * `node_config` - The configuration of the chain
+pub async fn ignite_chain<S>(
+    ctx: RelayerContext<S>,
+    store: Arc<S>,
     node_config: &SubstrateConfig,
-) -> crate::Result<()> {
+) -> crate::Result<()>
+where
+    S: RelayerStore,
+{
+    if !node_config.enabled {
+        return Ok(());
+    }
     let chain_id = node_config.chain_id;
     for pallet in &node_config.pallets {
         match pallet {
@@ -118,7 +137,7 @@ async fn ignite_tangle_runtime(
         }
     }
     // start the transaction queue for dkg-substrate extrinsics after starting other tasks.
-    start_tx_queue::<PolkadotConfig>(ctx.clone(), chain_id, store.clone())?;
+    start_tx_queue::<PolkadotConfig, S>(ctx.clone(), chain_id, store.clone())?;
     Ok(())
 }
 
@@ -132,13 +151,16 @@ async fn ignite_tangle_runtime(
 /// * `config` - DKG proposal handler configuration
 /// * `client` - DKG client
 /// * `chain_id` - An u32 representing the chain id of the chain
-/// * `store` -[Sled](https://sled.rs)-based database store
-pub fn start_dkg_proposal_handler(
-    ctx: RelayerContext,
+/// * `store` - The database store
+pub fn start_dkg_proposal_handler<S>(
+    ctx: RelayerContext<S>,
     config: &DKGProposalHandlerPalletConfig,
     chain_id: u32,
-    store: Arc<super::Store>,
-) -> crate::Result<()> {
+    store: Arc<S>,
+) -> crate::Result<()>
+where
+    S: RelayerStore,
+{
     // check first if we should start the events watcher for this contract.
     if !config.events_watcher.enabled {
         tracing::warn!(
@@ -155,8 +177,9 @@ pub fn start_dkg_proposal_handler(
     let metrics = ctx.metrics.clone();
     let my_config = config.clone();
     let task = async move {
-        let proposal_handler_watcher = DKGProposalHandlerWatcher::default();
-        let proposal_signed_handler = ProposalSignedHandler::default();
+        let proposal_handler_watcher =
+            DKGProposalHandlerWatcher::<S>::default();
+        let proposal_signed_handler = ProposalSignedHandler::<S>::default();
         let proposal_handler_watcher_task = proposal_handler_watcher.run(
             chain_id,
             ctx.clone(),
@@ -195,13 +218,16 @@ pub fn start_dkg_proposal_handler(
 /// * `config` - DKG pallet configuration
 /// * `client` - DKG client
 /// * `chain_id` - An u32 representing the chain id of the chain
-/// * `store` -[Sled](https://sled.rs)-based database store
-pub fn start_dkg_pallet_watcher(
-    ctx: RelayerContext,
+/// * `store` - The database store
+pub fn start_dkg_pallet_watcher<S>(
+    ctx: RelayerContext<S>,
     config: &DKGPalletConfig,
     chain_id: u32,
-    store: Arc<super::Store>,
-) -> crate::Result<()> {
+    store: Arc<S>,
+) -> crate::Result<()>
+where
+    S: RelayerStore,
+{
     // check first if we should start the events watcher for this pallet.
     if !config.events_watcher.enabled {
         tracing::warn!(
@@ -216,9 +242,9 @@ pub fn start_dkg_pallet_watcher(
     let metrics = ctx.metrics.clone();
     let my_config = config.clone();
     let task = async move {
-        let dkg_event_watcher = DKGMetadataWatcher::default();
+        let dkg_event_watcher = DKGMetadataWatcher::<S>::default();
         let public_key_changed_handler =
-            DKGPublicKeyChangedHandler::new(webb_config);
+            DKGPublicKeyChangedHandler::<S>::new(webb_config);
 
         let dkg_event_watcher_task = dkg_event_watcher.run(
             chain_id,
@@ -258,13 +284,16 @@ pub fn start_dkg_pallet_watcher(
 /// * `config` - VAnchorBn254 configuration
 /// * `client` - WebbProtocol client
 /// * `chain_id` - An u32 representing the chain id of the chain
-/// * `store` -[Sled](https://sled.rs)-based database store
-pub fn start_substrate_vanchor_event_watcher(
-    ctx: RelayerContext,
+/// * `store` - The database store
+pub fn start_substrate_vanchor_event_watcher<S>(
+    ctx: RelayerContext<S>,
     config: &VAnchorBn254PalletConfig,
     chain_id: u32,
-    store: Arc<super::Store>,
-) -> crate::Result<()> {
+    store: Arc<S>,
+) -> crate::Result<()>
+where
+    S: RelayerStore,
+{
     if !config.events_watcher.enabled {
         tracing::warn!(
             "Substrate VAnchor events watcher is disabled for ({}).",
@@ -286,6 +315,8 @@ pub fn start_substrate_vanchor_event_watcher(
             store.clone(),
             webb_proposals::TypedChainId::Substrate(chain_id),
             my_config.linked_anchors.clone(),
+            // the governed contracts are only known on the evm chains.
+            Vec::new(),
             my_config.proposal_signing_backend,
         )
         .await?;
@@ -294,16 +325,18 @@ pub fn start_substrate_vanchor_event_watcher(
                 let bridge_registry =
                     DkgBridgeRegistryBackend::new(backend.client.clone());
 
-                let deposit_handler = SubstrateVAnchorDepositHandler::new(
-                    backend,
-                    bridge_registry,
-                    my_config.linked_anchors,
-                );
-                let leaves_handler = SubstrateVAnchorLeavesHandler::default();
+                let deposit_handler =
+                    SubstrateVAnchorDepositHandler::<_, _, S>::new(
+                        backend,
+                        bridge_registry,
+                        my_config.linked_anchors,
+                    );
+                let leaves_handler =
+                    SubstrateVAnchorLeavesHandler::<S>::default();
                 let encrypted_output_handler =
-                    SubstrateVAnchorEncryptedOutputHandler::default();
+                    SubstrateVAnchorEncryptedOutputHandler::<S>::default();
 
-                let watcher = SubstrateVAnchorEventWatcher::default();
+                let watcher = SubstrateVAnchorEventWatcher::<S>::default();
                 let substrate_vanchor_watcher_task = watcher.run(
                     chain_id,
                     ctx.clone(),
@@ -337,16 +370,18 @@ pub fn start_substrate_vanchor_event_watcher(
                 let bridge_registry =
                     MockedBridgeRegistryBackend::builder().build();
 
-                let deposit_handler = SubstrateVAnchorDepositHandler::new(
-                    backend,
-                    bridge_registry,
-                    my_config.linked_anchors,
-                );
-                let leaves_handler = SubstrateVAnchorLeavesHandler::default();
+                let deposit_handler =
+                    SubstrateVAnchorDepositHandler::<_, _, S>::new(
+                        backend,
+                        bridge_registry,
+                        my_config.linked_anchors,
+                    );
+                let leaves_handler =
+                    SubstrateVAnchorLeavesHandler::<S>::default();
                 let encrypted_output_handler =
-                    SubstrateVAnchorEncryptedOutputHandler::default();
+                    SubstrateVAnchorEncryptedOutputHandler::<S>::default();
 
-                let watcher = SubstrateVAnchorEventWatcher::default();
+                let watcher = SubstrateVAnchorEventWatcher::<S>::default();
                 let substrate_vanchor_watcher_task = watcher.run(
                     chain_id,
                     ctx.clone(),
@@ -374,12 +409,106 @@ pub fn start_substrate_vanchor_event_watcher(
                     },
                 }
             }
+            ProposalSigningBackendSelector::Threshold(backend) => {
+                let bridge_registry =
+                    MockedBridgeRegistryBackend::builder().build();
+
+                let deposit_handler =
+                    SubstrateVAnchorDepositHandler::<_, _, S>::new(
+                        backend,
+                        bridge_registry,
+                        my_config.linked_anchors,
+                    );
+                let leaves_handler =
+                    SubstrateVAnchorLeavesHandler::<S>::default();
+                let encrypted_output_handler =
+                    SubstrateVAnchorEncryptedOutputHandler::<S>::default();
+
+                let watcher = SubstrateVAnchorEventWatcher::<S>::default();
+                let substrate_vanchor_watcher_task = watcher.run(
+                    chain_id,
+                    ctx.clone(),
+                    store.clone(),
+                    my_config.events_watcher,
+                    vec![
+                        Box::new(deposit_handler),
+                        Box::new(leaves_handler),
+                        Box::new(encrypted_output_handler),
+                    ],
+                    metrics.clone(),
+                );
+                tokio::select! {
+                    _ = substrate_vanchor_watcher_task => {
+                        tracing::warn!(
+                            "Substrate VAnchor watcher (Threshold Backend) task stopped for ({})",
+                            chain_id,
+                        );
+                    },
+                    _ = shutdown_signal.recv() => {
+                        tracing::trace!(
+                            "Stopping Substrate VAnchor watcher (Threshold Backend) for ({})",
+                            chain_id,
+                        );
+                    },
+                }
+            }
+            ProposalSigningBackendSelector::AirGapped(backend) => {
+                let inbox_task = backend.inbox_watcher().run();
+                let bridge_registry =
+                    MockedBridgeRegistryBackend::builder().build();
+
+                let deposit_handler =
+                    SubstrateVAnchorDepositHandler::<_, _, S>::new(
+                        backend,
+                        bridge_registry,
+                        my_config.linked_anchors,
+                    );
+                let leaves_handler =
+                    SubstrateVAnchorLeavesHandler::<S>::default();
+                let encrypted_output_handler =
+                    SubstrateVAnchorEncryptedOutputHandler::<S>::default();
+
+                let watcher = SubstrateVAnchorEventWatcher::<S>::default();
+                let substrate_vanchor_watcher_task = watcher.run(
+                    chain_id,
+                    ctx.clone(),
+                    store.clone(),
+                    my_config.events_watcher,
+                    vec![
+                        Box::new(deposit_handler),
+                        Box::new(leaves_handler),
+                        Box::new(encrypted_output_handler),
+                    ],
+                    metrics.clone(),
+                );
+                tokio::select! {
+                    _ = inbox_task => {
+                        tracing::warn!(
+                            "Inbox watcher (AirGapped Backend) task stopped for ({})",
+                            chain_id,
+                        );
+                    },
+                    _ = substrate_vanchor_watcher_task => {
+                        tracing::warn!(
+                            "Substrate VAnchor watcher (AirGapped Backend) task stopped for ({})",
+                            chain_id,
+                        );
+                    },
+                    _ = shutdown_signal.recv() => {
+                        tracing::trace!(
+                            "Stopping Substrate VAnchor watcher (AirGapped Backend) for ({})",
+                            chain_id,
+                        );
+                    },
+                }
+            }
             ProposalSigningBackendSelector::None => {
-                let leaves_handler = SubstrateVAnchorLeavesHandler::default();
+                let leaves_handler =
+                    SubstrateVAnchorLeavesHandler::<S>::default();
                 let encrypted_output_handler =
-                    SubstrateVAnchorEncryptedOutputHandler::default();
+                    SubstrateVAnchorEncryptedOutputHandler::<S>::default();
 
-                let watcher = SubstrateVAnchorEventWatcher::default();
+                let watcher = SubstrateVAnchorEventWatcher::<S>::default();
                 let substrate_vanchor_watcher_task = watcher.run(
                     chain_id,
                     ctx.clone(),
@@ -417,12 +546,15 @@ pub fn start_substrate_vanchor_event_watcher(
 }
 
 /// Starts the event watcher for Signature Bridge Pallet.
-pub async fn start_substrate_signature_bridge_events_watcher(
-    ctx: RelayerContext,
+pub async fn start_substrate_signature_bridge_events_watcher<S>(
+    ctx: RelayerContext<S>,
     config: &SignatureBridgePalletConfig,
     chain_id: u32,
-    store: Arc<super::Store>,
-) -> crate::Result<()> {
+    store: Arc<S>,
+) -> crate::Result<()>
+where
+    S: RelayerStore,
+{
     if !config.events_watcher.enabled {
         tracing::warn!(
             "Substrate Signature Bridge events watcher is disabled for ({}).",
@@ -438,8 +570,9 @@ pub async fn start_substrate_signature_bridge_events_watcher(
             "Substrate Signature Bridge watcher for ({}) Started.",
             chain_id
         );
-        let substrate_bridge_watcher = SubstrateBridgeEventWatcher::default();
-        let bridge_event_handler = MaintainerSetEventHandler::default();
+        let substrate_bridge_watcher =
+            SubstrateBridgeEventWatcher::<S>::default();
+        let bridge_event_handler = MaintainerSetEventHandler::<S>::default();
         let events_watcher_task = SubstrateEventWatcher::run(
             &substrate_bridge_watcher,
             chain_id,
@@ -490,14 +623,15 @@ pub async fn start_substrate_signature_bridge_events_watcher(
 ///
 /// * `ctx` - RelayContext reference that holds the configuration
 /// * `chain_name` - Name of the chain
-/// * `store` -[Sled](https://sled.rs)-based database store
-pub fn start_tx_queue<X>(
-    ctx: RelayerContext,
+/// * `store` - The database store
+pub fn start_tx_queue<X, S>(
+    ctx: RelayerContext<S>,
     chain_id: u32,
-    store: Arc<super::Store>,
+    store: Arc<S>,
 ) -> crate::Result<()>
 where
     X: subxt::Config + Send + Sync,
+    S: RelayerStore,
     <<X>::ExtrinsicParams as ExtrinsicParams<
         <X>::Index,
         <X>::Hash,
@@ -33,6 +33,7 @@ import * as BN from 'bn.js';
 
 export type CommonConfig = {
   features?: FeaturesConfig;
+  proposalsAdminToken?: string;
   evmEtherscan?: EvmEtherscanConfig;
   port: number;
   assets?: { [key: string]: UnlistedAssetConfig };
@@ -65,6 +66,7 @@ export class WebbRelayer {
     // Write the folder-wide configuration for this relayer instance
     type WrittenCommonConfig = {
       features?: ConvertToKebabCase<FeaturesConfig>;
+      'proposals-admin-token'?: string;
       'evm-etherscan'?: {
         [key: string]: ConvertToKebabCase<EtherscanApiConfig>;
       };
@@ -78,7 +80,9 @@ export class WebbRelayer {
         'data-query': opts.commonConfig.features?.dataQuery ?? true,
         'governance-relay': opts.commonConfig.features?.governanceRelay ?? true,
         'private-tx-relay': opts.commonConfig.features?.privateTxRelay ?? true,
+        'proposals-admin': opts.commonConfig.features?.proposalsAdmin ?? false,
       },
+      'proposals-admin-token': opts.commonConfig.proposalsAdminToken,
       'evm-etherscan': Object.fromEntries(
         Object.entries(opts.commonConfig.evmEtherscan ?? {}).map(
           ([key, { chainId, apiKey }]) => [
@@ -588,6 +592,7 @@ export interface FeaturesConfig {
   dataQuery?: boolean;
   governanceRelay?: boolean;
   privateTxRelay?: boolean;
+  proposalsAdmin?: boolean;
 }
 
 export interface SmartAnchorUpdatesConfig {